@@ -1,6 +1,21 @@
+use rocket::local::Client;
+use rocket::response::status::Custom;
 use std::fs::ReadDir;
+use std::net::TcpListener as StdTcpListener;
+use tokio::net::TcpListener;
+use tokio_rustls::rustls::ServerConfig;
 
 use super::prelude::*;
+use crate::core::auth::AuthError;
+use crate::core::backend::DiskState;
+use crate::core::compaction;
+use crate::core::configs::{Cluster as ClusterConfig, Role};
+use crate::core::handoff::Handoff;
+use crate::core::migration::{self, MigrationState};
+use crate::core::repair::Repair;
+use crate::core::scrubber;
+use crate::core::tls;
+use crate::grpc::GetSource;
 use backend::NodeDisk;
 
 #[derive(Debug, Clone)]
@@ -14,6 +29,19 @@ pub(crate) struct Node {
     name: String,
     address: String,
     vdisks: Vec<VDisk>,
+    // vdisks with local replicas on the unavailable disks, reported only for the local node
+    #[serde(skip_serializing_if = "Option::is_none")]
+    degraded_vdisks: Option<Vec<u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    disks_space: Option<Vec<DiskSpace>>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct DiskSpace {
+    name: String,
+    state: &'static str,
+    free_space: u64,
+    total_space: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -34,6 +62,8 @@ pub(crate) struct VDiskPartitions {
     node_name: String,
     disk_name: String,
     partitions: Vec<String>,
+    unloaded_partitions: Vec<String>,
+    index_memory: usize,
 }
 
 #[derive(Debug, Serialize, Clone)]
@@ -43,6 +73,83 @@ pub(crate) struct Partition {
     disk_name: String,
     timestamp: u64,
     records_count: usize,
+    loaded: bool,
+    index_memory: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ScrubReport {
+    checked: usize,
+    corrupted: Vec<CorruptedRecord>,
+    unchecked_partitions: Vec<UncheckedPartition>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CorruptedRecord {
+    vdisk_id: u32,
+    node_name: String,
+    disk_name: String,
+    partition_timestamp: u64,
+    key: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct UncheckedPartition {
+    vdisk_id: u32,
+    node_name: String,
+    disk_name: String,
+    partition_timestamp: u64,
+    records: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct CompactionReport {
+    merged_partitions: usize,
+    created_partitions: usize,
+    records: usize,
+    dropped_records: usize,
+    skipped_windows: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct EncryptionStatus {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_key_id: Option<u32>,
+    reencryption_running: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_reencryption: Option<CompactionReport>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct KeyEntry {
+    key: String,
+    timestamp: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct KeysPage {
+    keys: Vec<KeyEntry>,
+    next: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct ClusterMap {
+    version: u64,
+    config: String,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct MigrationStatus {
+    vdisk_id: u32,
+    disk_name: String,
+    source: String,
+    state: String,
+    error: Option<String>,
+    holders_total: usize,
+    holders_done: usize,
+    holders_skipped: usize,
+    records: usize,
+    bytes: u64,
 }
 
 #[derive(Debug)]
@@ -52,6 +159,48 @@ pub(crate) struct StatusExt {
     msg: String,
 }
 
+#[derive(Debug, Serialize)]
+pub(crate) struct DiskStatus {
+    name: String,
+    path: String,
+    state: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    vdisks: Vec<u32>,
+    operations: u64,
+    errors: u64,
+    latency_us: u128,
+    free_space: u64,
+    total_space: u64,
+    space: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    soft_watermark: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hard_watermark: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct Readiness {
+    ready: bool,
+    vdisks_total: usize,
+    vdisks_ready: usize,
+    loading_vdisks: Vec<u32>,
+}
+
+#[derive(Debug, Serialize)]
+pub(crate) struct DrainStatus {
+    state: &'static str,
+    in_flight: usize,
+}
+
+/// Request guard, which requires read role of the client
+#[derive(Debug)]
+pub(crate) struct ReadAccess;
+
+/// Request guard, which requires admin role of the client
+#[derive(Debug)]
+pub(crate) struct AdminAccess;
+
 #[derive(Debug, Serialize)]
 pub(crate) struct Dir {
     name: String,
@@ -59,15 +208,18 @@ pub(crate) struct Dir {
     children: Vec<Dir>,
 }
 
+const KEYS_PAGE_LIMIT: usize = 1000;
+
 fn runtime() -> Runtime {
     // TODO: run web server on same runtime as bob (update to async rocket when it's stable)
     debug!("HOT FIX: run web server on same runtime as bob");
     Runtime::new().expect("create runtime")
 }
 
-pub(crate) fn spawn(bob: BobServer, port: u16) {
+pub(crate) fn spawn(bob: BobServer, port: u16, tls: Option<ServerConfig>) {
     let routes = routes![
         status,
+        ready,
         vdisks,
         vdisk_by_id,
         partitions,
@@ -75,16 +227,48 @@ pub(crate) fn spawn(bob: BobServer, port: u16) {
         change_partition_state,
         delete_partition,
         alien,
+        alien_handoff,
         remount_vdisks_group,
+        repair_vdisk,
+        scrub_report,
+        scrub,
+        compaction_report,
+        compact,
+        encryption_status,
+        reencrypt,
         get_local_replica_directories,
         nodes,
         finalize_outdated_blobs,
-        vdisk_records_count
+        vdisk_records_count,
+        vdisk_keys,
+        cluster_map,
+        update_cluster_map,
+        reload_node_config,
+        drain_status,
+        drain,
+        undrain,
+        disks,
+        stop_disk,
+        start_disk,
+        migrations,
+        migration_by_vdisk_id
     ];
+    let mut config = Config::production();
+    if let Some(tls) = tls {
+        // tls of rocket requires other ring version, so it's terminated in front of rocket and
+        // requests are dispatched to rocket in process
+        let listener = StdTcpListener::bind(("0.0.0.0", port)).expect("bind http api port");
+        listener.set_nonblocking(true).expect("set non-blocking");
+        let listener = TcpListener::from_std(listener).expect("convert listener");
+        let rocket = Rocket::custom(config).manage(bob).mount("/", routes);
+        let client = Client::untracked(rocket).expect("http api");
+        info!("API server started");
+        tokio::spawn(tls::serve(listener, tls, client));
+        return;
+    }
+    config.set_port(port);
     let task = move || {
         info!("API server started");
-        let mut config = Config::production();
-        config.set_port(port);
         Rocket::custom(config)
             .manage(bob)
             .mount("/", routes)
@@ -107,12 +291,8 @@ fn collect_disks_info(bob: &BobServer) -> Vec<VDisk> {
 
 #[inline]
 fn get_vdisk_by_id(bob: &BobServer, id: u32) -> Option<VDisk> {
-    find_vdisk(bob, id).map(data_vdisk_to_scheme)
-}
-
-fn find_vdisk(bob: &BobServer, id: u32) -> Option<&DataVDisk> {
     let mapper = bob.grinder().backend().mapper();
-    mapper.get_vdisk(id)
+    mapper.get_vdisk(id).map(data_vdisk_to_scheme)
 }
 
 fn collect_replicas_info(replicas: &[NodeDisk]) -> Vec<Replica> {
@@ -133,13 +313,15 @@ fn not_acceptable_backend() -> Status {
     status
 }
 
-fn find_group<'a>(bob: &'a State<BobServer>, vdisk_id: u32) -> Result<&'a PearlGroup, StatusExt> {
-    let backend = bob.grinder().backend().inner();
+fn find_group(bob: &State<BobServer>, vdisk_id: u32) -> Result<PearlGroup, StatusExt> {
+    let backend = bob.grinder().backend();
     debug!("get backend: OK");
-    let groups = backend.vdisks_groups().ok_or_else(not_acceptable_backend)?;
+    let groups = runtime()
+        .block_on(backend.vdisks_groups())
+        .ok_or_else(not_acceptable_backend)?;
     debug!("get vdisks groups: OK");
     groups
-        .iter()
+        .into_iter()
         .find(|group| group.vdisk_id() == vdisk_id)
         .ok_or_else(|| {
             let err = format!("vdisk with id: {} not found", vdisk_id);
@@ -149,21 +331,61 @@ fn find_group<'a>(bob: &'a State<BobServer>, vdisk_id: u32) -> Result<&'a PearlG
 }
 
 #[get("/status")]
-fn status(bob: State<BobServer>) -> Json<Node> {
+fn status(bob: State<BobServer>, _access: ReadAccess) -> Json<Node> {
     let mapper = bob.grinder().backend().mapper();
     let name = mapper.local_node_name().to_owned();
     let address = mapper.local_node_address().to_owned();
     let vdisks = collect_disks_info(&bob);
+    let backend = bob.grinder().backend();
+    let degraded_vdisks = backend.degraded_vdisks();
+    let disks_space = backend
+        .disks()
+        .all()
+        .iter()
+        .map(|disk| DiskSpace {
+            name: disk.name().to_owned(),
+            state: disk.space_state().as_str(),
+            free_space: disk.free_space(),
+            total_space: disk.total_space(),
+        })
+        .collect();
     let node = Node {
         name,
         address,
         vdisks,
+        degraded_vdisks: Some(degraded_vdisks),
+        disks_space: Some(disks_space),
     };
     Json(node)
 }
 
+// probes of orchestrators have no credentials, so readiness is public
+#[get("/ready")]
+fn ready(bob: State<BobServer>) -> Custom<Json<Readiness>> {
+    let groups = runtime()
+        .block_on(bob.grinder().backend().vdisks_groups())
+        .unwrap_or_default();
+    let loading_vdisks: Vec<_> = groups
+        .iter()
+        .filter(|group| !group.is_ready())
+        .map(PearlGroup::vdisk_id)
+        .collect();
+    let readiness = Readiness {
+        ready: loading_vdisks.is_empty(),
+        vdisks_total: groups.len(),
+        vdisks_ready: groups.len() - loading_vdisks.len(),
+        loading_vdisks,
+    };
+    let status = if readiness.ready {
+        Status::Ok
+    } else {
+        Status::ServiceUnavailable
+    };
+    Custom(status, Json(readiness))
+}
+
 #[get("/nodes")]
-fn nodes(bob: State<BobServer>) -> Json<Vec<Node>> {
+fn nodes(bob: State<BobServer>, _access: ReadAccess) -> Json<Vec<Node>> {
     let mapper = bob.grinder().backend().mapper();
     let mut nodes = vec![];
     let vdisks = collect_disks_info(&bob);
@@ -185,6 +407,8 @@ fn nodes(bob: State<BobServer>) -> Json<Vec<Node>> {
             name: node.name().to_string(),
             address: node.address().to_string(),
             vdisks,
+            degraded_vdisks: None,
+            disks_space: None,
         };
 
         nodes.push(node);
@@ -193,13 +417,16 @@ fn nodes(bob: State<BobServer>) -> Json<Vec<Node>> {
 }
 
 #[get("/vdisks")]
-fn vdisks(bob: State<BobServer>) -> Json<Vec<VDisk>> {
+fn vdisks(bob: State<BobServer>, _access: ReadAccess) -> Json<Vec<VDisk>> {
     let vdisks = collect_disks_info(&bob);
     Json(vdisks)
 }
 
 #[delete("/blobs/outdated")]
-fn finalize_outdated_blobs(bob: State<BobServer>) -> Result<StatusExt, StatusExt> {
+fn finalize_outdated_blobs(
+    bob: State<BobServer>,
+    _access: AdminAccess,
+) -> Result<StatusExt, StatusExt> {
     let bob = bob.clone();
     runtime().spawn(async move {
         let backend = bob.grinder().backend();
@@ -213,12 +440,16 @@ fn finalize_outdated_blobs(bob: State<BobServer>) -> Result<StatusExt, StatusExt
 }
 
 #[get("/vdisks/<vdisk_id>")]
-fn vdisk_by_id(bob: State<BobServer>, vdisk_id: u32) -> Option<Json<VDisk>> {
+fn vdisk_by_id(bob: State<BobServer>, _access: ReadAccess, vdisk_id: u32) -> Option<Json<VDisk>> {
     get_vdisk_by_id(&bob, vdisk_id).map(Json)
 }
 
 #[get("/vdisks/<vdisk_id>/records/count")]
-fn vdisk_records_count(bob: State<BobServer>, vdisk_id: u32) -> Result<Json<u64>, StatusExt> {
+fn vdisk_records_count(
+    bob: State<BobServer>,
+    _access: ReadAccess,
+    vdisk_id: u32,
+) -> Result<Json<u64>, StatusExt> {
     let group = find_group(&bob, vdisk_id)?;
     let holders = group.holders();
     let sum = runtime().block_on(async move {
@@ -233,8 +464,69 @@ fn vdisk_records_count(bob: State<BobServer>, vdisk_id: u32) -> Result<Json<u64>
     Ok(Json(sum as u64))
 }
 
+#[get("/vdisks/<vdisk_id>/keys?<source>&<partition>&<after>&<limit>")]
+fn vdisk_keys(
+    bob: State<BobServer>,
+    _access: ReadAccess,
+    vdisk_id: u32,
+    source: Option<String>,
+    partition: Option<u64>,
+    after: Option<String>,
+    limit: Option<usize>,
+) -> Result<Json<KeysPage>, StatusExt> {
+    if get_vdisk_by_id(&bob, vdisk_id).is_none() {
+        let err = format!("vdisk with id: {} not found", vdisk_id);
+        return Err(StatusExt::new(Status::NotFound, false, err));
+    }
+    let source = match source.as_deref() {
+        None | Some("all") => GetSource::All,
+        Some("normal") => GetSource::Normal,
+        Some("alien") => GetSource::Alien,
+        Some(other) => {
+            let err = format!("unknown source: {}, expected all, normal or alien", other);
+            return Err(StatusExt::new(Status::BadRequest, false, err));
+        }
+    };
+    let backend = bob.grinder().backend();
+    let after = after
+        .map(|key| {
+            let key = key
+                .parse()
+                .map_err(|e| StatusExt::new(Status::BadRequest, false, e))?;
+            backend
+                .mapper()
+                .normalize_key(key)
+                .map_err(|e| StatusExt::new(Status::BadRequest, false, e.to_string()))
+        })
+        .transpose()?;
+    let limit = limit.unwrap_or(KEYS_PAGE_LIMIT);
+    // one more key is listed to find out if there is the next page
+    let versions = runtime()
+        .block_on(backend.list_keys(vdisk_id, source, partition, after, Some(limit + 1)))
+        .map_err(|e| StatusExt::new(Status::InternalServerError, false, e.to_string()))?;
+    let has_next = versions.len() > limit;
+    let keys = versions
+        .into_iter()
+        .take(limit)
+        .map(|version| KeyEntry {
+            key: version.key().to_string(),
+            timestamp: version.timestamp(),
+        })
+        .collect::<Vec<_>>();
+    let next = if has_next {
+        keys.last().map(|entry| entry.key.clone())
+    } else {
+        None
+    };
+    Ok(Json(KeysPage { keys, next }))
+}
+
 #[get("/vdisks/<vdisk_id>/partitions")]
-fn partitions(bob: State<BobServer>, vdisk_id: u32) -> Result<Json<VDiskPartitions>, StatusExt> {
+fn partitions(
+    bob: State<BobServer>,
+    _access: ReadAccess,
+    vdisk_id: u32,
+) -> Result<Json<VDiskPartitions>, StatusExt> {
     let group = find_group(&bob, vdisk_id)?;
     debug!("group with provided vdisk_id found");
     let holders = group.holders();
@@ -242,11 +534,22 @@ fn partitions(bob: State<BobServer>, vdisk_id: u32) -> Result<Json<VDiskPartitio
     debug!("get pearl holders: OK");
     let pearls: &[_] = pearls.as_ref();
     let partitions = pearls.iter().map(Holder::get_id).collect();
+    let mut unloaded_partitions = vec![];
+    let mut index_memory = 0;
+    for pearl in pearls {
+        if runtime().block_on(pearl.is_loaded()) {
+            index_memory += runtime().block_on(pearl.index_memory());
+        } else {
+            unloaded_partitions.push(pearl.get_id());
+        }
+    }
     let ps = VDiskPartitions {
         node_name: group.node_name().to_owned(),
         disk_name: group.disk_name().to_owned(),
         vdisk_id: group.vdisk_id(),
         partitions,
+        unloaded_partitions,
+        index_memory,
     };
     trace!("partitions: {:?}", ps);
     Ok(Json(ps))
@@ -255,6 +558,7 @@ fn partitions(bob: State<BobServer>, vdisk_id: u32) -> Result<Json<VDiskPartitio
 #[get("/vdisks/<vdisk_id>/partitions/<partition_id>")]
 fn partition_by_id(
     bob: State<'_, BobServer>,
+    _access: ReadAccess,
     vdisk_id: u32,
     partition_id: String,
 ) -> Result<Json<Partition>, StatusExt> {
@@ -273,6 +577,8 @@ fn partition_by_id(
         vdisk_id: group.vdisk_id(),
         timestamp: p.start_timestamp(),
         records_count: rt.block_on(p.records_count()),
+        loaded: rt.block_on(p.is_loaded()),
+        index_memory: rt.block_on(p.index_memory()),
     });
     partition.map(Json).ok_or_else(|| {
         let err = format!(
@@ -287,6 +593,7 @@ fn partition_by_id(
 #[post("/vdisks/<vdisk_id>/partitions/by_timestamp/<timestamp>/<action>")]
 fn change_partition_state(
     bob: State<BobServer>,
+    _access: AdminAccess,
     vdisk_id: u32,
     timestamp: u64,
     action: Action,
@@ -316,7 +623,11 @@ fn change_partition_state(
 }
 
 #[post("/vdisks/<vdisk_id>/remount")]
-fn remount_vdisks_group(bob: State<BobServer>, vdisk_id: u32) -> Result<StatusExt, StatusExt> {
+fn remount_vdisks_group(
+    bob: State<BobServer>,
+    _access: AdminAccess,
+    vdisk_id: u32,
+) -> Result<StatusExt, StatusExt> {
     let group = find_group(&bob, vdisk_id)?;
     let group = group.clone();
     debug!("HOT FIX: run web server on same runtime as bob");
@@ -335,9 +646,143 @@ fn remount_vdisks_group(bob: State<BobServer>, vdisk_id: u32) -> Result<StatusEx
     }
 }
 
+#[post("/vdisks/<vdisk_id>/repair")]
+fn repair_vdisk(
+    bob: State<BobServer>,
+    _access: AdminAccess,
+    vdisk_id: u32,
+) -> Result<StatusExt, StatusExt> {
+    let backend = bob.grinder().backend();
+    match runtime().block_on(Repair::run_vdisk(backend, vdisk_id)) {
+        Ok(report) => Ok(StatusExt::new(
+            Status::Ok,
+            true,
+            format!(
+                "vdisk {} repaired: {} records pulled, {} records pushed",
+                vdisk_id,
+                report.pulled(),
+                report.pushed()
+            ),
+        )),
+        Err(e) => Err(StatusExt::new(
+            Status::InternalServerError,
+            false,
+            e.to_string(),
+        )),
+    }
+}
+
+fn scrub_report_to_scheme(report: &scrubber::ScrubReport) -> ScrubReport {
+    let corrupted = report
+        .corrupted()
+        .iter()
+        .map(|record| CorruptedRecord {
+            vdisk_id: record.vdisk_id(),
+            node_name: record.node_name().to_owned(),
+            disk_name: record.disk_name().to_owned(),
+            partition_timestamp: record.start_timestamp(),
+            key: record.key().to_string(),
+        })
+        .collect();
+    let unchecked_partitions = report
+        .unchecked()
+        .iter()
+        .map(|holder| UncheckedPartition {
+            vdisk_id: holder.vdisk_id(),
+            node_name: holder.node_name().to_owned(),
+            disk_name: holder.disk_name().to_owned(),
+            partition_timestamp: holder.start_timestamp(),
+            records: holder.records(),
+        })
+        .collect();
+    ScrubReport {
+        checked: report.checked(),
+        corrupted,
+        unchecked_partitions,
+    }
+}
+
+#[get("/scrub")]
+fn scrub_report(bob: State<BobServer>, _access: ReadAccess) -> Option<Json<ScrubReport>> {
+    let report = runtime().block_on(bob.grinder().scrubber().last_report());
+    report.as_ref().map(scrub_report_to_scheme).map(Json)
+}
+
+#[post("/scrub")]
+fn scrub(bob: State<BobServer>, _access: AdminAccess) -> Json<ScrubReport> {
+    let grinder = bob.grinder();
+    let report = runtime().block_on(grinder.scrubber().run_once(grinder.backend()));
+    Json(scrub_report_to_scheme(&report))
+}
+
+fn compaction_report_to_scheme(report: &compaction::CompactionReport) -> CompactionReport {
+    CompactionReport {
+        merged_partitions: report.merged(),
+        created_partitions: report.created(),
+        records: report.records(),
+        dropped_records: report.dropped(),
+        skipped_windows: report.skipped(),
+    }
+}
+
+#[get("/compaction")]
+fn compaction_report(bob: State<BobServer>, _access: ReadAccess) -> Option<Json<CompactionReport>> {
+    let report = runtime().block_on(bob.grinder().compaction().last_report());
+    report.as_ref().map(compaction_report_to_scheme).map(Json)
+}
+
+#[post("/compaction")]
+fn compact(bob: State<BobServer>, _access: AdminAccess) -> Json<CompactionReport> {
+    let grinder = bob.grinder();
+    let report = runtime().block_on(grinder.compaction().run_once(grinder.backend()));
+    Json(compaction_report_to_scheme(&report))
+}
+
+async fn current_key_id(bob: &BobServer) -> Option<u32> {
+    let groups = bob.grinder().backend().vdisks_groups().await?;
+    groups.iter().find_map(PearlGroup::current_key_id)
+}
+
+#[get("/encryption")]
+fn encryption_status(bob: State<BobServer>, _access: ReadAccess) -> Json<EncryptionStatus> {
+    let compaction = bob.grinder().compaction();
+    let (current_key_id, last_reencryption) = runtime().block_on(async {
+        (
+            current_key_id(&bob).await,
+            compaction.last_reencryption().await,
+        )
+    });
+    Json(EncryptionStatus {
+        current_key_id,
+        reencryption_running: compaction.is_reencryption_running(),
+        last_reencryption: last_reencryption.as_ref().map(compaction_report_to_scheme),
+    })
+}
+
+#[post("/encryption/reencrypt")]
+fn reencrypt(bob: State<BobServer>, _access: AdminAccess) -> StatusExt {
+    let grinder = bob.grinder();
+    let key_id = match runtime().block_on(current_key_id(&bob)) {
+        Some(key_id) => key_id,
+        None => {
+            let msg = "encryption is not configured".to_owned();
+            return StatusExt::new(Status::BadRequest, false, msg);
+        }
+    };
+    let compaction = grinder.compaction().clone();
+    if compaction.start_reencryption(grinder.backend().clone()) {
+        let msg = format!("re-encryption with key {} started", key_id);
+        StatusExt::new(Status::Accepted, true, msg)
+    } else {
+        let msg = "re-encryption is already running".to_owned();
+        StatusExt::new(Status::Conflict, false, msg)
+    }
+}
+
 #[delete("/vdisks/<vdisk_id>/partitions/by_timestamp/<timestamp>")]
 fn delete_partition(
     bob: State<BobServer>,
+    _access: AdminAccess,
     vdisk_id: u32,
     timestamp: u64,
 ) -> Result<StatusExt, StatusExt> {
@@ -385,14 +830,236 @@ fn drop_directories(
     }
 }
 
+#[get("/cluster/map")]
+fn cluster_map(bob: State<BobServer>, _access: ReadAccess) -> Result<Json<ClusterMap>, StatusExt> {
+    let mapper = bob.grinder().backend().mapper();
+    let config = serde_yaml::to_string(mapper.cluster_config())
+        .map_err(|e| StatusExt::new(Status::InternalServerError, false, e.to_string()))?;
+    Ok(Json(ClusterMap {
+        version: mapper.version(),
+        config,
+    }))
+}
+
+#[put("/cluster/map", data = "<config>")]
+fn update_cluster_map(
+    bob: State<BobServer>,
+    _access: AdminAccess,
+    config: String,
+) -> Result<StatusExt, StatusExt> {
+    let cluster = ClusterConfig::get_from_string(&config)
+        .map_err(|e| StatusExt::new(Status::BadRequest, false, e))?;
+    let backend = bob.grinder().backend();
+    match runtime().block_on(backend.update_cluster_map(&cluster)) {
+        Ok(version) => Ok(StatusExt::new(
+            Status::Ok,
+            true,
+            format!("cluster map version {} applied", version),
+        )),
+        Err(e) => Err(StatusExt::new(Status::BadRequest, false, e.to_string())),
+    }
+}
+
+#[post("/configuration/reload")]
+fn reload_node_config(bob: State<BobServer>, _access: AdminAccess) -> Result<StatusExt, StatusExt> {
+    match bob.reload_node_config() {
+        Ok(changed) => Ok(StatusExt::new(
+            Status::Ok,
+            true,
+            format!(
+                "node config reloaded, changed fields: {}",
+                changed.join(", ")
+            ),
+        )),
+        Err(e) => Err(StatusExt::new(Status::BadRequest, false, e)),
+    }
+}
+
+#[get("/drain")]
+fn drain_status(bob: State<BobServer>, _access: ReadAccess) -> Json<DrainStatus> {
+    let drain = bob.drain();
+    Json(DrainStatus {
+        state: drain.state().as_str(),
+        in_flight: drain.in_flight(),
+    })
+}
+
+#[post("/drain")]
+fn drain(bob: State<BobServer>, _access: AdminAccess) -> StatusExt {
+    if bob.start_drain() {
+        let msg = "drain started".to_owned();
+        StatusExt::new(Status::Accepted, true, msg)
+    } else {
+        let msg = format!("node is {}", bob.drain().state().as_str());
+        StatusExt::new(Status::Conflict, false, msg)
+    }
+}
+
+#[post("/undrain")]
+fn undrain(bob: State<BobServer>, _access: AdminAccess) -> StatusExt {
+    if bob.undrain() {
+        let msg = "undrain started".to_owned();
+        StatusExt::new(Status::Accepted, true, msg)
+    } else {
+        let msg = format!("node is {}, not drained", bob.drain().state().as_str());
+        StatusExt::new(Status::Conflict, false, msg)
+    }
+}
+
+#[get("/disks")]
+fn disks(bob: State<BobServer>, _access: ReadAccess) -> Json<Vec<DiskStatus>> {
+    let backend = bob.grinder().backend();
+    let mapper = backend.mapper();
+    let disks = backend
+        .disks()
+        .all()
+        .iter()
+        .map(|disk| {
+            let state = disk.state();
+            let watermarks = disk.watermarks();
+            let reason = match &state {
+                DiskState::Failed(reason) => Some(reason.clone()),
+                _ => None,
+            };
+            DiskStatus {
+                name: disk.name().to_owned(),
+                path: disk.path().display().to_string(),
+                state: state.as_str(),
+                reason,
+                vdisks: mapper.get_vdisks_by_disk(disk.name()),
+                operations: disk.operations(),
+                errors: disk.errors(),
+                latency_us: disk.latency().as_micros(),
+                free_space: disk.free_space(),
+                total_space: disk.total_space(),
+                space: disk.space_state().as_str(),
+                soft_watermark: watermarks.soft(),
+                hard_watermark: watermarks.hard(),
+            }
+        })
+        .collect();
+    Json(disks)
+}
+
+#[post("/disks/<disk_name>/stop")]
+fn stop_disk(
+    bob: State<BobServer>,
+    _access: AdminAccess,
+    disk_name: String,
+) -> Result<StatusExt, StatusExt> {
+    let backend = bob.grinder().backend();
+    match runtime().block_on(backend.stop_disk(&disk_name)) {
+        Ok(()) => Ok(StatusExt::new(
+            Status::Ok,
+            true,
+            format!("disk {} is stopped", disk_name),
+        )),
+        Err(e) => Err(StatusExt::new(Status::NotFound, false, e.to_string())),
+    }
+}
+
+#[post("/disks/<disk_name>/start")]
+fn start_disk(
+    bob: State<BobServer>,
+    _access: AdminAccess,
+    disk_name: String,
+) -> Result<StatusExt, StatusExt> {
+    let backend = bob.grinder().backend();
+    if backend.disks().get(&disk_name).is_none() {
+        let msg = format!("disk {} not found", disk_name);
+        return Err(StatusExt::new(Status::NotFound, false, msg));
+    }
+    match runtime().block_on(backend.start_disk(&disk_name)) {
+        Ok(()) => Ok(StatusExt::new(
+            Status::Ok,
+            true,
+            format!("disk {} is started", disk_name),
+        )),
+        Err(e) => Err(StatusExt::new(
+            Status::InternalServerError,
+            false,
+            e.to_string(),
+        )),
+    }
+}
+
+fn migration_status_to_scheme(status: migration::MigrationStatus) -> MigrationStatus {
+    let (state, error) = match status.state() {
+        MigrationState::Running => ("running", None),
+        MigrationState::Done => ("done", None),
+        MigrationState::Incomplete => (
+            "incomplete",
+            Some(format!(
+                "{} holders without keys log are not migrated",
+                status.holders_skipped()
+            )),
+        ),
+        MigrationState::Failed(e) => ("failed", Some(e.clone())),
+    };
+    MigrationStatus {
+        vdisk_id: status.vdisk_id(),
+        disk_name: status.disk_name().to_owned(),
+        source: status.source().to_owned(),
+        state: state.to_owned(),
+        error,
+        holders_total: status.holders_total(),
+        holders_done: status.holders_done(),
+        holders_skipped: status.holders_skipped(),
+        records: status.records(),
+        bytes: status.bytes(),
+    }
+}
+
+#[get("/migrations")]
+fn migrations(bob: State<BobServer>, _access: ReadAccess) -> Json<Vec<MigrationStatus>> {
+    let statuses = bob.grinder().backend().migration().statuses();
+    Json(
+        statuses
+            .into_iter()
+            .map(migration_status_to_scheme)
+            .collect(),
+    )
+}
+
+#[get("/migrations/<vdisk_id>")]
+fn migration_by_vdisk_id(
+    bob: State<BobServer>,
+    _access: ReadAccess,
+    vdisk_id: u32,
+) -> Option<Json<MigrationStatus>> {
+    let status = bob.grinder().backend().migration().status(vdisk_id);
+    status.map(migration_status_to_scheme).map(Json)
+}
+
 #[get("/alien")]
-fn alien(_bob: State<BobServer>) -> &'static str {
-    "alien"
+fn alien(bob: State<BobServer>, _access: ReadAccess) -> Json<Vec<String>> {
+    let backend = bob.grinder().backend();
+    let groups = runtime().block_on(backend.alien_vdisks_groups());
+    let mut names = groups
+        .unwrap_or_default()
+        .iter()
+        .map(|group| group.node_name().to_owned())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+    Json(names)
+}
+
+#[post("/alien/handoff")]
+fn alien_handoff(bob: State<BobServer>, _access: AdminAccess) -> StatusExt {
+    let backend = bob.grinder().backend();
+    let drained = runtime().block_on(Handoff::run_once(backend));
+    StatusExt::new(
+        Status::Ok,
+        true,
+        format!("{} alien partitions handed off to owners", drained),
+    )
 }
 
 #[get("/vdisks/<vdisk_id>/replicas/local/dirs")]
 fn get_local_replica_directories(
     bob: State<BobServer>,
+    _access: ReadAccess,
     vdisk_id: u32,
 ) -> Result<Json<Vec<Dir>>, StatusExt> {
     let vdisk: VDisk = get_vdisk_by_id(&bob, vdisk_id).ok_or_else(|| {
@@ -402,7 +1069,8 @@ fn get_local_replica_directories(
             format!("VDisk {} not found", vdisk_id),
         )
     })?;
-    let local_node_name = bob.grinder().backend().mapper().local_node_name();
+    let mapper = bob.grinder().backend().mapper();
+    let local_node_name = mapper.local_node_name();
     let mut result = vec![];
     for replica in vdisk
         .replicas
@@ -468,6 +1136,40 @@ impl<'r> FromParam<'r> for Action {
     }
 }
 
+fn authorize(request: &Request, required: Role) -> RequestOutcome<(), String> {
+    let bob = match request.guard::<State<BobServer>>() {
+        Outcome::Success(bob) => bob,
+        _ => {
+            let msg = "bob server is not managed by rocket".to_owned();
+            return Outcome::Failure((Status::InternalServerError, msg));
+        }
+    };
+    let header = request.headers().get_one("Authorization");
+    match bob.authenticator().authorize(header, required) {
+        Ok(()) => Outcome::Success(()),
+        Err(e @ AuthError::Unauthenticated) => {
+            Outcome::Failure((Status::Unauthorized, e.to_string()))
+        }
+        Err(e) => Outcome::Failure((Status::Forbidden, e.to_string())),
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for ReadAccess {
+    type Error = String;
+
+    fn from_request(request: &'a Request<'r>) -> RequestOutcome<Self, Self::Error> {
+        authorize(request, Role::Read).map(|_| Self)
+    }
+}
+
+impl<'a, 'r> FromRequest<'a, 'r> for AdminAccess {
+    type Error = String;
+
+    fn from_request(request: &'a Request<'r>) -> RequestOutcome<Self, Self::Error> {
+        authorize(request, Role::Admin).map(|_| Self)
+    }
+}
+
 impl Responder<'_> for StatusExt {
     fn respond_to(self, _: &Request) -> RocketResult<'static> {
         let msg = format!("{{ \"ok\": {}, \"msg\": \"{}\" }}", self.ok, self.msg);
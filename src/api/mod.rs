@@ -15,9 +15,9 @@ pub mod prelude {
         rocket::{
             http::RawStr,
             http::Status,
-            request::{FromParam, Request},
+            request::{FromParam, FromRequest, Outcome as RequestOutcome, Request},
             response::{Responder, Response, Result as RocketResult},
-            Config, Rocket, State,
+            Config, Outcome, Rocket, State,
         },
         rocket_contrib::json::Json,
         server::Server as BobServer,
@@ -2,10 +2,12 @@
 extern crate log;
 
 use bob::grpc::bob_api_client::BobApiClient;
-use bob::grpc::{Blob, BlobKey, BlobMeta, GetRequest, PutRequest};
+use bob::grpc::{Blob, BlobKey, BlobMeta, DeleteRequest, GetRequest, PutOptions, PutRequest};
+use bob::BobKey;
 use clap::{App, Arg, ArgMatches, SubCommand};
 use http::Uri;
 use log::LevelFilter;
+use std::collections::HashMap;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tonic::Request;
 
@@ -19,19 +21,31 @@ async fn main() {
             "put" => {
                 let value = sub_mathes.value_of("size").unwrap();
                 let size = value.parse().expect("size must be usize");
+                let headers = get_headers(sub_mathes);
+                let ttl = sub_mathes
+                    .value_of("ttl")
+                    .map_or(0, |ttl| ttl.parse().expect("ttl must be u64"));
                 info!("PUT key: \"{}\" size: \"{}\"", key, size);
-                put(key, size).await;
+                put(key, size, headers, ttl).await;
             }
             "get" => {
                 info!("GET key:\"{}\" command", key);
                 get(key).await;
             }
+            "head" => {
+                info!("HEAD key:\"{}\" command", key);
+                head(key).await;
+            }
+            "delete" => {
+                info!("DELETE key:\"{}\" command", key);
+                delete(key).await;
+            }
             _ => {}
         }
     }
 }
 
-async fn put(key: u64, size: usize) {
+async fn put(key: BobKey, size: usize, headers: HashMap<String, String>, ttl: u64) {
     let addr: Uri = get_matches()
         .value_of("uri")
         .expect("has default value")
@@ -43,15 +57,24 @@ async fn put(key: u64, size: usize) {
         .duration_since(UNIX_EPOCH)
         .expect("msg: &str")
         .as_secs();
-    let meta = BlobMeta { timestamp };
+    let meta = BlobMeta {
+        timestamp,
+        headers,
+        expires_at: 0,
+    };
     let blob = Blob {
         data: vec![1; size],
         meta: Some(meta),
+        manifest: false,
+        checksum: None,
     };
     let message = PutRequest {
-        key: Some(BlobKey { key }),
+        key: Some(BlobKey::from(key)),
         data: Some(blob),
-        options: None,
+        options: Some(PutOptions {
+            ttl,
+            ..PutOptions::default()
+        }),
     };
     let put_req = Request::new(message);
 
@@ -59,7 +82,7 @@ async fn put(key: u64, size: usize) {
     info!("{:#?}", res);
 }
 
-async fn get(key: u64) {
+async fn get(key: BobKey) {
     let addr: Uri = get_matches()
         .value_of("uri")
         .expect("has default value")
@@ -68,7 +91,7 @@ async fn get(key: u64) {
     let mut client = BobApiClient::connect(addr).await.unwrap();
 
     let message = GetRequest {
-        key: Some(BlobKey { key }),
+        key: Some(BlobKey::from(key)),
         options: None,
     };
     let get_req = Request::new(message);
@@ -88,6 +111,49 @@ async fn get(key: u64) {
     }
 }
 
+async fn head(key: BobKey) {
+    let addr: Uri = get_matches()
+        .value_of("uri")
+        .expect("has default value")
+        .parse()
+        .expect("wrong format of url");
+    let mut client = BobApiClient::connect(addr).await.unwrap();
+
+    let message = GetRequest {
+        key: Some(BlobKey::from(key)),
+        options: None,
+    };
+    let res = client.head(Request::new(message)).await;
+    info!("{:#?}", res);
+}
+
+async fn delete(key: BobKey) {
+    let addr: Uri = get_matches()
+        .value_of("uri")
+        .expect("has default value")
+        .parse()
+        .expect("wrong format of url");
+    let mut client = BobApiClient::connect(addr).await.unwrap();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("msg: &str")
+        .as_secs();
+    let message = DeleteRequest {
+        key: Some(BlobKey::from(key)),
+        meta: Some(BlobMeta {
+            timestamp,
+            headers: HashMap::new(),
+            expires_at: 0,
+        }),
+        options: None,
+    };
+    let delete_req = Request::new(message);
+
+    let res = client.delete(delete_req).await;
+    info!("{:#?}", res);
+}
+
 fn get_matches<'a>() -> ArgMatches<'a> {
     let key_arg = Arg::with_name("key").takes_value(true).required(true);
     let uri_arg = Arg::with_name("uri")
@@ -96,21 +162,49 @@ fn get_matches<'a>() -> ArgMatches<'a> {
     let size_arg = Arg::with_name("size")
         .takes_value(true)
         .default_value("90000");
+    let header_arg = Arg::with_name("header")
+        .short("H")
+        .long("header")
+        .takes_value(true)
+        .multiple(true)
+        .number_of_values(1)
+        .help("blob header in name=value format");
+    let ttl_arg = Arg::with_name("ttl")
+        .long("ttl")
+        .takes_value(true)
+        .help("time to live of the blob in seconds");
     let put_sc = SubCommand::with_name("put")
         .arg(&key_arg)
         .arg(size_arg)
+        .arg(header_arg)
+        .arg(ttl_arg)
         .arg(&uri_arg);
     App::new("bobc")
         .subcommand(put_sc)
-        .subcommand(SubCommand::with_name("get").arg(key_arg).arg(uri_arg))
+        .subcommand(SubCommand::with_name("get").arg(&key_arg).arg(&uri_arg))
+        .subcommand(SubCommand::with_name("head").arg(&key_arg).arg(&uri_arg))
+        .subcommand(SubCommand::with_name("delete").arg(key_arg).arg(uri_arg))
         .get_matches()
 }
 
-fn get_key_value(matches: &'_ ArgMatches<'_>) -> u64 {
+fn get_key_value(matches: &'_ ArgMatches<'_>) -> BobKey {
     matches
         .value_of("key")
         .expect("key arg is required")
-        .to_string()
         .parse()
-        .expect("key must be u64")
+        .expect("key must be u64 or hex with 0x prefix")
+}
+
+fn get_headers(matches: &'_ ArgMatches<'_>) -> HashMap<String, String> {
+    matches
+        .values_of("header")
+        .into_iter()
+        .flatten()
+        .map(|header| {
+            let mut parts = header.splitn(2, '=');
+            let name = parts.next().expect("splitn returns at least one item");
+            let value = parts.next().expect("header must be in name=value format");
+            (name.to_owned(), value.to_owned())
+        })
+        .collect()
 }
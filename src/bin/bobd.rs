@@ -1,10 +1,14 @@
+use bob::auth::Authenticator;
 use bob::client::Factory;
 use bob::configs::cluster::Cluster as ClusterConfig;
 use bob::grinder::Grinder;
 use bob::grpc::bob_api_server::BobApiServer;
+use bob::grpc::bob_repair_server::BobRepairServer;
 use bob::mapper::Virtual;
 use bob::metrics;
+use bob::reload::ConfigReloader;
 use bob::server::Server as BobServer;
+use bob::tls;
 use clap::{App, Arg};
 use std::net::ToSocketAddrs;
 use tonic::transport::Server;
@@ -75,7 +79,9 @@ async fn main() {
     println!("Node config: {:?}", node_config);
     let node = cluster.get(node_config).unwrap();
 
-    log4rs::init_file(node.log_config(), Default::default()).unwrap();
+    let log_config = log4rs::config::load_config_file(node.log_config(), Default::default())
+        .expect("load log config");
+    let log_handle = log4rs::init_config(log_config).unwrap();
 
     let mut mapper = Virtual::new(&node, &cluster).await;
     let mut addr = node.bind().to_socket_addrs().unwrap().next().unwrap();
@@ -94,26 +100,54 @@ async fn main() {
 
     let metrics = metrics::init_counters(&node, &addr.to_string());
 
-    let bob = BobServer::new(Grinder::new(mapper, &node));
+    let authenticator = Authenticator::new(node.authentication());
+    let reloader = ConfigReloader::new(node_config.to_owned(), node.clone(), Some(log_handle));
+    let bob =
+        BobServer::new(Grinder::new(mapper, &node), authenticator).with_config_reloader(reloader);
 
     info!("Start backend");
-    bob.run_backend().await.unwrap();
+    // vdisks serve operations as soon as they are loaded, so the node doesn't wait for backend
+    let backend = bob.clone();
+    tokio::spawn(async move {
+        if let Err(e) = backend.run_backend().await {
+            error!("backend start failed: {}", e);
+            std::process::exit(1);
+        }
+        info!("Backend started");
+    });
     info!("Start API server");
     let http_api_port = matches
         .value_of("http_api_port")
         .and_then(|v| v.parse().ok())
         .expect("expect http_api_port port");
-    bob.run_api_server(http_api_port);
+    bob.run_api_server(http_api_port, node.tls())
+        .expect("run http api server");
 
     create_signal_handlers(&bob).unwrap();
-
-    let factory = Factory::new(node.operation_timeout(), metrics);
+    spawn_reload_handler(&bob, cluster_config.to_owned()).unwrap();
+
+    let client_tls = node
+        .tls()
+        .map(|config| tls::client_config(config).expect("client tls config"));
+    let factory = Factory::new(
+        node.operation_timeout(),
+        metrics,
+        client_tls,
+        bob.authenticator().node_credentials(),
+    );
     bob.run_periodic_tasks(factory);
-    let new_service = BobApiServer::new(bob);
-
-    Server::builder()
-        .tcp_nodelay(true)
+    let interceptor = bob.authenticator().interceptor();
+    let repair_service = BobRepairServer::with_interceptor(bob.clone(), interceptor.clone());
+    let new_service = BobApiServer::with_interceptor(bob, interceptor);
+
+    let mut server = Server::builder().tcp_nodelay(true);
+    if let Some(config) = node.tls() {
+        let tls = tls::server_config(config).expect("server tls config");
+        server = server.tls_config(tls).expect("set server tls config");
+    }
+    server
         .add_service(new_service)
+        .add_service(repair_service)
         .serve(addr)
         .await
         .unwrap();
@@ -143,3 +177,30 @@ fn spawn_signal_handler(
     });
     Ok(())
 }
+
+/// Reloads cluster config on SIGHUP and applies it, if its version was increased, then
+/// reloads node config and applies fields, which can be changed without restart
+fn spawn_reload_handler(
+    server: &BobServer,
+    path: String,
+) -> Result<(), Box<dyn std::error::Error>> {
+    use tokio::signal::unix::{signal, SignalKind};
+    let mut task = signal(SignalKind::hangup())?;
+    let server = server.clone();
+    tokio::spawn(async move {
+        while task.recv().await.is_some() {
+            info!("reload cluster config {}", path);
+            match ClusterConfig::try_get(&path) {
+                Ok(cluster) => match server.update_cluster_map(&cluster).await {
+                    Ok(version) => info!("cluster map version {} applied", version),
+                    Err(e) => error!("cluster map is not applied: {}", e),
+                },
+                Err(e) => error!("can't read cluster config: {}", e),
+            }
+            if let Err(e) = server.reload_node_config() {
+                error!("node config is not reloaded: {}", e);
+            }
+        }
+    });
+    Ok(())
+}
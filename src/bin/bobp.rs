@@ -1,6 +1,6 @@
 use bob::grpc::{
-    bob_api_client::BobApiClient, ExistRequest, GetOptions, GetRequest, GetSource, PutOptions,
-    PutRequest,
+    bob_api_client::BobApiClient, BatchBlob, ExistRequest, GetBatchRequest, GetOptions, GetRequest,
+    GetSource, PutBatchRequest, PutOptions, PutRequest,
 };
 use bob::grpc::{Blob, BlobKey, BlobMeta};
 use clap::{App, Arg, ArgMatches};
@@ -77,6 +77,7 @@ struct TaskConfig {
     payload_size: u64,
     direct: bool,
     measure_time: bool,
+    batch_size: u64,
 }
 
 impl TaskConfig {
@@ -87,6 +88,7 @@ impl TaskConfig {
             payload_size: matches.value_or_default("payload"),
             direct: matches.is_present("direct"),
             measure_time: false,
+            batch_size: matches.value_or_default("batch"),
         }
     }
 
@@ -107,6 +109,7 @@ impl TaskConfig {
                 remote_nodes: vec![],
                 force_node: true,
                 overwrite: false,
+                ttl: 0,
             })
         } else {
             None
@@ -125,6 +128,9 @@ impl Debug for TaskConfig {
             "payload size: {}, count: {}",
             self.payload_size, self.count
         )?;
+        if self.batch_size > 1 {
+            write!(f, ", batch size: {}", self.batch_size)?;
+        }
         if self.direct {
             write!(f, ", direct")
         } else {
@@ -445,6 +451,9 @@ fn finite_or_default(value: f64) -> f64 {
 }
 
 async fn get_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Statistics>) {
+    if task_conf.batch_size > 1 {
+        return get_batch_worker(net_conf, task_conf, stat).await;
+    }
     let mut client = net_conf.build_client().await;
 
     let options = task_conf.find_get_options();
@@ -452,7 +461,10 @@ async fn get_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Statis
     let measure_time = task_conf.is_time_measurement_thread();
     for i in task_conf.low_idx..upper_idx {
         let request = Request::new(GetRequest {
-            key: Some(BlobKey { key: i }),
+            key: Some(BlobKey {
+                key: i,
+                bytes_key: vec![],
+            }),
             options: options.clone(),
         });
         let res = if measure_time {
@@ -475,7 +487,59 @@ async fn get_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Statis
     }
 }
 
+async fn get_batch_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Statistics>) {
+    let mut client = net_conf.build_client().await;
+
+    let options = task_conf.find_get_options();
+    let upper_idx = task_conf.low_idx + task_conf.count;
+    let measure_time = task_conf.is_time_measurement_thread();
+    for low_idx in (task_conf.low_idx..upper_idx).step_by(task_conf.batch_size as usize) {
+        let high_idx = upper_idx.min(low_idx + task_conf.batch_size);
+        let request = Request::new(GetBatchRequest {
+            keys: (low_idx..high_idx)
+                .map(|i| BlobKey {
+                    key: i,
+                    bytes_key: vec![],
+                })
+                .collect(),
+            options: options.clone(),
+        });
+        let res = if measure_time {
+            let start = Instant::now();
+            let res = client.get_batch(request).await;
+            stat.save_single_thread_get_time(&start.elapsed());
+            res
+        } else {
+            client.get_batch(request).await
+        };
+        match res {
+            Err(status) => {
+                for _ in low_idx..high_idx {
+                    stat.save_get_error(Status::new(status.code(), status.message()))
+                        .await;
+                }
+            }
+            Ok(response) => {
+                for result in response.into_inner().results {
+                    if let Some(error) = result.error {
+                        stat.save_get_error(Status::new(Code::from(error.code), error.desc))
+                            .await;
+                    } else if let Some(blob) = result.blob {
+                        stat.get_size_bytes
+                            .fetch_add(blob.data.len() as u64, Ordering::SeqCst);
+                    }
+                }
+            }
+        }
+        stat.get_total
+            .fetch_add(high_idx - low_idx, Ordering::SeqCst);
+    }
+}
+
 async fn put_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Statistics>) {
+    if task_conf.batch_size > 1 {
+        return put_batch_worker(net_conf, task_conf, stat).await;
+    }
     let mut client = net_conf.build_client().await;
 
     let options: Option<PutOptions> = task_conf.find_put_options();
@@ -483,7 +547,10 @@ async fn put_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Statis
     let upper_idx = task_conf.low_idx + task_conf.count;
     for i in task_conf.low_idx..upper_idx {
         let blob = create_blob(&task_conf);
-        let key = BlobKey { key: i };
+        let key = BlobKey {
+            key: i,
+            bytes_key: vec![],
+        };
         let req = Request::new(PutRequest {
             key: Some(key),
             data: Some(blob),
@@ -502,9 +569,75 @@ async fn put_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Statis
         }
         stat.put_total.fetch_add(1, Ordering::SeqCst);
     }
+    verify_puts(&mut client, &task_conf, &stat).await;
+}
+
+async fn put_batch_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<Statistics>) {
+    let mut client = net_conf.build_client().await;
+
+    let options: Option<PutOptions> = task_conf.find_put_options();
+    let measure_time = task_conf.is_time_measurement_thread();
+    let upper_idx = task_conf.low_idx + task_conf.count;
+    for low_idx in (task_conf.low_idx..upper_idx).step_by(task_conf.batch_size as usize) {
+        let high_idx = upper_idx.min(low_idx + task_conf.batch_size);
+        let req = Request::new(PutBatchRequest {
+            blobs: (low_idx..high_idx)
+                .map(|i| BatchBlob {
+                    key: Some(BlobKey {
+                        key: i,
+                        bytes_key: vec![],
+                    }),
+                    blob: Some(create_blob(&task_conf)),
+                    error: None,
+                })
+                .collect(),
+            options: options.clone(),
+        });
+        let res = if measure_time {
+            let start = Instant::now();
+            let res = client.put_batch(req).await;
+            stat.save_single_thread_put_time(&start.elapsed());
+            res
+        } else {
+            client.put_batch(req).await
+        };
+        match res {
+            Err(status) => {
+                for _ in low_idx..high_idx {
+                    stat.save_put_error(Status::new(status.code(), status.message()))
+                        .await;
+                }
+            }
+            Ok(response) => {
+                for error in response
+                    .into_inner()
+                    .statuses
+                    .into_iter()
+                    .flat_map(|s| s.error)
+                {
+                    stat.save_put_error(Status::new(Code::from(error.code), error.desc))
+                        .await;
+                }
+            }
+        }
+        stat.put_total
+            .fetch_add(high_idx - low_idx, Ordering::SeqCst);
+    }
+    verify_puts(&mut client, &task_conf, &stat).await;
+}
+
+async fn verify_puts(
+    client: &mut BobApiClient<Channel>,
+    task_conf: &TaskConfig,
+    stat: &Statistics,
+) {
+    let upper_idx = task_conf.low_idx + task_conf.count;
     let req = Request::new(ExistRequest {
         keys: (task_conf.low_idx..upper_idx)
-            .map(|i| BlobKey { key: i })
+            .map(|i| BlobKey {
+                key: i,
+                bytes_key: vec![],
+            })
             .collect(),
         options: task_conf.find_get_options(),
     });
@@ -532,7 +665,10 @@ async fn ping_pong_worker(net_conf: NetConfig, task_conf: TaskConfig, stat: Arc<
     let upper_idx = task_conf.low_idx + task_conf.count;
     for i in task_conf.low_idx..upper_idx {
         let blob = create_blob(&task_conf);
-        let key = BlobKey { key: i };
+        let key = BlobKey {
+            key: i,
+            bytes_key: vec![],
+        };
         let put_request = Request::new(PutRequest {
             key: Some(key.clone()),
             data: Some(blob),
@@ -591,6 +727,7 @@ fn spawn_workers(
                 payload_size: task_conf.payload_size,
                 direct: task_conf.direct,
                 measure_time: i == 0,
+                batch_size: task_conf.batch_size,
             };
             match benchmark_conf.behavior {
                 Behavior::Put => tokio::spawn(put_worker(nc, tc, stat_inner)),
@@ -618,10 +755,14 @@ fn create_blob(task_conf: &TaskConfig) -> Blob {
             .duration_since(UNIX_EPOCH)
             .expect("msg: &str")
             .as_secs(),
+        headers: HashMap::new(),
+        expires_at: 0,
     };
     Blob {
         data: vec![0_u8; task_conf.payload_size as usize],
         meta: Some(meta),
+        manifest: false,
+        checksum: None,
     }
 }
 
@@ -701,6 +842,13 @@ fn get_matches() -> ArgMatches<'static> {
                 .takes_value(true)
                 .long("amount"),
         )
+        .arg(
+            Arg::with_name("batch")
+                .help("count of keys in batch get and put requests")
+                .takes_value(true)
+                .long("batch")
+                .default_value("1"),
+        )
         .arg(
             Arg::with_name("verify")
                 .help("verify results of put requests")
@@ -189,7 +189,7 @@ impl TestClusterConfiguration {
     fn create_cluster(&self) -> Cluster {
         let nodes = self.create_nodes();
         let vdisks = self.create_vdisks();
-        Cluster::new(nodes, vdisks)
+        Cluster::new(nodes, vdisks, 8, 0)
     }
 
     fn create_named_node_configuration(&self, node_index: u32) -> (String, Node) {
@@ -200,19 +200,32 @@ impl TestClusterConfiguration {
             "3sec".to_string(),
             "5000ms".to_string(),
             "10000ms".to_string(),
+            "1m".to_string(),
+            None,
+            None,
+            None,
+            "1h".to_string(),
+            None,
             "quorum".to_string(),
+            "one".to_string(),
             "pearl".to_string(),
             Some(self.get_pearl_config(node_index)),
             Some(MetricsConfig::new(
                 "bob".to_string(),
-                "127.0.0.1:2003".to_string(),
+                Some("127.0.0.1:2003".to_string()),
+                None,
             )),
+            None,
+            None,
+            None,
+            None,
             RefCell::default(),
             RefCell::default(),
             self.cleanup_interval.clone(),
             self.open_blobs_soft_limit,
             self.open_blobs_hard_limit,
             self.init_par_degree.unwrap_or(1),
+            1024 * 1024,
         );
         (Self::get_node_name(node_index), node)
     }
@@ -239,6 +252,9 @@ impl TestClusterConfiguration {
             ),
             10,
             true,
+            None,
+            None,
+            None,
         )
     }
 
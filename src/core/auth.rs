@@ -0,0 +1,514 @@
+use super::prelude::*;
+use crate::core::configs::{AuthConfig, Role};
+use ring::{constant_time::verify_slices_are_equal, hmac};
+use tonic::{
+    metadata::{AsciiMetadataValue, MetadataMap},
+    Interceptor,
+};
+
+const AUTHORIZATION: &str = "authorization";
+// role of the authenticated client, set by the server interceptor in place of the credentials
+const ROLE: &str = "x-bob-role";
+// set by the server interceptor for requests of the node user
+const NODE: &str = "x-bob-node";
+
+/// Credentials of the api client, sent in the authorization header.
+#[derive(Clone, PartialEq)]
+pub enum Credentials {
+    /// Static token, sent as `Bearer <token>`
+    Token(String),
+    /// Name and password, sent as `Basic <base64 of name:password>`
+    Basic {
+        /// Name of the user
+        name: String,
+        /// Password of the user
+        password: String,
+    },
+}
+
+impl Credentials {
+    fn parse(header: &str) -> Option<Self> {
+        if let Some(token) = header.strip_prefix("Bearer ") {
+            Some(Credentials::Token(token.trim().to_owned()))
+        } else {
+            let encoded = header.strip_prefix("Basic ")?;
+            let decoded = String::from_utf8(base64::decode(encoded.trim()).ok()?).ok()?;
+            let (name, password) = decoded.split_at(decoded.find(':')?);
+            Some(Credentials::Basic {
+                name: name.to_owned(),
+                password: password[1..].to_owned(),
+            })
+        }
+    }
+
+    /// Value of the authorization header
+    #[must_use]
+    pub fn header(&self) -> String {
+        match self {
+            Credentials::Token(token) => format!("Bearer {}", token),
+            Credentials::Basic { name, password } => {
+                format!("Basic {}", base64::encode(format!("{}:{}", name, password)))
+            }
+        }
+    }
+    /// Signature of the cluster map, nodes accept maps only from nodes with the same
+    /// node credentials
+    pub(crate) fn sign_cluster_map(&self, version: u64, config: &str) -> Vec<u8> {
+        let message = Self::cluster_map_message(version, config);
+        hmac::sign(&self.cluster_map_key(), &message)
+            .as_ref()
+            .to_vec()
+    }
+
+    pub(crate) fn verify_cluster_map(&self, version: u64, config: &str, signature: &[u8]) -> bool {
+        let message = Self::cluster_map_message(version, config);
+        hmac::verify(&self.cluster_map_key(), &message, signature).is_ok()
+    }
+
+    fn cluster_map_key(&self) -> hmac::Key {
+        hmac::Key::new(hmac::HMAC_SHA256, self.header().as_bytes())
+    }
+
+    fn cluster_map_message(version: u64, config: &str) -> Vec<u8> {
+        let mut message = version.to_be_bytes().to_vec();
+        message.extend_from_slice(config.as_bytes());
+        message
+    }
+}
+
+impl Debug for Credentials {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        match self {
+            Credentials::Token(_) => f.debug_tuple("Token").field(&"***").finish(),
+            Credentials::Basic { name, .. } => f
+                .debug_struct("Basic")
+                .field("name", name)
+                .field("password", &"***")
+                .finish(),
+        }
+    }
+}
+
+#[derive(Debug, ErrorTrait)]
+pub(crate) enum AuthError {
+    #[error("credentials are missing or invalid")]
+    Unauthenticated,
+    #[error("{required} role is required, client has {role}")]
+    Forbidden { role: Role, required: Role },
+}
+
+impl From<AuthError> for Status {
+    fn from(e: AuthError) -> Self {
+        match e {
+            AuthError::Unauthenticated => Status::unauthenticated(e.to_string()),
+            AuthError::Forbidden { .. } => Status::permission_denied(e.to_string()),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct User {
+    name: String,
+    role: Role,
+    token: Option<String>,
+    password: Option<String>,
+    node: bool,
+}
+
+impl User {
+    fn matches(&self, credentials: &Credentials) -> bool {
+        let equal = |expected: &Option<String>, actual: &str| {
+            expected.as_ref().map_or(false, |expected| {
+                verify_slices_are_equal(expected.as_bytes(), actual.as_bytes()).is_ok()
+            })
+        };
+        match credentials {
+            Credentials::Token(token) => equal(&self.token, token),
+            Credentials::Basic { name, password } => {
+                *name == self.name && equal(&self.password, password)
+            }
+        }
+    }
+}
+
+/// Checks credentials of the api clients against the users from the node config. Every client
+/// has admin role if authentication isn't configured.
+#[derive(Clone, Default)]
+pub struct Authenticator {
+    users: Option<Arc<Vec<User>>>,
+    node_credentials: Option<Credentials>,
+}
+
+impl Authenticator {
+    /// Creates authenticator with users from the config, access isn't restricted if not set
+    #[must_use]
+    pub fn new(config: Option<&AuthConfig>) -> Self {
+        config.map_or_else(Self::default, |config| {
+            let users = config
+                .users()
+                .iter()
+                .map(|user| User {
+                    name: user.name().to_owned(),
+                    role: user.role(),
+                    token: user.token().map(ToOwned::to_owned),
+                    password: user.password().map(ToOwned::to_owned),
+                    node: user.name() == config.node_user(),
+                })
+                .collect::<Vec<_>>();
+            let node_credentials = users.iter().find(|user| user.node).map(|user| {
+                match (&user.token, &user.password) {
+                    (Some(token), _) => Credentials::Token(token.clone()),
+                    (None, password) => Credentials::Basic {
+                        name: user.name.clone(),
+                        password: password.clone().unwrap_or_default(),
+                    },
+                }
+            });
+            Self {
+                users: Some(Arc::new(users)),
+                node_credentials,
+            }
+        })
+    }
+
+    /// Credentials for requests to other nodes
+    #[must_use]
+    pub fn node_credentials(&self) -> Option<Credentials> {
+        self.node_credentials.clone()
+    }
+
+    /// Interceptor of the grpc services, which rejects requests with invalid credentials.
+    /// Credentials are replaced with the role of the client, which is checked by each method.
+    #[must_use]
+    pub fn interceptor(&self) -> Interceptor {
+        let authenticator = self.clone();
+        Interceptor::new(move |mut request: Request<()>| {
+            let (role, node) = authenticator.identify(header(request.metadata()))?;
+            let role = role
+                .to_string()
+                .parse::<AsciiMetadataValue>()
+                .map_err(|e| Status::internal(e.to_string()))?;
+            let metadata = request.metadata_mut();
+            metadata.remove(AUTHORIZATION);
+            // role and node mark sent by client are overwritten
+            metadata.insert(ROLE, role);
+            metadata.remove(NODE);
+            if node {
+                metadata.insert(NODE, AsciiMetadataValue::from_static("true"));
+            }
+            Ok(request)
+        })
+    }
+
+    pub(crate) fn authenticate(&self, header: Option<&str>) -> Result<Role, AuthError> {
+        self.identify(header).map(|(role, _)| role)
+    }
+
+    // role of the client and whether it's the node user
+    fn identify(&self, header: Option<&str>) -> Result<(Role, bool), AuthError> {
+        let users = match &self.users {
+            Some(users) => users,
+            None => return Ok((Role::Admin, true)),
+        };
+        let credentials = header
+            .and_then(Credentials::parse)
+            .ok_or(AuthError::Unauthenticated)?;
+        users
+            .iter()
+            .find(|user| user.matches(&credentials))
+            .map(|user| (user.role, user.node))
+            .ok_or(AuthError::Unauthenticated)
+    }
+
+    pub(crate) fn authorize(&self, header: Option<&str>, required: Role) -> Result<(), AuthError> {
+        Self::check_role(self.authenticate(header)?, required)
+    }
+
+    /// Checks role, which is set by the [`Authenticator::interceptor`], so credentials aren't
+    /// verified again by each method
+    pub(crate) fn authorize_request<T>(
+        &self,
+        request: &Request<T>,
+        required: Role,
+    ) -> Result<(), Status> {
+        self.request_role(request)
+            .ok_or(AuthError::Unauthenticated)
+            .and_then(|role| Self::check_role(role, required))
+            .map_err(|e| {
+                debug!("request rejected: {}", e);
+                e.into()
+            })
+    }
+
+    /// Checks that request is sent by other node or by admin, e.g. only they can write
+    /// manifests and expiration of replicas, which are rejected from other clients
+    pub(crate) fn is_node_request<T>(&self, request: &Request<T>) -> bool {
+        self.users.is_none()
+            || request.metadata().contains_key(NODE)
+            || self.request_role(request) == Some(Role::Admin)
+    }
+
+    fn request_role<T>(&self, request: &Request<T>) -> Option<Role> {
+        if self.users.is_some() {
+            request
+                .metadata()
+                .get(ROLE)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|role| role.parse().ok())
+        } else {
+            Some(Role::Admin)
+        }
+    }
+
+    fn check_role(role: Role, required: Role) -> Result<(), AuthError> {
+        if role >= required {
+            Ok(())
+        } else {
+            Err(AuthError::Forbidden { role, required })
+        }
+    }
+}
+
+impl Debug for Authenticator {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("Authenticator")
+            .field("users", &self.users.as_ref().map(|users| users.len()))
+            .field("node_credentials", &self.node_credentials)
+            .finish()
+    }
+}
+
+/// Client interceptor, which adds credentials to every request
+pub(crate) fn client_interceptor(credentials: &Credentials) -> Result<Interceptor, String> {
+    let value = credentials
+        .header()
+        .parse::<AsciiMetadataValue>()
+        .map_err(|e| format!("invalid credentials: {}", e))?;
+    Ok(Interceptor::new(move |mut request: Request<()>| {
+        request.metadata_mut().insert(AUTHORIZATION, value.clone());
+        Ok(request)
+    }))
+}
+
+fn header(metadata: &MetadataMap) -> Option<&str> {
+    metadata
+        .get(AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{configs::UserConfig, test_utils};
+    use crate::grpc::{
+        bob_api_client::BobApiClient, bob_api_server::BobApiServer, Blob, BlobKey, BlobMeta, Null,
+        PutOptions, PutRequest,
+    };
+    use crate::server::Server as BobServer;
+    use tonic::{transport::Channel, Code};
+
+    fn authenticator() -> Authenticator {
+        let user = |name: &str, role: &str, token: Option<&str>, password: Option<&str>| {
+            UserConfig::new(
+                name.to_owned(),
+                role.to_owned(),
+                token.map(ToOwned::to_owned),
+                password.map(ToOwned::to_owned),
+            )
+        };
+        let config = AuthConfig::new(
+            vec![
+                user("node", "write", Some("node-token"), None),
+                user("writer", "write", Some("write-token"), None),
+                user("reader", "read", Some("read-token"), None),
+                user("operator", "admin", None, Some("secret")),
+            ],
+            "node".to_owned(),
+        );
+        Authenticator::new(Some(&config))
+    }
+
+    fn basic(name: &str, password: &str) -> String {
+        Credentials::Basic {
+            name: name.to_owned(),
+            password: password.to_owned(),
+        }
+        .header()
+    }
+
+    #[test]
+    fn authenticates_token_and_password() {
+        let auth = authenticator();
+        let role = auth.authenticate(Some("Bearer read-token")).unwrap();
+        assert_eq!(role, Role::Read);
+        let role = auth
+            .authenticate(Some(&basic("operator", "secret")))
+            .unwrap();
+        assert_eq!(role, Role::Admin);
+        assert_eq!(
+            auth.node_credentials(),
+            Some(Credentials::Token("node-token".to_owned()))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_credentials() {
+        let auth = authenticator();
+        for header in &[
+            None,
+            Some("Bearer wrong"),
+            Some("read-token"),
+            Some("Basic not-base64"),
+            Some(basic("reader", "read-token").as_str()),
+            Some(basic("operator", "wrong").as_str()),
+        ] {
+            assert!(matches!(
+                auth.authenticate(*header),
+                Err(AuthError::Unauthenticated)
+            ));
+        }
+    }
+
+    #[test]
+    fn checks_role_hierarchy() {
+        let auth = authenticator();
+        assert!(auth
+            .authorize(Some("Bearer read-token"), Role::Read)
+            .is_ok());
+        assert!(matches!(
+            auth.authorize(Some("Bearer read-token"), Role::Write),
+            Err(AuthError::Forbidden {
+                role: Role::Read,
+                required: Role::Write
+            })
+        ));
+        assert!(auth
+            .authorize(Some("Bearer node-token"), Role::Write)
+            .is_ok());
+        assert!(auth
+            .authorize(Some("Bearer node-token"), Role::Admin)
+            .is_err());
+        let admin = basic("operator", "secret");
+        assert!(auth.authorize(Some(&admin), Role::Admin).is_ok());
+    }
+
+    #[test]
+    fn cluster_map_signed_with_node_credentials() {
+        let node = authenticator().node_credentials().unwrap();
+        let signature = node.sign_cluster_map(1, "config");
+        assert!(node.verify_cluster_map(1, "config", &signature));
+        assert!(!node.verify_cluster_map(2, "config", &signature));
+        assert!(!node.verify_cluster_map(1, "changed", &signature));
+        let other = Credentials::Token("other-token".to_owned());
+        assert!(!other.verify_cluster_map(1, "config", &signature));
+        assert!(!node.verify_cluster_map(1, "config", &[]));
+    }
+
+    #[test]
+    fn disabled_authentication_allows_everything() {
+        let auth = Authenticator::new(None);
+        assert_eq!(auth.authenticate(None).unwrap(), Role::Admin);
+        assert!(auth.node_credentials().is_none());
+    }
+
+    async fn serve_grpc() -> SocketAddr {
+        let grinder = test_utils::single_node_grinder().await;
+        let auth = authenticator();
+        let service = BobApiServer::with_interceptor(
+            BobServer::new(grinder, auth.clone()),
+            auth.interceptor(),
+        );
+        test_utils::serve_grpc(service, None).await
+    }
+
+    async fn client(
+        address: SocketAddr,
+        credentials: Option<Credentials>,
+    ) -> BobApiClient<Channel> {
+        let channel = Endpoint::from_shared(format!("http://{}", address))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        match credentials {
+            Some(credentials) => {
+                BobApiClient::with_interceptor(channel, client_interceptor(&credentials).unwrap())
+            }
+            None => BobApiClient::new(channel),
+        }
+    }
+
+    #[tokio::test]
+    async fn grpc_checks_credentials_and_role() {
+        let address = serve_grpc().await;
+
+        let mut anonymous = client(address, None).await;
+        let res = anonymous.ping(Request::new(Null {})).await;
+        assert_eq!(res.unwrap_err().code(), Code::Unauthenticated);
+
+        let reader = Credentials::Token("read-token".to_owned());
+        let mut reader = client(address, Some(reader)).await;
+        assert!(reader.ping(Request::new(Null {})).await.is_ok());
+        let put = PutRequest {
+            key: Some(BlobKey {
+                key: 1,
+                bytes_key: vec![],
+            }),
+            data: None,
+            options: None,
+        };
+        let res = reader.put(Request::new(put.clone())).await;
+        assert_eq!(res.unwrap_err().code(), Code::PermissionDenied);
+
+        let mut forged = Request::new(put);
+        forged
+            .metadata_mut()
+            .insert(ROLE, AsciiMetadataValue::from_static("admin"));
+        let res = reader.put(forged).await;
+        assert_eq!(res.unwrap_err().code(), Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn grpc_accepts_replica_records_only_from_node() {
+        let address = serve_grpc().await;
+        let put = |key, manifest, expires_at| PutRequest {
+            key: Some(BlobKey {
+                key,
+                bytes_key: vec![],
+            }),
+            data: Some(Blob {
+                data: vec![],
+                meta: Some(BlobMeta {
+                    timestamp: 1,
+                    headers: HashMap::new(),
+                    expires_at,
+                }),
+                manifest,
+                checksum: None,
+            }),
+            options: Some(PutOptions {
+                remote_nodes: vec![],
+                force_node: true,
+                overwrite: false,
+                ttl: 0,
+            }),
+        };
+        let writer = Credentials::Token("write-token".to_owned());
+        let mut writer = client(address, Some(writer)).await;
+        let node = Credentials::Token("node-token".to_owned());
+        let mut node = client(address, Some(node)).await;
+
+        // manifest and expiration of the replica
+        for put in &[put(1, true, 0), put(2, false, u64::MAX)] {
+            let res = writer.put(Request::new(put.clone())).await;
+            assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+            let mut forged = Request::new(put.clone());
+            forged
+                .metadata_mut()
+                .insert(NODE, AsciiMetadataValue::from_static("true"));
+            let res = writer.put(forged).await;
+            assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+            assert!(node.put(Request::new(put.clone())).await.is_ok());
+        }
+    }
+}
@@ -52,7 +52,7 @@ impl Operation {
         Self {
             vdisk_id: self.vdisk_id,
             disk_path: None,
-            remote_node_name: Some(self.remote_node_name.as_ref().unwrap().to_owned()),
+            remote_node_name: self.remote_node_name.clone(),
         }
     }
 
@@ -85,6 +85,18 @@ pub(crate) trait BackendStorage: Debug {
     async fn exist(&self, op: Operation, keys: &[BobKey]) -> Result<Vec<bool>, Error>;
     async fn exist_alien(&self, op: Operation, keys: &[BobKey]) -> Result<Vec<bool>, Error>;
 
+    /// Version of the key without data, storages which can't read it separately read the
+    /// whole record
+    async fn head(&self, op: Operation, key: BobKey) -> Result<BobHead, Error> {
+        self.get(op, key).await.map(|data| BobHead::from(&data))
+    }
+
+    async fn head_alien(&self, op: Operation, key: BobKey) -> Result<BobHead, Error> {
+        self.get_alien(op, key)
+            .await
+            .map(|data| BobHead::from(&data))
+    }
+
     async fn run(&self) -> Result<()> {
         gauge!(BACKEND_STATE, BACKEND_STARTING);
         let result = self.run_backend().await;
@@ -100,27 +112,70 @@ pub(crate) trait BackendStorage: Debug {
         0
     }
 
+    /// Unloads least recently used storages to fit the index memory limit
+    async fn unload_cold_holders(&self) {}
+
     async fn shutdown(&self);
 
-    fn vdisks_groups(&self) -> Option<&[Group]> {
+    /// Opens storages closed by shutdown
+    async fn reopen(&self) -> Result<()> {
+        Ok(())
+    }
+
+    async fn vdisks_groups(&self) -> Option<Vec<Group>> {
+        None
+    }
+
+    async fn alien_vdisks_groups(&self) -> Option<Vec<Group>> {
+        None
+    }
+
+    /// Groups of vdisks, which were moved from the local node by the cluster map
+    async fn released_vdisks_groups(&self) -> Option<Vec<Group>> {
         None
     }
+
+    /// Closes and removes storage of the released vdisk
+    async fn drop_released_group(&self, _vdisk_id: VDiskID, _disk_name: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// Creates storages for vdisks, which were assigned to the local node by the new cluster
+    /// map, and releases storages of vdisks, which were moved to other nodes.
+    async fn apply_cluster_map(&self, _mapper: &Virtual) -> Result<()> {
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
 pub(crate) struct Backend {
     inner: Arc<dyn BackendStorage + Send + Sync>,
-    mapper: Arc<Virtual>,
+    mapper: Arc<ClusterMap>,
+    migration: Migration,
+    disks: Arc<Disks>,
 }
 
 impl Backend {
-    pub(crate) fn new(mapper: Arc<Virtual>, config: &NodeConfig) -> Self {
+    pub(crate) fn new(mapper: Arc<ClusterMap>, config: &NodeConfig) -> Self {
+        // only disks of the pearl backend are tracked, other backends don't use them
+        let disks = if config.backend_type() == BackendType::Pearl {
+            let free_space = config.free_space();
+            Arc::new(Disks::new(mapper.current().local_disks(), free_space))
+        } else {
+            Arc::default()
+        };
         let inner: Arc<dyn BackendStorage + Send + Sync + 'static> = match config.backend_type() {
-            BackendType::InMemory => Arc::new(MemBackend::new(&mapper)),
+            BackendType::InMemory => Arc::new(MemBackend::new(&mapper.current())),
             BackendType::Stub => Arc::new(StubBackend {}),
-            BackendType::Pearl => Arc::new(Pearl::new(mapper.clone(), config)),
+            BackendType::Pearl => Arc::new(Pearl::new(mapper.clone(), config, disks.clone())),
         };
-        Self { inner, mapper }
+        let migration = Migration::new(config.migration_bandwidth());
+        Self {
+            inner,
+            mapper,
+            migration,
+            disks,
+        }
     }
 
     pub(crate) async fn blobs_count(&self) -> (usize, usize) {
@@ -131,17 +186,203 @@ impl Backend {
         self.inner.index_memory().await
     }
 
-    pub(crate) fn mapper(&self) -> &Virtual {
+    pub(crate) async fn unload_cold_holders(&self) {
+        self.inner.unload_cold_holders().await;
+    }
+
+    pub(crate) async fn vdisks_groups(&self) -> Option<Vec<Group>> {
+        self.inner.vdisks_groups().await
+    }
+
+    pub(crate) async fn alien_vdisks_groups(&self) -> Option<Vec<Group>> {
+        self.inner.alien_vdisks_groups().await
+    }
+
+    pub(crate) async fn released_vdisks_groups(&self) -> Option<Vec<Group>> {
+        self.inner.released_vdisks_groups().await
+    }
+
+    /// Most recent versions of the vdisk keys stored on the local node sorted by key, deleted
+    /// and expired keys are skipped. Alien data of all remote nodes is merged. Keys are taken
+    /// from the keys logs, so only versions of the keys after `after` are read until `limit`
+    /// of them is found.
+    pub(crate) async fn list_keys(
+        &self,
+        vdisk_id: VDiskID,
+        source: GetSource,
+        start_timestamp: Option<u64>,
+        after: Option<BobKey>,
+        limit: Option<usize>,
+    ) -> Result<Vec<RecordVersion>, Error> {
+        let normal = self
+            .vdisks_groups()
+            .await
+            .ok_or_else(|| Error::failed("keys listing is supported only by pearl backend"))?
+            .into_iter()
+            .filter(|group| source != GetSource::Alien && group.vdisk_id() == vdisk_id);
+        let alien = self
+            .alien_vdisks_groups()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|group| source != GetSource::Normal && group.vdisk_id() == vdisk_id);
+        let groups = normal.chain(alien).collect::<Vec<_>>();
+        if groups.is_empty() && source == GetSource::Normal {
+            return Err(Error::vdisk_not_found(vdisk_id));
+        }
+        let mut keys = vec![];
+        for group in &groups {
+            keys.extend(group.keys(start_timestamp).await?);
+        }
+        keys.sort_unstable();
+        keys.dedup();
+        keys.retain(|&key| after.map_or(true, |after| key > after));
+        let batch = limit.unwrap_or(keys.len()).max(1);
+        let mut result = vec![];
+        for keys in keys.chunks(batch) {
+            let mut versions = vec![];
+            for group in &groups {
+                versions.extend(group.key_versions(start_timestamp, keys).await?);
+            }
+            let mut versions = RecordVersion::latest(versions);
+            versions.retain(|version| !version.is_tombstone());
+            result.extend(versions);
+            if let Some(limit) = limit.filter(|&limit| result.len() >= limit) {
+                result.truncate(limit);
+                break;
+            }
+        }
+        Ok(result)
+    }
+
+    pub(crate) fn migration(&self) -> &Migration {
+        &self.migration
+    }
+
+    pub(crate) fn disks(&self) -> &Arc<Disks> {
+        &self.disks
+    }
+
+    /// Vdisks with local replicas on the unavailable disks
+    pub(crate) fn degraded_vdisks(&self) -> Vec<VDiskID> {
+        let mapper = self.mapper.current();
+        let mut vdisks: Vec<_> = self
+            .disks
+            .all()
+            .iter()
+            .filter(|disk| !disk.is_available())
+            .flat_map(|disk| mapper.get_vdisks_by_disk(disk.name()))
+            .collect();
+        vdisks.sort_unstable();
+        vdisks.dedup();
+        vdisks
+    }
+
+    fn find_disk(&self, name: &str) -> Result<Arc<DiskHealth>, Error> {
+        self.disks
+            .get(name)
+            .ok_or_else(|| Error::failed(format!("disk {} not found", name)))
+    }
+
+    /// Fences disk and closes storages of its vdisks, so the disk can be replaced
+    pub(crate) async fn stop_disk(&self, name: &str) -> Result<(), Error> {
+        let disk = self.find_disk(name)?;
+        disk.stop();
+        for group in self.disk_groups(name).await {
+            group.close().await;
+        }
+        Ok(())
+    }
+
+    /// Checks disk, remounts storages of its vdisks and returns it to service
+    pub(crate) async fn start_disk(&self, name: &str) -> Result<(), Error> {
+        let disk = self.find_disk(name)?;
+        disk.start().await?;
+        for group in self.disk_groups(name).await {
+            group.remount().await.map_err(|e| {
+                let msg = format!("vdisk {} is not remounted: {:#}", group.vdisk_id(), e);
+                disk.fail(msg.clone());
+                Error::storage(msg)
+            })?;
+        }
+        Ok(())
+    }
+
+    async fn disk_groups(&self, name: &str) -> Vec<Group> {
+        let normal = self.vdisks_groups().await.unwrap_or_default();
+        let released = self.released_vdisks_groups().await.unwrap_or_default();
+        let alien = self.alien_vdisks_groups().await.unwrap_or_default();
+        normal
+            .into_iter()
+            .chain(released)
+            .chain(alien)
+            .filter(|group| group.disk_name() == name)
+            .collect()
+    }
+
+    /// Snapshot of the current cluster map
+    pub(crate) fn mapper(&self) -> Arc<Virtual> {
+        self.mapper.current()
+    }
+
+    pub(crate) fn cluster_map(&self) -> &Arc<ClusterMap> {
         &self.mapper
     }
 
+    /// Replaces cluster map with the new version and updates local storages,
+    /// returns version of the applied map. Data of the vdisks assigned to the local node
+    /// is migrated from their previous replicas in background.
+    pub(crate) async fn update_cluster_map(&self, cluster: &ClusterConfig) -> Result<u64, Error> {
+        let previous = self.mapper.current();
+        let apply = |mapper: Arc<Virtual>| async move {
+            // only pearl backend has vdisks groups and tracks its disks
+            if self.inner.vdisks_groups().await.is_some() {
+                self.disks.add(mapper.local_disks());
+            }
+            self.inner
+                .apply_cluster_map(&mapper)
+                .await
+                .map_err(|e| Error::failed(format!("cluster map is not applied: {}", e)))
+        };
+        let mapper = self.mapper.update(cluster, apply).await?;
+        if let Some(groups) = self.vdisks_groups().await {
+            let released = self.released_vdisks_groups().await.unwrap_or_default();
+            self.migration.start(&previous, &mapper, groups, &released);
+        }
+        Ok(mapper.version())
+    }
+
     pub(crate) fn inner(&self) -> &dyn BackendStorage {
         self.inner.as_ref()
     }
 
-    #[inline]
+    /// Migrations interrupted by restart are resumed once local storages are ready
     pub(crate) async fn run_backend(&self) -> Result<()> {
-        self.inner.run().await
+        self.inner.run().await?;
+        self.resume_migrations().await;
+        Ok(())
+    }
+
+    /// Opens storages closed by shutdown and resumes migrations stopped by it
+    pub(crate) async fn reopen(&self) -> Result<()> {
+        self.inner.reopen().await?;
+        self.resume_migrations().await;
+        Ok(())
+    }
+
+    async fn resume_migrations(&self) {
+        if let Some(groups) = self.vdisks_groups().await {
+            let released = self.released_vdisks_groups().await.unwrap_or_default();
+            self.migration
+                .resume(&self.mapper.current(), groups, &released);
+        }
+    }
+
+    pub(crate) async fn drop_released_group(&self, group: &Group) -> Result<(), Error> {
+        self.inner
+            .drop_released_group(group.vdisk_id(), group.disk_name())
+            .await
+            .map_err(|e| Error::storage(format!("{:#}", e)))
     }
 
     pub(crate) async fn put(
@@ -152,7 +393,7 @@ impl Backend {
     ) -> Result<(), Error> {
         trace!(">>>>>>- - - - - BACKEND PUT START - - - - -");
         let sw = Stopwatch::start_new();
-        let (vdisk_id, disk_path) = self.mapper.get_operation(key);
+        let (vdisk_id, disk_path) = self.mapper.current().get_operation(key);
         trace!(
             "get operation {:?}, /{:.3}ms/",
             disk_path,
@@ -190,6 +431,21 @@ impl Backend {
         res
     }
 
+    /// Writes tombstone for the key, deletion is routed the same way as put
+    pub(crate) async fn delete(
+        &self,
+        key: BobKey,
+        meta: BobMeta,
+        options: BobOptions,
+    ) -> Result<(), Error> {
+        debug!(
+            "DELETE[{}] write tombstone, timestamp: {}",
+            key,
+            meta.timestamp()
+        );
+        self.put(key, BobData::tombstone(meta), options).await
+    }
+
     #[inline]
     pub(crate) async fn put_local(
         &self,
@@ -213,18 +469,21 @@ impl Backend {
             debug!("PUT[{}] to backend: {:?}", key, operation);
             let result = self.inner.put(operation.clone(), key, data.clone()).await;
             match result {
-                Err(local_err) if !local_err.is_duplicate() => {
+                // full disk and loading vdisk aren't written to alien, so the cluster writes
+                // the record to other nodes
+                Err(local_err)
+                    if !local_err.is_duplicate()
+                        && !local_err.is_no_space()
+                        && !local_err.is_not_ready() =>
+                {
                     error!(
                         "PUT[{}][{}] local failed: {:?}",
                         key,
                         operation.disk_name_local(),
                         local_err
                     );
-                    // write to alien/<local name>
-                    let mut op = operation.clone_alien();
-                    op.set_remote_folder(self.mapper.local_node_name().to_owned());
                     self.inner
-                        .put_alien(op, key, data)
+                        .put_alien(self.local_alien(&operation), key, data)
                         .await
                         .map_err(|alien_err| {
                             Error::request_failed_completely(&local_err, &alien_err)
@@ -237,7 +496,7 @@ impl Backend {
     }
 
     pub(crate) async fn get(&self, key: BobKey, options: &BobOptions) -> Result<BobData, Error> {
-        let (vdisk_id, disk_path) = self.mapper.get_operation(key);
+        let (vdisk_id, disk_path) = self.mapper.current().get_operation(key);
 
         // we cannot get data from alien if it belong this node
 
@@ -277,7 +536,79 @@ impl Backend {
             self.inner.get_alien(operation, key).await
         } else {
             debug!("GET[{}][{}] to backend", key, operation.disk_name_local());
-            self.inner.get(operation, key).await
+            let vdisk_id = operation.vdisk_id();
+            let result = match self.inner.get(operation.clone(), key).await {
+                Err(e) if Self::may_be_in_local_alien(&e) => {
+                    let alien = self
+                        .inner
+                        .get_alien(self.local_alien(&operation), key)
+                        .await;
+                    Self::merge_local_alien(e, alien)
+                }
+                result => result,
+            };
+            self.migration.read_through(vdisk_id, key, result).await
+        }
+    }
+
+    // writes, which failed on the local disk, are written to alien/<local name>
+    fn local_alien(&self, operation: &Operation) -> Operation {
+        let mut op = operation.clone_alien();
+        op.set_remote_folder(self.mapper.current().local_node_name().to_owned());
+        op
+    }
+
+    // local alien folder has records written while the disk was unavailable, until they are
+    // handed off back to the disk
+    fn may_be_in_local_alien(error: &Error) -> bool {
+        error.is_disk_unavailable() || error.is_key_not_found()
+    }
+
+    // deletion on the disk hides older records of the local alien
+    fn merge_local_alien(error: Error, alien: Result<BobData, Error>) -> Result<BobData, Error> {
+        let deleted_at = error.deleted_at();
+        match alien {
+            Ok(data) if deleted_at.map_or(true, |ts| data.meta().timestamp() > ts) => Ok(data),
+            Err(e) if e.deleted_at() > deleted_at => Err(e),
+            _ => Err(error),
+        }
+    }
+
+    /// Version of the key without data, options are handled as by [`Backend::get`]
+    pub(crate) async fn head(&self, key: BobKey, options: &BobOptions) -> Result<BobHead, Error> {
+        let (vdisk_id, disk_path) = self.mapper.current().get_operation(key);
+        if options.get_normal() {
+            let path = disk_path.ok_or_else(|| {
+                error!("HEAD[{}] we read data but can't find path in config", key);
+                Error::internal()
+            })?;
+            // local replica of the migrating vdisk may miss the version
+            if self.migration.is_pending(vdisk_id) {
+                let op = Operation::new_local(vdisk_id, path);
+                return self
+                    .get_single(key, op)
+                    .await
+                    .map(|data| BobHead::from(&data));
+            }
+            let op = Operation::new_local(vdisk_id, path);
+            match self.inner.head(op.clone(), key).await {
+                Err(e) if Self::may_be_in_local_alien(&e) => self
+                    .inner
+                    .head_alien(self.local_alien(&op), key)
+                    .await
+                    .map_err(|_| e),
+                result => result,
+            }
+        } else if options.get_alien() {
+            self.inner
+                .head_alien(Operation::new_alien(vdisk_id), key)
+                .await
+        } else {
+            error!(
+                "HEAD[{}] can't read from anywhere {:?}, {:?}",
+                key, disk_path, options
+            );
+            Err(Error::internal())
         }
     }
 
@@ -289,17 +620,27 @@ impl Backend {
         let mut exist = vec![false; keys.len()];
         let keys_by_id_and_path = self.group_keys_by_operations(keys, options);
         for (operation, (keys, indexes)) in keys_by_id_and_path {
-            let result = self.inner.exist(operation, &keys).await;
-            if let Ok(result) = result {
-                for (&res, ind) in result.iter().zip(indexes) {
+            let result = self.inner.exist(operation.clone(), &keys).await;
+            if let Ok(result) = &result {
+                for (&res, &ind) in result.iter().zip(&indexes) {
                     exist[ind] |= res;
                 }
             }
+            let missed = result.map_or(true, |result| result.contains(&false));
+            if !operation.is_data_alien() && missed {
+                let op = self.local_alien(&operation);
+                if let Ok(result) = self.inner.exist_alien(op, &keys).await {
+                    for (&res, ind) in result.iter().zip(indexes) {
+                        exist[ind] |= res;
+                    }
+                }
+            }
         }
         Ok(exist)
     }
 
     pub async fn shutdown(&self) {
+        self.migration.stop();
         self.inner.shutdown().await;
     }
 
@@ -325,7 +666,7 @@ impl Backend {
     }
 
     fn find_operation(&self, key: BobKey, options: &BobOptions) -> Option<Operation> {
-        let (vdisk_id, path) = self.mapper.get_operation(key);
+        let (vdisk_id, path) = self.mapper.current().get_operation(key);
         if options.get_normal() {
             path.map(|path| Operation::new_local(vdisk_id, path))
         } else if options.get_alien() {
@@ -336,12 +677,12 @@ impl Backend {
     }
 
     pub(crate) async fn close_unneeded_active_blobs(&self, soft: usize, hard: usize) {
-        let groups = if let Some(groups) = self.inner.vdisks_groups() {
+        let groups = if let Some(groups) = self.inner.vdisks_groups().await {
             groups
         } else {
             return;
         };
-        for group in groups {
+        for group in &groups {
             let holders_lock = group.holders();
             let mut holders_write = holders_lock.write().await;
             let holders: &mut Vec<_> = holders_write.as_mut();
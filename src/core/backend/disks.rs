@@ -0,0 +1,441 @@
+use super::prelude::*;
+use std::ffi::CString;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::RwLock as SyncRwLock;
+
+use crate::core::configs::{FreeSpaceConfig, Watermarks};
+
+// disk is marked as failed after this count of operations failed in a row
+const ERRORS_LIMIT: u64 = 10;
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const CHECK_FILE_NAME: &str = ".bob_disk_check";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum DiskState {
+    Available,
+    /// Disk is fenced because of I/O errors and is returned to service only by admin
+    Failed(String),
+    /// Disk is fenced by admin, e.g. for replacement
+    Stopped,
+}
+
+/// Free space of the disk relative to its watermarks
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SpaceState {
+    Normal,
+    /// Free space is below the soft watermark
+    Low,
+    /// Free space is below the hard watermark, new writes are refused
+    Full,
+}
+
+impl SpaceState {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Normal => "normal",
+            Self::Low => "low",
+            Self::Full => "full",
+        }
+    }
+}
+
+impl DiskState {
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::Available => "available",
+            Self::Failed(_) => "failed",
+            Self::Stopped => "stopped",
+        }
+    }
+}
+
+/// Health of the local disk, which is tracked by results of the storage operations and by the
+/// periodic write checks
+#[derive(Debug)]
+pub(crate) struct DiskHealth {
+    name: String,
+    path: PathBuf,
+    state: SyncRwLock<DiskState>,
+    operations: AtomicU64,
+    errors: AtomicU64,
+    errors_in_row: AtomicU64,
+    // moving average of the operations latency
+    latency_us: AtomicU64,
+    free_space: AtomicU64,
+    total_space: AtomicU64,
+    watermarks: SyncRwLock<Watermarks>,
+}
+
+impl DiskHealth {
+    pub(crate) fn new(disk: &DiskPath, watermarks: Watermarks) -> Self {
+        let health = Self {
+            name: disk.name().to_owned(),
+            path: disk.path().into(),
+            state: SyncRwLock::new(DiskState::Available),
+            operations: AtomicU64::default(),
+            errors: AtomicU64::default(),
+            errors_in_row: AtomicU64::default(),
+            latency_us: AtomicU64::default(),
+            free_space: AtomicU64::default(),
+            total_space: AtomicU64::default(),
+            watermarks: SyncRwLock::new(watermarks),
+        };
+        // full disk refuses writes from the start, directory of the disk may be created later,
+        // so the space of its file system is measured
+        let existing = health.path.ancestors().find(|path| path.exists());
+        if let Some(Ok((free, total))) = existing.map(Self::space) {
+            health.update_space(free, total);
+        }
+        health
+    }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn state(&self) -> DiskState {
+        self.state.read().expect("disk state lock").clone()
+    }
+
+    pub(crate) fn is_available(&self) -> bool {
+        *self.state.read().expect("disk state lock") == DiskState::Available
+    }
+
+    /// Returns error if operations on the disk are not allowed
+    pub(crate) fn check_available(&self) -> Result<(), Error> {
+        if self.is_available() {
+            Ok(())
+        } else {
+            Err(Error::disk_unavailable(&self.name))
+        }
+    }
+
+    pub(crate) fn operations(&self) -> u64 {
+        self.operations.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn errors(&self) -> u64 {
+        self.errors.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn latency(&self) -> Duration {
+        Duration::from_micros(self.latency_us.load(Ordering::Relaxed))
+    }
+
+    pub(crate) fn free_space(&self) -> u64 {
+        self.free_space.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn total_space(&self) -> u64 {
+        self.total_space.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn watermarks(&self) -> Watermarks {
+        *self.watermarks.read().expect("watermarks lock")
+    }
+
+    pub(crate) fn set_watermarks(&self, watermarks: Watermarks) {
+        *self.watermarks.write().expect("watermarks lock") = watermarks;
+    }
+
+    /// Free space is measured on start and by the disk checks, disk which space can't be
+    /// measured is considered normal
+    pub(crate) fn space_state(&self) -> SpaceState {
+        if self.total_space() == 0 {
+            return SpaceState::Normal;
+        }
+        let free = self.free_space();
+        let watermarks = self.watermarks();
+        if watermarks.hard().map_or(false, |hard| free < hard) {
+            SpaceState::Full
+        } else if watermarks.soft().map_or(false, |soft| free < soft) {
+            SpaceState::Low
+        } else {
+            SpaceState::Normal
+        }
+    }
+
+    /// Returns error if free space of the disk is below the hard watermark
+    pub(crate) fn check_space(&self) -> Result<(), Error> {
+        if self.space_state() == SpaceState::Full {
+            Err(Error::no_space(&self.name))
+        } else {
+            Ok(())
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn record_success(&self, elapsed: Duration) {
+        self.operations.fetch_add(1, Ordering::Relaxed);
+        self.errors_in_row.store(0, Ordering::Relaxed);
+        let latency = elapsed.as_micros() as u64;
+        let previous = self.latency_us.load(Ordering::Relaxed);
+        let average = if previous == 0 {
+            latency
+        } else {
+            (previous * 7 + latency) / 8
+        };
+        self.latency_us.store(average, Ordering::Relaxed);
+    }
+
+    /// Counts failed operation, disk is marked as failed if too many operations failed in a row
+    pub(crate) fn record_error(&self, error: &Error) {
+        self.operations.fetch_add(1, Ordering::Relaxed);
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        let in_row = self.errors_in_row.fetch_add(1, Ordering::Relaxed) + 1;
+        if in_row >= ERRORS_LIMIT {
+            self.fail(format!(
+                "{} operations failed in a row, last: {}",
+                in_row, error
+            ));
+        }
+    }
+
+    pub(crate) fn fail(&self, reason: String) {
+        let mut state = self.state.write().expect("disk state lock");
+        if *state == DiskState::Available {
+            error!("disk {} is marked as failed: {}", self.name, reason);
+            *state = DiskState::Failed(reason);
+        }
+    }
+
+    pub(crate) fn stop(&self) {
+        warn!("disk {} is stopped", self.name);
+        *self.state.write().expect("disk state lock") = DiskState::Stopped;
+    }
+
+    /// Checks disk and returns it to service, counters are reset
+    pub(crate) async fn start(&self) -> Result<(), Error> {
+        self.probe().await?;
+        self.errors_in_row.store(0, Ordering::Relaxed);
+        self.errors.store(0, Ordering::Relaxed);
+        self.operations.store(0, Ordering::Relaxed);
+        *self.state.write().expect("disk state lock") = DiskState::Available;
+        info!("disk {} is available", self.name);
+        Ok(())
+    }
+
+    /// Writes and removes check file and updates free space of the disk
+    async fn probe(&self) -> Result<(), Error> {
+        let path = self.path.clone();
+        let timer = Instant::now();
+        let result = tokio::task::spawn_blocking(move || {
+            Self::write_check_file(&path)?;
+            Self::space(&path)
+        })
+        .await
+        .map_err(|e| Error::failed(format!("disk check task failed: {}", e)))?;
+        match result {
+            Ok((free, total)) => {
+                self.record_success(timer.elapsed());
+                self.update_space(free, total);
+                Ok(())
+            }
+            Err(e) => Err(Error::storage(format!(
+                "disk {} check failed: {}",
+                self.name, e
+            ))),
+        }
+    }
+
+    #[allow(clippy::cast_possible_wrap)]
+    fn update_space(&self, free: u64, total: u64) {
+        let previous = self.space_state();
+        self.free_space.store(free, Ordering::Relaxed);
+        self.total_space.store(total, Ordering::Relaxed);
+        gauge!(DISK_FREE_SPACE, free as i64, "disk" => self.name.clone());
+        gauge!(DISK_TOTAL_SPACE, total as i64, "disk" => self.name.clone());
+        let state = self.space_state();
+        if state != previous {
+            match state {
+                SpaceState::Normal => info!("disk {} has enough free space: {}", self.name, free),
+                SpaceState::Low => warn!("disk {} is low on space: {}", self.name, free),
+                SpaceState::Full => error!(
+                    "disk {} is full, free space {} is below hard watermark, writes are refused",
+                    self.name, free
+                ),
+            }
+        }
+    }
+
+    /// Checks available disk, disk is marked as failed if check fails
+    pub(crate) async fn check(&self) {
+        if self.is_available() {
+            if let Err(e) = self.probe().await {
+                self.fail(e.to_string());
+            }
+        }
+    }
+
+    fn write_check_file(dir: &Path) -> IOResult<()> {
+        let path = dir.join(CHECK_FILE_NAME);
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        file.write_all(b"bob")?;
+        file.sync_all()?;
+        remove_file(&path)
+    }
+
+    // returns free and total space of the file system in bytes
+    #[allow(clippy::useless_conversion)]
+    fn space(path: &Path) -> IOResult<(u64, u64)> {
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| IOError::new(IOErrorKind::InvalidInput, e))?;
+        let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+            return Err(IOError::last_os_error());
+        }
+        let block = u64::from(stat.f_frsize);
+        Ok((
+            u64::from(stat.f_bavail) * block,
+            u64::from(stat.f_blocks) * block,
+        ))
+    }
+}
+
+/// Health of all local disks of the node
+#[derive(Debug, Default)]
+pub(crate) struct Disks {
+    disks: SyncRwLock<HashMap<String, Arc<DiskHealth>>>,
+    free_space: SyncRwLock<FreeSpaceConfig>,
+}
+
+impl Disks {
+    pub(crate) fn new(disks: &[DiskPath], free_space: FreeSpaceConfig) -> Self {
+        let result = Self {
+            disks: SyncRwLock::default(),
+            free_space: SyncRwLock::new(free_space),
+        };
+        result.add(disks);
+        result
+    }
+
+    /// Starts tracking of the new disks, e.g. added by the cluster map update
+    pub(crate) fn add(&self, disks: &[DiskPath]) {
+        let free_space = self.free_space.read().expect("free space lock");
+        let mut health = self.disks.write().expect("disks lock");
+        for disk in disks {
+            health.entry(disk.name().to_owned()).or_insert_with(|| {
+                Arc::new(DiskHealth::new(disk, free_space.watermarks(disk.name())))
+            });
+        }
+    }
+
+    /// Applies free space watermarks of the reloaded config to all disks
+    pub(crate) fn set_free_space(&self, free_space: FreeSpaceConfig) {
+        for disk in self.all() {
+            disk.set_watermarks(free_space.watermarks(disk.name()));
+        }
+        *self.free_space.write().expect("free space lock") = free_space;
+    }
+
+    pub(crate) fn get(&self, name: &str) -> Option<Arc<DiskHealth>> {
+        self.disks.read().expect("disks lock").get(name).cloned()
+    }
+
+    /// Health of all disks sorted by name
+    pub(crate) fn all(&self) -> Vec<Arc<DiskHealth>> {
+        let mut disks: Vec<_> = self
+            .disks
+            .read()
+            .expect("disks lock")
+            .values()
+            .cloned()
+            .collect();
+        disks.sort_by(|a, b| a.name().cmp(b.name()));
+        disks
+    }
+
+    pub(crate) fn spawn_checker(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                for disk in self.all() {
+                    disk.check().await;
+                }
+                sleep(CHECK_INTERVAL).await;
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIR: &str = "/tmp/bob_disks";
+
+    #[tokio::test]
+    async fn disk_is_fenced_after_errors_and_started_after_check() {
+        let _ = std::fs::remove_dir_all(DIR);
+        let path = DiskPath::new("disk1".to_owned(), DIR.to_owned());
+        let disk = DiskHealth::new(&path, Watermarks::default());
+        disk.check().await;
+        assert_eq!(
+            disk.state().as_str(),
+            "failed",
+            "disk directory doesn't exist"
+        );
+        assert!(disk.check_available().unwrap_err().is_disk_unavailable());
+        assert!(disk.start().await.is_err());
+
+        std::fs::create_dir_all(DIR).unwrap();
+        disk.start().await.unwrap();
+        assert!(disk.total_space() > 0);
+        for _ in 1..ERRORS_LIMIT {
+            disk.record_error(&Error::storage("i/o error"));
+        }
+        disk.record_success(Duration::from_millis(1));
+        disk.record_error(&Error::storage("i/o error"));
+        assert!(disk.is_available(), "errors are not in a row");
+        for _ in 1..ERRORS_LIMIT {
+            disk.record_error(&Error::storage("i/o error"));
+        }
+        assert!(matches!(disk.state(), DiskState::Failed(reason) if reason.contains("i/o error")));
+        assert_eq!(disk.errors(), 2 * ERRORS_LIMIT - 1);
+
+        disk.start().await.unwrap();
+        assert!(disk.is_available());
+        assert_eq!(disk.errors(), 0);
+        disk.stop();
+        assert_eq!(disk.state(), DiskState::Stopped);
+        disk.check().await;
+        assert_eq!(
+            disk.state(),
+            DiskState::Stopped,
+            "stopped disk isn't checked"
+        );
+        std::fs::remove_dir_all(DIR).unwrap();
+    }
+
+    #[test]
+    fn space_state_follows_watermarks() {
+        let path = DiskPath::new("disk1".to_owned(), DIR.to_owned());
+        let disk = DiskHealth::new(&path, Watermarks::new(Some(100), Some(10)));
+        disk.update_space(0, 0);
+        assert_eq!(
+            disk.space_state(),
+            SpaceState::Normal,
+            "space isn't measured"
+        );
+        disk.update_space(100, 1000);
+        assert_eq!(disk.space_state(), SpaceState::Normal);
+        disk.update_space(99, 1000);
+        assert_eq!(disk.space_state(), SpaceState::Low);
+        assert!(disk.check_space().is_ok());
+        disk.update_space(9, 1000);
+        assert_eq!(disk.space_state(), SpaceState::Full);
+        assert!(disk.check_space().unwrap_err().is_no_space());
+        disk.set_watermarks(Watermarks::new(None, Some(5)));
+        assert_eq!(disk.space_state(), SpaceState::Normal);
+    }
+}
@@ -14,23 +14,49 @@ impl VDisk {
 
     async fn put(&self, key: BobKey, data: BobData) -> Result<(), Error> {
         debug!("PUT[{}] to vdisk", key);
-        self.inner.write().await.insert(key, data);
+        let mut repo = self.inner.write().await;
+        match repo.get(&key) {
+            Some(old) if old.is_tombstone() && old.meta().timestamp() > data.meta().timestamp() => {
+                debug!("PUT[{}] to vdisk: key was deleted later, skip", key);
+            }
+            _ => {
+                repo.insert(key, data);
+            }
+        }
         Ok(())
     }
 
     async fn get(&self, key: BobKey) -> Result<BobData, Error> {
-        if let Some(data) = self.inner.read().await.get(&key) {
-            debug!("GET[{}] from vdisk", key);
-            Ok(data.clone())
-        } else {
-            debug!("GET[{}] from vdisk failed. Cannot find key", key);
-            Err(Error::key_not_found(key))
+        match self.inner.read().await.get(&key) {
+            Some(data) if data.is_tombstone() => {
+                debug!("GET[{}] from vdisk: key is deleted", key);
+                Err(Error::key_deleted(key, data.meta().timestamp()))
+            }
+            Some(data) if data.meta().is_expired() => {
+                debug!("GET[{}] from vdisk: key is expired", key);
+                Err(Error::key_not_found(key))
+            }
+            Some(data) => {
+                debug!("GET[{}] from vdisk", key);
+                Ok(data.clone())
+            }
+            None => {
+                debug!("GET[{}] from vdisk failed. Cannot find key", key);
+                Err(Error::key_not_found(key))
+            }
         }
     }
 
     async fn exist(&self, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
         let repo = self.inner.read().await;
-        let result = keys.iter().map(|k| repo.get(k).is_some()).collect();
+        let result = keys
+            .iter()
+            .map(|k| {
+                repo.get(k).map_or(false, |data| {
+                    !data.is_tombstone() && !data.meta().is_expired()
+                })
+            })
+            .collect();
         Ok(result)
     }
 }
@@ -1,6 +1,7 @@
 use super::prelude::*;
 
 use super::mem_backend::MemDisk;
+use std::time::Duration;
 
 const VDISKS_COUNT: u32 = 10;
 
@@ -22,7 +23,7 @@ async fn test_mem_put_wrong_disk() {
     let retval = backend
         .put(
             Operation::new_local(0, DiskPath::new("invalid name".to_owned(), "".to_owned())),
-            1,
+            BobKey::from(1),
             BobData::new(vec![0], BobMeta::stub()),
         )
         .await;
@@ -36,7 +37,7 @@ async fn test_mem_put_get() {
     backend
         .put(
             Operation::new_local(0, DiskPath::new("name".to_owned(), "".to_owned())),
-            1,
+            BobKey::from(1),
             BobData::new(vec![1], BobMeta::stub()),
         )
         .await
@@ -44,7 +45,7 @@ async fn test_mem_put_get() {
     let retval = backend
         .get(
             Operation::new_local(0, DiskPath::new("name".to_owned(), "".to_owned())),
-            1,
+            BobKey::from(1),
         )
         .await
         .unwrap();
@@ -58,7 +59,7 @@ async fn test_mem_get_wrong_disk() {
     backend
         .put(
             Operation::new_local(0, DiskPath::new("name".to_owned(), "".to_owned())),
-            1,
+            BobKey::from(1),
             BobData::new(vec![1], BobMeta::stub()),
         )
         .await
@@ -66,7 +67,7 @@ async fn test_mem_get_wrong_disk() {
     let retval = backend
         .get(
             Operation::new_local(0, DiskPath::new("invalid name".to_owned(), "".to_owned())),
-            1,
+            BobKey::from(1),
         )
         .await;
     assert!(retval.err().unwrap().is_internal())
@@ -75,7 +76,7 @@ async fn test_mem_get_wrong_disk() {
 #[tokio::test]
 async fn test_mem_get_no_data() {
     let backend = new_direct(&["name".to_owned()], VDISKS_COUNT);
-    let key = 1;
+    let key = BobKey::from(1);
 
     let retval = backend
         .get(
@@ -85,3 +86,58 @@ async fn test_mem_get_no_data() {
         .await;
     assert!(retval.err().unwrap().is_key_not_found())
 }
+
+#[tokio::test]
+async fn test_mem_put_tombstone_get_no_data() {
+    let backend = new_direct(&["name".to_owned()], VDISKS_COUNT);
+    let op = Operation::new_local(0, DiskPath::new("name".to_owned(), "".to_owned()));
+    let key = BobKey::from(1);
+
+    backend
+        .put(op.clone(), key, BobData::new(vec![1], BobMeta::new(1)))
+        .await
+        .unwrap();
+    backend
+        .put(op.clone(), key, BobData::tombstone(BobMeta::new(2)))
+        .await
+        .unwrap();
+    let retval = backend.get(op.clone(), key).await;
+    assert!(retval.err().unwrap().is_key_not_found());
+    let exist = backend.exist(op.clone(), &[key]).await.unwrap();
+    assert_eq!(exist, vec![false]);
+
+    // older version must not resurrect deleted key
+    backend
+        .put(op.clone(), key, BobData::new(vec![1], BobMeta::new(1)))
+        .await
+        .unwrap();
+    let retval = backend.get(op, key).await;
+    assert!(retval.err().unwrap().is_key_not_found());
+}
+
+#[tokio::test]
+async fn test_mem_put_expired_get_no_data() {
+    let backend = new_direct(&["name".to_owned()], VDISKS_COUNT);
+    let op = Operation::new_local(0, DiskPath::new("name".to_owned(), "".to_owned()));
+    let expired = BobData::new(vec![1], BobMeta::new(1)).expire_after(Some(Duration::from_secs(1)));
+    let alive =
+        BobData::new(vec![2], BobMeta::stub()).expire_after(Some(Duration::from_secs(u64::MAX)));
+
+    backend
+        .put(op.clone(), BobKey::from(1), expired)
+        .await
+        .unwrap();
+    backend
+        .put(op.clone(), BobKey::from(2), alive)
+        .await
+        .unwrap();
+    let retval = backend.get(op.clone(), BobKey::from(1)).await;
+    assert!(retval.err().unwrap().is_key_not_found());
+    let retval = backend.get(op.clone(), BobKey::from(2)).await.unwrap();
+    assert_eq!(retval.into_inner(), vec![2]);
+    let exist = backend
+        .exist(op, &[BobKey::from(1), BobKey::from(2)])
+        .await
+        .unwrap();
+    assert_eq!(exist, vec![false, true]);
+}
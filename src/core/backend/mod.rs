@@ -1,4 +1,5 @@
 mod core;
+mod disks;
 mod mem_backend;
 mod pearl;
 mod stub_backend;
@@ -8,8 +9,9 @@ mod mem_tests;
 
 pub(crate) use self::{
     core::{Backend, Operation},
+    disks::{DiskHealth, DiskState, Disks},
     mem_backend::MemBackend,
-    pearl::{init_pearl, Group, Holder, Pearl},
+    pearl::{init_pearl, Group, Holder, HolderDigest, Pearl, RecordVersion},
     stub_backend::StubBackend,
 };
 pub(crate) use super::prelude::*;
@@ -20,6 +22,6 @@ mod prelude {
     pub(crate) use super::core::BackendStorage;
     pub(crate) use configs::BackendType;
     pub(crate) use data::{BobMeta, BobOptions};
-    pub(crate) use mapper::Virtual;
+    pub(crate) use mapper::{ClusterMap, Virtual};
     pub(crate) use tokio::sync::RwLock;
 }
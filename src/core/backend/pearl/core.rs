@@ -1,4 +1,5 @@
 use super::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 pub(crate) type BackendResult<T> = std::result::Result<T, Error>;
 pub(crate) type PearlStorage = Storage<Key>;
@@ -6,20 +7,29 @@ pub(crate) type PearlStorage = Storage<Key>;
 #[derive(Clone, Debug)]
 pub(crate) struct Pearl {
     settings: Arc<Settings>,
-    vdisks_groups: Arc<[Group]>,
+    vdisks_groups: Arc<RwLock<Vec<Group>>>,
+    // groups of vdisks moved to other replicas, kept open as migration sources
+    released_vdisks_groups: Arc<RwLock<Vec<Group>>>,
     alien_vdisks_groups: Arc<RwLock<Vec<Group>>>,
     node_name: String,
     init_par_degree: usize,
 }
 
 impl Pearl {
-    pub(crate) fn new(mapper: Arc<Virtual>, config: &NodeConfig) -> Self {
+    pub(crate) fn new(mapper: Arc<ClusterMap>, config: &NodeConfig, disks: Arc<Disks>) -> Self {
         debug!("initializing pearl backend");
-        let settings = Arc::new(Settings::new(config, mapper));
+        let settings = Arc::new(Settings::new(config, mapper.clone(), disks));
 
         let data = settings.clone().read_group_from_disk(config);
-        let vdisks_groups: Arc<[Group]> = Arc::from(data.as_slice());
-        trace!("count vdisk groups: {}", vdisks_groups.len());
+        trace!("count vdisk groups: {}", data.len());
+        let vdisks_groups = Arc::new(RwLock::new(data));
+
+        let released = settings.clone().released_groups(
+            &mapper.current(),
+            config.name(),
+            config.init_par_degree(),
+        );
+        trace!("count released vdisk groups: {}", released.len());
 
         let alien = settings
             .clone()
@@ -31,12 +41,17 @@ impl Pearl {
         Self {
             settings,
             vdisks_groups,
+            released_vdisks_groups: Arc::new(RwLock::new(released)),
             alien_vdisks_groups,
             node_name: config.name().to_string(),
             init_par_degree: config.init_par_degree(),
         }
     }
 
+    async fn find_local_pearl(&self, operation: &Operation) -> Option<Group> {
+        Self::find_pearl(self.vdisks_groups.read().await.iter(), operation)
+    }
+
     async fn find_alien_pearl(&self, operation: &Operation) -> BackendResult<Group> {
         Self::find_pearl(self.alien_vdisks_groups.read().await.iter(), operation).ok_or_else(|| {
             Error::failed(format!("cannot find actual alien folder. {:?}", operation))
@@ -52,6 +67,16 @@ impl Pearl {
             .cloned()
     }
 
+    // errors of the disk and vdisk state keep their kind, so they are reported with the dedicated
+    // codes
+    fn put_error(e: Error) -> Error {
+        if e.is_disk_unavailable() || e.is_no_space() || e.is_not_ready() {
+            e
+        } else {
+            Error::failed(format!("{:#?}", e))
+        }
+    }
+
     async fn get_or_create_alien_pearl(&self, operation: &Operation) -> BackendResult<Group> {
         trace!("try get alien pearl, operation {:?}", operation);
         let pearl = Self::find_pearl(self.alien_vdisks_groups.read().await.iter(), operation);
@@ -85,10 +110,30 @@ impl BackendStorage for Pearl {
         debug!("run pearl backend");
         let start = std::time::Instant::now();
         let mut inits_by_disk: HashMap<&str, Vec<_>> = HashMap::new();
-        let pearl_groups = self.alien_vdisks_groups.read().await;
-        for group in self.vdisks_groups.iter().chain(pearl_groups.iter()) {
+        // groups aren't locked during init, so ready vdisks serve operations
+        let groups = self.vdisks_groups.read().await.clone();
+        let pearl_groups = self.alien_vdisks_groups.read().await.clone();
+        let released_groups = self.released_vdisks_groups.read().await.clone();
+        let vdisks_ready = Arc::new(AtomicUsize::new(0));
+        gauge!(BACKEND_VDISKS_TOTAL, groups.len() as i64);
+        gauge!(BACKEND_VDISKS_READY, 0);
+        let local = groups.iter().map(|group| (group, true));
+        let alien = pearl_groups.iter().map(|group| (group, false));
+        let released = released_groups.iter().map(|group| (group, false));
+        for (group, is_local) in local.chain(alien).chain(released) {
             let group_c = group.clone();
-            let fut = async move { group_c.run().await };
+            let vdisks_ready = vdisks_ready.clone();
+            let fut = async move {
+                if let Err(e) = group_c.run().await {
+                    error!("{} init failed: {:?}", group_c, e);
+                    return Err(e);
+                }
+                if is_local {
+                    let ready = vdisks_ready.fetch_add(1, Ordering::Relaxed) + 1;
+                    gauge!(BACKEND_VDISKS_READY, ready as i64);
+                }
+                Ok(())
+            };
             match inits_by_disk.entry(group.disk_name()) {
                 Entry::Occupied(mut e) => {
                     e.get_mut().push(fut);
@@ -108,10 +153,12 @@ impl BackendStorage for Pearl {
         let futs = FuturesUnordered::new();
         for futures in par_buckets {
             futs.push(async move {
+                // the rest of vdisks are loaded even if one of them fails
+                let mut result = Ok::<(), anyhow::Error>(());
                 for f in futures {
-                    f.await?;
+                    result = result.and(f.await);
                 }
-                Ok::<(), anyhow::Error>(())
+                result
             });
         }
         futs.fold(Ok(()), |s, n| async move { s.and(n) }).await?;
@@ -123,17 +170,14 @@ impl BackendStorage for Pearl {
 
     async fn put(&self, op: Operation, key: BobKey, data: BobData) -> Result<(), Error> {
         debug!("PUT[{}] to pearl backend. operation: {:?}", key, op);
-        let vdisk_group = self
-            .vdisks_groups
-            .iter()
-            .find(|vd| vd.can_process_operation(&op));
+        let vdisk_group = self.find_local_pearl(&op).await;
 
         if let Some(group) = vdisk_group {
             let res = group.put(key, data).await;
             if let Err(e) = &res {
                 debug!("PUT[{}], error: {:?}", key, e);
             }
-            res.map_err(|e| Error::failed(format!("{:#?}", e)))
+            res.map_err(Self::put_error)
         } else {
             debug!("PUT[{}] Cannot find group, operation: {:?}", key, op);
             Err(Error::vdisk_not_found(op.vdisk_id()))
@@ -147,7 +191,7 @@ impl BackendStorage for Pearl {
         match vdisk_group {
             Ok(group) => {
                 let res = group.put(key, data.clone()).await;
-                res.map_err(|e| Error::failed(format!("{:#?}", e)))
+                res.map_err(Self::put_error)
             }
             Err(e) => {
                 error!(
@@ -161,10 +205,7 @@ impl BackendStorage for Pearl {
 
     async fn get(&self, op: Operation, key: BobKey) -> Result<BobData, Error> {
         debug!("Get[{}] from pearl backend. operation: {:?}", key, op);
-        let vdisk_group = self
-            .vdisks_groups
-            .iter()
-            .find(|vd| vd.can_process_operation(&op));
+        let vdisk_group = self.find_local_pearl(&op).await;
 
         if let Some(group) = vdisk_group {
             group.get(key).await
@@ -189,13 +230,27 @@ impl BackendStorage for Pearl {
         }
     }
 
+    async fn head(&self, op: Operation, key: BobKey) -> Result<BobHead, Error> {
+        if let Some(group) = self.find_local_pearl(&op).await {
+            group.head(key).await
+        } else {
+            error!("HEAD[{}] Cannot find storage, operation: {:?}", key, op);
+            Err(Error::vdisk_not_found(op.vdisk_id()))
+        }
+    }
+
+    async fn head_alien(&self, op: Operation, key: BobKey) -> Result<BobHead, Error> {
+        if let Ok(group) = self.find_alien_pearl(&op).await {
+            group.head(key).await
+        } else {
+            Err(Error::key_not_found(key))
+        }
+    }
+
     async fn exist(&self, operation: Operation, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
-        let vdisk_group = self
-            .vdisks_groups
-            .iter()
-            .find(|vd| vd.can_process_operation(&operation));
+        let vdisk_group = self.find_local_pearl(&operation).await;
         if let Some(group) = vdisk_group {
-            Ok(group.exist(&keys).await)
+            group.exist(&keys).await
         } else {
             Err(Error::internal())
         }
@@ -204,7 +259,7 @@ impl BackendStorage for Pearl {
     async fn exist_alien(&self, operation: Operation, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
         let vdisk_group = self.find_alien_pearl(&operation).await;
         if let Ok(group) = vdisk_group {
-            Ok(group.exist(&keys).await)
+            group.exist(&keys).await
         } else {
             Err(Error::internal())
         }
@@ -214,19 +269,17 @@ impl BackendStorage for Pearl {
         use futures::stream::FuturesUnordered;
         info!("begin shutdown");
         let futures = FuturesUnordered::new();
+        let groups = self.vdisks_groups.read().await;
+        let released = self.released_vdisks_groups.read().await;
         let aliens = self.alien_vdisks_groups.read().await;
-        for vdisk in self.vdisks_groups.iter().chain(aliens.iter()) {
+        for vdisk in groups.iter().chain(released.iter()).chain(aliens.iter()) {
             let holders = vdisk.holders();
             let holders = holders.read().await;
             for holder in holders.iter() {
-                let storage = holder.storage().read().await;
-                let storage = storage.storage().clone();
-                let id = holder.get_id();
+                let holder = holder.clone();
                 futures.push(async move {
-                    match storage.close().await {
-                        Ok(_) => debug!("holder {} closed", id),
-                        Err(e) => error!("error closing holder{}: {}", id, e),
-                    }
+                    holder.close().await;
+                    debug!("holder {} closed", holder.get_id());
                 });
             }
         }
@@ -234,9 +287,20 @@ impl BackendStorage for Pearl {
         info!("shutting down done");
     }
 
+    async fn reopen(&self) -> Result<()> {
+        let groups = self.vdisks_groups.read().await.clone();
+        let released = self.released_vdisks_groups.read().await.clone();
+        let aliens = self.alien_vdisks_groups.read().await.clone();
+        for group in groups.iter().chain(released.iter()).chain(aliens.iter()) {
+            group.remount().await?;
+        }
+        info!("storages are reopened");
+        Ok(())
+    }
+
     async fn blobs_count(&self) -> (usize, usize) {
         let mut cnt = 0;
-        for group in self.vdisks_groups.iter() {
+        for group in self.vdisks_groups.read().await.iter() {
             let holders_guard = group.holders();
             let holders = holders_guard.read().await;
             for holder in holders.iter() {
@@ -257,7 +321,7 @@ impl BackendStorage for Pearl {
 
     async fn index_memory(&self) -> usize {
         let mut cnt = 0;
-        for group in self.vdisks_groups.iter() {
+        for group in self.vdisks_groups.read().await.iter() {
             let holders_guard = group.holders();
             let holders = holders_guard.read().await;
             for holder in holders.iter() {
@@ -275,7 +339,145 @@ impl BackendStorage for Pearl {
         cnt
     }
 
-    fn vdisks_groups(&self) -> Option<&[Group]> {
-        Some(&self.vdisks_groups)
+    async fn unload_cold_holders(&self) {
+        let limit = if let Some(limit) = self.settings.config().index_memory_limit() {
+            limit as usize
+        } else {
+            return;
+        };
+        let actual_start = self.settings.get_actual_timestamp_start();
+        let mut groups = self.vdisks_groups.read().await.clone();
+        groups.extend(self.alien_vdisks_groups.read().await.iter().cloned());
+        let mut index_memory = 0;
+        let mut unloadable = vec![];
+        for group in &groups {
+            let holders = group.holders().read().await.clone();
+            for holder in holders {
+                if !holder.is_loaded().await {
+                    continue;
+                }
+                let memory = holder.index_memory().await;
+                index_memory += memory;
+                // actual holders receive all writes, so they are never unloaded
+                if !holder.is_actual(actual_start) {
+                    unloadable.push((holder.last_access().await, memory, holder));
+                }
+            }
+        }
+        if index_memory <= limit {
+            return;
+        }
+        debug!("index memory {} exceeds limit {}", index_memory, limit);
+        unloadable.sort_by_key(|(last_access, _, _)| *last_access);
+        for (_, memory, holder) in unloadable {
+            if index_memory <= limit {
+                break;
+            }
+            holder.unload().await;
+            index_memory -= memory;
+        }
+        if index_memory > limit {
+            warn!(
+                "index memory {} exceeds limit {} after unloading all cold holders",
+                index_memory, limit
+            );
+        }
+    }
+
+    async fn vdisks_groups(&self) -> Option<Vec<Group>> {
+        Some(self.vdisks_groups.read().await.clone())
+    }
+
+    async fn alien_vdisks_groups(&self) -> Option<Vec<Group>> {
+        Some(self.alien_vdisks_groups.read().await.clone())
+    }
+
+    async fn released_vdisks_groups(&self) -> Option<Vec<Group>> {
+        Some(self.released_vdisks_groups.read().await.clone())
+    }
+
+    async fn drop_released_group(&self, vdisk_id: VDiskID, disk_name: &str) -> Result<()> {
+        let group = {
+            let mut released = self.released_vdisks_groups.write().await;
+            let position = released
+                .iter()
+                .position(|group| group.vdisk_id() == vdisk_id && group.disk_name() == disk_name);
+            if let Some(position) = position {
+                released.remove(position)
+            } else {
+                return Ok(());
+            }
+        };
+        group.close().await;
+        std::fs::remove_dir_all(group.directory_path()).with_context(|| {
+            format!(
+                "released vdisk {} directory {:?} is not removed",
+                vdisk_id,
+                group.directory_path()
+            )
+        })?;
+        info!(
+            "released vdisk {} on disk {} is dropped",
+            vdisk_id, disk_name
+        );
+        Ok(())
+    }
+
+    async fn apply_cluster_map(&self, mapper: &Virtual) -> Result<()> {
+        let same =
+            |a: &Group, b: &Group| a.vdisk_id() == b.vdisk_id() && a.disk_name() == b.disk_name();
+        let assigned =
+            self.settings
+                .clone()
+                .local_groups(mapper, &self.node_name, self.init_par_degree);
+        let (reused, created) = {
+            let groups = self.vdisks_groups.read().await;
+            let mut released = self.released_vdisks_groups.write().await;
+            let mut reused = vec![];
+            let mut created = vec![];
+            for new in assigned
+                .iter()
+                .filter(|new| !groups.iter().any(|group| same(group, new)))
+            {
+                // vdisk returned to the node, its group is still open
+                if let Some(position) = released.iter().position(|group| same(group, new)) {
+                    reused.push(released.remove(position));
+                } else {
+                    created.push(new.clone());
+                }
+            }
+            (reused, created)
+        };
+        // groups are initialized before they become visible for operations
+        for group in &created {
+            group.run().await?;
+        }
+        for group in reused.iter().chain(created.iter()) {
+            info!(
+                "vdisk {} on disk {} is assigned to the node",
+                group.vdisk_id(),
+                group.disk_name()
+            );
+        }
+        let released = {
+            let mut groups = self.vdisks_groups.write().await;
+            let (kept, released): (Vec<_>, Vec<_>) = groups
+                .drain(..)
+                .partition(|group| assigned.iter().any(|new| same(group, new)));
+            *groups = kept;
+            groups.extend(reused);
+            groups.extend(created);
+            released
+        };
+        // released groups stay readable, so new owners can pull their data
+        for group in &released {
+            info!(
+                "vdisk {} on disk {} is released by the node",
+                group.vdisk_id(),
+                group.disk_name()
+            );
+        }
+        self.released_vdisks_groups.write().await.extend(released);
+        Ok(())
     }
 }
@@ -1,14 +1,19 @@
 use super::prelude::*;
+use ring::aead::NONCE_LEN;
+use std::collections::BTreeMap;
+use std::io::Read;
 
 #[derive(Clone, Debug)]
 pub(crate) struct Key(Vec<u8>);
 
-impl From<u64> for Key {
-    fn from(key: u64) -> Self {
-        Self(key.to_be_bytes().to_vec())
+impl From<BobKey> for Key {
+    fn from(key: BobKey) -> Self {
+        Self(key.as_ref().to_vec())
     }
 }
 
+// pearl doesn't check the length, but index requires keys of the same length in every blob,
+// which is provided by cluster `key_length`
 impl KeyTrait for Key {
     const LEN: u16 = 8;
 }
@@ -19,36 +24,408 @@ impl AsRef<[u8]> for Key {
     }
 }
 
+bitflags! {
+    #[derive(Default)]
+    pub(crate) struct RecordFlags: u8 {
+        const TOMBSTONE = 0x01;
+        const MANIFEST = 0x02;
+        const HEADERS = 0x04;
+        const CHECKSUM = 0x08;
+        const EXPIRES = 0x10;
+        const ZSTD = 0x20;
+        const LZ4 = 0x40;
+        const ENCRYPTED = 0x80;
+    }
+}
+
+/// Record layout: `[timestamp: u64 BE][data]`.
+/// Records with non empty flags set the highest bit of the timestamp and keep flags right after
+/// it: `[timestamp | EXTENDED_MARK: u64 BE][flags: u8][data]`, so old records are still readable.
+/// Records with `CHECKSUM` flag keep CRC32C of data right after flags: `[checksum: u32 BE]`.
+/// Records with `EXPIRES` flag keep expiration timestamp after checksum: `[expires at: u64 BE]`.
+/// Records with `HEADERS` flag keep headers before data:
+/// `[headers len: u32 BE][count: u16 BE]([name len: u16 BE][name][value len: u16 BE][value])*`.
+/// Records with `ZSTD` or `LZ4` flag keep compressed data, checksum is calculated before
+/// compression. Only data up to `MAX_COMPRESSED_LEN` is compressed, so longer decompressed data
+/// means corrupted record.
+/// Records with `ENCRYPTED` flag keep id of the key and nonce after headers:
+/// `[key id: u32 BE][nonce: 12 bytes]`, data is encrypted after compression and followed by
+/// authentication tag. Checksum of the encrypted record is encrypted with its data:
+/// `[checksum: u32 BE][data]`, record key and head are authenticated with the tag.
 pub(crate) struct Data {
     data: Vec<u8>,
     timestamp: u64,
+    flags: RecordFlags,
+    checksum: u32,
+    expires_at: u64,
+    headers: BTreeMap<String, String>,
+    key_id: u32,
+    nonce: [u8; NONCE_LEN],
 }
 
 impl Data {
     const TIMESTAMP_LEN: usize = 8;
+    const FLAGS_LEN: usize = 1;
+    const EXTENDED_MARK: u64 = 1 << 63;
+    const HEADERS_LEN: usize = 4;
+    const CHECKSUM_LEN: usize = 4;
+    const KEY_ID_LEN: usize = 4;
+    const LZ4_SIZE_LEN: usize = 4;
+    const MAX_COMPRESSED_LEN: usize = 1 << 30;
+    // pearl meta of the record keeps its head and data size, so version of the record is read
+    // without data
+    const HEAD_META: &'static str = "head";
+    const SIZE_META: &'static str = "size";
 
     pub(crate) fn to_vec(&self) -> Vec<u8> {
-        let mut result = self.timestamp.to_be_bytes().to_vec();
+        let mut result = self.head_to_vec();
         result.extend_from_slice(&self.data);
         result
     }
 
-    pub(crate) fn from_bytes(data: &[u8]) -> Result<BobData, Error> {
-        let (ts, bob_data) = data.split_at(Self::TIMESTAMP_LEN);
-        let bytes = ts
-            .try_into()
-            .map_err(|e| Error::storage(format!("parse error: {}", e)))?;
-        let timestamp = u64::from_be_bytes(bytes);
-        let meta = BobMeta::new(timestamp);
-        Ok(BobData::new(bob_data.to_vec(), meta))
+    // all fields stored before data
+    fn head_to_vec(&self) -> Vec<u8> {
+        if self.flags.is_empty() {
+            return self.timestamp.to_be_bytes().to_vec();
+        }
+        let mut header = (self.timestamp | Self::EXTENDED_MARK)
+            .to_be_bytes()
+            .to_vec();
+        header.push(self.flags.bits());
+        if self.flags.contains(RecordFlags::CHECKSUM)
+            && !self.flags.contains(RecordFlags::ENCRYPTED)
+        {
+            header.extend_from_slice(&self.checksum.to_be_bytes());
+        }
+        if self.flags.contains(RecordFlags::EXPIRES) {
+            header.extend_from_slice(&self.expires_at.to_be_bytes());
+        }
+        if self.flags.contains(RecordFlags::HEADERS) {
+            let headers = Self::headers_to_vec(&self.headers);
+            header.extend_from_slice(&(headers.len() as u32).to_be_bytes());
+            header.extend_from_slice(&headers);
+        }
+        if self.flags.contains(RecordFlags::ENCRYPTED) {
+            header.extend_from_slice(&self.key_id.to_be_bytes());
+            header.extend_from_slice(&self.nonce);
+        }
+        header
+    }
+
+    /// Pearl meta with the head of the record and given size of the data before compression
+    /// and encryption
+    pub(crate) fn pearl_meta(&self, size: usize) -> PearlMeta {
+        let mut meta = PearlMeta::new();
+        meta.insert(Self::HEAD_META.to_owned(), self.head_to_vec());
+        meta.insert(
+            Self::SIZE_META.to_owned(),
+            (size as u64).to_be_bytes().to_vec(),
+        );
+        meta
+    }
+
+    /// Version of the record from pearl meta, `None` if record was written without meta
+    pub(crate) fn head_from_meta(meta: &PearlMeta) -> Option<Result<BobHead, Error>> {
+        let head = meta.get(Self::HEAD_META)?;
+        let size = meta.get(Self::SIZE_META)?;
+        Some(Self::parse(head).and_then(|(record, _)| {
+            let size = size
+                .as_slice()
+                .try_into()
+                .map_err(|_| Error::storage("parse error: record size is missing"))?;
+            Ok(BobHead::new(
+                record.bob_meta(),
+                u64::from_be_bytes(size),
+                record.flags.contains(RecordFlags::TOMBSTONE),
+                record.flags.contains(RecordFlags::MANIFEST),
+            ))
+        }))
+    }
+
+    /// Compresses data if it's not smaller than the configured min size and compression reduces
+    /// its size, returns sizes of the data before and after compression
+    pub(crate) fn compress(&mut self, config: &CompressionConfig) -> Option<(usize, usize)> {
+        if self.data.len() < config.min_size() || self.data.len() > Self::MAX_COMPRESSED_LEN {
+            return None;
+        }
+        let (compressed, flag) = match config.algorithm() {
+            CompressionAlgorithm::Zstd => {
+                match zstd::stream::encode_all(&self.data[..], config.level()) {
+                    Ok(compressed) => (compressed, RecordFlags::ZSTD),
+                    Err(e) => {
+                        warn!("record is stored uncompressed, zstd error: {}", e);
+                        return None;
+                    }
+                }
+            }
+            CompressionAlgorithm::Lz4 => (
+                lz4_flex::compress_prepend_size(&self.data),
+                RecordFlags::LZ4,
+            ),
+        };
+        if compressed.len() >= self.data.len() {
+            return None;
+        }
+        let sizes = (self.data.len(), compressed.len());
+        self.data = compressed;
+        self.flags |= flag;
+        Some(sizes)
+    }
+
+    /// Encrypts data with the current key of the keyring, tombstones have no data and are
+    /// stored as is
+    pub(crate) fn encrypt(&mut self, keyring: &Keyring, key: BobKey) -> Result<(), Error> {
+        if self.flags.contains(RecordFlags::TOMBSTONE) {
+            return Ok(());
+        }
+        self.key_id = keyring.current_key_id();
+        self.nonce = keyring.nonce()?;
+        self.flags |= RecordFlags::ENCRYPTED;
+        // checksum of the plain data would reveal it, so it's encrypted too
+        if self.flags.contains(RecordFlags::CHECKSUM) {
+            let mut data = Vec::with_capacity(Self::CHECKSUM_LEN + self.data.len());
+            data.extend_from_slice(&self.checksum.to_be_bytes());
+            data.extend_from_slice(&self.data);
+            self.data = data;
+        }
+        let aad = Self::aad(key, &self.head_to_vec());
+        keyring.seal(key, self.nonce, &aad, &mut self.data)
+    }
+
+    fn aad(key: BobKey, head: &[u8]) -> Vec<u8> {
+        let mut aad = key.as_ref().to_vec();
+        aad.extend_from_slice(head);
+        aad
+    }
+
+    // payload that can't be decompressed is corrupted like the one with wrong checksum
+    fn decompress(flags: RecordFlags, data: Vec<u8>, key: BobKey) -> Result<Vec<u8>, Error> {
+        let result = if flags.contains(RecordFlags::ZSTD) {
+            Self::zstd_decompress(&data)
+        } else if flags.contains(RecordFlags::LZ4) {
+            Self::lz4_decompress(&data)
+        } else {
+            return Ok(data);
+        };
+        result.map_err(|e| {
+            warn!("record {} can't be decompressed: {}", key, e);
+            Error::data_corrupted(key)
+        })
+    }
+
+    fn zstd_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+        let decoder = zstd::stream::read::Decoder::new(data).map_err(|e| e.to_string())?;
+        let mut result = vec![];
+        decoder
+            .take(Self::MAX_COMPRESSED_LEN as u64 + 1)
+            .read_to_end(&mut result)
+            .map_err(|e| format!("zstd: {}", e))?;
+        if result.len() > Self::MAX_COMPRESSED_LEN {
+            return Err("zstd: decompressed data is too long".to_owned());
+        }
+        Ok(result)
+    }
+
+    // size of the decompressed data is stored by lz4 as u32 LE before the compressed data
+    fn lz4_decompress(data: &[u8]) -> Result<Vec<u8>, String> {
+        if data.len() < Self::LZ4_SIZE_LEN {
+            return Err("lz4: size is missing".to_owned());
+        }
+        let (size, data) = data.split_at(Self::LZ4_SIZE_LEN);
+        let size = u32::from_le_bytes(size.try_into().expect("4 bytes")) as usize;
+        if size > Self::MAX_COMPRESSED_LEN {
+            return Err(format!("lz4: decompressed size {} is too long", size));
+        }
+        lz4_flex::decompress(data, size).map_err(|e| format!("lz4: {}", e))
+    }
+
+    /// Checks if data of the record is compressed with zstd, which is too slow to be
+    /// decompressed on the runtime threads
+    pub(crate) fn is_zstd(data: &[u8]) -> Result<bool, Error> {
+        let (record, _) = Self::parse(data)?;
+        Ok(record.flags.contains(RecordFlags::ZSTD))
+    }
+
+    /// Checks if record isn't encrypted or encrypted with other key, tombstones are never
+    /// encrypted, so they don't need re-encryption
+    pub(crate) fn needs_reencryption(data: &[u8], key_id: u32) -> Result<bool, Error> {
+        let (record, _) = Self::parse(data)?;
+        Ok(!record.flags.contains(RecordFlags::TOMBSTONE)
+            && (!record.flags.contains(RecordFlags::ENCRYPTED) || record.key_id != key_id))
+    }
+
+    // parses fields stored before data, returns record without data and the rest of bytes
+    fn parse(data: &[u8]) -> Result<(Self, &[u8]), Error> {
+        if data.len() < Self::TIMESTAMP_LEN {
+            return Err(Error::storage(format!(
+                "parse error: record is too short: {}",
+                data.len()
+            )));
+        }
+        let (ts, mut rest) = data.split_at(Self::TIMESTAMP_LEN);
+        let timestamp = u64::from_be_bytes(ts.try_into().expect("8 bytes"));
+        let mut record = Self {
+            data: vec![],
+            timestamp,
+            flags: RecordFlags::default(),
+            checksum: 0,
+            expires_at: 0,
+            headers: BTreeMap::new(),
+            key_id: 0,
+            nonce: [0; NONCE_LEN],
+        };
+        if timestamp & Self::EXTENDED_MARK == 0 {
+            return Ok((record, rest));
+        }
+        record.timestamp = timestamp & !Self::EXTENDED_MARK;
+        let mut next = |len: usize, field: &str| -> Result<&[u8], Error> {
+            if rest.len() < len {
+                let msg = format!("parse error: record {} is missing", field);
+                return Err(Error::storage(msg));
+            }
+            let (item, tail) = rest.split_at(len);
+            rest = tail;
+            Ok(item)
+        };
+        let flags = next(Self::FLAGS_LEN, "flags")?[0];
+        record.flags = RecordFlags::from_bits(flags)
+            .ok_or_else(|| Error::storage(format!("parse error: unknown flags {}", flags)))?;
+        if record.flags.contains(RecordFlags::CHECKSUM)
+            && !record.flags.contains(RecordFlags::ENCRYPTED)
+        {
+            let checksum = next(Self::CHECKSUM_LEN, "checksum")?;
+            record.checksum = u32::from_be_bytes(checksum.try_into().expect("4 bytes"));
+        }
+        if record.flags.contains(RecordFlags::EXPIRES) {
+            let expires_at = next(Self::TIMESTAMP_LEN, "expiration")?;
+            record.expires_at = u64::from_be_bytes(expires_at.try_into().expect("8 bytes"));
+        }
+        if record.flags.contains(RecordFlags::HEADERS) {
+            let len = next(Self::HEADERS_LEN, "headers")?;
+            let len = u32::from_be_bytes(len.try_into().expect("4 bytes")) as usize;
+            record.headers = Self::headers_from_bytes(next(len, "headers")?)?;
+        }
+        if record.flags.contains(RecordFlags::ENCRYPTED) {
+            let key_id = next(Self::KEY_ID_LEN, "key id")?;
+            record.key_id = u32::from_be_bytes(key_id.try_into().expect("4 bytes"));
+            record.nonce = next(NONCE_LEN, "nonce")?.try_into().expect("nonce");
+        }
+        Ok((record, rest))
+    }
+
+    fn bob_meta(&self) -> BobMeta {
+        let mut meta = BobMeta::with_headers(self.timestamp, self.headers.clone());
+        if self.flags.contains(RecordFlags::EXPIRES) {
+            meta.set_expires_at(Some(self.expires_at));
+        }
+        meta
+    }
+
+    /// Parses record, encrypted payload is decrypted with the keyring
+    pub(crate) fn from_bytes(
+        data: &[u8],
+        key: BobKey,
+        keyring: Option<&Keyring>,
+    ) -> Result<BobData, Error> {
+        let (mut record, payload) = Self::parse(data)?;
+        let head = &data[..data.len() - payload.len()];
+        let flags = record.flags;
+        let meta = record.bob_meta();
+        let mut payload = payload.to_vec();
+        if flags.contains(RecordFlags::ENCRYPTED) {
+            let keyring = keyring.ok_or_else(|| {
+                Error::encryption_key_missing(format!(
+                    "record {} is encrypted, but encryption isn't configured",
+                    key
+                ))
+            })?;
+            let aad = Self::aad(key, head);
+            keyring.open(key, record.key_id, record.nonce, &aad, &mut payload)?;
+            if flags.contains(RecordFlags::CHECKSUM) {
+                if payload.len() < Self::CHECKSUM_LEN {
+                    return Err(Error::data_corrupted(key));
+                }
+                let data = payload.split_off(Self::CHECKSUM_LEN);
+                record.checksum = u32::from_be_bytes(payload[..].try_into().expect("4 bytes"));
+                payload = data;
+            }
+        }
+        let data = if flags.contains(RecordFlags::TOMBSTONE) {
+            BobData::tombstone(meta)
+        } else if flags.contains(RecordFlags::MANIFEST) {
+            BobData::manifest(Self::decompress(flags, payload, key)?, meta)
+        } else {
+            BobData::new(Self::decompress(flags, payload, key)?, meta)
+        };
+        Ok(if flags.contains(RecordFlags::CHECKSUM) {
+            data.with_checksum(record.checksum)
+        } else {
+            data
+        })
+    }
+
+    fn headers_to_vec(headers: &BTreeMap<String, String>) -> Vec<u8> {
+        let mut result = (headers.len() as u16).to_be_bytes().to_vec();
+        for (name, value) in headers {
+            for s in &[name, value] {
+                result.extend_from_slice(&(s.len() as u16).to_be_bytes());
+                result.extend_from_slice(s.as_bytes());
+            }
+        }
+        result
+    }
+
+    fn headers_from_bytes(mut data: &[u8]) -> Result<BTreeMap<String, String>, Error> {
+        let mut next = |len: usize| -> Result<&[u8], Error> {
+            if data.len() < len {
+                return Err(Error::storage("parse error: record headers are too short"));
+            }
+            let (item, rest) = data.split_at(len);
+            data = rest;
+            Ok(item)
+        };
+        let count = u16::from_be_bytes(next(2)?.try_into().expect("2 bytes"));
+        let mut next_string = || -> Result<String, Error> {
+            let len = u16::from_be_bytes(next(2)?.try_into().expect("2 bytes"));
+            String::from_utf8(next(len as usize)?.to_vec())
+                .map_err(|e| Error::storage(format!("parse error: {}", e)))
+        };
+        (0..count)
+            .map(|_| Ok((next_string()?, next_string()?)))
+            .collect()
     }
 }
 
 impl From<BobData> for Data {
     fn from(data: BobData) -> Self {
+        let mut flags = RecordFlags::default();
+        if data.is_tombstone() {
+            flags |= RecordFlags::TOMBSTONE;
+        }
+        if data.is_manifest() {
+            flags |= RecordFlags::MANIFEST;
+        }
+        if data.checksum().is_some() {
+            flags |= RecordFlags::CHECKSUM;
+        }
+        let checksum = data.checksum().unwrap_or_default();
+        let expires_at = data.meta().expires_at();
+        if expires_at.is_some() {
+            flags |= RecordFlags::EXPIRES;
+        }
+        let headers = data.meta().headers().clone();
+        if !headers.is_empty() {
+            flags |= RecordFlags::HEADERS;
+        }
         Self {
             timestamp: data.meta().timestamp(),
             data: data.into_inner(),
+            flags,
+            checksum,
+            expires_at: expires_at.unwrap_or_default(),
+            headers,
+            key_id: 0,
+            nonce: [0; NONCE_LEN],
         }
     }
 }
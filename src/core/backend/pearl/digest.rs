@@ -0,0 +1,157 @@
+use super::prelude::*;
+use crate::grpc::{HolderHash, KeyVersion};
+use ring::digest::Context;
+
+/// Most recent version of the key stored in holder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct RecordVersion {
+    key: BobKey,
+    timestamp: u64,
+    tombstone: bool,
+}
+
+impl RecordVersion {
+    pub(crate) fn new(key: BobKey, timestamp: u64, tombstone: bool) -> Self {
+        Self {
+            key,
+            timestamp,
+            tombstone,
+        }
+    }
+
+    pub(crate) fn from_head(key: BobKey, head: &BobHead) -> Self {
+        Self::new(key, head.meta().timestamp(), head.is_tombstone())
+    }
+
+    pub(crate) fn key(&self) -> BobKey {
+        self.key
+    }
+
+    pub(crate) fn timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub(crate) fn is_tombstone(&self) -> bool {
+        self.tombstone
+    }
+
+    /// Ordering is the same as in [`BobData::most_recent`]
+    pub(crate) fn is_newer_than(&self, other: &Self) -> bool {
+        (self.timestamp, self.tombstone) > (other.timestamp, other.tombstone)
+    }
+
+    /// Keeps the most recent version of each key, result is sorted by key
+    pub(crate) fn latest(versions: impl IntoIterator<Item = Self>) -> Vec<Self> {
+        let mut latest: HashMap<BobKey, Self> = HashMap::new();
+        for version in versions {
+            match latest.get(&version.key) {
+                Some(current) if !version.is_newer_than(current) => {}
+                _ => {
+                    latest.insert(version.key, version);
+                }
+            }
+        }
+        let mut versions = latest.into_iter().map(|(_, v)| v).collect::<Vec<_>>();
+        versions.sort_by_key(Self::key);
+        versions
+    }
+}
+
+/// Digest of the holder content, used to find diverged holders of the vdisk replicas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct HolderDigest {
+    start_timestamp: u64,
+    records: usize,
+    // holders without keys log can't be enumerated, so their hash is unknown
+    hash: Option<Vec<u8>>,
+}
+
+impl HolderDigest {
+    pub(crate) fn new(start_timestamp: u64, records: usize, hash: Option<Vec<u8>>) -> Self {
+        Self {
+            start_timestamp,
+            records,
+            hash,
+        }
+    }
+
+    pub(crate) fn from_versions(start_timestamp: u64, versions: &[RecordVersion]) -> Self {
+        let mut sorted = versions.to_vec();
+        sorted.sort_by_key(RecordVersion::key);
+        let mut context = Context::new(&SHA256);
+        for version in &sorted {
+            context.update(version.key.as_ref());
+            context.update(&version.timestamp.to_be_bytes());
+            context.update(&[u8::from(version.tombstone)]);
+        }
+        let hash = context.finish().as_ref().to_vec();
+        Self::new(start_timestamp, versions.len(), Some(hash))
+    }
+
+    pub(crate) fn unknown(start_timestamp: u64, records: usize) -> Self {
+        Self::new(start_timestamp, records, None)
+    }
+
+    pub(crate) fn start_timestamp(&self) -> u64 {
+        self.start_timestamp
+    }
+
+    pub(crate) fn records(&self) -> usize {
+        self.records
+    }
+
+    pub(crate) fn hash(&self) -> Option<&[u8]> {
+        self.hash.as_deref()
+    }
+
+    /// Returns false if any of the digests is unknown
+    pub(crate) fn differs_from(&self, other: &Self) -> bool {
+        match (self.hash(), other.hash()) {
+            (Some(hash), Some(other)) => hash != other,
+            _ => false,
+        }
+    }
+}
+
+impl TryFrom<KeyVersion> for RecordVersion {
+    type Error = Error;
+
+    fn try_from(version: KeyVersion) -> Result<Self, Error> {
+        let key = version
+            .key
+            .ok_or_else(|| Error::invalid_key("version without key"))?;
+        let key = BobKey::try_from(key)?;
+        Ok(Self::new(key, version.timestamp, version.tombstone))
+    }
+}
+
+impl From<RecordVersion> for KeyVersion {
+    fn from(version: RecordVersion) -> Self {
+        Self {
+            key: Some(BlobKey::from(version.key)),
+            timestamp: version.timestamp,
+            tombstone: version.tombstone,
+        }
+    }
+}
+
+impl From<HolderHash> for HolderDigest {
+    fn from(holder: HolderHash) -> Self {
+        let hash = if holder.hash.is_empty() {
+            None
+        } else {
+            Some(holder.hash)
+        };
+        Self::new(holder.start_timestamp, holder.records as usize, hash)
+    }
+}
+
+impl From<HolderDigest> for HolderHash {
+    fn from(digest: HolderDigest) -> Self {
+        Self {
+            start_timestamp: digest.start_timestamp,
+            records: digest.records as u64,
+            hash: digest.hash.unwrap_or_default(),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+use super::prelude::*;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// Keys of the records encryption. New records are encrypted with the current key, old keys
+/// are kept to read records written before rotation. Record key and head are used as associated
+/// data, so encrypted payload can't be moved to another key and head can't be changed.
+pub(crate) struct Keyring {
+    current: u32,
+    keys: HashMap<u32, LessSafeKey>,
+    random: SystemRandom,
+}
+
+impl Keyring {
+    pub(crate) fn new(config: &EncryptionConfig) -> Result<Self, String> {
+        let (current, keys) = config.keys()?;
+        let keys = keys
+            .into_iter()
+            .map(|(id, key)| {
+                UnboundKey::new(&AES_256_GCM, &key)
+                    .map(|key| (id, LessSafeKey::new(key)))
+                    .map_err(|_| format!("key {} is not valid AES-256 key", id))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            current,
+            keys,
+            random: SystemRandom::new(),
+        })
+    }
+
+    /// Id of the key, which is used for new records
+    pub(crate) fn current_key_id(&self) -> u32 {
+        self.current
+    }
+
+    /// Random nonce for the next record, nonce is stored in the record head, so it's
+    /// authenticated with the head
+    pub(crate) fn nonce(&self) -> Result<[u8; NONCE_LEN], Error> {
+        let mut nonce = [0; NONCE_LEN];
+        self.random
+            .fill(&mut nonce)
+            .map_err(|_| Error::internal())?;
+        Ok(nonce)
+    }
+
+    /// Encrypts data in place with the current key
+    pub(crate) fn seal(
+        &self,
+        key: BobKey,
+        nonce: [u8; NONCE_LEN],
+        aad: &[u8],
+        data: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        self.keys[&self.current]
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(aad), data)
+            .map_err(|_| Error::storage(format!("can't encrypt record {}", key)))
+    }
+
+    /// Decrypts data in place, data which doesn't match authentication tag is considered
+    /// corrupted
+    pub(crate) fn open(
+        &self,
+        key: BobKey,
+        key_id: u32,
+        nonce: [u8; NONCE_LEN],
+        aad: &[u8],
+        data: &mut Vec<u8>,
+    ) -> Result<(), Error> {
+        let cipher = self.keys.get(&key_id).ok_or_else(|| {
+            Error::encryption_key_missing(format!(
+                "record {} is encrypted with unknown key {}",
+                key, key_id
+            ))
+        })?;
+        let len = cipher
+            .open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(aad), data)
+            .map_err(|_| Error::data_corrupted(key))?
+            .len();
+        data.truncate(len);
+        Ok(())
+    }
+}
+
+// keys mustn't get to the logs
+impl Debug for Keyring {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let mut ids: Vec<_> = self.keys.keys().collect();
+        ids.sort_unstable();
+        f.debug_struct("Keyring")
+            .field("current", &self.current)
+            .field("keys", &ids)
+            .finish()
+    }
+}
@@ -1,4 +1,13 @@
 use super::prelude::*;
+use std::collections::BTreeMap;
+use std::fs::rename;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// compacted holder is written to the directory with this prefix and renamed when it's complete
+const COMPACTION_DIR_PREFIX: &str = "compaction_";
+// source holder with the same name as the compacted one is moved to the directory with this
+// prefix and dropped after swap
+const REPLACED_DIR_PREFIX: &str = "replaced_";
 
 #[derive(Clone, Debug)]
 pub(crate) struct Group {
@@ -11,6 +20,9 @@ pub(crate) struct Group {
     owner_node_name: String,
     created_holder_indexes: Arc<RwLock<HashMap<u64, usize>>>,
     dump_sem: Arc<Semaphore>,
+    disk: Option<Arc<DiskHealth>>,
+    // set when holders are loaded, operations on the loading group are refused
+    ready: Arc<AtomicBool>,
 }
 
 impl Group {
@@ -23,6 +35,7 @@ impl Group {
         owner_node_name: String,
         dump_sem: Arc<Semaphore>,
     ) -> Self {
+        let disk = settings.disk_health(&disk_name);
         Self {
             holders: Arc::new(RwLock::new(vec![])),
             settings,
@@ -33,6 +46,8 @@ impl Group {
             owner_node_name,
             created_holder_indexes: Arc::default(),
             dump_sem,
+            disk,
+            ready: Arc::default(),
         }
     }
 
@@ -70,14 +85,72 @@ impl Group {
         debug!("{}: save holders to group", self);
         self.add_range(holders).await;
         debug!("{}: start holders", self);
-        self.run_pearls().await
+        self.run_pearls().await?;
+        self.set_ready(true);
+        info!(
+            "vdisk {} on disk {} is ready",
+            self.vdisk_id, self.disk_name
+        );
+        Ok(())
     }
 
     pub async fn remount(&self) -> Result<()> {
+        self.set_ready(false);
         self.holders.write().await.clear();
         self.run().await
     }
 
+    pub fn is_ready(&self) -> bool {
+        self.ready.load(Ordering::Acquire)
+    }
+
+    pub(crate) fn set_ready(&self, ready: bool) {
+        self.ready.store(ready, Ordering::Release);
+    }
+
+    fn check_ready(&self) -> Result<(), Error> {
+        if self.is_ready() {
+            Ok(())
+        } else {
+            trace!("{} isn't ready", self);
+            Err(Error::vdisk_is_not_ready())
+        }
+    }
+
+    /// Closes storages of all holders, they are opened again by remount
+    pub async fn close(&self) {
+        let holders = self.holders.read().await;
+        for holder in holders.iter() {
+            holder.close().await;
+        }
+    }
+
+    fn check_disk(&self) -> Result<(), Error> {
+        self.disk
+            .as_ref()
+            .map_or(Ok(()), |disk| disk.check_available())
+    }
+
+    fn check_space(&self) -> Result<(), Error> {
+        self.disk.as_ref().map_or(Ok(()), |disk| disk.check_space())
+    }
+
+    fn is_disk_available(&self) -> bool {
+        self.disk.as_ref().map_or(true, |disk| disk.is_available())
+    }
+
+    fn record_disk_success(&self, timer: Instant) {
+        if let Some(disk) = &self.disk {
+            disk.record_success(timer.elapsed());
+        }
+    }
+
+    fn record_disk_error(&self, error: &Error) {
+        if let Some(disk) = &self.disk {
+            disk.record_error(error);
+        }
+    }
+
     async fn run_pearls(&self) -> Result<()> {
         let holders = self.holders.write().await;
 
@@ -104,7 +177,7 @@ impl Group {
         self.find_actual_holder(data)
             .or_else(|e| {
                 debug!("cannot find pearl: {}", e);
-                self.create_write_pearl(data.meta().timestamp())
+                self.create_write_pearl(data)
             })
             .await
     }
@@ -125,7 +198,12 @@ impl Group {
     }
 
     // create pearl for current write
-    async fn create_write_pearl(&self, ts: u64) -> Result<Holder> {
+    async fn create_write_pearl(&self, data: &BobData) -> Result<Holder> {
+        // new holders aren't created on the full disk, except for tombstones
+        if !data.is_tombstone() {
+            self.check_space()?;
+        }
+        let ts = data.meta().timestamp();
         let mut indexes = self.created_holder_indexes.write().await;
         let created_holder_index = indexes.get(&ts).copied();
         let index = if let Some(exisiting_index) = created_holder_index {
@@ -161,35 +239,65 @@ impl Group {
     }
 
     pub async fn put(&self, key: BobKey, data: BobData) -> Result<(), Error> {
-        let holder = self
-            .get_actual_holder(&data)
-            .await
-            .map_err(|e| Error::failed(format!("{:#?}", e)))?;
-        Self::put_common(holder, key, data).await
+        self.check_ready()?;
+        self.check_disk()?;
+        // tombstones are written to the full disk, so records can be deleted to free space
+        if !data.is_tombstone() {
+            self.check_space()?;
+        }
+        let holder = self.get_write_holder(&data).await?;
+        match self.put_common(holder.clone(), key, &data).await {
+            // holder was replaced by compaction or removed while write was waiting for it, so
+            // write goes to the holder, which took its place
+            Err(e) if e.is_not_ready() && holder.is_closed_for_writes() => {
+                let holder = self.get_write_holder(&data).await?;
+                self.put_common(holder, key, &data).await
+            }
+            result => result,
+        }
     }
 
-    async fn put_common(holder: Holder, key: BobKey, data: BobData) -> Result<(), Error> {
-        let result = holder.write(key, data).await;
-        if let Err(e) = result {
-            if !e.is_duplicate() && !e.is_not_ready() {
-                error!("pearl holder will restart: {:?}", e);
-                holder.try_reinit().await?;
-                holder
-                    .prepare_storage()
-                    .await
-                    .map_err(|e| Error::storage(format!("{:#?}", e)))?;
-                debug!("backend pearl group put common storage prepared");
+    async fn get_write_holder(&self, data: &BobData) -> Result<Holder, Error> {
+        self.get_actual_holder(data).await.map_err(|e| {
+            e.downcast::<Error>()
+                .unwrap_or_else(|e| Error::failed(format!("{:#?}", e)))
+        })
+    }
+
+    async fn put_common(&self, holder: Holder, key: BobKey, data: &BobData) -> Result<(), Error> {
+        let timer = Instant::now();
+        match holder.write(key, data).await {
+            Ok(()) => {
+                self.record_disk_success(timer);
+                Ok(())
+            }
+            Err(e) if e.is_not_ready() => Err(e),
+            Err(e) => {
+                self.record_disk_error(&e);
+                // storages on the failed disk are remounted only after the disk is started
+                if self.is_disk_available() {
+                    error!("pearl holder will restart: {:?}", e);
+                    holder.try_reinit().await?;
+                    holder
+                        .prepare_storage()
+                        .await
+                        .map_err(|e| Error::storage(format!("{:#?}", e)))?;
+                    debug!("backend pearl group put common storage prepared");
+                }
+                Err(e)
             }
         }
-        Ok(())
     }
 
     pub async fn get(&self, key: BobKey) -> Result<BobData, Error> {
+        self.check_ready()?;
+        self.check_disk()?;
         let holders = self.holders.read().await;
         let mut has_error = false;
+        let mut corrupted_until = None;
         let mut results = vec![];
         for holder in holders.iter() {
-            let get = Self::get_common(holder.clone(), key).await;
+            let get = self.get_common(holder.clone(), key).await;
             match get {
                 Ok(data) => {
                     trace!("get data: {:?} from: {:?}", data, holder);
@@ -198,6 +306,9 @@ impl Group {
                 Err(err) => {
                     if err.is_key_not_found() {
                         debug!("{} not found in {:?}", key, holder)
+                    } else if err.is_data_corrupted() {
+                        corrupted_until = corrupted_until.max(Some(holder.end_timestamp()));
+                        error!("{} in {:?}", err, holder);
                     } else {
                         has_error = true;
                         error!("get error: {}, from : {:?}", err, holder);
@@ -205,6 +316,12 @@ impl Group {
                 }
             }
         }
+        // corrupted version is ignored only if there is a valid version from the newer holder
+        if let Some(end) = corrupted_until {
+            if results.iter().all(|data| data.meta().timestamp() < end) {
+                return Err(Error::data_corrupted(key));
+            }
+        }
         if results.is_empty() {
             if has_error {
                 debug!("cannot read from some pearls");
@@ -215,15 +332,31 @@ impl Group {
             }
         } else {
             debug!("get with max timestamp, from {} results", results.len());
-            Ok(Settings::choose_most_recent_data(results)
-                .expect("results cannot be empty, because of the previous check"))
+            let data = Settings::choose_most_recent_data(results)
+                .expect("results cannot be empty, because of the previous check");
+            if data.is_tombstone() {
+                debug!("{} deleted at {}", key, data.meta().timestamp());
+                Err(Error::key_deleted(key, data.meta().timestamp()))
+            } else if data.meta().is_expired() {
+                debug!("{} expired at {:?}", key, data.meta().expires_at());
+                Err(Error::key_not_found(key))
+            } else {
+                Ok(data)
+            }
         }
     }
 
-    async fn get_common(holder: Holder, key: BobKey) -> Result<BobData, Error> {
+    async fn get_common(&self, holder: Holder, key: BobKey) -> Result<BobData, Error> {
+        let timer = Instant::now();
         let result = holder.read(key).await;
-        if let Err(e) = &result {
-            if !e.is_key_not_found() && !e.is_not_ready() {
+        match &result {
+            // record encrypted with unknown key isn't a disk failure
+            Err(e) if e.is_not_ready() || e.is_encryption_key_missing() => {}
+            Err(e) if !e.is_key_not_found() && !e.is_data_corrupted() => {
+                self.record_disk_error(e);
+                if !self.is_disk_available() {
+                    return result;
+                }
                 holder.try_reinit().await?;
                 holder
                     .prepare_storage()
@@ -231,21 +364,270 @@ impl Group {
                     .map_err(|e| Error::storage(format!("{:#?}", e)))?;
                 debug!("backend pearl group get common storage prepared");
             }
+            _ => self.record_disk_success(timer),
         }
         result
     }
 
-    pub async fn exist(&self, keys: &[BobKey]) -> Vec<bool> {
-        let mut exist = vec![false; keys.len()];
-        let holders = self.holders.read().await;
-        for (ind, &key) in keys.iter().enumerate() {
-            for holder in holders.iter() {
-                if !exist[ind] {
-                    exist[ind] = holder.exist(key).await.unwrap_or(false);
+    pub async fn exist(&self, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
+        self.check_ready()?;
+        let holders = self.holders.read().await.clone();
+        let mut exist = Vec::with_capacity(keys.len());
+        for &key in keys {
+            let head = Self::latest_head(&holders, key).await.unwrap_or_default();
+            exist.push(head.map_or(false, |head| {
+                !head.is_tombstone() && !head.meta().is_expired()
+            }));
+        }
+        Ok(exist)
+    }
+
+    /// Returns the most recent version of the key without reading data, deleted and expired
+    /// keys are not found as by [`Group::get`]. Corrupted data isn't detected.
+    pub async fn head(&self, key: BobKey) -> Result<BobHead, Error> {
+        self.check_ready()?;
+        self.check_disk()?;
+        let holders = self.holders.read().await.clone();
+        match Self::latest_head(&holders, key).await? {
+            Some(head) if head.is_tombstone() => {
+                Err(Error::key_deleted(key, head.meta().timestamp()))
+            }
+            Some(head) if !head.meta().is_expired() => Ok(head),
+            _ => Err(Error::key_not_found(key)),
+        }
+    }
+
+    // key may be deleted by the tombstone in any holder, so versions are compared, but only
+    // holders which index contains the key read its version from meta
+    async fn latest_head(holders: &[Holder], key: BobKey) -> Result<Option<BobHead>, Error> {
+        let mut latest: Option<BobHead> = None;
+        let mut error = None;
+        for holder in holders {
+            if !holder.exist(key).await.unwrap_or(false) {
+                continue;
+            }
+            match holder.read_head(key).await {
+                Ok(head) => {
+                    if latest
+                        .as_ref()
+                        .map_or(true, |latest| head.is_newer_than(latest))
+                    {
+                        latest = Some(head);
+                    }
+                }
+                Err(e) => {
+                    debug!("{} version not read from {:?}: {}", key, holder, e);
+                    error = Some(e);
+                }
+            }
+        }
+        match (latest, error) {
+            (None, Some(e)) => Err(e),
+            (latest, _) => Ok(latest),
+        }
+    }
+
+    /// Returns digests of all holders, used to compare vdisk replicas. Merged holders are split
+    /// by timestamp periods, so they are comparable with holders of not compacted replicas.
+    pub async fn digest(&self) -> BackendResult<Vec<HolderDigest>> {
+        self.check_ready()?;
+        let holders = self.holders.read().await.clone();
+        let mut digests = Vec::with_capacity(holders.len());
+        for holder in holders {
+            if !self.is_merged(&holder) || !holder.has_keys_log() {
+                digests.push(holder.digest().await?);
+                continue;
+            }
+            let mut periods = BTreeMap::<_, Vec<_>>::new();
+            for version in holder.versions().await? {
+                let period = self.period_start(&holder, version.timestamp());
+                periods.entry(period).or_default().push(version);
+            }
+            digests.extend(
+                periods
+                    .into_iter()
+                    .map(|(start, versions)| HolderDigest::from_versions(start, &versions)),
+            );
+        }
+        Ok(digests)
+    }
+
+    /// Returns versions of the keys in holder with given start timestamp, empty if there is no
+    /// such holder. Versions of merged holder are limited to the period with given start.
+    pub async fn versions(&self, start_timestamp: u64) -> BackendResult<Vec<RecordVersion>> {
+        self.check_ready()?;
+        if let Some(holder) = self.find_holder(start_timestamp).await {
+            let mut versions = holder.versions().await?;
+            if self.is_merged(&holder) {
+                versions.retain(|version| {
+                    self.period_start(&holder, version.timestamp()) == start_timestamp
+                });
+            }
+            Ok(versions)
+        } else {
+            Ok(vec![])
+        }
+    }
+
+    /// Returns keys from the keys logs of holders sorted by key, records aren't read.
+    pub async fn keys(&self, start_timestamp: Option<u64>) -> BackendResult<Vec<BobKey>> {
+        self.check_ready()?;
+        let holders = self.holders.read().await.clone();
+        let mut keys = vec![];
+        for holder in holders {
+            if start_timestamp.map_or(false, |ts| ts != holder.start_timestamp()) {
+                continue;
+            }
+            if !holder.has_keys_log() && holder.records_count().await > 0 {
+                return Err(Error::failed(format!(
+                    "pearl:{} of vdisk {} has no keys log and can't be listed",
+                    holder.start_timestamp(),
+                    self.vdisk_id
+                )));
+            }
+            keys.extend(holder.keys().await?);
+        }
+        keys.sort_unstable();
+        keys.dedup();
+        Ok(keys)
+    }
+
+    /// Returns most recent versions of the keys from record meta, tombstones included.
+    /// Versions are limited to the holder with given start timestamp if it's set, otherwise
+    /// versions from all holders are merged. Expired versions are returned as tombstones, so
+    /// they hide older versions of other groups as on read.
+    pub async fn key_versions(
+        &self,
+        start_timestamp: Option<u64>,
+        keys: &[BobKey],
+    ) -> BackendResult<Vec<RecordVersion>> {
+        self.check_ready()?;
+        let holders: Vec<_> = self
+            .holders
+            .read()
+            .await
+            .iter()
+            .filter(|holder| start_timestamp.map_or(true, |ts| ts == holder.start_timestamp()))
+            .cloned()
+            .collect();
+        let mut versions = Vec::with_capacity(keys.len());
+        for &key in keys {
+            match Self::latest_head(&holders, key).await {
+                Ok(Some(head)) => {
+                    let deleted = head.is_tombstone() || head.meta().is_expired();
+                    versions.push(RecordVersion::new(key, head.meta().timestamp(), deleted));
+                }
+                Ok(None) => {}
+                // corrupted key is skipped, so repair pulls it from replicas
+                Err(e) if e.is_data_corrupted() => warn!("{}", e),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(versions)
+    }
+
+    /// Returns most recent records for the keys from holder with given start timestamp,
+    /// tombstones are returned as is
+    pub async fn records(
+        &self,
+        start_timestamp: u64,
+        keys: &[BobKey],
+    ) -> BackendResult<Vec<(BobKey, BobData)>> {
+        let mut records = Vec::with_capacity(keys.len());
+        for &key in keys {
+            if let Some(data) = self.record(start_timestamp, key).await? {
+                records.push((key, data));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Returns most recent record of the key from holder with given start timestamp, `None` if
+    /// record is corrupted
+    pub async fn record(
+        &self,
+        start_timestamp: u64,
+        key: BobKey,
+    ) -> BackendResult<Option<BobData>> {
+        self.check_ready()?;
+        let holder = self
+            .find_holder(start_timestamp)
+            .await
+            .ok_or_else(|| Error::failed(format!("pearl:{} not found", start_timestamp)))?;
+        match holder.read(key).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) if e.is_data_corrupted() => {
+                warn!("skip record: {}", e);
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Returns most recent records for the keys from all holders, tombstones are returned as
+    /// is and missing keys are skipped
+    pub async fn latest_records(&self, keys: &[BobKey]) -> BackendResult<Vec<(BobKey, BobData)>> {
+        let mut records = Vec::with_capacity(keys.len());
+        for &key in keys {
+            if let Some(data) = self.latest_record(key).await? {
+                records.push((key, data));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Returns most recent record of the key from all holders, `None` if key is missing or
+    /// record is corrupted
+    pub async fn latest_record(&self, key: BobKey) -> BackendResult<Option<BobData>> {
+        match self.get(key).await {
+            Ok(data) => Ok(Some(data)),
+            Err(e) => {
+                if let Some(timestamp) = e.deleted_at() {
+                    Ok(Some(BobData::tombstone(BobMeta::new(timestamp))))
+                } else if e.is_data_corrupted() {
+                    warn!("skip record: {}", e);
+                    Ok(None)
+                } else if e.is_key_not_found() {
+                    Ok(None)
+                } else {
+                    Err(e)
                 }
             }
         }
-        exist
+    }
+
+    // merged holder is found by the start of any period it covers
+    async fn find_holder(&self, start_timestamp: u64) -> Option<Holder> {
+        let holders = self.holders.read().await;
+        holders
+            .iter()
+            .find(|holder| holder.start_timestamp() == start_timestamp)
+            .or_else(|| {
+                holders.iter().find(|holder| {
+                    self.is_merged(holder) && holder.gets_into_interval(start_timestamp)
+                })
+            })
+            .cloned()
+    }
+
+    /// Id of the key used to encrypt new records, `None` if encryption isn't configured
+    pub(crate) fn current_key_id(&self) -> Option<u32> {
+        self.settings
+            .keyring()
+            .map(|keyring| keyring.current_key_id())
+    }
+
+    /// Merged holders are the result of compaction and span several timestamp periods
+    pub(crate) fn is_merged(&self, holder: &Holder) -> bool {
+        holder.end_timestamp() - holder.start_timestamp() > self.settings.timestamp_period_as_secs()
+    }
+
+    fn period_start(&self, holder: &Holder, timestamp: u64) -> u64 {
+        let period = self.settings.timestamp_period_as_secs();
+        let timestamp = timestamp
+            .max(holder.start_timestamp())
+            .min(holder.end_timestamp() - 1);
+        holder.start_timestamp() + (timestamp - holder.start_timestamp()) / period * period
     }
 
     pub fn holders(&self) -> Arc<RwLock<Vec<Holder>>> {
@@ -260,6 +642,10 @@ impl Group {
         &self.disk_name
     }
 
+    pub(crate) fn directory_path(&self) -> &Path {
+        &self.directory_path
+    }
+
     pub fn vdisk_id(&self) -> u32 {
         self.vdisk_id
     }
@@ -297,32 +683,232 @@ impl Group {
             return Err(Error::pearl_change_state(msg));
         }
         for holder in &holders {
-            let lock_guard = holder.storage();
-            let pearl_sync = lock_guard.write().await;
-            let storage = pearl_sync.storage().clone();
-            if let Err(e) = storage.close().await {
-                warn!("pearl closed: {:?}", e);
-            }
+            holder.close().await;
         }
         Ok(holders)
     }
 
+    /// Removes holder from group, closes it and drops its directory. Holder is removed only if
+    /// it has exactly `records_count` records, so writes made after the holder content was
+    /// handed off or found expired are not lost.
+    pub async fn remove_drained_holder(
+        &self,
+        start_timestamp: u64,
+        records_count: usize,
+    ) -> BackendResult<bool> {
+        let mut holders = self.holders.write().await;
+        let position = holders
+            .iter()
+            .position(|holder| holder.start_timestamp() == start_timestamp);
+        let holder = if let Some(position) = position {
+            let holder = &holders[position];
+            // count is checked after writes in progress are finished
+            holder.close_for_writes().await;
+            if holder.records_count().await != records_count {
+                debug!("{}: holder {} got new records", self, start_timestamp);
+                holder.open_for_writes();
+                return Ok(false);
+            }
+            holders.remove(position)
+        } else {
+            return Err(Error::pearl_change_state(format!(
+                "pearl:{} not found",
+                start_timestamp
+            )));
+        };
+        // holder indexes are shifted by removal
+        self.created_holder_indexes.write().await.clear();
+        drop(holders);
+        holder.close().await;
+        holder.drop_directory()?;
+        Ok(true)
+    }
+
+    /// Creates holder for the result of the compaction of holders in [start, end). Holder is
+    /// written to the temporary directory and becomes visible to operations only after swap.
+    pub(crate) fn create_compaction_holder(
+        &self,
+        start_timestamp: u64,
+        end_timestamp: u64,
+    ) -> Holder {
+        let hash = self.get_owner_node_hash();
+        let partition_name = PartitionName::merged(start_timestamp, &hash, end_timestamp);
+        let dir_name = format!("{}{}", COMPACTION_DIR_PREFIX, partition_name);
+        self.create_holder(&partition_name, &dir_name)
+    }
+
+    /// Replaces source holders, given by start timestamps and records counts, with the compacted
+    /// one. Sources are replaced only if they still have the same records counts and no other
+    /// holder overlaps with the compacted one, otherwise `false` is returned and the compacted
+    /// holder is left in its directory to be dropped by the caller.
+    pub(crate) async fn swap_compacted(
+        &self,
+        compacted: Holder,
+        sources: &[(u64, usize)],
+    ) -> BackendResult<bool> {
+        compacted.close().await;
+        let hash = self.get_owner_node_hash();
+        let partition_name = PartitionName::merged(
+            compacted.start_timestamp(),
+            &hash,
+            compacted.end_timestamp(),
+        );
+        let holder = self.create_holder(&partition_name, &partition_name.to_string());
+
+        let mut holders = self.holders.write().await;
+        let mut positions = Vec::with_capacity(sources.len());
+        for &(start_timestamp, _) in sources {
+            let position = holders
+                .iter()
+                .position(|holder| holder.start_timestamp() == start_timestamp);
+            if let Some(position) = position {
+                positions.push(position);
+            } else {
+                debug!("{}: holder {} was removed", self, start_timestamp);
+                return Ok(false);
+            }
+        }
+        let replaced: Vec<_> = positions
+            .iter()
+            .map(|&position| holders[position].clone())
+            .collect();
+        // writes, which found source before the lock was taken, are finished before the check
+        for source in &replaced {
+            source.close_for_writes().await;
+        }
+        let mut changed = false;
+        for (source, &(start_timestamp, records_count)) in replaced.iter().zip(sources) {
+            if source.records_count().await != records_count {
+                debug!("{}: holder {} was changed", self, start_timestamp);
+                changed = true;
+            }
+        }
+        // holder could be created in the gap between sources by write with old timestamp
+        let overlapped = holders.iter().enumerate().any(|(position, holder)| {
+            !positions.contains(&position)
+                && holder.start_timestamp() < compacted.end_timestamp()
+                && compacted.start_timestamp() < holder.end_timestamp()
+        });
+        if overlapped {
+            debug!("{}: compacted holder overlaps with new holder", self);
+        }
+        let occupied =
+            holder.path().exists() && !replaced.iter().any(|source| source.path() == holder.path());
+        if changed || overlapped || occupied {
+            for source in &replaced {
+                source.open_for_writes();
+            }
+            return if occupied {
+                Err(Error::storage(format!(
+                    "{:?} already exists",
+                    holder.path()
+                )))
+            } else {
+                Ok(false)
+            };
+        }
+
+        if let Err(e) = Self::replace_sources(&compacted, &holder, &replaced).await {
+            self.restore_sources(&compacted, &holder, &replaced).await;
+            return Err(e);
+        }
+        positions.sort_unstable();
+        for position in positions.into_iter().rev() {
+            holders.remove(position);
+        }
+        holders.push(holder);
+        // holder indexes are shifted by removal
+        self.created_holder_indexes.write().await.clear();
+        drop(holders);
+        for source in &replaced {
+            Stuff::drop_directory(&Self::replaced_path(source.path()))?;
+        }
+        Ok(true)
+    }
+
+    // sources are moved aside before the compacted holder takes its place, so the interrupted
+    // swap is finished or rolled back on start, see `restore_replaced`
+    async fn replace_sources(
+        compacted: &Holder,
+        holder: &Holder,
+        sources: &[Holder],
+    ) -> BackendResult<()> {
+        for source in sources {
+            source.close().await;
+            Self::rename_directory(source.path(), &Self::replaced_path(source.path()))?;
+        }
+        Self::rename_directory(compacted.path(), holder.path())?;
+        holder
+            .prepare_storage()
+            .await
+            .map_err(|e| Error::storage(format!("{:#}", e)))
+    }
+
+    async fn restore_sources(&self, compacted: &Holder, holder: &Holder, sources: &[Holder]) {
+        // compacted holder is dropped by the caller
+        if holder.path().exists() && !compacted.path().exists() {
+            holder.close().await;
+            if let Err(e) = Self::rename_directory(holder.path(), compacted.path()) {
+                error!("{}: {}", self, e);
+                return;
+            }
+        }
+        for source in sources {
+            let replaced = Self::replaced_path(source.path());
+            if replaced.exists() {
+                if let Err(e) = Self::rename_directory(&replaced, source.path()) {
+                    error!("{}: {}", self, e);
+                    continue;
+                }
+            }
+            if let Err(e) = source.prepare_storage().await {
+                error!(
+                    "{}: can't restore holder {}: {:#}",
+                    self,
+                    source.start_timestamp(),
+                    e
+                );
+            }
+            source.open_for_writes();
+        }
+    }
+
+    fn replaced_path(path: &Path) -> PathBuf {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        path.with_file_name(format!("{}{}", REPLACED_DIR_PREFIX, name))
+    }
+
+    fn rename_directory(from: &Path, to: &Path) -> BackendResult<()> {
+        rename(from, to)
+            .map_err(|e| Error::storage(format!("cannot rename {:?} to {:?}: {}", from, to, e)))
+    }
+
     pub fn create_pearl_holder(&self, start_timestamp: u64, hash: &str) -> Holder {
-        let end_timestamp = start_timestamp + self.settings.timestamp_period_as_secs();
+        let partition_name = PartitionName::new(start_timestamp, hash);
+        self.create_holder(&partition_name, &partition_name.to_string())
+    }
+
+    fn create_holder(&self, partition_name: &PartitionName, dir_name: &str) -> Holder {
+        let start_timestamp = partition_name.timestamp;
+        let end_timestamp = partition_name
+            .end_timestamp
+            .unwrap_or_else(|| start_timestamp + self.settings.timestamp_period_as_secs());
         let mut path = self.directory_path.clone();
         info!("creating pearl holder {}", path.as_path().display());
-        let partition_name = PartitionName::new(start_timestamp, &hash);
-        path.push(partition_name.to_string());
+        path.push(dir_name);
         let mut config = self.settings.config().clone();
         let prefix = config.blob_file_name_prefix().to_owned();
-        config.set_blob_file_name_prefix(format!("{}_{}", prefix, hash));
+        config.set_blob_file_name_prefix(format!("{}_{}", prefix, partition_name.hash));
         Holder::new(
             start_timestamp,
             end_timestamp,
             self.vdisk_id,
+            self.disk_name.clone(),
             path,
             config,
             self.dump_sem.clone(),
+            self.settings.key_length(),
+            self.settings.keyring().cloned(),
         )
     }
 
@@ -346,6 +932,7 @@ impl Group {
     pub(crate) fn read_vdisk_directory(&self) -> BackendResult<Vec<Holder>> {
         Stuff::check_or_create_directory(&self.directory_path)?;
 
+        self.restore_replaced()?;
         let mut holders = vec![];
         let pearl_directories = Settings::get_all_subdirectories(&self.directory_path)?;
         for entry in pearl_directories {
@@ -354,10 +941,14 @@ impl Group {
                 .into_string()
                 .map_err(|e| warn!("cannot parse file name: {:?}, {:?}", entry, e))
             {
+                if file_name.starts_with(COMPACTION_DIR_PREFIX) {
+                    warn!("dropping unfinished compaction {}", file_name);
+                    Stuff::drop_directory(&entry.path())?;
+                    continue;
+                }
                 let partition_name = PartitionName::try_from_string(&file_name);
                 if let Some(partition_name) = partition_name {
-                    let pearl_holder =
-                        self.create_pearl_holder(partition_name.timestamp, &partition_name.hash);
+                    let pearl_holder = self.create_holder(&partition_name, &file_name);
                     holders.push(pearl_holder);
                 } else {
                     warn!("failed to parse partition name from {}", file_name);
@@ -367,6 +958,44 @@ impl Group {
         Ok(holders)
     }
 
+    // sources of the compacted holder are moved aside before it takes its place, so replaced
+    // holder is obsolete if the compacted holder covering its start exists, otherwise the swap
+    // was interrupted and the replaced holder is restored
+    fn restore_replaced(&self) -> BackendResult<()> {
+        let mut replaced = vec![];
+        let mut partitions = vec![];
+        for entry in Settings::get_all_subdirectories(&self.directory_path)? {
+            let file_name = entry.file_name();
+            match file_name.to_str() {
+                Some(name) if name.starts_with(REPLACED_DIR_PREFIX) => {
+                    replaced.push(name[REPLACED_DIR_PREFIX.len()..].to_owned());
+                }
+                Some(name) if !name.starts_with(COMPACTION_DIR_PREFIX) => {
+                    partitions.extend(PartitionName::try_from_string(name));
+                }
+                _ => {}
+            }
+        }
+        for name in replaced {
+            let path = self.directory_path.join(&name);
+            let start_timestamp = PartitionName::try_from_string(&name)
+                .map(|partition| partition.timestamp)
+                .unwrap_or_default();
+            let compacted = partitions.iter().any(|partition| {
+                partition.timestamp <= start_timestamp
+                    && start_timestamp < partition.end_timestamp.unwrap_or(partition.timestamp + 1)
+            });
+            if path.exists() || compacted {
+                info!("dropping replaced holder {}", name);
+                Stuff::drop_directory(&Self::replaced_path(&path))?;
+            } else {
+                warn!("restoring replaced holder {}", name);
+                Self::rename_directory(&Self::replaced_path(&path), &path)?;
+            }
+        }
+        Ok(())
+    }
+
     fn get_owner_node_hash(&self) -> String {
         let hash = digest(&SHA256, self.owner_node_name.as_bytes());
         let hash = hash.as_ref();
@@ -421,9 +1050,11 @@ impl Display for Group {
     }
 }
 
+// merged partitions span several timestamp periods, so their end timestamp is kept in the name
 struct PartitionName {
     timestamp: u64,
     hash: String,
+    end_timestamp: Option<u64>,
 }
 
 impl PartitionName {
@@ -431,6 +1062,15 @@ impl PartitionName {
         Self {
             timestamp,
             hash: hash.to_string(),
+            end_timestamp: None,
+        }
+    }
+
+    fn merged(timestamp: u64, hash: &str, end_timestamp: u64) -> Self {
+        Self {
+            timestamp,
+            hash: hash.to_string(),
+            end_timestamp: Some(end_timestamp),
         }
     }
 
@@ -439,6 +1079,11 @@ impl PartitionName {
         let timestamp_string = iter.next();
         timestamp_string.and_then(|timestamp_string| {
             let hash_string = iter.next().unwrap_or("");
+            let end_timestamp = iter.next().and_then(|end| {
+                end.parse()
+                    .map_err(|e| warn!("failed to parse end timestamp, {:?}", e))
+                    .ok()
+            });
             timestamp_string
                 .parse()
                 .map_err(|e| warn!("failed to parse timestamp, {:?}", e))
@@ -446,6 +1091,7 @@ impl PartitionName {
                 .map(|timestamp| Self {
                     timestamp,
                     hash: hash_string.to_string(),
+                    end_timestamp,
                 })
         })
     }
@@ -453,6 +1099,10 @@ impl PartitionName {
 
 impl Display for PartitionName {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}_{}", self.timestamp, self.hash)
+        write!(f, "{}_{}", self.timestamp, self.hash)?;
+        if let Some(end_timestamp) = self.end_timestamp {
+            write!(f, "_{}", end_timestamp)?;
+        }
+        Ok(())
     }
 }
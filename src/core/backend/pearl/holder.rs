@@ -1,4 +1,6 @@
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::UNIX_EPOCH;
+use tokio::sync::RwLockReadGuard;
 
 use super::prelude::*;
 
@@ -11,31 +13,49 @@ pub(crate) struct Holder {
     start_timestamp: u64,
     end_timestamp: u64,
     vdisk: VDiskID,
+    disk_name: String,
     disk_path: PathBuf,
     config: PearlConfig,
     storage: Arc<RwLock<PearlSync>>,
     last_write_ts: Arc<RwLock<u64>>,
+    last_access: Arc<RwLock<Instant>>,
+    load_lock: Arc<RwLock<()>>,
+    // set while holder is replaced or removed, so its records count can't change
+    closed_for_writes: Arc<AtomicBool>,
     dump_sem: Arc<Semaphore>,
+    keys: KeysLog,
+    keyring: Option<Arc<Keyring>>,
 }
 
 impl Holder {
+    #[allow(clippy::too_many_arguments)]
     pub(crate) fn new(
         start_timestamp: u64,
         end_timestamp: u64,
         vdisk: VDiskID,
+        disk_name: String,
         disk_path: PathBuf,
         config: PearlConfig,
         dump_sem: Arc<Semaphore>,
+        key_length: usize,
+        keyring: Option<Arc<Keyring>>,
     ) -> Self {
+        let keys = KeysLog::new(&disk_path, key_length);
         Self {
             start_timestamp,
             end_timestamp,
             vdisk,
+            disk_name,
             disk_path,
             config,
             storage: Arc::new(RwLock::new(PearlSync::new())),
             last_write_ts: Arc::new(RwLock::new(0)),
+            last_access: Arc::new(RwLock::new(Instant::now())),
+            load_lock: Arc::default(),
+            closed_for_writes: Arc::default(),
             dump_sem,
+            keys,
+            keyring,
         }
     }
 
@@ -55,6 +75,10 @@ impl Holder {
             .to_owned()
     }
 
+    pub(crate) fn path(&self) -> &Path {
+        &self.disk_path
+    }
+
     pub(crate) fn storage(&self) -> &RwLock<PearlSync> {
         &self.storage
     }
@@ -69,6 +93,14 @@ impl Holder {
         storage.index_memory().await
     }
 
+    pub(crate) async fn is_loaded(&self) -> bool {
+        !self.storage.read().await.is_unloaded()
+    }
+
+    pub(crate) async fn last_access(&self) -> Instant {
+        *self.last_access.read().await
+    }
+
     pub(crate) fn is_actual(&self, current_start: u64) -> bool {
         self.start_timestamp == current_start
     }
@@ -121,9 +153,93 @@ impl Holder {
     }
 
     pub(crate) async fn close_active_blob(&mut self) {
-        let storage = self.storage.write().await;
-        storage.storage().close_active_blob().await;
-        warn!("Active blob of {} closed", self.get_id());
+        let state = self.storage.write().await;
+        if let Some(storage) = &state.storage {
+            storage.close_active_blob().await;
+            warn!("Active blob of {} closed", self.get_id());
+        }
+        if let Err(e) = self.keys.sync().await {
+            error!("pearl {}: {}", self.get_id(), e);
+        }
+    }
+
+    /// Closes storage to free memory occupied by its index, storage is loaded again on the
+    /// next access
+    pub(crate) async fn unload(&self) {
+        let _load = self.load_lock.write().await;
+        let mut state = self.storage.write().await;
+        if !state.is_ready() {
+            return;
+        }
+        // writes hold the load lock, so keys log can't change here
+        let records_count = state.records_count().await;
+        let filter = self.keys_filter(records_count).await.unwrap_or_else(|e| {
+            warn!(
+                "pearl {} is unloaded without keys filter: {}",
+                self.get_id(),
+                e
+            );
+            None
+        });
+        let blobs_count = state.blobs_count().await;
+        if let Err(e) = state.get().close().await {
+            error!("can't close pearl storage: {:?}", e);
+        }
+        state.unload(records_count, blobs_count, filter);
+        info!("pearl {} of vdisk {} unloaded", self.get_id(), self.vdisk);
+    }
+
+    // filter of the incomplete log would hide existing records, so such holder has no filter
+    async fn keys_filter(&self, records_count: usize) -> BackendResult<Option<KeysFilter>> {
+        if !self.keys_log_covers(records_count).await? {
+            return Ok(None);
+        }
+        Ok(Some(KeysFilter::new(&self.keys.read().await?)))
+    }
+
+    /// Loads storage if it was unloaded, storage isn't unloaded while the returned guard is held
+    async fn load(&self) -> BackendResult<RwLockReadGuard<'_, ()>> {
+        let guard = self.load_lock.read().await;
+        if !self.storage.read().await.is_unloaded() {
+            return Ok(guard);
+        }
+        drop(guard);
+        let guard = self.load_lock.write().await;
+        // storage could be loaded by another operation while waiting for the lock
+        if self.storage.read().await.is_unloaded() {
+            info!("pearl {} of vdisk {} loading", self.get_id(), self.vdisk);
+            self.prepare_storage()
+                .await
+                .map_err(|e| Error::storage(format!("{:#?}", e)))?;
+        }
+        Ok(guard.downgrade())
+    }
+
+    /// Marks holder as recently used. Reads of the keys, which holder doesn't contain, don't
+    /// mark it, because every read checks all holders of the group.
+    async fn touch(&self) {
+        *self.last_access.write().await = Instant::now();
+    }
+
+    /// Checks keys filter of unloaded holder, so it isn't loaded to read missing key
+    async fn may_contain(&self, key: BobKey) -> bool {
+        self.storage.read().await.may_contain(key)
+    }
+
+    /// Refuses new writes and waits for the writes in progress, so records count doesn't change
+    /// until holder is opened for writes again
+    pub(crate) async fn close_for_writes(&self) {
+        self.closed_for_writes.store(true, Ordering::SeqCst);
+        // writes hold the storage lock, so taking it waits for them
+        drop(self.storage.write().await);
+    }
+
+    pub(crate) fn open_for_writes(&self) {
+        self.closed_for_writes.store(false, Ordering::SeqCst);
+    }
+
+    pub(crate) fn is_closed_for_writes(&self) -> bool {
+        self.closed_for_writes.load(Ordering::SeqCst)
     }
 
     pub async fn update(&self, storage: Storage<Key>) {
@@ -136,65 +252,299 @@ impl Holder {
         );
     }
 
-    pub async fn write(&self, key: BobKey, data: BobData) -> BackendResult<()> {
+    pub async fn write(&self, key: BobKey, data: &BobData) -> BackendResult<()> {
+        let _load = self.load().await?;
+        self.touch().await;
         let state = self.storage.read().await;
 
-        if state.is_ready() {
+        if self.is_closed_for_writes() {
+            trace!(
+                "Vdisk: {}, holder {} is closed for writes",
+                self.vdisk,
+                self.get_id()
+            );
+            Err(Error::vdisk_is_not_ready())
+        } else if state.is_ready() {
             let storage = state.get();
             *self.last_write_ts.write().await = Self::get_current_ts();
             trace!("Vdisk: {}, write key: {}", self.vdisk, key);
-            Self::write_disk(storage, Key::from(key), data.clone()).await
+            self.write_disk(storage, key, data.clone()).await
         } else {
             trace!("Vdisk: {} isn't ready for writing: {:?}", self.vdisk, state);
             Err(Error::vdisk_is_not_ready())
         }
     }
 
-    // @TODO remove redundant return result
     #[allow(clippy::cast_possible_truncation)]
-    async fn write_disk(storage: PearlStorage, key: Key, data: BobData) -> BackendResult<()> {
-        counter!(PEARL_PUT_COUNTER, 1);
+    async fn write_disk(
+        &self,
+        storage: PearlStorage,
+        key: BobKey,
+        data: BobData,
+    ) -> BackendResult<()> {
+        let vdisk = self.vdisk.to_string();
+        counter!(PEARL_PUT_COUNTER, 1, "vdisk" => vdisk.clone(), "disk" => self.disk_name.clone());
         let timer = Instant::now();
-        storage
-            .write(key, Data::from(data).to_vec())
+        let size = data.inner().len();
+        let mut record = Data::from(data);
+        if let Some(compression) = self.config.compression() {
+            let sizes = match compression.algorithm() {
+                // zstd is too slow for the runtime threads
+                CompressionAlgorithm::Zstd => {
+                    let compression = compression.clone();
+                    let (compressed, sizes) = tokio::task::spawn_blocking(move || {
+                        let sizes = record.compress(&compression);
+                        (record, sizes)
+                    })
+                    .await
+                    .map_err(|e| Error::failed(format!("zstd compression failed: {}", e)))?;
+                    record = compressed;
+                    sizes
+                }
+                CompressionAlgorithm::Lz4 => record.compress(compression),
+            };
+            if let Some((size, compressed)) = sizes {
+                counter!(PEARL_COMPRESSED_RECORDS_COUNTER, 1);
+                counter!(PEARL_COMPRESSION_INPUT_BYTES, size as u64);
+                counter!(PEARL_COMPRESSION_OUTPUT_BYTES, compressed as u64);
+            }
+        }
+        if let Some(keyring) = &self.keyring {
+            record.encrypt(keyring, key)?;
+        }
+        let meta = record.pearl_meta(size);
+        match storage
+            .write_with(Key::from(key), record.to_vec(), meta)
             .await
-            .unwrap_or_else(|e| {
-                counter!(PEARL_PUT_ERROR_COUNTER, 1);
+        {
+            // record missing in the log can't be handed off and is hidden by keys filter, so
+            // write fails and is retried by client
+            Ok(()) => {
+                if let Err(e) = self.keys.append(key).await {
+                    counter!(PEARL_PUT_ERROR_COUNTER, 1, "vdisk" => vdisk.clone(), "disk" => self.disk_name.clone());
+                    error!("error on keys log write: {:?}", e);
+                    return Err(e);
+                }
+            }
+            Err(e) => {
+                counter!(PEARL_PUT_ERROR_COUNTER, 1, "vdisk" => vdisk.clone(), "disk" => self.disk_name.clone());
                 error!("error on write: {:?}", e);
-                //TODO check duplicate
-            });
-        counter!(PEARL_PUT_TIMER, timer.elapsed().as_nanos() as u64);
+                // duplicates are skipped by pearl, so any error is an i/o failure
+                return Err(Error::storage(format!("{:#}", e)));
+            }
+        }
+        counter!(PEARL_PUT_TIMER, timer.elapsed().as_nanos() as u64, "vdisk" => vdisk, "disk" => self.disk_name.clone());
         Ok(())
     }
 
+    /// Reads all versions of the key stored in holder and returns the most recent one,
+    /// tombstones are returned as is.
     #[allow(clippy::cast_possible_truncation)]
     pub async fn read(&self, key: BobKey) -> Result<BobData, Error> {
+        if !self.may_contain(key).await {
+            return Err(Error::key_not_found(key));
+        }
+        let _load = self.load().await?;
         let state = self.storage.read().await;
         if state.is_ready() {
             let storage = state.get();
             trace!("Vdisk: {}, read key: {}", self.vdisk, key);
-            counter!(PEARL_GET_COUNTER, 1);
+            let vdisk = self.vdisk.to_string();
+            counter!(PEARL_GET_COUNTER, 1, "vdisk" => vdisk.clone(), "disk" => self.disk_name.clone());
             let timer = Instant::now();
-            let res = storage
-                .read(Key::from(key))
-                .await
-                .map(|r| Data::from_bytes(&r))
-                .map_err(|e| {
-                    counter!(PEARL_GET_ERROR_COUNTER, 1);
-                    trace!("error on read: {:?}", e);
-                    match e.downcast_ref::<PearlError>().unwrap().kind() {
-                        ErrorKind::RecordNotFound => Error::key_not_found(key),
-                        _ => Error::storage(e.to_string()),
-                    }
-                });
-            counter!(PEARL_GET_TIMER, timer.elapsed().as_nanos() as u64);
-            res?
+            let res = self.read_most_recent(&storage, key).await.map_err(|e| {
+                counter!(PEARL_GET_ERROR_COUNTER, 1, "vdisk" => vdisk.clone(), "disk" => self.disk_name.clone());
+                trace!("error on read: {:?}", e);
+                e
+            });
+            counter!(PEARL_GET_TIMER, timer.elapsed().as_nanos() as u64, "vdisk" => vdisk, "disk" => self.disk_name.clone());
+            if res.is_ok() {
+                self.touch().await;
+            }
+            res
         } else {
             trace!("Vdisk: {} isn't ready for reading: {:?}", self.vdisk, state);
             Err(Error::vdisk_is_not_ready())
         }
     }
 
+    /// Corrupted record is ignored if there is a valid record of the same or newer version,
+    /// so it's enough to write the same version again to repair it.
+    async fn read_most_recent(
+        &self,
+        storage: &PearlStorage,
+        key: BobKey,
+    ) -> Result<BobData, Error> {
+        let entries = storage
+            .read_all(Key::from(key))
+            .await
+            .map_err(|e| Error::storage(e.to_string()))?;
+        let mut records = Vec::with_capacity(entries.len());
+        let mut corrupted_timestamp = None;
+        for entry in entries {
+            let data = entry
+                .load_data()
+                .await
+                .map_err(|e| Error::storage(e.to_string()))?;
+            let data = self.parse_record(data, key).await?;
+            if let Err(e) = data.verify_checksum(key) {
+                warn!("{}, timestamp: {}", e, data.meta().timestamp());
+                counter!(PEARL_CORRUPTED_RECORDS_COUNTER, 1);
+                corrupted_timestamp = corrupted_timestamp.max(Some(data.meta().timestamp()));
+            } else {
+                records.push(data);
+            }
+        }
+        let most_recent = Settings::choose_most_recent_data(records);
+        match (most_recent, corrupted_timestamp) {
+            (Some(data), Some(timestamp)) if data.meta().timestamp() < timestamp => {
+                Err(Error::data_corrupted(key))
+            }
+            (Some(data), _) => Ok(data),
+            (None, Some(_)) => Err(Error::data_corrupted(key)),
+            (None, None) => Err(Error::key_not_found(key)),
+        }
+    }
+
+    // zstd is too slow for the runtime threads, so such records are parsed in the blocking pool
+    async fn parse_record(&self, data: Vec<u8>, key: BobKey) -> Result<BobData, Error> {
+        if !Data::is_zstd(&data)? {
+            return Data::from_bytes(&data, key, self.keyring.as_deref());
+        }
+        let keyring = self.keyring.clone();
+        tokio::task::spawn_blocking(move || Data::from_bytes(&data, key, keyring.as_deref()))
+            .await
+            .map_err(|e| Error::failed(format!("zstd decompression failed: {}", e)))?
+    }
+
+    /// Reads versions of the key from pearl meta and returns the most recent one, data is read
+    /// only for records written without meta
+    pub(crate) async fn read_head(&self, key: BobKey) -> Result<BobHead, Error> {
+        if !self.may_contain(key).await {
+            return Err(Error::key_not_found(key));
+        }
+        let _load = self.load().await?;
+        let state = self.storage.read().await;
+        if !state.is_ready() {
+            trace!("Vdisk: {} isn't ready for reading: {:?}", self.vdisk, state);
+            return Err(Error::vdisk_is_not_ready());
+        }
+        let entries = state
+            .get()
+            .read_all(Key::from(key))
+            .await
+            .map_err(|e| Error::storage(e.to_string()))?;
+        let mut latest: Option<BobHead> = None;
+        for mut entry in entries {
+            let meta = entry
+                .load_meta()
+                .await
+                .map_err(|e| Error::storage(e.to_string()))?;
+            let head = match meta.and_then(Data::head_from_meta) {
+                Some(head) => head?,
+                None => {
+                    let data = entry
+                        .load_data()
+                        .await
+                        .map_err(|e| Error::storage(e.to_string()))?;
+                    BobHead::from(&self.parse_record(data, key).await?)
+                }
+            };
+            if latest
+                .as_ref()
+                .map_or(true, |latest| head.is_newer_than(latest))
+            {
+                latest = Some(head);
+            }
+        }
+        let head = latest.ok_or_else(|| Error::key_not_found(key))?;
+        self.touch().await;
+        Ok(head)
+    }
+
+    /// Checks if holder has records, which are not encrypted with the current key, always
+    /// `false` if encryption isn't configured
+    pub(crate) async fn needs_reencryption(&self) -> BackendResult<bool> {
+        let key_id = match &self.keyring {
+            Some(keyring) => keyring.current_key_id(),
+            None => return Ok(false),
+        };
+        let _load = self.load().await?;
+        for key in self.keys().await? {
+            let storage = self.storage.read().await.get();
+            let entries = storage
+                .read_all(Key::from(key))
+                .await
+                .map_err(|e| Error::storage(e.to_string()))?;
+            for entry in entries {
+                let data = entry
+                    .load_data()
+                    .await
+                    .map_err(|e| Error::storage(e.to_string()))?;
+                if Data::needs_reencryption(&data, key_id)? {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    /// Returns keys written to holder, see [`KeysLog`]
+    pub(crate) async fn keys(&self) -> BackendResult<Vec<BobKey>> {
+        self.keys.read().await
+    }
+
+    pub(crate) fn has_keys_log(&self) -> bool {
+        self.keys.exists()
+    }
+
+    /// Checks that keys log lists all records, so records missing in the log can't be lost by
+    /// operations, which remove holder after processing of the listed keys
+    pub(crate) async fn has_complete_keys_log(&self) -> BackendResult<bool> {
+        let records = self.records_count().await;
+        self.keys_log_covers(records).await
+    }
+
+    async fn keys_log_covers(&self, records: usize) -> BackendResult<bool> {
+        if !self.has_keys_log() {
+            return Ok(false);
+        }
+        let entries = self.keys.entries_count().await?;
+        if entries < records {
+            warn!(
+                "keys log of pearl {} is incomplete: {} keys, {} records",
+                self.get_id(),
+                entries,
+                records
+            );
+        }
+        Ok(entries >= records)
+    }
+
+    /// Returns most recent versions of the keys written to holder, payloads aren't read
+    pub(crate) async fn versions(&self) -> BackendResult<Vec<RecordVersion>> {
+        let keys = self.keys().await?;
+        let mut versions = Vec::with_capacity(keys.len());
+        for key in keys {
+            // corrupted key is skipped, so repair pulls it from replicas
+            match self.read_head(key).await {
+                Ok(head) => versions.push(RecordVersion::from_head(key, &head)),
+                Err(e) if e.is_data_corrupted() => warn!("{}", e),
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(versions)
+    }
+
+    pub(crate) async fn digest(&self) -> BackendResult<HolderDigest> {
+        let records = self.records_count().await;
+        if records > 0 && !self.has_keys_log() {
+            return Ok(HolderDigest::unknown(self.start_timestamp, records));
+        }
+        let versions = self.versions().await?;
+        Ok(HolderDigest::from_versions(self.start_timestamp, &versions))
+    }
+
     pub async fn try_reinit(&self) -> BackendResult<()> {
         let mut state = self.storage.write().await;
         if state.is_reinit() {
@@ -207,27 +557,51 @@ impl Holder {
         } else {
             state.init();
             trace!("Vdisk: {} set as reinit, state: {:?}", self.vdisk, state);
-            let storage = state.get();
-            trace!("Vdisk: {} close old Pearl", self.vdisk);
-            let result = storage.close().await;
-            if let Err(e) = result {
-                error!("can't close pearl storage: {:?}", e);
-                // we can't do anything
+            // unloaded holder has no storage to close
+            if let Some(storage) = state.storage.clone() {
+                trace!("Vdisk: {} close old Pearl", self.vdisk);
+                let result = storage.close().await;
+                if let Err(e) = result {
+                    error!("can't close pearl storage: {:?}", e);
+                    // we can't do anything
+                }
             }
             Ok(())
         }
     }
 
+    /// Closes storage, holder isn't ready until it's initialized again
+    pub(crate) async fn close(&self) {
+        let mut state = self.storage.write().await;
+        state.init();
+        if let Some(storage) = state.storage.clone() {
+            if let Err(e) = storage.close().await {
+                error!("can't close pearl storage: {:?}", e);
+            }
+        }
+        if let Err(e) = self.keys.sync().await {
+            error!("pearl {}: {}", self.get_id(), e);
+        }
+    }
+
     pub async fn exist(&self, key: BobKey) -> Result<bool, Error> {
+        if !self.may_contain(key).await {
+            return Ok(false);
+        }
+        let _load = self.load().await?;
         let state = self.storage.read().await;
         if state.is_ready() {
             trace!("Vdisk: {}, check key: {}", self.vdisk, key);
             let pearl_key = Key::from(key);
             let storage = state.get();
-            storage.contains(pearl_key).await.map_err(|e| {
+            let exist = storage.contains(pearl_key).await.map_err(|e| {
                 error!("{}", e);
                 Error::internal()
-            })
+            })?;
+            if exist {
+                self.touch().await;
+            }
+            Ok(exist)
         } else {
             trace!("Vdisk: {} not ready for reading: {:?}", self.vdisk, state);
             Err(Error::vdisk_is_not_ready())
@@ -336,6 +710,8 @@ pub(crate) enum PearlState {
     Normal,
     // pearl restarting
     Initializing,
+    // pearl is closed to free memory, it's loaded again on access
+    Unloaded,
 }
 
 #[derive(Clone, Debug)]
@@ -343,6 +719,9 @@ pub(crate) struct PearlSync {
     storage: Option<PearlStorage>,
     state: PearlState,
     start_time_test: u8,
+    unloaded_records_count: usize,
+    unloaded_blobs_count: usize,
+    keys_filter: Option<KeysFilter>,
 }
 impl PearlSync {
     pub(crate) fn new() -> Self {
@@ -350,30 +729,43 @@ impl PearlSync {
             storage: None,
             state: PearlState::Initializing,
             start_time_test: 0,
+            unloaded_records_count: 0,
+            unloaded_blobs_count: 0,
+            keys_filter: None,
         }
     }
 
-    pub(crate) fn storage(&self) -> &PearlStorage {
-        self.storage.as_ref().expect("pearl storage")
-    }
-
+    // storage is missing until holder is loaded for the first time or after it's unloaded,
+    // counts of the unloaded storage can't change, because it's loaded before any write
     pub(crate) async fn records_count(&self) -> usize {
-        self.storage().records_count().await
+        match &self.storage {
+            Some(storage) => storage.records_count().await,
+            None => self.unloaded_records_count,
+        }
     }
 
     pub(crate) async fn index_memory(&self) -> usize {
-        self.storage().index_memory().await
+        match &self.storage {
+            Some(storage) => storage.index_memory().await,
+            None => 0,
+        }
     }
 
     pub(crate) async fn active_blob_records_count(&self) -> usize {
-        self.storage()
-            .records_count_in_active_blob()
-            .await
-            .unwrap_or_default()
+        match &self.storage {
+            Some(storage) => storage
+                .records_count_in_active_blob()
+                .await
+                .unwrap_or_default(),
+            None => 0,
+        }
     }
 
     pub(crate) async fn blobs_count(&self) -> usize {
-        self.storage().blobs_count().await
+        match &self.storage {
+            Some(storage) => storage.blobs_count().await,
+            None => self.unloaded_blobs_count,
+        }
     }
 
     #[inline]
@@ -396,6 +788,32 @@ impl PearlSync {
         self.state == PearlState::Initializing
     }
 
+    #[inline]
+    pub(crate) fn is_unloaded(&self) -> bool {
+        self.state == PearlState::Unloaded
+    }
+
+    #[inline]
+    pub(crate) fn unload(
+        &mut self,
+        records_count: usize,
+        blobs_count: usize,
+        keys_filter: Option<KeysFilter>,
+    ) {
+        self.storage = None;
+        self.unloaded_records_count = records_count;
+        self.unloaded_blobs_count = blobs_count;
+        self.keys_filter = keys_filter;
+        self.set_state(PearlState::Unloaded);
+    }
+
+    // filter is kept only while storage is unloaded, holder without keys log may contain any key
+    pub(crate) fn may_contain(&self, key: BobKey) -> bool {
+        self.keys_filter
+            .as_ref()
+            .map_or(true, |filter| filter.contains(key))
+    }
+
     #[inline]
     pub(crate) fn set_state(&mut self, state: PearlState) {
         self.state = state;
@@ -404,6 +822,7 @@ impl PearlSync {
     #[inline]
     pub(crate) fn set(&mut self, storage: PearlStorage) {
         self.storage = Some(storage);
+        self.keys_filter = None;
         self.start_time_test += 1;
     }
 
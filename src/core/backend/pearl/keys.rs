@@ -0,0 +1,153 @@
+use super::prelude::*;
+use std::{
+    collections::{hash_map::DefaultHasher, HashSet},
+    hash::{Hash, Hasher},
+};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::AsyncWriteExt,
+    sync::Mutex,
+};
+
+const KEYS_LOG_FILE_NAME: &str = "keys.log";
+// about 1% of false positives
+const FILTER_BITS_PER_KEY: usize = 10;
+const FILTER_HASHES: u64 = 7;
+
+/// Append only log of the keys written to holder. Pearl can't iterate over stored records, so
+/// the log is the only way to enumerate holder content. Holders written before the log was
+/// introduced have no log and can't be enumerated. The log isn't synced on each write, as pearl
+/// records aren't, so its tail may be lost on crash, see [`KeysLog::entries_count`].
+#[derive(Clone, Debug)]
+pub(crate) struct KeysLog {
+    path: PathBuf,
+    key_length: usize,
+    file: Arc<Mutex<Option<File>>>,
+}
+
+impl KeysLog {
+    pub(crate) fn new(holder_path: &Path, key_length: usize) -> Self {
+        Self {
+            path: holder_path.join(KEYS_LOG_FILE_NAME),
+            key_length,
+            file: Arc::default(),
+        }
+    }
+
+    pub(crate) async fn append(&self, key: BobKey) -> BackendResult<()> {
+        let mut file = self.file.lock().await;
+        if file.is_none() {
+            let opened = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&self.path)
+                .await
+                .map_err(|e| Error::storage(format!("cannot open keys log: {}", e)))?;
+            *file = Some(opened);
+        }
+        let file = file.as_mut().expect("keys log file is opened above");
+        file.write_all(key.as_ref())
+            .await
+            .map_err(|e| Error::storage(format!("cannot write to keys log: {}", e)))?;
+        // tokio file writes in background, flush waits for the write and returns its error
+        file.flush()
+            .await
+            .map_err(|e| Error::storage(format!("cannot write to keys log: {}", e)))
+    }
+
+    pub(crate) async fn sync(&self) -> BackendResult<()> {
+        if let Some(file) = self.file.lock().await.as_mut() {
+            file.sync_data()
+                .await
+                .map_err(|e| Error::storage(format!("cannot sync keys log: {}", e)))?;
+        }
+        Ok(())
+    }
+
+    /// Count of the appended keys including repeated ones. Each record is appended once, so
+    /// it's less than records count of the holder only if keys were lost.
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) async fn entries_count(&self) -> BackendResult<usize> {
+        match tokio::fs::metadata(&self.path).await {
+            Ok(metadata) => Ok(metadata.len() as usize / self.key_length),
+            Err(e) if e.kind() == IOErrorKind::NotFound => Ok(0),
+            Err(e) => Err(Error::storage(format!("cannot read keys log: {}", e))),
+        }
+    }
+
+    /// Returns unique keys in the order of the first write.
+    pub(crate) async fn read(&self) -> BackendResult<Vec<BobKey>> {
+        let buf = match tokio::fs::read(&self.path).await {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == IOErrorKind::NotFound => return Ok(vec![]),
+            Err(e) => return Err(Error::storage(format!("cannot read keys log: {}", e))),
+        };
+        let mut seen = HashSet::new();
+        // incomplete tail may remain after crash, it is skipped by chunks_exact
+        let keys = buf
+            .chunks_exact(self.key_length)
+            .filter_map(BobKey::from_bytes)
+            .filter(|key| seen.insert(*key))
+            .collect();
+        Ok(keys)
+    }
+
+    pub(crate) fn exists(&self) -> bool {
+        self.path.exists()
+    }
+}
+
+/// Bloom filter of the keys from [`KeysLog`]. Unloaded holder keeps it instead of the pearl
+/// index, so reads of the keys, which holder doesn't contain, don't load the holder.
+#[derive(Clone)]
+pub(crate) struct KeysFilter {
+    bits: Vec<u64>,
+}
+
+impl Debug for KeysFilter {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        f.debug_struct("KeysFilter")
+            .field("bits", &(self.bits.len() * 64))
+            .finish()
+    }
+}
+
+impl KeysFilter {
+    pub(crate) fn new(keys: &[BobKey]) -> Self {
+        let words = (keys.len() * FILTER_BITS_PER_KEY / 64).max(1);
+        let mut filter = Self {
+            bits: vec![0; words],
+        };
+        for key in keys {
+            for bit in filter.bits(*key) {
+                filter.bits[bit / 64] |= 1 << (bit % 64);
+            }
+        }
+        filter
+    }
+
+    /// Returns `false` if key is definitely missing
+    pub(crate) fn contains(&self, key: BobKey) -> bool {
+        self.bits(key)
+            .all(|bit| self.bits[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    // double hashing: i-th bit is h1 + i * h2
+    fn bits(&self, key: BobKey) -> impl Iterator<Item = usize> {
+        let len = self.bits.len() as u64 * 64;
+        let first = Self::hash(key, 0);
+        let second = Self::hash(key, 1) | 1;
+        (0..FILTER_HASHES).map(move |i| {
+            #[allow(clippy::cast_possible_truncation)]
+            let bit = (first.wrapping_add(i.wrapping_mul(second)) % len) as usize;
+            bit
+        })
+    }
+
+    fn hash(key: BobKey, seed: u64) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        seed.hash(&mut hasher);
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+}
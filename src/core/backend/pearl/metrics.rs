@@ -2,15 +2,27 @@ use super::prelude::*;
 
 pub const PEARL_PUT_COUNTER: &str = "pearl.put_count";
 pub const PEARL_PUT_ERROR_COUNTER: &str = "pearl.put_error_count";
+// timers are counters, which sum processing time in nanoseconds
 pub const PEARL_PUT_TIMER: &str = "pearl.put_timer";
 
 pub const PEARL_GET_COUNTER: &str = "pearl.get_count";
 pub const PEARL_GET_ERROR_COUNTER: &str = "pearl.get_error_count";
 pub const PEARL_GET_TIMER: &str = "pearl.get_timer";
 
+pub const PEARL_CORRUPTED_RECORDS_COUNTER: &str = "pearl.corrupted_records_count";
+
+// compression ratio is input bytes divided by output bytes
+pub const PEARL_COMPRESSED_RECORDS_COUNTER: &str = "pearl.compressed_records_count";
+pub const PEARL_COMPRESSION_INPUT_BYTES: &str = "pearl.compression_input_bytes";
+pub const PEARL_COMPRESSION_OUTPUT_BYTES: &str = "pearl.compression_output_bytes";
+
 pub fn init_pearl() {
     counter!(PEARL_GET_COUNTER, 0);
     counter!(PEARL_PUT_COUNTER, 0);
     counter!(PEARL_GET_ERROR_COUNTER, 0);
     counter!(PEARL_PUT_ERROR_COUNTER, 0);
+    counter!(PEARL_CORRUPTED_RECORDS_COUNTER, 0);
+    counter!(PEARL_COMPRESSED_RECORDS_COUNTER, 0);
+    counter!(PEARL_COMPRESSION_INPUT_BYTES, 0);
+    counter!(PEARL_COMPRESSION_OUTPUT_BYTES, 0);
 }
@@ -1,7 +1,10 @@
 mod core;
 mod data;
+mod digest;
+mod encryption;
 mod group;
 mod holder;
+mod keys;
 mod metrics;
 mod settings;
 mod stuff;
@@ -10,6 +13,7 @@ mod stuff;
 mod tests;
 
 pub(crate) use self::core::{BackendResult, Pearl, PearlStorage};
+pub(crate) use self::digest::{HolderDigest, RecordVersion};
 pub(crate) use self::group::Group;
 pub(crate) use self::holder::Holder;
 pub(crate) use self::metrics::init_pearl;
@@ -19,17 +23,22 @@ mod prelude {
     pub(crate) use super::*;
 
     pub(crate) use self::metrics::{
-        PEARL_GET_COUNTER, PEARL_GET_ERROR_COUNTER, PEARL_GET_TIMER, PEARL_PUT_COUNTER,
-        PEARL_PUT_ERROR_COUNTER, PEARL_PUT_TIMER,
+        PEARL_COMPRESSED_RECORDS_COUNTER, PEARL_COMPRESSION_INPUT_BYTES,
+        PEARL_COMPRESSION_OUTPUT_BYTES, PEARL_CORRUPTED_RECORDS_COUNTER, PEARL_GET_COUNTER,
+        PEARL_GET_ERROR_COUNTER, PEARL_GET_TIMER, PEARL_PUT_COUNTER, PEARL_PUT_ERROR_COUNTER,
+        PEARL_PUT_TIMER,
     };
     pub(crate) use ::pearl::{
-        filter::Config as BloomConfig, rio, Builder, Error as PearlError, ErrorKind,
-        Key as KeyTrait, Storage,
+        filter::Config as BloomConfig, rio, Builder, Key as KeyTrait, Meta as PearlMeta, Storage,
     };
     pub(crate) use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDateTime, Utc};
-    pub(crate) use configs::Pearl as PearlConfig;
+    pub(crate) use configs::{
+        CompressionAlgorithm, CompressionConfig, EncryptionConfig, Pearl as PearlConfig,
+    };
     pub(crate) use data::{Data, Key};
+    pub(crate) use encryption::Keyring;
     pub(crate) use holder::Holder;
+    pub(crate) use keys::{KeysFilter, KeysLog};
     pub(crate) use settings::Settings;
     pub(crate) use stuff::Stuff;
 
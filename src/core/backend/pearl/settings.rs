@@ -6,13 +6,16 @@ pub(crate) struct Settings {
     alien_folder: PathBuf,
     timestamp_period: Duration,
     config: PearlConfig,
-    mapper: Arc<Virtual>,
+    keyring: Option<Arc<Keyring>>,
+    mapper: Arc<ClusterMap>,
+    disks: Arc<Disks>,
 }
 
 impl Settings {
-    pub(crate) fn new(config: &NodeConfig, mapper: Arc<Virtual>) -> Self {
+    pub(crate) fn new(config: &NodeConfig, mapper: Arc<ClusterMap>, disks: Arc<Disks>) -> Self {
         let config = config.pearl().clone();
         let alien_folder = if let Some(alien_disk) = config.alien_disk() {
+            let mapper = mapper.current();
             let disk_path = mapper
                 .get_disk(alien_disk)
                 .expect("cannot find alien disk in config")
@@ -22,33 +25,65 @@ impl Settings {
             config.settings().alien_root_dir_name().into()
         };
 
+        // key file is checked by config validation
+        let keyring = config
+            .encryption()
+            .map(|encryption| Arc::new(Keyring::new(encryption).expect("load encryption keys")));
+
         Self {
             bob_prefix_path: config.settings().root_dir_name().to_owned(),
             alien_folder,
             timestamp_period: config.settings().timestamp_period(),
             mapper,
             config,
+            keyring,
+            disks,
         }
     }
 
+    /// Health of the local disk, alien directory without configured disk isn't tracked
+    pub(crate) fn disk_health(&self, disk_name: &str) -> Option<Arc<DiskHealth>> {
+        self.disks.get(disk_name)
+    }
+
+    pub(crate) fn key_length(&self) -> usize {
+        self.mapper.current().key_length()
+    }
+
     pub(crate) fn config(&self) -> &PearlConfig {
         &self.config
     }
 
+    /// Keys of the records encryption, `None` if encryption isn't configured
+    pub(crate) fn keyring(&self) -> Option<&Arc<Keyring>> {
+        self.keyring.as_ref()
+    }
+
     pub(crate) fn read_group_from_disk(self: Arc<Self>, config: &NodeConfig) -> Vec<Group> {
+        let mapper = self.mapper.current();
+        self.local_groups(&mapper, config.name(), config.init_par_degree())
+    }
+
+    /// Creates groups for all vdisks, which are placed on the local disks by the mapper
+    pub(crate) fn local_groups(
+        self: Arc<Self>,
+        mapper: &Virtual,
+        node_name: &str,
+        init_par_degree: usize,
+    ) -> Vec<Group> {
         let mut result = vec![];
-        for disk in self.mapper.local_disks() {
-            let vdisks = self.mapper.get_vdisks_by_disk(disk.name());
-            let dump_sem = Arc::new(Semaphore::new(config.init_par_degree()));
+        for disk in mapper.local_disks() {
+            let vdisks = mapper.get_vdisks_by_disk(disk.name());
+            let dump_sem = Arc::new(Semaphore::new(init_par_degree));
             let iter = vdisks.iter().map(|&vdisk_id| {
                 let path = self.normal_path(disk.path(), vdisk_id);
                 Group::new(
                     self.clone(),
                     vdisk_id,
-                    config.name().to_owned(),
+                    node_name.to_owned(),
                     disk.name().to_owned(),
                     path,
-                    config.name().to_owned(),
+                    node_name.to_owned(),
                     dump_sem.clone(),
                 )
             });
@@ -57,6 +92,57 @@ impl Settings {
         result
     }
 
+    /// Creates groups for vdisk directories on the local disks, which are not placed there by
+    /// the mapper. They are left by the previous cluster map and are kept until new replicas
+    /// hold their data.
+    pub(crate) fn released_groups(
+        self: Arc<Self>,
+        mapper: &Virtual,
+        node_name: &str,
+        init_par_degree: usize,
+    ) -> Vec<Group> {
+        let mut result = vec![];
+        for disk in mapper.local_disks() {
+            let root = PathBuf::from(format!("{}/{}/", disk.path(), self.bob_prefix_path));
+            if !root.exists() {
+                continue;
+            }
+            let entries = match Self::get_all_subdirectories(&root) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    warn!("can't read released vdisks of disk {}: {}", disk.name(), e);
+                    continue;
+                }
+            };
+            let assigned = mapper.get_vdisks_by_disk(disk.name());
+            let dump_sem = Arc::new(Semaphore::new(init_par_degree));
+            for entry in entries {
+                let vdisk_id = if let Some(vdisk_id) = entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.parse::<VDiskID>().ok())
+                {
+                    vdisk_id
+                } else {
+                    continue;
+                };
+                if assigned.contains(&vdisk_id) {
+                    continue;
+                }
+                result.push(Group::new(
+                    self.clone(),
+                    vdisk_id,
+                    node_name.to_owned(),
+                    disk.name().to_owned(),
+                    self.normal_path(disk.path(), vdisk_id),
+                    node_name.to_owned(),
+                    dump_sem.clone(),
+                ));
+            }
+        }
+        result
+    }
+
     pub(crate) fn read_alien_directory(
         self: Arc<Self>,
         config: &NodeConfig,
@@ -69,7 +155,7 @@ impl Settings {
 
                 for vdisk_id in vdisks {
                     if let Ok((entry, vdisk_id)) = self.try_parse_vdisk_id(vdisk_id) {
-                        if self.mapper.is_vdisk_on_node(&node_name, vdisk_id) {
+                        if self.mapper.current().is_vdisk_on_node(&node_name, vdisk_id) {
                             let disk_name = config
                                 .pearl()
                                 .alien_disk()
@@ -120,6 +206,8 @@ impl Settings {
             node_name.to_owned(),
             Arc::new(Semaphore::new(1)),
         );
+        // new alien group has no holders to load
+        group.set_ready(true);
         Ok(group)
     }
 
@@ -152,6 +240,7 @@ impl Settings {
         })?;
         if self
             .mapper
+            .current()
             .nodes()
             .values()
             .any(|node| node.name() == file_name)
@@ -178,6 +267,7 @@ impl Settings {
 
         let vdisk = self
             .mapper
+            .current()
             .get_vdisks_ids()
             .into_iter()
             .find(|vdisk| *vdisk == vdisk_id);
@@ -231,10 +321,9 @@ impl Settings {
         Stuff::get_start_timestamp_by_std_time(self.timestamp_period, SystemTime::now())
     }
 
+    /// Tombstone wins over data with the same timestamp
     #[inline]
     pub(crate) fn choose_most_recent_data(records: Vec<BobData>) -> Option<BobData> {
-        records
-            .into_iter()
-            .max_by(|x, y| x.meta().timestamp().cmp(&y.meta().timestamp()))
+        BobData::most_recent(records)
     }
 }
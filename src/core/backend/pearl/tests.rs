@@ -1,15 +1,43 @@
 use super::prelude::*;
 
 use crate::core::backend::pearl::core::Pearl as PearlBackend;
+use crate::core::compaction::{Compaction, CompactionReport};
+use crate::core::configs::CompactionConfig;
+use crate::core::handoff::Handoff;
+use crate::core::migration::MigrationState;
+use crate::core::retention::Retention;
+use crate::core::scrubber::Scrubber;
 use std::fs::remove_dir_all;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, UNIX_EPOCH};
 
 static DISK_NAME: &str = "disk1";
 static PEARL_PATH: &str = "/tmp/d1/";
+static PEARL_DELETE_PATH: &str = "/tmp/d2/";
+static PEARL_ALIEN_PATH: &str = "/tmp/d3/";
+static PEARL_HEADERS_PATH: &str = "/tmp/d4/";
+static PEARL_CORRUPTED_PATH: &str = "/tmp/d5/";
+static PEARL_EXPIRED_PATH: &str = "/tmp/d6/";
+static PEARL_CLUSTER_MAP_PATH: &str = "/tmp/d7/";
+static PEARL_MIGRATION_PATH: &str = "/tmp/d8/";
+static PEARL_LIST_KEYS_PATH: &str = "/tmp/d9/";
+static PEARL_STOPPED_DISK_PATH: &str = "/tmp/d10/";
+static PEARL_FULL_DISK_PATH: &str = "/tmp/d11/";
+static PEARL_UNLOAD_PATH: &str = "/tmp/d12/";
+static PEARL_NOT_READY_PATH: &str = "/tmp/d13/";
+static PEARL_COMPACTION_PATH: &str = "/tmp/d14/";
+static PEARL_COMPRESSION_PATH: &str = "/tmp/d15/";
+static PEARL_ENCRYPTION_PATH: &str = "/tmp/d16/";
+static PEARL_HANDOFF_PATH: &str = "/tmp/d17/";
+static PEARL_MIGRATION_RESUME_PATH: &str = "/tmp/d18/";
+static PEARL_REOPEN_PATH: &str = "/tmp/d19/";
+static PEARL_TOMBSTONES_PATH: &str = "/tmp/d20/";
+static PEARL_SWAP_PATH: &str = "/tmp/d21/";
 const KEY_ID: u64 = 1;
 const TIMESTAMP: u64 = 1;
 
-fn drop_pearl() {
-    let path = PathBuf::from(PEARL_PATH);
+fn drop_pearl(path: &str) {
+    let path = PathBuf::from(path);
     if path.exists() {
         remove_dir_all(path).unwrap();
     }
@@ -21,13 +49,14 @@ async fn create_backend(node_config: &str, cluster_config: &str) -> PearlBackend
     let node = NodeConfig::get_from_string(node_config, &cluster).unwrap();
     debug!("node: {:?}", node);
 
-    let mapper = Arc::new(Virtual::new(&node, &cluster).await);
+    let mapper = Arc::new(ClusterMap::new(Virtual::new(&node, &cluster).await));
     debug!("mapper: {:?}", mapper);
-    PearlBackend::new(mapper, &node)
+    let disks = Disks::new(mapper.current().local_disks(), node.free_space());
+    let disks = Arc::new(disks);
+    PearlBackend::new(mapper, &node, disks)
 }
 
-async fn backend() -> PearlBackend {
-    let node_config = "
+static NODE_CONFIG: &str = "
 log_config: logger.yaml
 name: local_node
 quorum: 1
@@ -48,45 +77,1202 @@ pearl:                             # used only for 'backend_type: pearl'
     timestamp_period: 1d           # period when new pearl directory created. required for 'pearl'
     create_pearl_wait_delay: 100ms
 ";
-    let cluster_config = "
+
+async fn backend(path: &str) -> PearlBackend {
+    backend_with_node_config(path, NODE_CONFIG).await
+}
+
+async fn backend_with_node_config(path: &str, node_config: &str) -> PearlBackend {
+    let cluster_config = format!(
+        "
 nodes:
     - name: local_node
       address: 127.0.0.1:20000
       disks:
         - name: disk1
-          path: /tmp/d1
+          path: {}
 vdisks:
     - id: 0
       replicas:
         - node: local_node
           disk: disk1
-";
+",
+        path
+    );
     debug!("node_config: {}", node_config);
     debug!("cluster_config: {}", cluster_config);
-    create_backend(node_config, cluster_config).await
+    create_backend(node_config, &cluster_config).await
 }
 
 #[tokio::test]
 async fn test_write_multiple_read() {
     test_utils::init_logger();
-    drop_pearl();
+    drop_pearl(PEARL_PATH);
     let vdisk_id = 0;
-    let backend = backend().await;
+    let backend = backend(PEARL_PATH).await;
     backend.run_backend().await.unwrap();
     let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
     let operation = Operation::new_local(vdisk_id, path);
     let data = BobData::new(vec![], BobMeta::new(TIMESTAMP));
-    let write = backend.put(operation.clone(), KEY_ID, data).await;
+    let write = backend.put(operation.clone(), KEY_ID.into(), data).await;
     assert!(write.is_ok());
 
-    let mut read = backend.get(operation.clone(), KEY_ID).await;
+    let mut read = backend.get(operation.clone(), KEY_ID.into()).await;
     assert_eq!(TIMESTAMP, read.unwrap().meta().timestamp());
-    read = backend.get(operation.clone(), KEY_ID).await;
+    read = backend.get(operation.clone(), KEY_ID.into()).await;
     assert_eq!(TIMESTAMP, read.unwrap().meta().timestamp());
 
-    let res = backend.get(operation.clone(), KEY_ID).await;
+    let res = backend.get(operation.clone(), KEY_ID.into()).await;
     assert_eq!(TIMESTAMP, res.unwrap().meta().timestamp());
-    let res = backend.get(operation, KEY_ID).await;
+    let res = backend.get(operation, KEY_ID.into()).await;
     assert_eq!(TIMESTAMP, res.unwrap().meta().timestamp());
-    drop_pearl();
+    drop_pearl(PEARL_PATH);
+}
+
+#[tokio::test]
+async fn test_storages_reopened_after_shutdown() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_REOPEN_PATH);
+    let vdisk_id = 0;
+    let backend = backend(PEARL_REOPEN_PATH).await;
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(vdisk_id, path);
+    let data = BobData::new(vec![1], BobMeta::new(TIMESTAMP));
+    backend
+        .put(operation.clone(), KEY_ID.into(), data)
+        .await
+        .unwrap();
+
+    backend.shutdown().await;
+    assert!(backend.get(operation.clone(), KEY_ID.into()).await.is_err());
+    backend.reopen().await.unwrap();
+    let read = backend.get(operation.clone(), KEY_ID.into()).await.unwrap();
+    assert_eq!(read.inner(), &[1]);
+    let data = BobData::new(vec![2], BobMeta::new(TIMESTAMP + 1));
+    backend
+        .put(operation, (KEY_ID + 1).into(), data)
+        .await
+        .unwrap();
+    drop_pearl(PEARL_REOPEN_PATH);
+}
+
+#[tokio::test]
+async fn test_headers_are_persisted() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_HEADERS_PATH);
+    let vdisk_id = 0;
+    let backend = backend(PEARL_HEADERS_PATH).await;
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(vdisk_id, path);
+    let headers = vec![
+        ("content-type".to_owned(), "image/png".to_owned()),
+        ("filename".to_owned(), "photo.png".to_owned()),
+    ]
+    .into_iter()
+    .collect();
+    let meta = BobMeta::with_headers(TIMESTAMP, headers);
+    let data = BobData::new(vec![1, 2, 3], meta.clone());
+    let write = backend.put(operation.clone(), KEY_ID.into(), data).await;
+    assert!(write.is_ok());
+
+    let read = backend.get(operation, KEY_ID.into()).await.unwrap();
+    assert_eq!(read.meta(), &meta);
+    assert_eq!(read.inner(), &[1, 2, 3]);
+    drop_pearl(PEARL_HEADERS_PATH);
+}
+
+#[tokio::test]
+async fn test_corrupted_record_detected_and_repaired() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_CORRUPTED_PATH);
+    let vdisk_id = 0;
+    let backend = backend(PEARL_CORRUPTED_PATH).await;
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(vdisk_id, path);
+    let valid = BobData::new(vec![1, 2, 3], BobMeta::new(TIMESTAMP)).checksummed();
+    let corrupted = valid.clone().with_checksum(!valid.checksum().unwrap());
+    let write = backend
+        .put(operation.clone(), KEY_ID.into(), corrupted)
+        .await;
+    assert!(write.is_ok());
+
+    let read = backend.get(operation.clone(), KEY_ID.into()).await;
+    assert!(read.err().unwrap().is_data_corrupted());
+    let group = &backend.vdisks_groups().await.unwrap()[0];
+    let report = Scrubber::scrub_group(group).await;
+    assert_eq!(report.checked(), 1);
+    assert_eq!(report.corrupted().len(), 1);
+    assert_eq!(report.corrupted()[0].key(), BobKey::from(KEY_ID));
+
+    // the same version written again hides corrupted record
+    let write = backend.put(operation.clone(), KEY_ID.into(), valid).await;
+    assert!(write.is_ok());
+    let read = backend.get(operation, KEY_ID.into()).await.unwrap();
+    assert_eq!(read.inner(), &[1, 2, 3]);
+    assert!(Scrubber::scrub_group(group).await.corrupted().is_empty());
+
+    // records of the holder without keys log can't be verified
+    let holder = group.holders().read().await[0].clone();
+    std::fs::remove_file(holder.path().join("keys.log")).unwrap();
+    let report = Scrubber::scrub_group(group).await;
+    assert_eq!(report.checked(), 0);
+    assert_eq!(report.unchecked().len(), 1);
+    assert_eq!(report.unchecked()[0].records(), 2);
+    drop_pearl(PEARL_CORRUPTED_PATH);
+}
+
+#[tokio::test]
+async fn test_tombstone_hides_data_in_older_holders() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_DELETE_PATH);
+    let vdisk_id = 0;
+    let backend = backend(PEARL_DELETE_PATH).await;
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(vdisk_id, path);
+    let data = BobData::new(vec![1], BobMeta::new(TIMESTAMP));
+    let write = backend.put(operation.clone(), KEY_ID.into(), data).await;
+    assert!(write.is_ok());
+    let keys = [KEY_ID.into(), (KEY_ID + 1).into()];
+    let exist = backend.exist(operation.clone(), &keys).await.unwrap();
+    assert_eq!(exist, vec![true, false]);
+
+    // timestamp period is 1d, so tombstone goes to the next holder
+    let delete_timestamp = TIMESTAMP + 2 * 24 * 60 * 60;
+    let tombstone = BobData::tombstone(BobMeta::new(delete_timestamp));
+    let write = backend
+        .put(operation.clone(), KEY_ID.into(), tombstone)
+        .await;
+    assert!(write.is_ok());
+
+    let read = backend.get(operation.clone(), KEY_ID.into()).await;
+    assert!(read.err().unwrap().is_key_not_found());
+    let exist = backend.exist(operation, &[KEY_ID.into()]).await.unwrap();
+    assert_eq!(exist, vec![false]);
+    drop_pearl(PEARL_DELETE_PATH);
+}
+
+#[tokio::test]
+async fn test_list_keys_merges_holders() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_LIST_KEYS_PATH);
+    let vdisk_id = 0;
+    let backend = backend(PEARL_LIST_KEYS_PATH).await;
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(vdisk_id, path);
+    let next_timestamp = TIMESTAMP + 2 * 24 * 60 * 60;
+    let mut expired = BobMeta::new(TIMESTAMP);
+    expired.set_expires_at(Some(TIMESTAMP));
+    let records = vec![
+        (2, BobData::new(vec![2], BobMeta::new(TIMESTAMP))),
+        (1, BobData::new(vec![1], BobMeta::new(TIMESTAMP))),
+        (3, BobData::new(vec![3], BobMeta::new(next_timestamp))),
+        (1, BobData::tombstone(BobMeta::new(next_timestamp))),
+        (4, BobData::new(vec![4], expired)),
+    ];
+    for (key, data) in records {
+        let write = backend.put(operation.clone(), key.into(), data).await;
+        assert!(write.is_ok());
+    }
+
+    let group = &backend.vdisks_groups().await.unwrap()[0];
+    let keys = group.keys(None).await.unwrap();
+    let versions = group.key_versions(None, &keys).await.unwrap();
+    let expected = vec![
+        RecordVersion::new(1.into(), next_timestamp, true),
+        RecordVersion::new(2.into(), TIMESTAMP, false),
+        RecordVersion::new(3.into(), next_timestamp, false),
+        // expired version is listed as deleted
+        RecordVersion::new(4.into(), TIMESTAMP, true),
+    ];
+    assert_eq!(versions, expected);
+
+    let first_holder = group.holders().read().await[0].start_timestamp();
+    let keys = group.keys(Some(first_holder)).await.unwrap();
+    let versions = group.key_versions(Some(first_holder), &keys).await.unwrap();
+    let expected = vec![
+        RecordVersion::new(1.into(), TIMESTAMP, false),
+        RecordVersion::new(2.into(), TIMESTAMP, false),
+        RecordVersion::new(4.into(), TIMESTAMP, true),
+    ];
+    assert_eq!(versions, expected);
+    drop_pearl(PEARL_LIST_KEYS_PATH);
+}
+
+#[tokio::test]
+async fn test_drained_alien_holder_removed() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_ALIEN_PATH);
+    let vdisk_id = 0;
+    let backend = backend(PEARL_ALIEN_PATH).await;
+    backend.run_backend().await.unwrap();
+    let mut operation = Operation::new_alien(vdisk_id);
+    operation.set_remote_folder("local_node".to_owned());
+    let data = BobData::new(vec![1], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put_alien(operation.clone(), KEY_ID.into(), data)
+        .await;
+    assert!(write.is_ok());
+    // timestamp period is 1d, so data goes to the next holder
+    let timestamp = TIMESTAMP + 2 * 24 * 60 * 60;
+    let data = BobData::new(vec![2], BobMeta::new(timestamp));
+    let write = backend
+        .put_alien(operation.clone(), KEY_ID.into(), data)
+        .await;
+    assert!(write.is_ok());
+
+    let groups = backend.alien_vdisks_groups().await.unwrap();
+    assert_eq!(groups.len(), 1);
+    let group = &groups[0];
+    let holders = group.holders().read().await.clone();
+    assert_eq!(holders.len(), 2);
+    for holder in &holders {
+        assert_eq!(holder.keys().await.unwrap(), vec![BobKey::from(KEY_ID)]);
+    }
+
+    let newest = holders
+        .iter()
+        .max_by_key(|holder| holder.start_timestamp())
+        .unwrap();
+    let removed = group
+        .remove_drained_holder(newest.start_timestamp(), 0)
+        .await
+        .unwrap();
+    assert!(!removed);
+    let removed = group
+        .remove_drained_holder(newest.start_timestamp(), 1)
+        .await
+        .unwrap();
+    assert!(removed);
+    assert_eq!(group.holders().read().await.len(), 1);
+
+    let read = backend.get_alien(operation, KEY_ID.into()).await.unwrap();
+    assert_eq!(TIMESTAMP, read.meta().timestamp());
+    drop_pearl(PEARL_ALIEN_PATH);
+}
+
+#[tokio::test]
+async fn test_expired_holder_removed() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_EXPIRED_PATH);
+    let vdisk_id = 0;
+    let backend = backend(PEARL_EXPIRED_PATH).await;
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(vdisk_id, path);
+    let ttl = Some(Duration::from_secs(1));
+    let expired = BobData::new(vec![1], BobMeta::new(TIMESTAMP)).expire_after(ttl);
+    let write = backend.put(operation.clone(), KEY_ID.into(), expired).await;
+    assert!(write.is_ok());
+    // timestamp period is 1d, so data goes to the next holder
+    let timestamp = TIMESTAMP + 2 * 24 * 60 * 60;
+    let data = BobData::new(vec![2], BobMeta::new(timestamp));
+    let write = backend.put(operation.clone(), 2.into(), data).await;
+    assert!(write.is_ok());
+
+    let read = backend.get(operation.clone(), KEY_ID.into()).await;
+    assert!(read.err().unwrap().is_key_not_found());
+    let group = &backend.vdisks_groups().await.unwrap()[0];
+    assert_eq!(group.holders().read().await.len(), 2);
+    assert_eq!(Retention::remove_expired_holders(group).await, 1);
+    let holders = group.holders().read().await.clone();
+    assert_eq!(holders.len(), 1);
+    assert_eq!(holders[0].keys().await.unwrap(), vec![BobKey::from(2)]);
+
+    // expired version is kept, while older partition has version without expiration
+    let timestamp = TIMESTAMP + 4 * 24 * 60 * 60;
+    let expired = BobData::new(vec![3], BobMeta::new(timestamp)).expire_after(ttl);
+    let write = backend.put(operation.clone(), 2.into(), expired).await;
+    assert!(write.is_ok());
+    assert_eq!(Retention::remove_expired_holders(group).await, 0);
+    assert_eq!(group.holders().read().await.len(), 2);
+    let read = backend.get(operation, 2.into()).await;
+    assert!(read.err().unwrap().is_key_not_found());
+    drop_pearl(PEARL_EXPIRED_PATH);
+}
+
+fn two_node_cluster_config(path: &str, local_vdisk: u32, version: u64) -> ClusterConfig {
+    let cluster_config = format!(
+        "
+nodes:
+    - name: local_node
+      address: 127.0.0.1:20000
+      disks:
+        - name: disk1
+          path: {path}
+    - name: other_node
+      address: 127.0.0.1:20001
+      disks:
+        - name: disk1
+          path: {path}
+vdisks:
+    - id: 0
+      replicas:
+        - node: {first}
+          disk: disk1
+    - id: 1
+      replicas:
+        - node: {second}
+          disk: disk1
+version: {version}
+",
+        path = path,
+        first = if local_vdisk == 0 {
+            "local_node"
+        } else {
+            "other_node"
+        },
+        second = if local_vdisk == 1 {
+            "local_node"
+        } else {
+            "other_node"
+        },
+        version = version
+    );
+    ClusterConfig::get_from_string(&cluster_config).unwrap()
+}
+
+#[tokio::test]
+async fn test_cluster_map_moves_vdisk_groups() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_CLUSTER_MAP_PATH);
+    let cluster = two_node_cluster_config(PEARL_CLUSTER_MAP_PATH, 0, 0);
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    let mapper = Virtual::new(&node, &cluster).await;
+    let backend = PearlBackend::new(Arc::new(ClusterMap::new(mapper)), &node, Arc::default());
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let data = BobData::new(vec![], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put(Operation::new_local(0, path.clone()), KEY_ID.into(), data)
+        .await;
+    assert!(write.is_ok());
+
+    let cluster = two_node_cluster_config(PEARL_CLUSTER_MAP_PATH, 1, 1);
+    let mapper = Virtual::new(&node, &cluster).await;
+    backend.apply_cluster_map(&mapper).await.unwrap();
+    let groups = backend.vdisks_groups().await.unwrap();
+    assert_eq!(groups.len(), 1);
+    assert_eq!(groups[0].vdisk_id(), 1);
+    let read = backend
+        .get(Operation::new_local(0, path.clone()), KEY_ID.into())
+        .await;
+    assert!(read.is_err());
+    let data = BobData::new(vec![], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put(Operation::new_local(1, path), KEY_ID.into(), data)
+        .await;
+    assert!(write.is_ok());
+    drop_pearl(PEARL_CLUSTER_MAP_PATH);
+}
+
+fn handoff_client(node: Node, puts: Arc<AtomicUsize>) -> BobClient {
+    let mut client = BobClient::default();
+    client.expect_clone().returning(move || {
+        let mut client = BobClient::default();
+        let (name, puts) = (node.name().to_owned(), puts.clone());
+        client.expect_put().returning(move |_key, _data, _options| {
+            puts.fetch_add(1, Ordering::SeqCst);
+            test_utils::put_ok(name.clone())
+        });
+        let name = node.name().to_owned();
+        client.expect_exist().returning(move |keys, _options| {
+            Ok(NodeOutput::new(name.clone(), vec![true; keys.len()]))
+        });
+        client
+    });
+    client
+}
+
+#[tokio::test]
+async fn test_alien_holder_handed_off_to_owner() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_HANDOFF_PATH);
+    let cluster = two_node_cluster_config(PEARL_HANDOFF_PATH, 0, 0);
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    let mapper = Virtual::new(&node, &cluster).await;
+    let backend = Backend::new(Arc::new(ClusterMap::new(mapper)), &node);
+    backend.run_backend().await.unwrap();
+    let mut operation = Operation::new_alien(1);
+    operation.set_remote_folder("other_node".to_owned());
+    let data = BobData::new(vec![1], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put_local(KEY_ID.into(), data, operation.clone())
+        .await;
+    assert!(write.is_ok());
+
+    // owner is unreachable
+    assert_eq!(Handoff::run_once(&backend).await, 0);
+    let puts = Arc::new(AtomicUsize::new(0));
+    let mapper = backend.mapper();
+    let owner = mapper
+        .nodes()
+        .values()
+        .find(|node| node.name() == "other_node")
+        .unwrap();
+    owner
+        .set_connection(handoff_client(owner.clone(), puts.clone()))
+        .await;
+    // holder is still written to
+    assert_eq!(Handoff::run_once(&backend).await, 0);
+    assert_eq!(puts.load(Ordering::SeqCst), 0);
+
+    sleep(Duration::from_secs(11)).await;
+    // records missing in the keys log can't be handed off, so holder is kept
+    let alien = backend.alien_vdisks_groups().await.unwrap().remove(0);
+    let keys_log = alien.holders().read().await[0].path().join("keys.log");
+    let keys = std::fs::read(&keys_log).unwrap();
+    std::fs::write(&keys_log, b"").unwrap();
+    assert_eq!(Handoff::run_once(&backend).await, 0);
+    assert_eq!(puts.load(Ordering::SeqCst), 0);
+    std::fs::write(&keys_log, keys).unwrap();
+    assert_eq!(Handoff::run_once(&backend).await, 1);
+    assert_eq!(puts.load(Ordering::SeqCst), 1);
+    let alien = backend.alien_vdisks_groups().await.unwrap().remove(0);
+    assert!(alien.holders().read().await.is_empty());
+    let read = backend.get_local(KEY_ID.into(), operation).await;
+    assert!(read.err().unwrap().is_key_not_found());
+    drop_pearl(PEARL_HANDOFF_PATH);
+}
+
+fn two_disk_cluster_config(path: &str, disk: &str, version: u64) -> ClusterConfig {
+    let cluster_config = format!(
+        "
+nodes:
+    - name: local_node
+      address: 127.0.0.1:20000
+      disks:
+        - name: disk1
+          path: {path}disk1
+        - name: disk2
+          path: {path}disk2
+vdisks:
+    - id: 0
+      replicas:
+        - node: local_node
+          disk: {disk}
+version: {version}
+",
+        path = path,
+        disk = disk,
+        version = version
+    );
+    ClusterConfig::get_from_string(&cluster_config).unwrap()
+}
+
+#[tokio::test]
+async fn test_migration_moves_records_between_local_disks() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_MIGRATION_PATH);
+    let cluster = two_disk_cluster_config(PEARL_MIGRATION_PATH, "disk1", 0);
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    let mapper = Virtual::new(&node, &cluster).await;
+    let backend = Backend::new(Arc::new(ClusterMap::new(mapper)), &node);
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new("disk1".to_owned(), "".to_owned());
+    let data = BobData::new(vec![1, 2, 3], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put_local(KEY_ID.into(), data, Operation::new_local(0, path))
+        .await;
+    assert!(write.is_ok());
+
+    let cluster = two_disk_cluster_config(PEARL_MIGRATION_PATH, "disk2", 1);
+    backend.update_cluster_map(&cluster).await.unwrap();
+    let path = DiskPath::new("disk2".to_owned(), "".to_owned());
+    let read = backend
+        .get_local(KEY_ID.into(), Operation::new_local(0, path.clone()))
+        .await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3]);
+
+    let mut state = None;
+    for _ in 0..50 {
+        state = backend.migration().status(0).map(|s| s.state().clone());
+        if state == Some(MigrationState::Done) {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(state, Some(MigrationState::Done));
+    assert_eq!(backend.migration().status(0).unwrap().records(), 1);
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    assert_eq!(group.disk_name(), "disk2");
+    let records = group.latest_records(&[KEY_ID.into()]).await.unwrap();
+    assert_eq!(records.len(), 1);
+    assert!(!group.directory_path().join("migration.yaml").exists());
+
+    // released group is dropped once the new replica holds its records
+    let released = backend.released_vdisks_groups().await.unwrap().remove(0);
+    assert_eq!(Handoff::drop_replicated(&backend).await, 1);
+    assert!(backend.released_vdisks_groups().await.unwrap().is_empty());
+    assert!(!released.directory_path().exists());
+    drop_pearl(PEARL_MIGRATION_PATH);
+}
+
+#[tokio::test]
+async fn test_migration_resumed_after_restart() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_MIGRATION_RESUME_PATH);
+    let cluster = two_disk_cluster_config(PEARL_MIGRATION_RESUME_PATH, "disk1", 0);
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    let mapper = Virtual::new(&node, &cluster).await;
+    let backend = Backend::new(Arc::new(ClusterMap::new(mapper)), &node);
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new("disk1".to_owned(), "".to_owned());
+    let data = BobData::new(vec![1, 2, 3], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put_local(KEY_ID.into(), data, Operation::new_local(0, path))
+        .await;
+    assert!(write.is_ok());
+    backend.shutdown().await;
+
+    // node was stopped after the map update, before the migration is finished
+    let cluster = two_disk_cluster_config(PEARL_MIGRATION_RESUME_PATH, "disk2", 1);
+    let target = format!("{}disk2/bob/0/", PEARL_MIGRATION_RESUME_PATH);
+    create_dir_all(&target).unwrap();
+    let progress = "sources:\n  - local_node/disk1\nrecords: 0\nbytes: 0\n";
+    std::fs::write(format!("{}migration.yaml", target), progress).unwrap();
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    let mapper = Virtual::new(&node, &cluster).await;
+    let backend = Backend::new(Arc::new(ClusterMap::new(mapper)), &node);
+    backend.run_backend().await.unwrap();
+    let released = backend.released_vdisks_groups().await.unwrap();
+    assert_eq!(released.len(), 1);
+    assert_eq!(released[0].disk_name(), "disk1");
+
+    let mut state = None;
+    for _ in 0..50 {
+        state = backend.migration().status(0).map(|s| s.state().clone());
+        if state == Some(MigrationState::Done) {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert_eq!(state, Some(MigrationState::Done));
+    assert_eq!(backend.migration().status(0).unwrap().records(), 1);
+    assert!(!PathBuf::from(format!("{}migration.yaml", target)).exists());
+    let path = DiskPath::new("disk2".to_owned(), "".to_owned());
+    let read = backend
+        .get_local(KEY_ID.into(), Operation::new_local(0, path))
+        .await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3]);
+    drop_pearl(PEARL_MIGRATION_RESUME_PATH);
+}
+
+#[tokio::test]
+async fn test_stopped_disk_writes_go_to_alien() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_STOPPED_DISK_PATH);
+    let cluster = two_disk_cluster_config(PEARL_STOPPED_DISK_PATH, "disk2", 0);
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    let mapper = Virtual::new(&node, &cluster).await;
+    let backend = Backend::new(Arc::new(ClusterMap::new(mapper)), &node);
+    backend.run_backend().await.unwrap();
+    let operation = Operation::new_local(0, DiskPath::new("disk2".to_owned(), "".to_owned()));
+    backend.stop_disk("disk2").await.unwrap();
+    assert_eq!(backend.degraded_vdisks(), vec![0]);
+
+    let data = BobData::new(vec![1, 2, 3], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put_local(KEY_ID.into(), data.clone(), operation.clone())
+        .await;
+    assert!(write.is_ok(), "write to alien on the available disk");
+    let alien = backend.alien_vdisks_groups().await.unwrap().remove(0);
+    assert_eq!(alien.disk_name(), "disk1");
+    assert!(alien.get(KEY_ID.into()).await.is_ok());
+    let read = backend.get_local(KEY_ID.into(), operation.clone()).await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3], "read from local alien");
+    let options = BobOptions::new_get(Some(GetOptions::new_local()));
+    let exist = backend.exist(&[KEY_ID.into()], &options).await;
+    assert_eq!(exist.unwrap(), vec![true]);
+    let read = backend
+        .get_local((KEY_ID + 1).into(), operation.clone())
+        .await
+        .unwrap_err();
+    assert!(read.is_disk_unavailable());
+
+    backend.start_disk("disk2").await.unwrap();
+    assert!(backend.degraded_vdisks().is_empty());
+    let read = backend.get_local(KEY_ID.into(), operation.clone()).await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3], "not handed off yet");
+    let write = backend
+        .put_local(KEY_ID.into(), data, operation.clone())
+        .await;
+    assert!(write.is_ok());
+    let read = backend.get_local(KEY_ID.into(), operation).await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3]);
+    drop_pearl(PEARL_STOPPED_DISK_PATH);
+}
+
+#[tokio::test]
+async fn test_full_disk_refuses_writes() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_FULL_DISK_PATH);
+    let cluster = two_disk_cluster_config(PEARL_FULL_DISK_PATH, "disk2", 0);
+    let full_config = format!(
+        "{}\nfree_space:\n  hard_watermark: {}\n",
+        NODE_CONFIG,
+        u64::MAX
+    );
+    let full = NodeConfig::get_from_string(&full_config, &cluster).unwrap();
+    let mapper = Virtual::new(&full, &cluster).await;
+    let backend = Backend::new(Arc::new(ClusterMap::new(mapper)), &full);
+    backend.run_backend().await.unwrap();
+    let operation = Operation::new_local(0, DiskPath::new("disk2".to_owned(), "".to_owned()));
+    let data = BobData::new(vec![1, 2, 3], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put_local(KEY_ID.into(), data.clone(), operation.clone())
+        .await;
+    assert!(
+        write.unwrap_err().is_no_space(),
+        "space is measured on start"
+    );
+
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    backend.disks().set_free_space(node.free_space());
+    let write = backend
+        .put_local(KEY_ID.into(), data.clone(), operation.clone())
+        .await;
+    assert!(write.is_ok());
+
+    backend.disks().set_free_space(full.free_space());
+    let write = backend
+        .put_local(KEY_ID.into(), data, operation.clone())
+        .await
+        .unwrap_err();
+    assert!(write.is_no_space());
+    assert!(
+        backend.alien_vdisks_groups().await.unwrap().is_empty(),
+        "full disk isn't written to local alien"
+    );
+    let read = backend.get_local(KEY_ID.into(), operation.clone()).await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3]);
+
+    // deletes are allowed to free space, also in the new holder
+    let day = 24 * 60 * 60;
+    for timestamp in &[TIMESTAMP + 1, TIMESTAMP + 2 * day] {
+        let tombstone = BobData::tombstone(BobMeta::new(*timestamp));
+        let write = backend
+            .put_local(KEY_ID.into(), tombstone, operation.clone())
+            .await;
+        assert!(write.is_ok());
+    }
+    let data = BobData::new(vec![1], BobMeta::new(TIMESTAMP + 2 * day));
+    let write = backend
+        .put_local((KEY_ID + 1).into(), data, operation)
+        .await;
+    assert!(write.unwrap_err().is_no_space());
+    drop_pearl(PEARL_FULL_DISK_PATH);
+}
+
+#[tokio::test]
+async fn test_cold_holder_unloaded_and_loaded_on_read() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_UNLOAD_PATH);
+    let cluster = two_disk_cluster_config(PEARL_UNLOAD_PATH, "disk2", 0);
+    let operation = Operation::new_local(0, DiskPath::new("disk2".to_owned(), "".to_owned()));
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let day = 24 * 60 * 60;
+    let records = vec![
+        (KEY_ID, TIMESTAMP),
+        (KEY_ID + 1, TIMESTAMP + day),
+        (KEY_ID + 2, now),
+    ];
+    // the first run has no limit, it fills holders to measure their index memory
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    let mapper = Arc::new(ClusterMap::new(Virtual::new(&node, &cluster).await));
+    let backend = PearlBackend::new(mapper, &node, Arc::default());
+    backend.run_backend().await.unwrap();
+    for &(key, timestamp) in &records {
+        let data = BobData::new(vec![1, 2, 3], BobMeta::new(timestamp));
+        backend
+            .put(operation.clone(), key.into(), data)
+            .await
+            .unwrap();
+    }
+    backend.shutdown().await;
+
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    let mapper = Arc::new(ClusterMap::new(Virtual::new(&node, &cluster).await));
+    let backend = PearlBackend::new(mapper, &node, Arc::default());
+    backend.run_backend().await.unwrap();
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    let mut memory = 0;
+    for holder in group.holders().read().await.iter() {
+        memory += holder.index_memory().await;
+    }
+    backend.shutdown().await;
+
+    // unloading of a single cold holder is enough to fit the limit
+    let node_config = format!("{}  index_memory_limit: {}\n", NODE_CONFIG, memory - 1);
+    let node = NodeConfig::get_from_string(&node_config, &cluster).unwrap();
+    let mapper = Arc::new(ClusterMap::new(Virtual::new(&node, &cluster).await));
+    let backend = PearlBackend::new(mapper, &node, Arc::default());
+    backend.run_backend().await.unwrap();
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    let holders = group.holders().read().await.clone();
+    let find = |timestamp| {
+        holders
+            .iter()
+            .find(|holder| holder.gets_into_interval(timestamp))
+            .unwrap()
+    };
+    let (cold, warm, actual) = (find(TIMESTAMP), find(TIMESTAMP + day), find(now));
+    // read makes the second cold holder the most recently used one
+    let read = backend.get(operation.clone(), (KEY_ID + 1).into()).await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3]);
+    backend.unload_cold_holders().await;
+    assert!(!cold.is_loaded().await);
+    assert_eq!(cold.index_memory().await, 0);
+    assert_eq!(cold.records_count().await, 1);
+    assert!(warm.is_loaded().await, "recently used holder is kept");
+    assert!(actual.is_loaded().await, "actual holder is never unloaded");
+
+    let read = backend.get(operation.clone(), (KEY_ID + 3).into()).await;
+    assert!(read.unwrap_err().is_key_not_found());
+    assert!(
+        !cold.is_loaded().await,
+        "missing key is filtered by keys log"
+    );
+    let read = backend.get(operation.clone(), KEY_ID.into()).await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3]);
+    assert!(cold.is_loaded().await);
+
+    // keys log tail lost on crash, so holder is unloaded without filter
+    assert!(cold.has_complete_keys_log().await.unwrap());
+    std::fs::write(cold.path().join("keys.log"), b"").unwrap();
+    assert!(!cold.has_complete_keys_log().await.unwrap());
+    cold.unload().await;
+    assert!(!cold.is_loaded().await);
+    let read = backend.get(operation, KEY_ID.into()).await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3]);
+    drop_pearl(PEARL_UNLOAD_PATH);
+}
+
+#[tokio::test]
+async fn test_loading_vdisk_refuses_operations() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_NOT_READY_PATH);
+    let cluster = two_disk_cluster_config(PEARL_NOT_READY_PATH, "disk2", 0);
+    let node = NodeConfig::get_from_string(NODE_CONFIG, &cluster).unwrap();
+    let mapper = Virtual::new(&node, &cluster).await;
+    let backend = Backend::new(Arc::new(ClusterMap::new(mapper)), &node);
+    let operation = Operation::new_local(0, DiskPath::new("disk2".to_owned(), "".to_owned()));
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    assert!(!group.is_ready());
+    let data = BobData::new(vec![1, 2, 3], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put_local(KEY_ID.into(), data.clone(), operation.clone())
+        .await;
+    assert!(
+        write.unwrap_err().is_not_ready(),
+        "loading vdisk isn't written to alien"
+    );
+    let read = backend.get_local(KEY_ID.into(), operation.clone()).await;
+    assert!(read.unwrap_err().is_not_ready());
+
+    backend.run_backend().await.unwrap();
+    assert!(group.is_ready());
+    let aliens = backend.alien_vdisks_groups().await.unwrap();
+    assert!(aliens.is_empty(), "nothing is written to alien");
+    backend
+        .put_local(KEY_ID.into(), data, operation.clone())
+        .await
+        .unwrap();
+    let read = backend.get_local(KEY_ID.into(), operation).await;
+    assert_eq!(read.unwrap().inner(), &[1, 2, 3]);
+    drop_pearl(PEARL_NOT_READY_PATH);
+}
+
+#[tokio::test]
+async fn test_compaction_keeps_most_recent_versions() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_COMPACTION_PATH);
+    let backend = backend(PEARL_COMPACTION_PATH).await;
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(0, path);
+    // timestamp period is 1d, so each version goes to its own holder
+    let day = 24 * 60 * 60;
+    let deleted = KEY_ID + 1;
+    let records = vec![
+        (KEY_ID, BobData::new(vec![1], BobMeta::new(TIMESTAMP))),
+        (KEY_ID, BobData::new(vec![2], BobMeta::new(TIMESTAMP + day))),
+        (deleted, BobData::new(vec![3], BobMeta::new(TIMESTAMP))),
+        (
+            deleted,
+            BobData::tombstone(BobMeta::new(TIMESTAMP + 2 * day)),
+        ),
+    ];
+    for (key, data) in records {
+        backend
+            .put(operation.clone(), key.into(), data)
+            .await
+            .unwrap();
+    }
+
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    let compaction = Compaction::new(CompactionConfig::default());
+    let mut report = CompactionReport::default();
+    compaction.compact_group(&group, &mut report).await;
+    assert_eq!(report.merged(), 3);
+    assert_eq!(report.created(), 1);
+    assert_eq!(report.records(), 1);
+    assert_eq!(report.dropped(), 3);
+
+    let holders = group.holders().read().await.clone();
+    let merged: Vec<_> = holders
+        .iter()
+        .filter(|holder| holder.gets_into_interval(TIMESTAMP))
+        .collect();
+    assert_eq!(merged.len(), 1);
+    assert_eq!(merged[0].end_timestamp(), 3 * day);
+    assert_eq!(merged[0].keys().await.unwrap(), vec![KEY_ID.into()]);
+    let read = backend.get(operation.clone(), KEY_ID.into()).await;
+    assert_eq!(read.unwrap().inner(), &[2]);
+    let read = backend.get(operation, deleted.into()).await;
+    assert!(read.unwrap_err().is_key_not_found());
+    // merged holder is compared with replicas by timestamp periods
+    let digests = group.digest().await.unwrap();
+    assert!(digests.iter().any(|digest| digest.start_timestamp() == day));
+    let versions = group.versions(day).await.unwrap();
+    assert_eq!(versions.len(), 1);
+    assert!(group.versions(0).await.unwrap().is_empty());
+    drop_pearl(PEARL_COMPACTION_PATH);
+}
+
+#[tokio::test]
+async fn test_compaction_keeps_recent_tombstones() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_TOMBSTONES_PATH);
+    let backend = backend(PEARL_TOMBSTONES_PATH).await;
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(0, path);
+    let day = 24 * 60 * 60;
+    let week = 7 * day;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    // both holders are closed and belong to the previous window
+    let start = (now / week - 1) * week;
+    let records = vec![
+        (KEY_ID, BobData::new(vec![1], BobMeta::new(start))),
+        (KEY_ID, BobData::tombstone(BobMeta::new(start + day))),
+    ];
+    for (key, data) in records {
+        backend
+            .put(operation.clone(), key.into(), data)
+            .await
+            .unwrap();
+    }
+
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    let config = CompactionConfig::new(None, "7d".to_owned(), None, "30d".to_owned());
+    let compaction = Compaction::new(config);
+    let mut report = CompactionReport::default();
+    compaction.compact_group(&group, &mut report).await;
+    assert_eq!(report.created(), 1);
+    assert_eq!(report.records(), 1, "tombstone is kept during grace period");
+    let versions = group.key_versions(None, &[KEY_ID.into()]).await.unwrap();
+    assert_eq!(
+        versions,
+        vec![RecordVersion::new(KEY_ID.into(), start + day, true)]
+    );
+    drop_pearl(PEARL_TOMBSTONES_PATH);
+}
+
+#[tokio::test]
+async fn test_interrupted_compaction_swap_finished_on_start() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_SWAP_PATH);
+    let backend = backend(PEARL_SWAP_PATH).await;
+    backend.run_backend().await.unwrap();
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(0, path);
+    let day = 24 * 60 * 60;
+    let records = vec![
+        (KEY_ID, TIMESTAMP, 1),
+        (KEY_ID, TIMESTAMP + day, 2),
+        (KEY_ID + 1, TIMESTAMP + 10 * day, 3),
+    ];
+    for (key, timestamp, value) in records {
+        let data = BobData::new(vec![value], BobMeta::new(timestamp));
+        backend
+            .put(operation.clone(), key.into(), data)
+            .await
+            .unwrap();
+    }
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    // holder closed for writes by swap keeps its records count
+    let source = holder_of(&group, TIMESTAMP).await;
+    source.close_for_writes().await;
+    let data = BobData::new(vec![4], BobMeta::new(TIMESTAMP));
+    let write = backend
+        .put(operation.clone(), (KEY_ID + 2).into(), data)
+        .await;
+    assert!(write.unwrap_err().is_not_ready());
+    assert_eq!(source.records_count().await, 1);
+    source.open_for_writes();
+
+    let compaction = Compaction::new(CompactionConfig::default());
+    let mut report = CompactionReport::default();
+    compaction.compact_group(&group, &mut report).await;
+    assert_eq!(report.created(), 1);
+    // swap was interrupted after the compacted holder took place of the sources
+    let merged = holder_of(&group, TIMESTAMP).await;
+    let name = merged.path().file_name().unwrap().to_str().unwrap();
+    let hash = name.split('_').nth(1).unwrap();
+    let directory = merged.path().parent().unwrap().to_owned();
+    std::fs::create_dir(directory.join(format!("replaced_0_{}", hash))).unwrap();
+    // swap was interrupted before the compacted holder took place of the source
+    let single = holder_of(&group, TIMESTAMP + 10 * day).await;
+    let single_name = single.path().file_name().unwrap().to_str().unwrap();
+    let replaced = directory.join(format!("replaced_{}", single_name));
+    backend.shutdown().await;
+    std::fs::rename(single.path(), &replaced).unwrap();
+
+    let restarted = self::backend(PEARL_SWAP_PATH).await;
+    restarted.run_backend().await.unwrap();
+    let group = restarted.vdisks_groups().await.unwrap().remove(0);
+    assert_eq!(group.holders().read().await.len(), 2);
+    let read = restarted.get(operation.clone(), KEY_ID.into()).await;
+    assert_eq!(read.unwrap().inner(), &[2]);
+    let read = restarted.get(operation, (KEY_ID + 1).into()).await;
+    assert_eq!(read.unwrap().inner(), &[3]);
+    let leftovers = std::fs::read_dir(&directory)
+        .unwrap()
+        .filter(|entry| {
+            let name = entry.as_ref().unwrap().file_name();
+            name.to_string_lossy().starts_with("replaced_")
+        })
+        .count();
+    assert_eq!(leftovers, 0);
+    drop_pearl(PEARL_SWAP_PATH);
+}
+
+async fn holder_of(group: &Group, timestamp: u64) -> Holder {
+    let holders = group.holders().read().await.clone();
+    holders
+        .into_iter()
+        .find(|holder| holder.gets_into_interval(timestamp))
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_compressed_and_uncompressed_records_coexist() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_COMPRESSION_PATH);
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(0, path);
+    let data = BobData::new(vec![7; 4096], BobMeta::new(TIMESTAMP)).checksummed();
+    let backend = backend(PEARL_COMPRESSION_PATH).await;
+    backend.run_backend().await.unwrap();
+    backend
+        .put(operation.clone(), KEY_ID.into(), data.clone())
+        .await
+        .unwrap();
+    backend.shutdown().await;
+
+    for algorithm in &["zstd", "lz4"] {
+        let config = CompressionConfig::new(algorithm.to_string(), 1024, None);
+        let mut record = Data::from(data.clone());
+        let (size, compressed) = record.compress(&config).unwrap();
+        assert!(compressed < size);
+        let bytes = record.to_vec();
+        let read = Data::from_bytes(&bytes, KEY_ID.into(), None).unwrap();
+        assert_eq!(read.inner(), data.inner());
+        assert_eq!(read.checksum(), data.checksum());
+        // broken payload is reported as corrupted record, so it's read from replicas
+        let truncated = &bytes[..bytes.len() - 4];
+        let read = Data::from_bytes(truncated, KEY_ID.into(), None);
+        assert!(read.unwrap_err().is_data_corrupted());
+        // small records are stored as is
+        let small = BobData::new(vec![7; 16], BobMeta::new(TIMESTAMP));
+        assert!(Data::from(small).compress(&config).is_none());
+    }
+    // lz4 size isn't trusted, so corrupted size doesn't allocate the memory
+    let config = CompressionConfig::new("lz4".to_owned(), 1024, None);
+    let mut record = Data::from(data.clone());
+    let (_, compressed) = record.compress(&config).unwrap();
+    let mut bytes = record.to_vec();
+    let payload = bytes.len() - compressed;
+    bytes[payload..payload + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+    let read = Data::from_bytes(&bytes, KEY_ID.into(), None);
+    assert!(read.unwrap_err().is_data_corrupted());
+
+    let node_config = format!(
+        "{}  compression:\n    algorithm: zstd\n    min_size: 1024\n",
+        NODE_CONFIG
+    );
+    let backend = backend_with_node_config(PEARL_COMPRESSION_PATH, &node_config).await;
+    backend.run_backend().await.unwrap();
+    let compressed = BobData::new(vec![8; 4096], BobMeta::new(TIMESTAMP)).checksummed();
+    backend
+        .put(operation.clone(), (KEY_ID + 1).into(), compressed)
+        .await
+        .unwrap();
+    let read = backend.get(operation.clone(), KEY_ID.into()).await.unwrap();
+    assert_eq!(read.inner(), &[7; 4096][..]);
+    let read = backend
+        .get(operation.clone(), (KEY_ID + 1).into())
+        .await
+        .unwrap();
+    assert_eq!(read.inner(), &[8; 4096][..]);
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    let storage = holder_of(&group, TIMESTAMP)
+        .await
+        .storage()
+        .read()
+        .await
+        .get();
+    for (key, compressed) in vec![(KEY_ID, false), (KEY_ID + 1, true)] {
+        let entries = storage
+            .read_all(Key::from(BobKey::from(key)))
+            .await
+            .unwrap();
+        let stored = entries[0].load_data().await.unwrap();
+        assert_eq!(stored.len() < 4096, compressed);
+    }
+    // size of the compressed record is read from its meta
+    let head = backend.head(operation, (KEY_ID + 1).into()).await.unwrap();
+    assert_eq!(head.size(), 4096);
+    assert_eq!(head.meta().timestamp(), TIMESTAMP);
+    backend.shutdown().await;
+    drop_pearl(PEARL_COMPRESSION_PATH);
+}
+
+fn write_key_file(path: &str, current: u32, ids: &[u8]) {
+    let keys: String = ids
+        .iter()
+        .map(|&id| format!("  {}: {}\n", id, base64::encode([id; 32])))
+        .collect();
+    let content = format!("current: {}\nkeys:\n{}", current, keys);
+    std::fs::write(path, content).unwrap();
+}
+
+// payload is bound to the record key and head and can't be read without keys
+fn check_encrypted_record(key_file: &str, data: BobData, plain: u64) {
+    let keyring = Keyring::new(&EncryptionConfig::new(key_file.to_owned())).unwrap();
+    let mut record = Data::from(data.clone().checksummed());
+    record.encrypt(&keyring, KEY_ID.into()).unwrap();
+    let bytes = record.to_vec();
+    assert!(Data::needs_reencryption(&bytes, 2).unwrap());
+    let read = Data::from_bytes(&bytes, KEY_ID.into(), Some(&keyring)).unwrap();
+    assert_eq!(read.inner(), &[2; 64][..]);
+    assert_eq!(read.checksum(), data.checksummed().checksum());
+    let err = Data::from_bytes(&bytes, plain.into(), Some(&keyring)).unwrap_err();
+    assert!(err.is_data_corrupted());
+    let mut tampered = bytes.clone();
+    tampered[7] ^= 1;
+    let err = Data::from_bytes(&tampered, KEY_ID.into(), Some(&keyring)).unwrap_err();
+    assert!(err.is_data_corrupted());
+    let err = Data::from_bytes(&bytes, KEY_ID.into(), None).unwrap_err();
+    assert!(err.is_encryption_key_missing());
+    let other_key_file = format!("{}other_keys.yaml", PEARL_ENCRYPTION_PATH);
+    write_key_file(&other_key_file, 2, &[2]);
+    let other_keyring = Keyring::new(&EncryptionConfig::new(other_key_file)).unwrap();
+    let err = Data::from_bytes(&bytes, KEY_ID.into(), Some(&other_keyring)).unwrap_err();
+    assert!(err.is_encryption_key_missing());
+}
+
+#[tokio::test]
+async fn test_encrypted_records_survive_key_rotation() {
+    test_utils::init_logger();
+    drop_pearl(PEARL_ENCRYPTION_PATH);
+    std::fs::create_dir_all(PEARL_ENCRYPTION_PATH).unwrap();
+    let key_file = format!("{}keys.yaml", PEARL_ENCRYPTION_PATH);
+    let node_config = format!("{}  encryption:\n    key_file: {}\n", NODE_CONFIG, key_file);
+    let path = DiskPath::new(DISK_NAME.to_owned(), "".to_owned());
+    let operation = Operation::new_local(0, path);
+    let plain = KEY_ID + 1;
+    let backend = backend(PEARL_ENCRYPTION_PATH).await;
+    backend.run_backend().await.unwrap();
+    let data = BobData::new(vec![1; 64], BobMeta::new(TIMESTAMP));
+    backend
+        .put(operation.clone(), plain.into(), data)
+        .await
+        .unwrap();
+    backend.shutdown().await;
+
+    write_key_file(&key_file, 1, &[1, 2]);
+    let backend = backend_with_node_config(PEARL_ENCRYPTION_PATH, &node_config).await;
+    backend.run_backend().await.unwrap();
+    let data = BobData::new(vec![2; 64], BobMeta::new(TIMESTAMP));
+    backend
+        .put(operation.clone(), KEY_ID.into(), data.clone())
+        .await
+        .unwrap();
+    backend.shutdown().await;
+
+    check_encrypted_record(&key_file, data, plain);
+
+    write_key_file(&key_file, 2, &[1, 2]);
+    let backend = backend_with_node_config(PEARL_ENCRYPTION_PATH, &node_config).await;
+    backend.run_backend().await.unwrap();
+    let read = backend.get(operation.clone(), KEY_ID.into()).await.unwrap();
+    assert_eq!(read.inner(), &[2; 64][..]);
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    assert_eq!(group.current_key_id(), Some(2));
+    let compaction = Compaction::new(CompactionConfig::default());
+    let mut report = CompactionReport::default();
+    compaction.reencrypt_group(&group, &mut report).await;
+    assert_eq!(report.merged(), 1);
+    assert_eq!(report.records(), 2);
+    assert_eq!(report.skipped(), 0);
+    let holders = group.holders().read().await.clone();
+    assert_eq!(holders.len(), 1);
+    assert!(!holders[0].needs_reencryption().await.unwrap());
+    backend.shutdown().await;
+
+    // rewritten holder has the same name as its source, so it's replaced in place
+    write_key_file(&key_file, 3, &[2, 3]);
+    let backend = backend_with_node_config(PEARL_ENCRYPTION_PATH, &node_config).await;
+    backend.run_backend().await.unwrap();
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    let mut report = CompactionReport::default();
+    compaction.reencrypt_group(&group, &mut report).await;
+    assert_eq!(report.merged(), 1);
+    assert_eq!(group.holders().read().await.len(), 1);
+    backend.shutdown().await;
+
+    // old keys aren't needed after re-encryption
+    write_key_file(&key_file, 3, &[3]);
+    let backend = backend_with_node_config(PEARL_ENCRYPTION_PATH, &node_config).await;
+    backend.run_backend().await.unwrap();
+    let read = backend.get(operation.clone(), KEY_ID.into()).await.unwrap();
+    assert_eq!(read.inner(), &[2; 64][..]);
+    let read = backend.get(operation.clone(), plain.into()).await.unwrap();
+    assert_eq!(read.inner(), &[1; 64][..]);
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let data = BobData::new(vec![3; 64], BobMeta::new(now));
+    backend
+        .put(operation.clone(), plain.into(), data)
+        .await
+        .unwrap();
+    backend.shutdown().await;
+
+    // active holder keeps old key until it's closed, so re-encryption reports it
+    write_key_file(&key_file, 4, &[3, 4]);
+    let backend = backend_with_node_config(PEARL_ENCRYPTION_PATH, &node_config).await;
+    backend.run_backend().await.unwrap();
+    let group = backend.vdisks_groups().await.unwrap().remove(0);
+    let mut report = CompactionReport::default();
+    compaction.reencrypt_group(&group, &mut report).await;
+    assert_eq!(report.merged(), 1);
+    assert_eq!(report.skipped(), 1);
+    let read = backend.get(operation, plain.into()).await.unwrap();
+    assert_eq!(read.inner(), &[3; 64][..]);
+    backend.shutdown().await;
+    drop_pearl(PEARL_ENCRYPTION_PATH);
 }
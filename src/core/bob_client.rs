@@ -2,16 +2,28 @@
 
 pub(crate) mod b_client {
     use super::super::prelude::*;
-    use super::{ExistResult, GetResult, PingResult, PutResult};
+    use super::{
+        ClusterMapResult, DeleteResult, DigestResult, ExistResult, GetBatchResult, GetResult,
+        HeadResult, OperationTimeout, PingResult, PutBatchResult, PutResult, RecordsResult,
+        VersionsResult,
+    };
+    use crate::core::auth::{client_interceptor, Credentials};
+    use crate::core::backend::{HolderDigest, RecordVersion};
+    use crate::grpc::{
+        bob_repair_client::BobRepairClient, BatchBlob, DigestRequest, GetBatchRequest,
+        PutBatchRequest, Record, RecordsRequest, VersionsRequest,
+    };
     use mockall::mock;
 
     /// Client for interaction with bob backend
     #[derive(Clone)]
     pub(crate) struct BobClient {
         node: Node,
-        operation_timeout: Duration,
+        operation_timeout: OperationTimeout,
         client: BobApiClient<Channel>,
+        repair: BobRepairClient<Channel>,
         metrics: BobClientMetrics,
+        credentials: Option<Credentials>,
     }
 
     impl BobClient {
@@ -21,18 +33,35 @@ pub(crate) mod b_client {
         #[allow(dead_code)]
         pub(crate) async fn create(
             node: Node,
-            operation_timeout: Duration,
+            operation_timeout: OperationTimeout,
             metrics: BobClientMetrics,
+            tls: Option<ClientTlsConfig>,
+            credentials: Option<Credentials>,
         ) -> Result<Self, String> {
-            let endpoint = Endpoint::from(node.get_uri()).tcp_nodelay(true);
-            let client = BobApiClient::connect(endpoint)
-                .await
-                .map_err(|e| e.to_string())?;
+            let mut endpoint = Endpoint::from(node.get_uri()).tcp_nodelay(true);
+            if let Some(tls) = tls {
+                endpoint = endpoint.tls_config(tls).map_err(|e| e.to_string())?;
+            }
+            let channel = endpoint.connect().await.map_err(|e| e.to_string())?;
+            let (client, repair) = if let Some(credentials) = &credentials {
+                let interceptor = client_interceptor(credentials)?;
+                (
+                    BobApiClient::with_interceptor(channel.clone(), interceptor.clone()),
+                    BobRepairClient::with_interceptor(channel, interceptor),
+                )
+            } else {
+                (
+                    BobApiClient::new(channel.clone()),
+                    BobRepairClient::new(channel),
+                )
+            };
             Ok(Self {
                 node,
                 client,
+                repair,
                 operation_timeout,
                 metrics,
+                credentials,
             })
         }
 
@@ -47,16 +76,9 @@ pub(crate) mod b_client {
         #[allow(dead_code)]
         pub(crate) async fn put(&self, key: BobKey, d: BobData, options: PutOptions) -> PutResult {
             debug!("real client put called");
-            let meta = BlobMeta {
-                timestamp: d.meta().timestamp(),
-            };
-            let blob = Blob {
-                meta: Some(meta),
-                data: d.into_inner(),
-            };
             let message = PutRequest {
-                key: Some(BlobKey { key }),
-                data: Some(blob),
+                key: Some(BlobKey::from(key)),
+                data: Some(Blob::from(d)),
                 options: Some(options),
             };
             let request = Request::new(message);
@@ -65,7 +87,7 @@ pub(crate) mod b_client {
             let mut client = self.client.clone();
             let node_name = self.node.name().to_owned();
             let future = client.put(request);
-            if timeout(self.operation_timeout, future).await.is_ok() {
+            if timeout(self.operation_timeout.get(), future).await.is_ok() {
                 self.metrics.put_timer_stop(timer);
                 Ok(NodeOutput::new(node_name, ()))
             } else {
@@ -83,18 +105,21 @@ pub(crate) mod b_client {
             let timer = BobClientMetrics::start_timer();
 
             let message = GetRequest {
-                key: Some(BlobKey { key }),
+                key: Some(BlobKey::from(key)),
                 options: Some(options),
             };
             let request = Request::new(message);
-            let result = timeout(self.operation_timeout, client.get(request)).await;
+            let result = timeout(self.operation_timeout.get(), client.get(request)).await;
             match result {
                 Ok(Ok(data)) => {
                     self.metrics.get_timer_stop(timer);
-                    let ans = data.into_inner();
-                    let meta = BobMeta::new(ans.meta.expect("get blob meta").timestamp);
-                    let inner = BobData::new(ans.data, meta);
-                    Ok(NodeOutput::new(node_name.clone(), inner))
+                    match Self::verified_data(key, data.into_inner()) {
+                        Ok(inner) => Ok(NodeOutput::new(node_name, inner)),
+                        Err(e) => {
+                            self.metrics.get_error_count();
+                            Err(NodeOutput::new(node_name, e))
+                        }
+                    }
                 }
                 Ok(Err(e)) => {
                     self.metrics.get_error_count();
@@ -105,12 +130,148 @@ pub(crate) mod b_client {
             }
         }
 
+        /// Reads version of the key without data, size of the large blob is taken from its
+        /// manifest
+        #[cfg_attr(test, allow(dead_code))]
+        pub(crate) async fn head(&self, key: BobKey, options: GetOptions) -> HeadResult {
+            let node_name = self.node.name().to_owned();
+            let mut client = self.client.clone();
+            let request = Request::new(GetRequest {
+                key: Some(BlobKey::from(key)),
+                options: Some(options),
+            });
+            let result = timeout(self.operation_timeout.get(), client.head(request)).await;
+            match result {
+                Ok(Ok(response)) => {
+                    let head = response.into_inner();
+                    match head.meta {
+                        Some(meta) => {
+                            let head =
+                                BobHead::new(BobMeta::from(meta), head.size, false, head.manifest);
+                            Ok(NodeOutput::new(node_name, head))
+                        }
+                        None => Err(NodeOutput::new(
+                            node_name,
+                            Error::failed("head without meta"),
+                        )),
+                    }
+                }
+                Ok(Err(e)) => Err(NodeOutput::new(node_name, Error::from(e))),
+                Err(_) => Err(NodeOutput::new(node_name, Error::timeout())),
+            }
+        }
+
+        /// Reads keys with one request, results are in the order of keys
+        #[cfg_attr(test, allow(dead_code))]
+        pub(crate) async fn get_batch(
+            &self,
+            keys: Vec<BobKey>,
+            options: GetOptions,
+        ) -> GetBatchResult {
+            let node_name = self.node.name().to_owned();
+            let mut client = self.client.clone();
+            self.metrics.get_count();
+            let timer = BobClientMetrics::start_timer();
+            let message = GetBatchRequest {
+                keys: keys.iter().copied().map(BlobKey::from).collect(),
+                options: Some(options),
+            };
+            let request = Request::new(message);
+            let result = timeout(self.operation_timeout.get(), client.get_batch(request)).await;
+            self.metrics.get_timer_stop(timer);
+            match result {
+                Ok(Ok(response)) => {
+                    let results = response.into_inner().results;
+                    if results.len() == keys.len() {
+                        let results = keys
+                            .into_iter()
+                            .zip(results)
+                            .map(|(key, result)| Self::batch_data(key, result))
+                            .collect();
+                        Ok(NodeOutput::new(node_name, results))
+                    } else {
+                        self.metrics.get_error_count();
+                        let error = Error::failed("count of results doesn't match keys");
+                        Err(NodeOutput::new(node_name, error))
+                    }
+                }
+                Ok(Err(e)) => {
+                    self.metrics.get_error_count();
+                    Err(NodeOutput::new(node_name, Error::from(e)))
+                }
+                Err(_) => {
+                    self.metrics.get_error_count();
+                    Err(NodeOutput::new(node_name, Error::timeout()))
+                }
+            }
+        }
+
+        /// Writes records with one request, results are in the order of records
+        #[cfg_attr(test, allow(dead_code))]
+        pub(crate) async fn put_batch(
+            &self,
+            records: Vec<(BobKey, BobData)>,
+            options: PutOptions,
+        ) -> PutBatchResult {
+            let node_name = self.node.name().to_owned();
+            let mut client = self.client.clone();
+            self.metrics.put_count();
+            let timer = BobClientMetrics::start_timer();
+            let count = records.len();
+            let blobs = records
+                .into_iter()
+                .map(|(key, data)| BatchBlob {
+                    key: Some(BlobKey::from(key)),
+                    blob: Some(Blob::from(data)),
+                    error: None,
+                })
+                .collect();
+            let message = PutBatchRequest {
+                blobs,
+                options: Some(options),
+            };
+            let request = Request::new(message);
+            let result = timeout(self.operation_timeout.get(), client.put_batch(request)).await;
+            self.metrics.put_timer_stop(timer);
+            match result {
+                Ok(Ok(response)) => {
+                    let statuses = response.into_inner().statuses;
+                    if statuses.len() == count {
+                        let results = statuses
+                            .into_iter()
+                            .map(|status| status.error.map_or(Ok(()), |e| Err(Error::from(e))))
+                            .collect();
+                        Ok(NodeOutput::new(node_name, results))
+                    } else {
+                        self.metrics.put_error_count();
+                        let error = Error::failed("count of statuses doesn't match records");
+                        Err(NodeOutput::new(node_name, error))
+                    }
+                }
+                Ok(Err(e)) => {
+                    self.metrics.put_error_count();
+                    Err(NodeOutput::new(node_name, Error::from(e)))
+                }
+                Err(_) => {
+                    self.metrics.put_error_count();
+                    Err(NodeOutput::new(node_name, Error::timeout()))
+                }
+            }
+        }
+
         #[allow(dead_code)]
         pub(crate) async fn ping(&self) -> PingResult {
             let mut client = self.client.clone();
-            let result = timeout(self.operation_timeout, client.ping(Request::new(Null {}))).await;
+            let result = timeout(
+                self.operation_timeout.get(),
+                client.ping(Request::new(Null {})),
+            )
+            .await;
             match result {
-                Ok(Ok(_)) => Ok(NodeOutput::new(self.node.name().to_owned(), ())),
+                Ok(Ok(response)) => Ok(NodeOutput::new(
+                    self.node.name().to_owned(),
+                    response.into_inner(),
+                )),
                 Ok(Err(e)) => Err(NodeOutput::new(self.node.name().to_owned(), Error::from(e))),
                 Err(_) => {
                     warn!("node {} ping timeout, reset connection", self.node.name());
@@ -127,7 +288,7 @@ pub(crate) mod b_client {
             let mut client = self.client.clone();
             self.metrics.exist_count();
             let timer = BobClientMetrics::start_timer();
-            let keys = keys.into_iter().map(|key| BlobKey { key }).collect();
+            let keys = keys.into_iter().map(BlobKey::from).collect();
             let message = ExistRequest {
                 keys,
                 options: Some(options),
@@ -142,6 +303,217 @@ pub(crate) mod b_client {
             result
         }
 
+        #[cfg_attr(test, allow(dead_code))]
+        pub(crate) async fn delete(
+            &self,
+            key: BobKey,
+            meta: BobMeta,
+            options: DeleteOptions,
+        ) -> DeleteResult {
+            let node_name = self.node.name().to_owned();
+            let mut client = self.client.clone();
+            self.metrics.delete_count();
+            let timer = BobClientMetrics::start_timer();
+            let message = DeleteRequest {
+                key: Some(BlobKey::from(key)),
+                meta: Some(BlobMeta::from(&meta)),
+                options: Some(options),
+            };
+            let request = Request::new(message);
+            let result = timeout(self.operation_timeout.get(), client.delete(request)).await;
+            self.metrics.delete_timer_stop(timer);
+            match result {
+                Ok(Ok(_)) => Ok(NodeOutput::new(node_name, ())),
+                Ok(Err(e)) => {
+                    self.metrics.delete_error_count();
+                    Err(NodeOutput::new(node_name, Error::from(e)))
+                }
+                Err(_) => {
+                    self.metrics.delete_error_count();
+                    Err(NodeOutput::new(node_name, Error::timeout()))
+                }
+            }
+        }
+
+        #[cfg_attr(test, allow(dead_code))]
+        pub(crate) async fn digest(&self, vdisk_id: VDiskID) -> DigestResult {
+            let node_name = self.node.name().to_owned();
+            let mut client = self.repair.clone();
+            let request = Request::new(DigestRequest { vdisk_id });
+            let result = timeout(self.operation_timeout.get(), client.get_digest(request)).await;
+            match result {
+                Ok(Ok(response)) => {
+                    let holders = response.into_inner().holders;
+                    let digest = holders.into_iter().map(HolderDigest::from).collect();
+                    Ok(NodeOutput::new(node_name, digest))
+                }
+                Ok(Err(e)) => Err(NodeOutput::new(node_name, Error::from(e))),
+                Err(_) => Err(NodeOutput::new(node_name, Error::timeout())),
+            }
+        }
+
+        #[cfg_attr(test, allow(dead_code))]
+        pub(crate) async fn versions(
+            &self,
+            vdisk_id: VDiskID,
+            start_timestamp: u64,
+        ) -> VersionsResult {
+            let node_name = self.node.name().to_owned();
+            let mut client = self.repair.clone();
+            let request = Request::new(VersionsRequest {
+                vdisk_id,
+                start_timestamp,
+            });
+            let result = timeout(self.operation_timeout.get(), client.get_versions(request)).await;
+            match result {
+                Ok(Ok(response)) => {
+                    let versions = response.into_inner().versions;
+                    let versions = versions.into_iter().map(RecordVersion::try_from).collect();
+                    match versions {
+                        Ok(versions) => Ok(NodeOutput::new(node_name, versions)),
+                        Err(e) => Err(NodeOutput::new(node_name, e)),
+                    }
+                }
+                Ok(Err(e)) => Err(NodeOutput::new(node_name, Error::from(e))),
+                Err(_) => Err(NodeOutput::new(node_name, Error::timeout())),
+            }
+        }
+
+        /// Requests records from the remote holder, every message of the stream must arrive
+        /// within operation timeout
+        #[cfg_attr(test, allow(dead_code))]
+        pub(crate) async fn records(
+            &self,
+            vdisk_id: VDiskID,
+            start_timestamp: u64,
+            keys: Vec<BobKey>,
+        ) -> RecordsResult {
+            self.request_records(RecordsRequest {
+                vdisk_id,
+                start_timestamp,
+                keys: keys.into_iter().map(BlobKey::from).collect(),
+                latest: false,
+            })
+            .await
+        }
+
+        /// Requests most recent records of the keys from all holders of the remote vdisk
+        /// replica, missing keys are skipped
+        #[cfg_attr(test, allow(dead_code))]
+        pub(crate) async fn latest_records(
+            &self,
+            vdisk_id: VDiskID,
+            keys: Vec<BobKey>,
+        ) -> RecordsResult {
+            self.request_records(RecordsRequest {
+                vdisk_id,
+                start_timestamp: 0,
+                keys: keys.into_iter().map(BlobKey::from).collect(),
+                latest: true,
+            })
+            .await
+        }
+
+        async fn request_records(&self, request: RecordsRequest) -> RecordsResult {
+            let node_name = self.node.name().to_owned();
+            let mut client = self.repair.clone();
+            let request = Request::new(request);
+            let mut stream =
+                match timeout(self.operation_timeout.get(), client.get_records(request)).await {
+                    Ok(Ok(response)) => response.into_inner(),
+                    Ok(Err(e)) => return Err(NodeOutput::new(node_name, Error::from(e))),
+                    Err(_) => return Err(NodeOutput::new(node_name, Error::timeout())),
+                };
+            let mut records = vec![];
+            loop {
+                match timeout(self.operation_timeout.get(), stream.message()).await {
+                    Ok(Ok(Some(record))) => {
+                        if let Some((key, data)) = Self::extract_record(record) {
+                            // corrupted record is skipped, so it's requested on the next repair
+                            match data.verify_checksum(key) {
+                                Ok(()) => records.push((key, data)),
+                                Err(e) => warn!("skip record from {}: {}", node_name, e),
+                            }
+                        } else {
+                            let error = Error::failed("record without key or blob");
+                            return Err(NodeOutput::new(node_name, error));
+                        }
+                    }
+                    Ok(Ok(None)) => return Ok(NodeOutput::new(node_name, records)),
+                    Ok(Err(e)) => return Err(NodeOutput::new(node_name, Error::from(e))),
+                    Err(_) => return Err(NodeOutput::new(node_name, Error::timeout())),
+                }
+            }
+        }
+
+        /// Requests cluster map of the remote node
+        #[cfg_attr(test, allow(dead_code))]
+        pub(crate) async fn cluster_map(&self) -> ClusterMapResult {
+            let node_name = self.node.name().to_owned();
+            let mut client = self.repair.clone();
+            let result = timeout(
+                self.operation_timeout.get(),
+                client.get_cluster_map(Request::new(Null {})),
+            )
+            .await;
+            match result {
+                Ok(Ok(response)) => {
+                    let map = response.into_inner();
+                    // without authentication any node is trusted
+                    let trusted = self.credentials.as_ref().map_or(true, |credentials| {
+                        credentials.verify_cluster_map(map.version, &map.config, &map.signature)
+                    });
+                    if !trusted {
+                        let error = Error::failed("cluster map signature is invalid");
+                        return Err(NodeOutput::new(node_name, error));
+                    }
+                    match ClusterConfig::get_from_string(&map.config) {
+                        Ok(cluster) if cluster.version() == map.version => {
+                            Ok(NodeOutput::new(node_name, cluster))
+                        }
+                        Ok(_) => {
+                            let error = Error::failed("cluster map version mismatch");
+                            Err(NodeOutput::new(node_name, error))
+                        }
+                        Err(e) => Err(NodeOutput::new(node_name, Error::failed(e))),
+                    }
+                }
+                Ok(Err(e)) => Err(NodeOutput::new(node_name, Error::from(e))),
+                Err(_) => Err(NodeOutput::new(node_name, Error::timeout())),
+            }
+        }
+
+        fn verified_data(key: BobKey, blob: Blob) -> Result<BobData, Error> {
+            let data = BobData::try_from(blob)?;
+            data.verify_checksum(key)?;
+            Ok(data)
+        }
+
+        fn batch_data(key: BobKey, result: BatchBlob) -> Result<BobData, Error> {
+            match (result.blob, result.error) {
+                (_, Some(error)) => Err(Error::from(error)),
+                (Some(blob), None) => Self::verified_data(key, blob),
+                (None, None) => Err(Error::failed("result without blob and error")),
+            }
+        }
+
+        fn extract_record(record: Record) -> Option<(BobKey, BobData)> {
+            let key = BobKey::try_from(record.key?).ok()?;
+            let blob = record.blob?;
+            let meta = BobMeta::from(blob.meta?);
+            let mut data = if record.tombstone {
+                BobData::tombstone(meta)
+            } else if blob.manifest {
+                BobData::manifest(blob.data, meta)
+            } else {
+                BobData::new(blob.data, meta)
+            };
+            if let Some(checksum) = blob.checksum {
+                data = data.with_checksum(checksum.crc32c);
+            }
+            Some((key, data))
+        }
+
         fn get_exist_result(
             node_name: String,
             exist_response: Result<Response<ExistResponse>, Status>,
@@ -155,12 +527,21 @@ pub(crate) mod b_client {
 
     mock! {
         pub(crate) BobClient {
-            pub(crate) async fn create(node: Node, operation_timeout: Duration, metrics: BobClientMetrics) -> Result<Self, String>;
+            pub(crate) async fn create(node: Node, operation_timeout: OperationTimeout, metrics: BobClientMetrics, tls: Option<ClientTlsConfig>, credentials: Option<Credentials>) -> Result<Self, String>;
             pub(crate) async fn put(&self, key: BobKey, d: BobData, options: PutOptions) -> PutResult;
             pub(crate) async fn get(&self, key: BobKey, options: GetOptions) -> GetResult;
+            pub(crate) async fn head(&self, key: BobKey, options: GetOptions) -> HeadResult;
+            pub(crate) async fn get_batch(&self, keys: Vec<BobKey>, options: GetOptions) -> GetBatchResult;
+            pub(crate) async fn put_batch(&self, records: Vec<(BobKey, BobData)>, options: PutOptions) -> PutBatchResult;
             pub(crate) async fn ping(&self) -> PingResult;
             pub(crate) fn node(&self) -> &Node;
             pub(crate) async fn exist(&self, keys: Vec<BobKey>, options: GetOptions) -> ExistResult;
+            pub(crate) async fn delete(&self, key: BobKey, meta: BobMeta, options: DeleteOptions) -> DeleteResult;
+            pub(crate) async fn digest(&self, vdisk_id: VDiskID) -> DigestResult;
+            pub(crate) async fn versions(&self, vdisk_id: VDiskID, start_timestamp: u64) -> VersionsResult;
+            pub(crate) async fn records(&self, vdisk_id: VDiskID, start_timestamp: u64, keys: Vec<BobKey>) -> RecordsResult;
+            pub(crate) async fn latest_records(&self, vdisk_id: VDiskID, keys: Vec<BobKey>) -> RecordsResult;
+            pub(crate) async fn cluster_map(&self) -> ClusterMapResult;
         }
         impl Clone for BobClient {
             fn clone(&self) -> Self;
@@ -171,9 +552,10 @@ pub(crate) mod b_client {
         fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
             f.debug_struct("RealBobClient")
                 .field("client", &"BobApiClient<Channel>")
+                .field("repair", &"BobRepairClient<Channel>")
                 .field("metrics", &self.metrics)
                 .field("node", &self.node)
-                .field("operation_timeout", &self.operation_timeout)
+                .field("operation_timeout", &self.operation_timeout.get())
                 .finish()
         }
     }
@@ -187,46 +569,107 @@ cfg_if! {
     }
 }
 
+use super::auth::Credentials;
+use super::backend::{HolderDigest, RecordVersion};
 use super::prelude::*;
+use crate::grpc::PingResponse;
+use std::sync::RwLock as SyncRwLock;
 
 pub(crate) type PutResult = Result<NodeOutput<()>, NodeOutput<Error>>;
 
 pub(crate) type GetResult = Result<NodeOutput<BobData>, NodeOutput<Error>>;
 
-pub(crate) type PingResult = Result<NodeOutput<()>, NodeOutput<Error>>;
+pub(crate) type HeadResult = Result<NodeOutput<BobHead>, NodeOutput<Error>>;
+
+pub(crate) type GetBatchResult = Result<NodeOutput<Vec<Result<BobData, Error>>>, NodeOutput<Error>>;
+
+pub(crate) type PutBatchResult = Result<NodeOutput<Vec<Result<(), Error>>>, NodeOutput<Error>>;
+
+pub(crate) type PingResult = Result<NodeOutput<PingResponse>, NodeOutput<Error>>;
 
 pub(crate) type ExistResult = Result<NodeOutput<Vec<bool>>, NodeOutput<Error>>;
 
+pub(crate) type DeleteResult = Result<NodeOutput<()>, NodeOutput<Error>>;
+
+pub(crate) type DigestResult = Result<NodeOutput<Vec<HolderDigest>>, NodeOutput<Error>>;
+
+pub(crate) type VersionsResult = Result<NodeOutput<Vec<RecordVersion>>, NodeOutput<Error>>;
+
+pub(crate) type RecordsResult = Result<NodeOutput<Vec<(BobKey, BobData)>>, NodeOutput<Error>>;
+
+pub(crate) type ClusterMapResult = Result<NodeOutput<ClusterConfig>, NodeOutput<Error>>;
+
+/// Operation timeout shared by the factory and the clients it produced, so it can be changed
+/// for the existing connections
+#[derive(Debug, Clone)]
+pub(crate) struct OperationTimeout(Arc<SyncRwLock<Duration>>);
+
+impl OperationTimeout {
+    fn new(timeout: Duration) -> Self {
+        Self(Arc::new(SyncRwLock::new(timeout)))
+    }
+
+    pub(crate) fn get(&self) -> Duration {
+        *self.0.read().expect("operation timeout lock")
+    }
+
+    fn set(&self, timeout: Duration) {
+        *self.0.write().expect("operation timeout lock") = timeout;
+    }
+}
+
 /// Bob metrics factory
 #[derive(Clone)]
 pub struct Factory {
-    operation_timeout: Duration,
+    operation_timeout: OperationTimeout,
     metrics: Arc<dyn MetricsContainerBuilder + Send + Sync>,
+    tls: Option<ClientTlsConfig>,
+    credentials: Option<Credentials>,
 }
 
 impl Factory {
-    /// Creates new instance of the [`Factory`]
+    /// Creates new instance of the [`Factory`], connections are plaintext if `tls` is not set
+    /// and requests are sent without `credentials` if they are not set
     #[must_use]
     pub fn new(
         operation_timeout: Duration,
         metrics: Arc<dyn MetricsContainerBuilder + Send + Sync>,
+        tls: Option<ClientTlsConfig>,
+        credentials: Option<Credentials>,
     ) -> Self {
         Factory {
-            operation_timeout,
+            operation_timeout: OperationTimeout::new(operation_timeout),
             metrics,
+            tls,
+            credentials,
         }
     }
+
+    /// Changes operation timeout of the produced clients, including already connected ones
+    pub(crate) fn set_operation_timeout(&self, timeout: Duration) {
+        self.operation_timeout.set(timeout);
+    }
+
     pub(crate) async fn produce(&self, node: Node) -> Result<BobClient, String> {
         let metrics = self.metrics.clone().get_metrics(&node.counter_display());
-        BobClient::create(node, self.operation_timeout, metrics).await
+        BobClient::create(
+            node,
+            self.operation_timeout.clone(),
+            metrics,
+            self.tls.clone(),
+            self.credentials.clone(),
+        )
+        .await
     }
 }
 
 impl Debug for Factory {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         f.debug_struct("Factory")
-            .field("operation_timeout", &self.operation_timeout)
+            .field("operation_timeout", &self.operation_timeout.get())
             .field("metrics", &"<dyn MetricsContainerBuilder>")
+            .field("tls", &self.tls.is_some())
+            .field("credentials", &self.credentials)
             .finish()
     }
 }
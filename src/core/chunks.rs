@@ -0,0 +1,434 @@
+use super::prelude::*;
+use crate::grpc::BlobChunk;
+use futures::{channel::mpsc::Sender, SinkExt};
+use ring::digest::{digest, SHA512};
+use std::convert::TryInto;
+use std::mem;
+
+/// List of chunks of the large blob. Manifest is stored under the blob key, chunks are stored
+/// under keys of the same length derived from the blob key and timestamp, so every chunk is
+/// routed and replicated as a regular record.
+/// Chunks of overwritten and deleted blobs are removed by tombstones, see [`ReplacedChunks`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Manifest {
+    size: u64,
+    chunks: Vec<BobKey>,
+}
+
+impl Manifest {
+    const VERSION: u8 = 1;
+    const HEADER_LEN: usize = 14;
+
+    pub(crate) fn chunk_key(key: BobKey, timestamp: u64, index: u32) -> BobKey {
+        let mut bytes = Vec::with_capacity(key.len() + 12);
+        bytes.extend_from_slice(key.as_ref());
+        bytes.extend_from_slice(&timestamp.to_be_bytes());
+        bytes.extend_from_slice(&index.to_be_bytes());
+        // sha512 has the length of the longest key
+        let hash = digest(&SHA512, &bytes);
+        BobKey::from_bytes(&hash.as_ref()[..key.len()]).expect("key is not longer than max")
+    }
+
+    /// Total size of the blob in bytes
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub(crate) fn chunks(&self) -> &[BobKey] {
+        &self.chunks
+    }
+
+    /// Layout: `[version: u8][size: u64 BE][count: u32 BE][key length: u8][chunk keys]`
+    pub(crate) fn to_bytes(&self) -> Vec<u8> {
+        let key_length = self.chunks.first().map_or(0, BobKey::len);
+        let mut bytes = Vec::with_capacity(Self::HEADER_LEN + self.chunks.len() * key_length);
+        bytes.push(Self::VERSION);
+        bytes.extend_from_slice(&self.size.to_be_bytes());
+        bytes.extend_from_slice(&(self.chunks.len() as u32).to_be_bytes());
+        bytes.push(key_length as u8);
+        for key in &self.chunks {
+            bytes.extend_from_slice(key.as_ref());
+        }
+        bytes
+    }
+
+    pub(crate) fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < Self::HEADER_LEN || bytes[0] != Self::VERSION {
+            return Err(Error::storage("parse error: invalid manifest header"));
+        }
+        let size = u64::from_be_bytes(bytes[1..9].try_into().expect("8 bytes"));
+        let count = u32::from_be_bytes(bytes[9..13].try_into().expect("4 bytes")) as usize;
+        let key_length = bytes[13] as usize;
+        let keys = &bytes[Self::HEADER_LEN..];
+        if keys.len() != count * key_length || (count > 0 && key_length == 0) {
+            return Err(Error::storage(format!(
+                "parse error: manifest with {} chunks has {} bytes of keys",
+                count,
+                keys.len()
+            )));
+        }
+        let chunks = keys
+            .chunks(key_length.max(1))
+            .map(|key| {
+                BobKey::from_bytes(key)
+                    .ok_or_else(|| Error::storage("parse error: manifest key is too long"))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self { size, chunks })
+    }
+}
+
+/// Splits streamed blob into chunks of `stream_chunk_size` and puts them through grinder.
+/// Manifest is written after all chunks, so partially written blob is never visible.
+pub(crate) struct ChunkedPut<'a> {
+    grinder: &'a Grinder,
+    key: BobKey,
+    meta: BobMeta,
+    options: Option<PutOptions>,
+    buffer: Vec<u8>,
+    manifest: Manifest,
+}
+
+impl<'a> ChunkedPut<'a> {
+    pub(crate) fn new(
+        grinder: &'a Grinder,
+        key: BobKey,
+        meta: BobMeta,
+        options: Option<PutOptions>,
+    ) -> Self {
+        Self {
+            grinder,
+            key,
+            meta,
+            options,
+            buffer: Vec::new(),
+            manifest: Manifest {
+                size: 0,
+                chunks: Vec::new(),
+            },
+        }
+    }
+
+    pub(crate) async fn write(&mut self, data: &[u8]) -> Result<(), Error> {
+        let chunk_size = self.grinder.stream_chunk_size();
+        self.buffer.extend_from_slice(data);
+        while self.buffer.len() >= chunk_size {
+            let rest = self.buffer.split_off(chunk_size);
+            let chunk = mem::replace(&mut self.buffer, rest);
+            self.put_chunk(chunk).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn finish(mut self) -> Result<(), Error> {
+        let options = BobOptions::new_put(self.options.clone());
+        let replaced =
+            ReplacedChunks::find(self.grinder, self.key, self.meta.timestamp(), &options).await;
+        let data = if self.manifest.chunks.is_empty() {
+            debug!("PUT[{}] streamed blob fits into one chunk", self.key);
+            BobData::new(mem::take(&mut self.buffer), self.meta.clone())
+        } else {
+            if !self.buffer.is_empty() {
+                let chunk = mem::take(&mut self.buffer);
+                self.put_chunk(chunk).await?;
+            }
+            debug!(
+                "PUT[{}] put manifest of {} chunks",
+                self.key,
+                self.manifest.chunks.len()
+            );
+            BobData::manifest(self.manifest.to_bytes(), self.meta.clone())
+        };
+        self.grinder
+            .put(self.key, data.checksummed(), options)
+            .await?;
+        replaced.remove(self.grinder, self.key).await;
+        Ok(())
+    }
+
+    async fn put_chunk(&mut self, chunk: Vec<u8>) -> Result<(), Error> {
+        let index = self.manifest.chunks.len() as u32;
+        let chunk_key = Manifest::chunk_key(self.key, self.meta.timestamp(), index);
+        let size = chunk.len() as u64;
+        trace!("PUT[{}] put chunk {} as {}", self.key, index, chunk_key);
+        // headers are kept only in manifest
+        let data = BobData::new(chunk, BobMeta::new(self.meta.timestamp())).checksummed();
+        let options = BobOptions::new_put(self.options.clone());
+        self.grinder.put(chunk_key, data, options).await?;
+        self.manifest.chunks.push(chunk_key);
+        self.manifest.size += size;
+        Ok(())
+    }
+}
+
+/// Chunks of the blob version, which is replaced by the client put or delete. Chunks are taken
+/// from the current manifest before the write and removed by tombstones with the timestamp of
+/// the new version after the write succeeds. Writes of other nodes are replicas of the already
+/// handled client request, so they have nothing to remove.
+#[derive(Debug, Default)]
+pub(crate) struct ReplacedChunks {
+    chunks: Vec<BobKey>,
+    timestamp: u64,
+}
+
+impl ReplacedChunks {
+    pub(crate) async fn find(
+        grinder: &Grinder,
+        key: BobKey,
+        timestamp: u64,
+        options: &BobOptions,
+    ) -> Self {
+        if options.flags().contains(BobFlags::FORCE_NODE) {
+            return Self::default();
+        }
+        // current version is checked by head, so data of the small blob isn't read
+        let options = BobOptions::new_get(None);
+        match grinder.head(key, &options).await {
+            Ok(head) if Self::is_replaced(head.is_manifest(), head.meta(), timestamp) => {}
+            Ok(_) => return Self::default(),
+            Err(e) => {
+                trace!("PUT[{}] no current version: {}", key, e);
+                return Self::default();
+            }
+        }
+        let current = match grinder.get(key, &options).await {
+            Ok(current) => current,
+            Err(e) => {
+                warn!(
+                    "PUT[{}] manifest of replaced version isn't read: {}",
+                    key, e
+                );
+                return Self::default();
+            }
+        };
+        if !Self::is_replaced(current.is_manifest(), current.meta(), timestamp) {
+            return Self::default();
+        }
+        match Manifest::from_bytes(current.inner()) {
+            Ok(manifest) => Self {
+                chunks: manifest.chunks,
+                timestamp,
+            },
+            Err(e) => {
+                warn!(
+                    "PUT[{}] chunks of replaced version aren't removed: {}",
+                    key, e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    // chunks of the version with the same timestamp have the same keys as the new ones
+    fn is_replaced(is_manifest: bool, meta: &BobMeta, timestamp: u64) -> bool {
+        is_manifest && meta.timestamp() < timestamp
+    }
+
+    pub(crate) async fn remove(self, grinder: &Grinder, key: BobKey) {
+        for chunk_key in self.chunks {
+            let meta = BobMeta::new(self.timestamp);
+            let options = BobOptions::new_delete(None);
+            if let Err(e) = grinder.delete(chunk_key, meta, options).await {
+                warn!(
+                    "chunk {} of replaced version of {} isn't removed: {}",
+                    chunk_key, key, e
+                );
+            }
+        }
+    }
+}
+
+async fn get_chunk(
+    grinder: &Grinder,
+    key: BobKey,
+    chunk_key: BobKey,
+    options: &BobOptions,
+) -> Result<Vec<u8>, Error> {
+    match grinder.get(chunk_key, options).await {
+        Ok(data) => Ok(data.into_inner()),
+        Err(e) if e.is_key_not_found() => Err(Error::failed(format!(
+            "chunk {} of blob {} is missing",
+            chunk_key, key
+        ))),
+        Err(e) => Err(e),
+    }
+}
+
+/// Size of the blob stored as manifest
+pub(crate) fn blob_size(manifest: &BobData) -> Result<u64, Error> {
+    Manifest::from_bytes(manifest.inner()).map(|manifest| manifest.size())
+}
+
+/// Reads all chunks of the manifest into one blob
+pub(crate) async fn assemble(
+    grinder: &Grinder,
+    key: BobKey,
+    manifest: BobData,
+    options: &BobOptions,
+) -> Result<BobData, Error> {
+    let meta = manifest.meta().clone();
+    let manifest = Manifest::from_bytes(manifest.inner())?;
+    let mut inner = Vec::with_capacity(manifest.size() as usize);
+    for &chunk_key in manifest.chunks() {
+        inner.extend(get_chunk(grinder, key, chunk_key, options).await?);
+    }
+    Ok(BobData::new(inner, meta).checksummed())
+}
+
+/// Sends blob by chunks, meta is set only in the first chunk. Chunks of manifest are read one
+/// by one, so only one chunk is kept in memory.
+pub(crate) async fn send(
+    grinder: Arc<Grinder>,
+    key: BobKey,
+    data: BobData,
+    options: BobOptions,
+    mut tx: Sender<Result<BlobChunk, Status>>,
+) {
+    let mut meta = Some(BlobMeta::from(data.meta()));
+    if !data.is_manifest() {
+        let chunk_size = grinder.stream_chunk_size();
+        let inner = data.into_inner();
+        // empty blob is still sent as one chunk with meta
+        let mut chunks: Vec<_> = inner.chunks(chunk_size).map(<[u8]>::to_vec).collect();
+        if chunks.is_empty() {
+            chunks.push(Vec::new());
+        }
+        for data in chunks {
+            let message = BlobChunk {
+                data,
+                meta: meta.take(),
+            };
+            if tx.send(Ok(message)).await.is_err() {
+                debug!("GET[{}] stream was closed by client", key);
+                return;
+            }
+        }
+        return;
+    }
+    let manifest = match Manifest::from_bytes(data.inner()) {
+        Ok(manifest) => manifest,
+        Err(e) => {
+            error!("GET[{}] {}", key, e);
+            let _ = tx.send(Err(e.into())).await;
+            return;
+        }
+    };
+    for &chunk_key in manifest.chunks() {
+        let message = match get_chunk(&grinder, key, chunk_key, &options).await {
+            Ok(data) => BlobChunk {
+                data,
+                meta: meta.take(),
+            },
+            Err(e) => {
+                error!("GET[{}] {}", key, e);
+                let _ = tx.send(Err(e.into())).await;
+                return;
+            }
+        };
+        if tx.send(Ok(message)).await.is_err() {
+            debug!("GET[{}] stream was closed by client", key);
+            return;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, ChunkedPut, Manifest, ReplacedChunks};
+    use crate::core::{prelude::*, test_utils};
+
+    #[test]
+    fn manifest_bytes_roundtrip() {
+        let manifest = Manifest {
+            size: 100,
+            chunks: vec![1.into(), 2.into(), u64::MAX.into()],
+        };
+        let bytes = manifest.to_bytes();
+        assert_eq!(Manifest::from_bytes(&bytes).unwrap(), manifest);
+        assert!(Manifest::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn chunk_keys_differ_between_versions() {
+        let key = BobKey::from(1);
+        assert_ne!(
+            Manifest::chunk_key(key, 10, 0),
+            Manifest::chunk_key(key, 10, 1)
+        );
+        assert_ne!(
+            Manifest::chunk_key(key, 10, 0),
+            Manifest::chunk_key(key, 11, 0)
+        );
+        assert_eq!(
+            Manifest::chunk_key(key, 10, 0),
+            Manifest::chunk_key(key, 10, 0)
+        );
+        let long_key = BobKey::from_bytes(&[1; 32]).unwrap();
+        assert_eq!(Manifest::chunk_key(long_key, 10, 0).len(), 32);
+    }
+
+    #[tokio::test]
+    async fn chunked_put_is_assembled_on_get() {
+        let grinder = test_utils::single_node_grinder().await;
+        grinder.run_backend().await.unwrap();
+
+        let key = BobKey::from(1);
+        let blob: Vec<u8> = (0..grinder.stream_chunk_size() * 2 + 10)
+            .map(|i| i as u8)
+            .collect();
+        let mut put = ChunkedPut::new(&grinder, key, BobMeta::new(1), None);
+        for part in blob.chunks(1000) {
+            put.write(part).await.unwrap();
+        }
+        put.finish().await.unwrap();
+
+        let options = BobOptions::new_get(None);
+        let manifest = grinder.get(key, &options).await.unwrap();
+        assert!(manifest.is_manifest());
+        assert_eq!(
+            Manifest::from_bytes(manifest.inner())
+                .unwrap()
+                .chunks()
+                .len(),
+            3
+        );
+        let data = assemble(&grinder, key, manifest, &options).await.unwrap();
+        assert_eq!(data.inner(), blob.as_slice());
+    }
+
+    async fn chunks_of(grinder: &Grinder, key: BobKey) -> Vec<BobKey> {
+        let manifest = grinder.get(key, &BobOptions::new_get(None)).await.unwrap();
+        Manifest::from_bytes(manifest.inner()).unwrap().chunks
+    }
+
+    #[tokio::test]
+    async fn chunks_of_replaced_blob_are_removed() {
+        let grinder = test_utils::single_node_grinder().await;
+        grinder.run_backend().await.unwrap();
+
+        let key = BobKey::from(1);
+        let blob = vec![1; grinder.stream_chunk_size() * 2];
+        for timestamp in 1..=2 {
+            let mut put = ChunkedPut::new(&grinder, key, BobMeta::new(timestamp), None);
+            put.write(&blob).await.unwrap();
+            put.finish().await.unwrap();
+        }
+        let old_chunks: Vec<_> = (0..2).map(|i| Manifest::chunk_key(key, 1, i)).collect();
+        let chunks = chunks_of(&grinder, key).await;
+        let options = BobOptions::new_get(None);
+        for chunk in &old_chunks {
+            assert!(grinder.get(*chunk, &options).await.is_err());
+        }
+        for chunk in &chunks {
+            assert!(grinder.get(*chunk, &options).await.is_ok());
+        }
+
+        let replaced = ReplacedChunks::find(&grinder, key, 3, &options).await;
+        let delete = grinder.delete(key, BobMeta::new(3), BobOptions::new_delete(None));
+        delete.await.unwrap();
+        replaced.remove(&grinder, key).await;
+        for chunk in &chunks {
+            assert!(grinder.get(*chunk, &options).await.is_err());
+        }
+    }
+}
@@ -1,10 +1,12 @@
 use super::prelude::*;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock as SyncRwLock;
 use std::time::Duration;
 
 pub(crate) struct Cleaner {
-    old_blobs_check_timeout: Duration,
-    soft_open_blobs: usize,
-    hard_open_blobs: usize,
+    old_blobs_check_timeout: SyncRwLock<Duration>,
+    soft_open_blobs: AtomicUsize,
+    hard_open_blobs: AtomicUsize,
 }
 
 impl Cleaner {
@@ -14,26 +16,41 @@ impl Cleaner {
         hard_open_blobs: usize,
     ) -> Self {
         Self {
-            old_blobs_check_timeout,
-            soft_open_blobs,
-            hard_open_blobs,
+            old_blobs_check_timeout: SyncRwLock::new(old_blobs_check_timeout),
+            soft_open_blobs: AtomicUsize::new(soft_open_blobs),
+            hard_open_blobs: AtomicUsize::new(hard_open_blobs),
         }
     }
 
-    pub(crate) fn spawn_task(&self, backend: Arc<Backend>) {
-        tokio::spawn(Self::task(
-            backend,
-            self.old_blobs_check_timeout,
-            self.soft_open_blobs,
-            self.hard_open_blobs,
-        ));
+    /// Changes settings of the running task, new period is applied after the current one ends
+    pub(crate) fn update(
+        &self,
+        old_blobs_check_timeout: Duration,
+        soft_open_blobs: usize,
+        hard_open_blobs: usize,
+    ) {
+        *self.old_blobs_check_timeout.write().expect("cleaner lock") = old_blobs_check_timeout;
+        self.soft_open_blobs
+            .store(soft_open_blobs, Ordering::Relaxed);
+        self.hard_open_blobs
+            .store(hard_open_blobs, Ordering::Relaxed);
+    }
+
+    fn period(&self) -> Duration {
+        *self.old_blobs_check_timeout.read().expect("cleaner lock")
+    }
+
+    pub(crate) fn spawn_task(self: Arc<Self>, backend: Arc<Backend>) -> JoinHandle<()> {
+        tokio::spawn(self.task(backend))
     }
 
-    async fn task(backend: Arc<Backend>, t: Duration, soft: usize, hard: usize) {
-        let mut interval = interval(t);
+    async fn task(self: Arc<Self>, backend: Arc<Backend>) {
         loop {
-            interval.tick().await;
+            let soft = self.soft_open_blobs.load(Ordering::Relaxed);
+            let hard = self.hard_open_blobs.load(Ordering::Relaxed);
             backend.close_unneeded_active_blobs(soft, hard).await;
+            backend.unload_cold_holders().await;
+            sleep(self.period()).await;
         }
     }
 }
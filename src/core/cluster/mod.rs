@@ -20,16 +20,33 @@ pub(crate) trait Cluster {
     async fn put(&self, key: BobKey, data: BobData) -> Result<(), Error>;
     async fn get(&self, key: BobKey) -> Result<BobData, Error>;
     async fn exist(&self, keys: &[BobKey]) -> Result<Vec<bool>, Error>;
+    async fn delete(&self, key: BobKey, meta: BobMeta) -> Result<(), Error>;
+
+    /// Reads keys, results are in the order of keys
+    async fn get_batch(&self, keys: &[BobKey]) -> Vec<Result<BobData, Error>> {
+        future::join_all(keys.iter().map(|&key| self.get(key))).await
+    }
+
+    /// Writes records, results are in the order of records
+    async fn put_batch(&self, records: Vec<(BobKey, BobData)>) -> Vec<Result<(), Error>> {
+        let requests = records.into_iter().map(|(key, data)| self.put(key, data));
+        future::join_all(requests).await
+    }
 }
 
 pub(crate) fn get_cluster(
-    mapper: Arc<Virtual>,
+    mapper: Arc<ClusterMap>,
     config: &NodeConfig,
     backend: Arc<Backend>,
 ) -> Arc<dyn Cluster + Send + Sync> {
     match config.cluster_policy() {
         "simple" => Arc::new(SimpleQuorum::new(mapper, config.quorum())),
-        "quorum" => Arc::new(Quorum::new(backend, mapper, config.quorum())),
+        "quorum" => Arc::new(Quorum::new(
+            backend,
+            mapper,
+            config.quorum(),
+            config.read_consistency(),
+        )),
         p => panic!("unknown cluster policy: {}", p),
     }
 }
@@ -1,19 +1,89 @@
 use tokio::task::JoinError;
 
 use super::prelude::*;
+use crate::core::link_manager::ClusterCallFuture;
 
-pub(crate) async fn get_any(
+/// The most recent version of the key found during lookup. Deletion is kept as tombstone, so
+/// replicas and aliens, which missed the deletion, don't bring the deleted data back
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Lookup {
+    newest: Option<BobData>,
+}
+
+impl Lookup {
+    /// Adds the answer of a replica, errors other than deletion are returned back
+    pub(crate) fn add(&mut self, result: Result<BobData, Error>) -> Result<(), Error> {
+        let data = match result {
+            Ok(data) => data,
+            Err(e) => match e.deleted_at() {
+                Some(timestamp) => BobData::tombstone(BobMeta::new(timestamp)),
+                None => return Err(e),
+            },
+        };
+        // tombstone wins over data with the same timestamp
+        self.newest = BobData::most_recent(self.newest.take().into_iter().chain(Some(data)));
+        Ok(())
+    }
+
+    /// Checks if data is found, tombstone doesn't stop lookup, because newer version may be
+    /// stored elsewhere
+    pub(crate) fn is_found(&self) -> bool {
+        self.newest
+            .as_ref()
+            .map_or(false, |data| !data.is_tombstone())
+    }
+
+    /// Found data or tombstone
+    pub(crate) fn newest(&self) -> Option<&BobData> {
+        self.newest.as_ref()
+    }
+
+    pub(crate) fn into_result(self, key: BobKey) -> Result<BobData, Error> {
+        match self.newest {
+            Some(data) if data.is_tombstone() => {
+                Err(Error::key_deleted(key, data.meta().timestamp()))
+            }
+            Some(data) => Ok(data),
+            None => Err(Error::key_not_found(key)),
+        }
+    }
+}
+
+/// Reads key from all nodes, so deletion on any of them hides older data on the others
+pub(crate) async fn get_all(
     key: BobKey,
     target_nodes: impl Iterator<Item = &Node>,
     options: GetOptions,
-) -> Option<NodeOutput<BobData>> {
+    lookup: &mut Lookup,
+) {
     let requests: FuturesUnordered<_> = target_nodes
         .map(|node| LinkManager::call_node(node, |conn| conn.get(key, options.clone()).boxed()))
         .collect();
-    requests
-        .filter_map(|res| future::ready(res.ok()))
-        .next()
-        .await
+    let results: Vec<_> = requests.collect().await;
+    for result in results {
+        let (node_name, result) = match result {
+            Ok(output) => (output.node_name().to_owned(), Ok(output.into_inner())),
+            Err(output) => (output.node_name().to_owned(), Err(output.into_inner())),
+        };
+        if let Err(e) = lookup.add(result) {
+            debug!("GET[{}] {} returned error: {}", key, node_name, e);
+        }
+    }
+}
+
+// tombstones are replicated with the same routing as data, but through delete request
+pub(crate) fn put_or_delete(
+    conn: &BobClient,
+    key: BobKey,
+    data: BobData,
+    options: PutOptions,
+) -> ClusterCallFuture<'_, ()> {
+    if data.is_tombstone() {
+        conn.delete(key, data.meta().clone(), DeleteOptions::from(options))
+            .boxed()
+    } else {
+        conn.put(key, data, options).boxed()
+    }
 }
 
 fn call_node_put(
@@ -24,7 +94,7 @@ fn call_node_put(
 ) -> JoinHandle<Result<NodeOutput<()>, NodeOutput<Error>>> {
     debug!("PUT[{}] put to {}", key, node.name());
     let task = async move {
-        LinkManager::call_node(&node, |conn| conn.put(key, data, options).boxed()).await
+        LinkManager::call_node(&node, |conn| put_or_delete(conn, key, data, options)).await
     };
     tokio::spawn(task)
 }
@@ -103,84 +173,101 @@ pub(crate) fn group_keys_by_nodes(
     keys_by_nodes
 }
 
+/// Groups keys, which have no data found yet, by the node selected for them
+pub(crate) fn group_missing_keys<'a>(
+    keys: &[BobKey],
+    results: &[Lookup],
+    mut select_node: impl FnMut(BobKey) -> Option<&'a Node>,
+) -> HashMap<Node, (Vec<BobKey>, Vec<usize>)> {
+    let mut keys_by_node: HashMap<_, (Vec<_>, Vec<_>)> = HashMap::new();
+    for (ind, (&key, result)) in keys.iter().zip(results).enumerate() {
+        if result.is_found() {
+            continue;
+        }
+        if let Some(node) = select_node(key) {
+            let (keys, indexes) = keys_by_node.entry(node.clone()).or_default();
+            keys.push(key);
+            indexes.push(ind);
+        }
+    }
+    keys_by_node
+}
+
+/// Sends one batch get per node, found records and deletions are merged into results by key
+/// indexes, the most recent version wins if several nodes have the key
+pub(crate) async fn get_batch_on_nodes(
+    keys_by_node: HashMap<Node, (Vec<BobKey>, Vec<usize>)>,
+    options: GetOptions,
+    results: &mut [Lookup],
+) {
+    let requests: FuturesUnordered<_> = keys_by_node
+        .into_iter()
+        .map(|(node, (keys, indexes))| {
+            let options = options.clone();
+            async move {
+                let result =
+                    LinkManager::call_node(&node, |conn| conn.get_batch(keys, options).boxed())
+                        .await;
+                (result, indexes)
+            }
+        })
+        .collect();
+    let responses: Vec<_> = requests.collect().await;
+    for (response, indexes) in responses {
+        match response {
+            Ok(output) => {
+                let node_name = output.node_name().to_owned();
+                for (result, ind) in output.into_inner().into_iter().zip(indexes) {
+                    if let Err(e) = results[ind].add(result) {
+                        trace!("batch GET on {} returned error: {}", node_name, e);
+                    }
+                }
+            }
+            Err(e) => debug!("batch GET on {} failed: {}", e.node_name(), e.inner()),
+        }
+    }
+}
+
 pub(crate) async fn lookup_local_alien(
     backend: &Backend,
     key: BobKey,
     vdisk_id: VDiskID,
-) -> Option<BobData> {
+    lookup: &mut Lookup,
+) {
     let op = Operation::new_alien(vdisk_id);
-    match backend.get_local(key, op).await {
-        Ok(data) => {
-            debug!("GET[{}] key found in local node alien", key);
-            return Some(data);
-        }
+    match lookup.add(backend.get_local(key, op).await) {
+        Ok(()) => debug!("GET[{}] version found in local node alien", key),
         Err(e) if e.is_key_not_found() => debug!("GET[{}] not found in local alien", key),
         Err(e) => error!("local node backend returned error: {}", e),
-    };
-    None
-}
-
-pub(crate) async fn lookup_local_node(
-    backend: &Backend,
-    key: BobKey,
-    vdisk_id: VDiskID,
-    disk_path: Option<DiskPath>,
-) -> Option<BobData> {
-    if let Some(path) = disk_path {
-        debug!("local node has vdisk replica, check local");
-        let op = Operation::new_local(vdisk_id, path);
-        match backend.get_local(key, op).await {
-            Ok(data) => {
-                debug!("GET[{}] key found in local node", key);
-                return Some(data);
-            }
-            Err(e) if e.is_key_not_found() => debug!("GET[{}] not found in local node", key),
-            Err(e) => error!("local node backend returned error: {}", e),
-        }
     }
-    None
 }
 
-pub(crate) async fn lookup_remote_aliens(mapper: &Virtual, key: BobKey) -> Option<BobData> {
+pub(crate) async fn lookup_remote_aliens(mapper: &Virtual, key: BobKey, lookup: &mut Lookup) {
     let local_node = mapper.local_node_name();
     let target_nodes = mapper
         .nodes()
         .values()
         .filter(|node| node.name() != local_node);
-    let result = get_any(key, target_nodes, GetOptions::new_alien()).await;
-    if let Some(answer) = result {
-        debug!(
-            "GET[{}] take data from node: {}, timestamp: {}",
-            key,
-            answer.node_name(),
-            answer.timestamp()
-        );
-        Some(answer.into_inner())
-    } else {
-        debug!("GET[{}] data not found on any node in alien dir", key);
-        None
-    }
+    get_all(key, target_nodes, GetOptions::new_alien(), lookup).await;
+    debug!(
+        "GET[{}] most recent version after lookup in alien dirs: {:?}",
+        key,
+        lookup.newest().map(|data| data.meta().timestamp())
+    );
 }
 
-pub(crate) async fn lookup_remote_nodes(mapper: &Virtual, key: BobKey) -> Option<BobData> {
+pub(crate) async fn lookup_remote_nodes(mapper: &Virtual, key: BobKey, lookup: &mut Lookup) {
     let local_node = mapper.local_node_name();
     let target_nodes = mapper
         .get_target_nodes_for_key(key)
         .iter()
         .filter(|node| node.name() != local_node);
-    let result = get_any(key, target_nodes, GetOptions::new_local()).await;
-    if let Some(answer) = result {
-        debug!(
-            "GET[{}] take data from node: {}, timestamp: {}",
-            key,
-            answer.node_name(),
-            answer.timestamp()
-        );
-        Some(answer.into_inner())
-    } else {
-        debug!("GET[{}] data not found on any node in regular dir", key);
-        None
-    }
+    get_all(key, target_nodes, GetOptions::new_local(), lookup).await;
+    debug!(
+        "GET[{}] most recent version after lookup in regular dirs: {:?}",
+        key,
+        lookup.newest().map(|data| data.meta().timestamp())
+    );
 }
 
 pub(crate) async fn put_local_all(
@@ -217,7 +304,7 @@ pub(crate) async fn put_sup_nodes(
     let mut ret = vec![];
     for (node, options) in requests {
         let result = LinkManager::call_node(&node, |client| {
-            Box::pin(client.put(key, data.clone(), options.clone()))
+            put_or_delete(client, key, data.clone(), options.clone())
         })
         .await;
         debug!("{:?}", result);
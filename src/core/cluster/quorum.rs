@@ -6,16 +6,328 @@ type Tasks = FuturesUnordered<JoinHandle<Result<NodeOutput<()>, NodeOutput<Error
 #[derive(Clone)]
 pub(crate) struct Quorum {
     backend: Arc<Backend>,
-    mapper: Arc<Virtual>,
+    mapper: Arc<ClusterMap>,
     quorum: usize,
+    read_consistency: ReadConsistency,
+}
+
+#[derive(Debug, Clone)]
+enum Replica {
+    Local(VDiskID, DiskPath),
+    Remote(Node),
 }
 
 impl Quorum {
-    pub(crate) fn new(backend: Arc<Backend>, mapper: Arc<Virtual>, quorum: usize) -> Self {
+    pub(crate) fn new(
+        backend: Arc<Backend>,
+        mapper: Arc<ClusterMap>,
+        quorum: usize,
+        read_consistency: ReadConsistency,
+    ) -> Self {
         Self {
             backend,
             mapper,
             quorum,
+            read_consistency,
+        }
+    }
+
+    async fn get_any(&self, key: BobKey) -> Result<BobData, Error> {
+        debug!("GET[{}] ~~~LOOKUP LOCAL NODE~~~", key);
+        let mapper = self.mapper.current();
+        let (vdisk_id, disk_path) = mapper.get_operation(key);
+        let mut lookup = Lookup::default();
+        let mut corrupted = None;
+        if let Some(path) = disk_path {
+            let op = Operation::new_local(vdisk_id, path.clone());
+            match lookup.add(self.backend.get_local(key, op).await) {
+                Ok(()) if lookup.is_found() => {
+                    debug!("GET[{}] key found in local node", key);
+                    return lookup.into_result(key);
+                }
+                Ok(()) => debug!("GET[{}] deleted in local node", key),
+                Err(e) if e.is_key_not_found() => debug!("GET[{}] not found in local node", key),
+                Err(e) if e.is_not_ready() => {
+                    debug!("GET[{}] local vdisk is loading, read from replicas", key)
+                }
+                Err(e) if e.is_data_corrupted() => {
+                    warn!(
+                        "GET[{}] local replica is corrupted, read from replicas",
+                        key
+                    );
+                    corrupted = Some(Replica::Local(vdisk_id, path));
+                }
+                Err(e) => error!("local node backend returned error: {}", e),
+            }
+        }
+        debug!("GET[{}] ~~~LOOKUP REMOTE NODES~~~", key);
+        lookup_remote_nodes(&mapper, key, &mut lookup).await;
+        if let (Some(replica), Some(data)) = (corrupted, lookup.newest()) {
+            tokio::spawn(self.clone().read_repair(key, data.clone(), vec![replica]));
+        }
+        if lookup.is_found() {
+            return lookup.into_result(key);
+        }
+        self.get_aliens(key, vdisk_id, lookup).await
+    }
+
+    // versions found on replicas are passed in lookup, so their deletion hides stale aliens
+    async fn get_aliens(
+        &self,
+        key: BobKey,
+        vdisk_id: VDiskID,
+        mut lookup: Lookup,
+    ) -> Result<BobData, Error> {
+        debug!("GET[{}] ~~~LOOKUP LOCAL NODE ALIEN~~~", key);
+        lookup_local_alien(&self.backend, key, vdisk_id, &mut lookup).await;
+        if lookup.is_found() {
+            return lookup.into_result(key);
+        }
+
+        debug!("GET[{}] ~~~LOOKUP REMOTE NODES ALIEN~~~", key);
+        lookup_remote_aliens(&self.mapper.current(), key, &mut lookup).await;
+        if !lookup.is_found() {
+            info!("GET[{}] Key not found", key);
+        }
+        lookup.into_result(key)
+    }
+
+    /// Reads key from all replicas, returns the most recent version if at least `required`
+    /// replicas answered, and repairs replicas with outdated or missing version
+    async fn get_consistent(&self, key: BobKey, required: usize) -> Result<BobData, Error> {
+        debug!("GET[{}] ~~~LOOKUP {} REPLICAS~~~", key, required);
+        let mapper = self.mapper.current();
+        let (vdisk_id, disk_path) = mapper.get_operation(key);
+        let mut versions = Vec::new();
+        if let Some(path) = disk_path {
+            let op = Operation::new_local(vdisk_id, path.clone());
+            let result = self.backend.get_local(key, op).await;
+            if let Some(version) = replica_version(key, "local node", result) {
+                versions.push((Replica::Local(vdisk_id, path), version));
+            }
+        }
+        let local_node = mapper.local_node_name();
+        let target_nodes: Vec<_> = mapper
+            .get_target_nodes_for_key(key)
+            .iter()
+            .filter(|node| node.name() != local_node)
+            .cloned()
+            .collect();
+        let results = LinkManager::call_nodes(target_nodes.iter(), |conn| {
+            conn.get(key, GetOptions::new_local()).boxed()
+        })
+        .await;
+        for result in results {
+            let (node_name, result) = match result {
+                Ok(output) => (output.node_name().to_owned(), Ok(output.into_inner())),
+                Err(output) => (output.node_name().to_owned(), Err(output.into_inner())),
+            };
+            if let Some(version) = replica_version(key, &node_name, result) {
+                if let Some(node) = target_nodes.iter().find(|node| node.name() == node_name) {
+                    versions.push((Replica::Remote(node.clone()), version));
+                }
+            }
+        }
+        if versions.len() < required {
+            warn!(
+                "GET[{}] read consistency was not reached: {} of {} replicas answered",
+                key,
+                versions.len(),
+                required
+            );
+            return Err(Error::failed(format!(
+                "{} of {} replicas answered",
+                versions.len(),
+                required
+            )));
+        }
+        let newest = BobData::most_recent(versions.iter().filter_map(|(_, data)| data.clone()));
+        if let Some(newest) = newest {
+            let stale: Vec<_> = versions
+                .into_iter()
+                .filter(|(_, data)| is_stale(data.as_ref(), &newest))
+                .map(|(replica, _)| replica)
+                .collect();
+            if !stale.is_empty() {
+                debug!("GET[{}] spawn read-repair of {} replicas", key, stale.len());
+                tokio::spawn(self.clone().read_repair(key, newest.clone(), stale));
+            }
+            if newest.is_tombstone() {
+                Err(Error::key_deleted(key, newest.meta().timestamp()))
+            } else {
+                Ok(newest)
+            }
+        } else {
+            self.get_aliens(key, vdisk_id, Lookup::default()).await
+        }
+    }
+
+    /// Batch version of the read from any replica: local replicas are read first, then remote
+    /// replicas are asked round by round with one request per node for the keys still missing,
+    /// and aliens are looked up the same way. Corrupted local records are not read-repaired.
+    async fn get_batch_any(&self, keys: &[BobKey]) -> Vec<Result<BobData, Error>> {
+        let mapper = self.mapper.current();
+        let local_node = mapper.local_node_name();
+        let mut results = vec![Lookup::default(); keys.len()];
+        debug!("GET_BATCH[{}] ~~~LOOKUP LOCAL NODE~~~", keys.len());
+        for (&key, result) in keys.iter().zip(results.iter_mut()) {
+            let (vdisk_id, disk_path) = mapper.get_operation(key);
+            if let Some(path) = disk_path {
+                let op = Operation::new_local(vdisk_id, path);
+                match result.add(self.backend.get_local(key, op).await) {
+                    Ok(()) => {}
+                    Err(e) if e.is_key_not_found() => {
+                        debug!("GET[{}] not found in local node", key);
+                    }
+                    Err(e) => warn!("GET[{}] local node returned error: {}", key, e),
+                }
+            }
+        }
+        debug!("GET_BATCH[{}] ~~~LOOKUP REMOTE NODES~~~", keys.len());
+        let mut round = 0;
+        loop {
+            let keys_by_node = group_missing_keys(keys, &results, |key| {
+                mapper
+                    .get_target_nodes_for_key(key)
+                    .iter()
+                    .filter(|node| node.name() != local_node)
+                    .nth(round)
+            });
+            if keys_by_node.is_empty() {
+                break;
+            }
+            get_batch_on_nodes(keys_by_node, GetOptions::new_local(), &mut results).await;
+            round += 1;
+        }
+        debug!("GET_BATCH[{}] ~~~LOOKUP ALIENS~~~", keys.len());
+        for (&key, result) in keys.iter().zip(results.iter_mut()) {
+            if !result.is_found() {
+                let vdisk_id = mapper.vdisk_id_from_key(key);
+                lookup_local_alien(&self.backend, key, vdisk_id, result).await;
+            }
+        }
+        for node in mapper.nodes().values() {
+            if node.name() != local_node {
+                let keys_by_node = group_missing_keys(keys, &results, |_| Some(node));
+                get_batch_on_nodes(keys_by_node, GetOptions::new_alien(), &mut results).await;
+            }
+        }
+        keys.iter()
+            .zip(results)
+            .map(|(&key, lookup)| lookup.into_result(key))
+            .collect()
+    }
+
+    /// Batch version of the quorum put: records are written to the local node and then sent to
+    /// remote replicas with one request per node. Keys without quorum are written to aliens
+    /// before return, aliens of the rest are written in background.
+    async fn put_batch_at_least(&self, records: Vec<(BobKey, BobData)>) -> Vec<Result<(), Error>> {
+        let mapper = self.mapper.current();
+        let local_node = mapper.local_node_name();
+        let mut ok_counts = vec![0_usize; records.len()];
+        let mut failed_nodes = vec![Vec::new(); records.len()];
+        let mut records_by_node: HashMap<Node, (Vec<_>, Vec<_>)> = HashMap::new();
+        for (ind, (key, data)) in records.iter().enumerate() {
+            let (vdisk_id, disk_path) = mapper.get_operation(*key);
+            if let Some(path) = disk_path {
+                match put_local_node(&self.backend, *key, data.clone(), vdisk_id, path).await {
+                    Ok(()) => ok_counts[ind] += 1,
+                    Err(e) => {
+                        error!("PUT[{}] local put failed: {}", key, e);
+                        failed_nodes[ind].push(local_node.to_owned());
+                    }
+                }
+            }
+            let target_nodes = mapper.get_target_nodes_for_key(*key);
+            for node in target_nodes.iter().filter(|node| node.name() != local_node) {
+                if node.is_draining() {
+                    failed_nodes[ind].push(node.name().to_owned());
+                    continue;
+                }
+                let (records, indexes) = records_by_node.entry(node.clone()).or_default();
+                records.push((*key, data.clone()));
+                indexes.push(ind);
+            }
+        }
+        let requests: FuturesUnordered<_> = records_by_node
+            .into_iter()
+            .map(|(node, (records, indexes))| async move {
+                let result = LinkManager::call_node(&node, |conn| {
+                    conn.put_batch(records, PutOptions::new_local()).boxed()
+                })
+                .await;
+                (node, result, indexes)
+            })
+            .collect();
+        let responses: Vec<_> = requests.collect().await;
+        for (node, response, indexes) in responses {
+            match response {
+                Ok(output) => {
+                    for (result, ind) in output.into_inner().into_iter().zip(indexes) {
+                        if let Err(e) = result {
+                            debug!("batch PUT on {} failed: {}", node.name(), e);
+                            failed_nodes[ind].push(node.name().to_owned());
+                        } else {
+                            ok_counts[ind] += 1;
+                        }
+                    }
+                }
+                Err(e) => {
+                    warn!("batch PUT on {} failed: {}", node.name(), e.inner());
+                    for ind in indexes {
+                        failed_nodes[ind].push(node.name().to_owned());
+                    }
+                }
+            }
+        }
+        let mut results = Vec::with_capacity(records.len());
+        let outcomes = ok_counts.into_iter().zip(failed_nodes);
+        for ((key, data), (ok_count, failed_nodes)) in records.into_iter().zip(outcomes) {
+            let result = if failed_nodes.is_empty() {
+                Ok(())
+            } else if ok_count >= self.quorum {
+                let q = self.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = q.put_aliens(failed_nodes, key, data).await {
+                        error!("{}", e);
+                    }
+                });
+                Ok(())
+            } else {
+                warn!(
+                    "PUT[{}] quorum was not reached. ok {}, quorum {}",
+                    key, ok_count, self.quorum
+                );
+                self.put_aliens(failed_nodes, key, data).await
+            };
+            results.push(result);
+        }
+        results
+    }
+
+    async fn read_repair(self, key: BobKey, data: BobData, replicas: Vec<Replica>) {
+        for replica in replicas {
+            let result = match &replica {
+                Replica::Local(vdisk_id, path) => {
+                    put_local_node(&self.backend, key, data.clone(), *vdisk_id, path.clone()).await
+                }
+                Replica::Remote(node) => LinkManager::call_node(node, |conn| {
+                    put_or_delete(conn, key, data.clone(), PutOptions::new_local())
+                })
+                .await
+                .map(|_| ())
+                .map_err(NodeOutput::into_inner),
+            };
+            match result {
+                Ok(()) => {
+                    debug!("GET[{}] read-repair of {:?} succeed", key, replica);
+                    counter!(CLUSTER_READ_REPAIR_COUNTER, 1);
+                }
+                Err(e) => {
+                    warn!("GET[{}] read-repair of {:?} failed: {}", key, replica, e);
+                    counter!(CLUSTER_READ_REPAIR_ERROR_COUNTER, 1);
+                }
+            }
         }
     }
 
@@ -25,13 +337,14 @@ impl Quorum {
         let mut remote_ok_count = 0_usize;
         let mut at_least = self.quorum;
         let mut failed_nodes = Vec::new();
-        let (vdisk_id, disk_path) = self.mapper.get_operation(key);
+        let mapper = self.mapper.current();
+        let (vdisk_id, disk_path) = mapper.get_operation(key);
         if let Some(path) = disk_path {
             debug!("disk path is present, try put local");
             let res = put_local_node(&self.backend, key, data.clone(), vdisk_id, path).await;
             if let Err(e) = res {
                 error!("{}", e);
-                failed_nodes.push(self.mapper.local_node_name().to_owned());
+                failed_nodes.push(mapper.local_node_name().to_owned());
             } else {
                 local_put_ok += 1;
                 at_least -= 1;
@@ -44,7 +357,7 @@ impl Quorum {
 
         debug!("PUT[{}] ~~~PUT TO REMOTE NODES~~~", key);
         let (tasks, errors) = self.put_remote_nodes(key, data.clone(), at_least).await;
-        let all_count = self.mapper.get_target_nodes_for_key(key).len();
+        let all_count = mapper.get_target_nodes_for_key(key).len();
         remote_ok_count += all_count - errors.len() - tasks.len() - local_put_ok;
         failed_nodes.extend(errors.iter().map(|e| e.node_name().to_string()));
         if remote_ok_count + local_put_ok >= self.quorum {
@@ -107,15 +420,32 @@ impl Quorum {
         data: BobData,
         at_least: usize,
     ) -> (Tasks, Vec<NodeOutput<Error>>) {
-        let local_node = self.mapper.local_node_name();
-        let target_nodes = self.mapper.get_target_nodes_for_key(key);
+        let mapper = self.mapper.current();
+        let local_node = mapper.local_node_name();
+        let target_nodes = mapper.get_target_nodes_for_key(key);
         debug!(
             "PUT[{}] cluster quorum put remote nodes {} total target nodes",
             key,
             target_nodes.len(),
         );
-        let target_nodes = target_nodes.iter().filter(|node| node.name() != local_node);
-        put_at_least(key, data, target_nodes, at_least, PutOptions::new_local()).await
+        let (draining, target_nodes): (Vec<_>, Vec<_>) = target_nodes
+            .iter()
+            .filter(|node| node.name() != local_node)
+            .partition(|node| node.is_draining());
+        let (tasks, mut errors) = put_at_least(
+            key,
+            data,
+            target_nodes.into_iter(),
+            at_least,
+            PutOptions::new_local(),
+        )
+        .await;
+        // draining nodes are skipped, so their replicas are written to support nodes
+        errors.extend(draining.into_iter().map(|node| {
+            debug!("PUT[{}] skip draining node {}", key, node.name());
+            NodeOutput::new(node.name().to_owned(), Error::failed("node is draining"))
+        }));
+        (tasks, errors)
     }
 
     pub(crate) async fn put_aliens(
@@ -133,7 +463,8 @@ impl Quorum {
             return Err(Error::internal());
         }
         trace!("selection of free nodes available for data writing");
-        let sup_nodes = self.mapper.get_support_nodes(key, failed_nodes.len());
+        let mapper = self.mapper.current();
+        let sup_nodes = mapper.get_support_nodes(key, failed_nodes.len());
         debug!("PUT[{}] sup put nodes: {:?}", key, &sup_nodes);
         let nodes_need_remote_backup: Vec<_> = failed_nodes.drain(..sup_nodes.len()).collect();
         let queries: Vec<_> = sup_nodes
@@ -151,7 +482,7 @@ impl Quorum {
             )
         };
         debug!("need additional local alien copies: {}", failed_nodes.len());
-        let vdisk_id = self.mapper.vdisk_id_from_key(key);
+        let vdisk_id = mapper.vdisk_id_from_key(key);
         let operation = Operation::new_alien(vdisk_id);
         let local_put = put_local_all(
             &self.backend,
@@ -181,30 +512,18 @@ impl Cluster for Quorum {
 
     //todo check no data (no error)
     async fn get(&self, key: BobKey) -> Result<BobData, Error> {
-        debug!("GET[{}] ~~~LOOKUP LOCAL NODE~~~", key);
-        let (vdisk_id, disk_path) = self.mapper.get_operation(key);
-        if let Some(data) = lookup_local_node(&self.backend, key, vdisk_id, disk_path).await {
-            return Ok(data);
-        }
-        debug!("GET[{}] ~~~LOOKUP REMOTE NODES~~~", key);
-        if let Some(data) = lookup_remote_nodes(&self.mapper, key).await {
-            return Ok(data);
-        }
-        debug!("GET[{}] ~~~LOOKUP LOCAL NODE ALIEN~~~", key);
-        if let Some(data) = lookup_local_alien(&self.backend, key, vdisk_id).await {
-            return Ok(data);
-        }
-
-        debug!("GET[{}] ~~~LOOKUP REMOTE NODES ALIEN~~~", key);
-        if let Some(data) = lookup_remote_aliens(&self.mapper, key).await {
-            return Ok(data);
+        match self.read_consistency {
+            ReadConsistency::One => self.get_any(key).await,
+            ReadConsistency::Quorum => self.get_consistent(key, self.quorum).await,
+            ReadConsistency::All => {
+                let replicas = self.mapper.current().get_target_nodes_for_key(key).len();
+                self.get_consistent(key, replicas).await
+            }
         }
-        info!("GET[{}] Key not found", key);
-        Err(Error::key_not_found(key))
     }
 
     async fn exist(&self, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
-        let keys_by_nodes = group_keys_by_nodes(&self.mapper, keys);
+        let keys_by_nodes = group_keys_by_nodes(&self.mapper.current(), keys);
         debug!(
             "EXIST Nodes for fan out: {:?}",
             &keys_by_nodes.keys().flatten().collect::<Vec<_>>()
@@ -223,4 +542,55 @@ impl Cluster for Quorum {
         }
         Ok(exist)
     }
+
+    async fn delete(&self, key: BobKey, meta: BobMeta) -> Result<(), Error> {
+        debug!("DELETE[{}] write tombstone with quorum", key);
+        self.put_at_least(key, BobData::tombstone(meta)).await
+    }
+
+    async fn get_batch(&self, keys: &[BobKey]) -> Vec<Result<BobData, Error>> {
+        if self.read_consistency == ReadConsistency::One {
+            self.get_batch_any(keys).await
+        } else {
+            future::join_all(keys.iter().map(|&key| self.get(key))).await
+        }
+    }
+
+    async fn put_batch(&self, records: Vec<(BobKey, BobData)>) -> Vec<Result<(), Error>> {
+        self.put_batch_at_least(records).await
+    }
+}
+
+// returns `None` if replica didn't answer, tombstones are returned as data
+fn replica_version(
+    key: BobKey,
+    replica: &str,
+    result: Result<BobData, Error>,
+) -> Option<Option<BobData>> {
+    match result {
+        Ok(data) => Some(Some(data)),
+        Err(e) if e.is_key_not_found() => {
+            debug!("GET[{}] not found on {}", key, replica);
+            Some(
+                e.deleted_at()
+                    .map(|ts| BobData::tombstone(BobMeta::new(ts))),
+            )
+        }
+        // corrupted replica is considered as missing one, so it gets repaired
+        Err(e) if e.is_data_corrupted() => {
+            warn!("GET[{}] replica on {} is corrupted", key, replica);
+            Some(None)
+        }
+        Err(e) => {
+            warn!("GET[{}] {} returned error: {}", key, replica, e);
+            None
+        }
+    }
+}
+
+fn is_stale(data: Option<&BobData>, newest: &BobData) -> bool {
+    data.map_or(true, |data| {
+        (data.meta().timestamp(), data.is_tombstone())
+            < (newest.meta().timestamp(), newest.is_tombstone())
+    })
 }
@@ -1,18 +1,19 @@
 use super::prelude::*;
 
 pub(crate) struct Quorum {
-    mapper: Arc<Virtual>,
+    mapper: Arc<ClusterMap>,
     quorum: usize,
 }
 
 impl Quorum {
-    pub(crate) fn new(mapper: Arc<Virtual>, quorum: usize) -> Self {
+    pub(crate) fn new(mapper: Arc<ClusterMap>, quorum: usize) -> Self {
         Self { quorum, mapper }
     }
 
     #[inline]
     fn get_target_nodes(&self, key: BobKey) -> Vec<Node> {
         self.mapper
+            .current()
             .get_vdisk_for_key(key)
             .expect("get vdisk for key failed")
             .nodes()
@@ -53,6 +54,7 @@ impl Cluster for Quorum {
                     remote_nodes: vec![], //TODO check
                     force_node: true,
                     overwrite: false,
+                    ttl: 0,
                 },
             ))
         });
@@ -81,17 +83,42 @@ impl Cluster for Quorum {
         let reqs = LinkManager::call_nodes(target_nodes.iter(), |conn| {
             conn.get(key, GetOptions::new_local()).boxed()
         });
-        let results = reqs.await;
-        let ok_results = results
-            .iter()
-            .filter_map(|r| r.as_ref().ok())
-            .collect::<Vec<_>>();
-
-        ok_results
-            .get(0)
-            .map_or(Err(Error::key_not_found(key)), |res| {
-                Ok(res.inner().clone())
-            })
+        // deletion on any replica hides older data on the others
+        let mut lookup = Lookup::default();
+        let mut corrupted = Vec::new();
+        for result in reqs.await {
+            match result {
+                Err(e) if e.inner().is_data_corrupted() => {
+                    warn!("GET[{}] replica on {} is corrupted", key, e.node_name());
+                    corrupted.push(e.node_name().to_owned());
+                }
+                result => {
+                    let result = result
+                        .map(NodeOutput::into_inner)
+                        .map_err(NodeOutput::into_inner);
+                    if let Err(e) = lookup.add(result) {
+                        debug!("GET[{}] replica returned error: {}", key, e);
+                    }
+                }
+            }
+        }
+        if corrupted.is_empty() {
+            return lookup.into_result(key);
+        }
+        // corrupted replica is overwritten with the version of other replica or alien
+        if !lookup.is_found() {
+            lookup_remote_aliens(&self.mapper.current(), key, &mut lookup).await;
+        }
+        let data = lookup
+            .newest()
+            .cloned()
+            .ok_or_else(|| Error::data_corrupted(key))?;
+        let corrupted = target_nodes
+            .into_iter()
+            .filter(|node| corrupted.iter().any(|name| name == node.name()))
+            .collect();
+        tokio::spawn(read_repair(key, data, corrupted));
+        lookup.into_result(key)
     }
 
     async fn exist(&self, keys: &[BobKey]) -> Result<Vec<bool>, Error> {
@@ -114,4 +141,56 @@ impl Cluster for Quorum {
         }
         Ok(exist)
     }
+
+    async fn delete(&self, key: BobKey, meta: BobMeta) -> Result<(), Error> {
+        let target_nodes = self.get_target_nodes(key);
+
+        debug!("DELETE[{}]: Nodes for fan out: {:?}", key, target_nodes);
+
+        let l_quorum = self.quorum;
+        let reqs = LinkManager::call_nodes(target_nodes.iter(), |conn| {
+            conn.delete(key, meta.clone(), DeleteOptions::new_local())
+                .boxed()
+        });
+        let results = reqs.await;
+        let total_count = results.len();
+        let errors = results.iter().filter(|r| r.is_err()).collect::<Vec<_>>();
+        let ok_count = total_count - errors.len();
+        debug!(
+            "DELETE[{}] total requests: {} ok: {} quorum: {}",
+            key, total_count, ok_count, l_quorum
+        );
+        if ok_count >= l_quorum {
+            Ok(())
+        } else {
+            Err(backend::Error::failed(format!(
+                "failed: total requests: {}, ok: {}, quorum: {}, errors: {:?}",
+                total_count, ok_count, l_quorum, errors
+            )))
+        }
+    }
+}
+
+async fn read_repair(key: BobKey, data: BobData, nodes: Vec<Node>) {
+    let results = LinkManager::call_nodes(nodes.iter(), |conn| {
+        put_or_delete(conn, key, data.clone(), PutOptions::new_local())
+    })
+    .await;
+    for result in results {
+        match result {
+            Ok(res) => {
+                debug!("GET[{}] read-repair of {} succeed", key, res.node_name());
+                counter!(CLUSTER_READ_REPAIR_COUNTER, 1);
+            }
+            Err(e) => {
+                warn!(
+                    "GET[{}] read-repair of {} failed: {}",
+                    key,
+                    e.node_name(),
+                    e.inner()
+                );
+                counter!(CLUSTER_READ_REPAIR_ERROR_COUNTER, 1);
+            }
+        }
+    }
 }
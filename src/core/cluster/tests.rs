@@ -25,6 +25,25 @@ fn put_err(client: &mut BobClient, node: Node, call: Arc<CountCall>) {
     });
 }
 
+fn delete_ok(client: &mut BobClient, node: Node, call: Arc<CountCall>) {
+    client
+        .expect_delete()
+        .returning(move |_key, _meta, _options| {
+            call.delete_inc();
+            test_utils::delete_ok(node.name().to_owned())
+        });
+}
+
+fn delete_err(client: &mut BobClient, node: Node, call: Arc<CountCall>) {
+    debug!("mock BobClient return error on DELETE");
+    client
+        .expect_delete()
+        .returning(move |_key, _meta, _options| {
+            call.delete_inc();
+            test_utils::delete_err(node.name().to_owned())
+        });
+}
+
 fn get_ok_timestamp(client: &mut BobClient, node: Node, call: Arc<CountCall>, timestamp: u64) {
     trace!("get ok timestamp");
     client.expect_get().returning(move |_key, _options| {
@@ -42,9 +61,39 @@ fn get_err(client: &mut BobClient, node: Node, call: Arc<CountCall>) {
     });
 }
 
+fn put_batch(client: &mut BobClient, node: Node, call: Arc<CountCall>, ok: bool) {
+    client
+        .expect_put_batch()
+        .returning(move |records, _options| {
+            call.batch_inc();
+            let results = records
+                .iter()
+                .map(|_| if ok { Ok(()) } else { Err(Error::internal()) })
+                .collect();
+            Ok(NodeOutput::new(node.name().to_owned(), results))
+        });
+}
+
+fn get_batch(client: &mut BobClient, node: Node, call: Arc<CountCall>, timestamp: Option<u64>) {
+    client.expect_get_batch().returning(move |keys, _options| {
+        call.batch_inc();
+        let results = keys
+            .iter()
+            .map(|&key| {
+                timestamp.map_or(Err(Error::key_not_found(key)), |timestamp| {
+                    Ok(BobData::new(vec![], BobMeta::new(timestamp)))
+                })
+            })
+            .collect();
+        Ok(NodeOutput::new(node.name().to_owned(), results))
+    });
+}
+
 struct CountCall {
     put_count: AtomicU64,
     get_count: AtomicU64,
+    delete_count: AtomicU64,
+    batch_count: AtomicU64,
 }
 
 impl CountCall {
@@ -52,9 +101,27 @@ impl CountCall {
         Self {
             put_count: AtomicU64::new(0),
             get_count: AtomicU64::new(0),
+            delete_count: AtomicU64::new(0),
+            batch_count: AtomicU64::new(0),
         }
     }
 
+    fn batch_inc(&self) {
+        self.batch_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn batch_count(&self) -> u64 {
+        self.batch_count.load(Ordering::Relaxed)
+    }
+
+    fn delete_inc(&self) {
+        self.delete_count.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn delete_count(&self) -> u64 {
+        self.delete_count.load(Ordering::Relaxed)
+    }
+
     fn put_inc(&self) {
         self.put_count.fetch_add(1, Ordering::SeqCst);
     }
@@ -86,8 +153,28 @@ async fn create_cluster(
     cluster: &ClusterConfig,
     map: &[(&str, Call, Arc<CountCall>)],
 ) -> (Quorum, Arc<Backend>) {
-    let mapper = Arc::new(Virtual::new(&node, &cluster).await);
-    for node in mapper.nodes().values() {
+    create_cluster_with_consistency(node, cluster, map, ReadConsistency::One).await
+}
+
+async fn create_cluster_with_consistency(
+    node: &NodeConfig,
+    cluster: &ClusterConfig,
+    map: &[(&str, Call, Arc<CountCall>)],
+    read_consistency: ReadConsistency,
+) -> (Quorum, Arc<Backend>) {
+    let mapper = create_mapper(node, cluster, map).await;
+    let backend = Arc::new(Backend::new(mapper.clone(), &node));
+    let quorum = Quorum::new(backend.clone(), mapper, node.quorum(), read_consistency);
+    (quorum, backend)
+}
+
+async fn create_mapper(
+    node: &NodeConfig,
+    cluster: &ClusterConfig,
+    map: &[(&str, Call, Arc<CountCall>)],
+) -> Arc<ClusterMap> {
+    let mapper = Arc::new(ClusterMap::new(Virtual::new(&node, &cluster).await));
+    for node in mapper.current().nodes().values() {
         let mut client = BobClient::default();
         let (_, func, call) = map
             .iter()
@@ -97,9 +184,7 @@ async fn create_cluster(
 
         node.set_connection(client).await;
     }
-
-    let backend = Arc::new(Backend::new(mapper.clone(), &node));
-    (Quorum::new(backend.clone(), mapper, node.quorum()), backend)
+    mapper
 }
 
 fn create_ok_node(name: &str, set_put_ok: bool, set_get_ok: bool) -> (&str, Call, Arc<CountCall>) {
@@ -124,11 +209,20 @@ fn create_node(
                  set_get_ok: bool,
                  timestamp: u64| {
             ping_ok(client, n.clone());
+            put_batch(client, n.clone(), c.clone(), set_put_ok);
+            get_batch(
+                client,
+                n.clone(),
+                c.clone(),
+                Some(timestamp).filter(|_| set_get_ok),
+            );
             if set_put_ok {
                 put_ok(client, n.clone(), c.clone());
+                delete_ok(client, n.clone(), c.clone());
             } else {
                 debug!("node fn set to put_err");
                 put_err(client, n.clone(), c.clone());
+                delete_err(client, n.clone(), c.clone());
             }
             if set_get_ok {
                 get_ok_timestamp(client, n, c, timestamp);
@@ -179,7 +273,7 @@ async fn simple_one_node_put_ok() {
 
     let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
 
-    let key = 1;
+    let key = BobKey::from(1);
     let result = quorum
         .put(key, BobData::new(vec![], BobMeta::new(11)))
         .await;
@@ -209,7 +303,7 @@ async fn simple_two_node_one_vdisk_cluster_put_ok() {
         .map(|(name, _, call)| ((*name).to_string(), call.clone()))
         .collect();
     let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
-    let key = 2;
+    let key = BobKey::from(2);
     let result = quorum
         .put(key, BobData::new(vec![], BobMeta::new(11)))
         .await;
@@ -244,22 +338,26 @@ async fn simple_two_node_two_vdisk_one_replica_cluster_put_ok() {
         .collect();
     let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
 
-    let mut result = quorum.put(3, BobData::new(vec![], BobMeta::new(11))).await;
+    let mut result = quorum
+        .put(BobKey::from(3), BobData::new(vec![], BobMeta::new(11)))
+        .await;
 
     assert!(result.is_ok());
     assert_eq!(0, calls[0].1.put_count());
     assert_eq!(1, calls[1].1.put_count());
 
-    result = quorum.put(4, BobData::new(vec![], BobMeta::new(11))).await;
+    result = quorum
+        .put(BobKey::from(4), BobData::new(vec![], BobMeta::new(11)))
+        .await;
 
     assert!(result.is_ok());
     // assert_eq!(1, calls[0].1.put_count());
     warn!("can't track put result, because it doesn't pass through mock client");
     assert_eq!(1, calls[1].1.put_count());
-    let key = 3;
+    let key = BobKey::from(3);
     let mut get = backend.get_local(key, Operation::new_alien(0)).await;
     assert!(get.err().unwrap().is_key_not_found());
-    let key = 4;
+    let key = BobKey::from(4);
     get = backend.get_local(key, Operation::new_alien(0)).await;
     assert!(get.err().unwrap().is_key_not_found());
 }
@@ -312,7 +410,9 @@ async fn two_node_one_vdisk_cluster_one_node_failed_put_ok() {
         .collect();
     let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
 
-    let result = quorum.put(5, BobData::new(vec![], BobMeta::new(11))).await;
+    let result = quorum
+        .put(BobKey::from(5), BobData::new(vec![], BobMeta::new(11)))
+        .await;
     sleep(Duration::from_millis(1000)).await;
 
     assert!(result.is_ok());
@@ -341,7 +441,9 @@ async fn three_node_two_vdisk_cluster_second_node_failed_put_ok() {
     let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
 
     sleep(Duration::from_millis(1)).await;
-    let result = quorum.put(0, BobData::new(vec![], BobMeta::new(11))).await;
+    let result = quorum
+        .put(BobKey::from(0), BobData::new(vec![], BobMeta::new(11)))
+        .await;
     sleep(Duration::from_millis(1000)).await;
     assert!(result.is_ok());
     // assert_eq!(1, calls[0].1.put_count());
@@ -350,6 +452,36 @@ async fn three_node_two_vdisk_cluster_second_node_failed_put_ok() {
     assert_eq!(1, calls[2].1.put_count());
 }
 
+/// 3 node, 2 vdisk, 2 replics in vdisk, quorum = 2
+/// second node is draining => it's skipped => write one data local + one sup node => put ok
+#[tokio::test]
+async fn three_node_two_vdisk_cluster_draining_node_skipped_put_ok() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(3, 2, 2, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+        create_ok_node("2", true, true),
+    ];
+
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+    let mapper = backend.mapper();
+    let draining = mapper.nodes().values().find(|n| n.name() == "1").unwrap();
+    draining.set_draining(true);
+
+    let result = quorum
+        .put(BobKey::from(0), BobData::new(vec![], BobMeta::new(11)))
+        .await;
+    sleep(Duration::from_millis(1000)).await;
+    assert!(result.is_ok());
+    assert_eq!(0, calls[1].1.put_count());
+    assert_eq!(1, calls[2].1.put_count());
+}
+
 /// 3 node, 2 vdisk, 2 replics in vdisk, quorum = 2
 /// one node failed => write one data local + one sup node(failed) => quorum => put err
 // #[tokio::test]
@@ -402,7 +534,9 @@ async fn three_node_two_vdisk_cluster_one_node_failed_put_ok2() {
         .collect();
     let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
 
-    let result = quorum.put(0, BobData::new(vec![], BobMeta::new(11))).await;
+    let result = quorum
+        .put(BobKey::from(0), BobData::new(vec![], BobMeta::new(11)))
+        .await;
     sleep(Duration::from_millis(1000)).await;
 
     assert!(result.is_ok());
@@ -411,7 +545,9 @@ async fn three_node_two_vdisk_cluster_one_node_failed_put_ok2() {
     assert_eq!(1, calls[1].1.put_count());
     assert_eq!(0, calls[2].1.put_count());
 
-    let get = backend.get_local(0, Operation::new_alien(0)).await;
+    let get = backend
+        .get_local(BobKey::from(0), Operation::new_alien(0))
+        .await;
     assert!(get.is_err());
 }
 
@@ -435,7 +571,9 @@ async fn three_node_one_vdisk_cluster_one_node_failed_put_ok() {
     let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
 
     info!("put local: 0");
-    let result = quorum.put(0, BobData::new(vec![], BobMeta::new(11))).await;
+    let result = quorum
+        .put(BobKey::from(0), BobData::new(vec![], BobMeta::new(11)))
+        .await;
     assert!(result.is_ok());
     // assert_eq!(1, calls[0].1.put_count());
     warn!("can't track put result, because it doesn't pass through mock client");
@@ -444,7 +582,9 @@ async fn three_node_one_vdisk_cluster_one_node_failed_put_ok() {
 
     sleep(Duration::from_millis(32)).await;
     info!("get local backend: 0");
-    let get = backend.get_local(0, Operation::new_alien(0)).await;
+    let get = backend
+        .get_local(BobKey::from(0), Operation::new_alien(0))
+        .await;
     debug!("{:?}", get);
     assert!(get.is_ok());
 }
@@ -461,7 +601,7 @@ async fn simple_one_node_get_err() {
     info!("actions created");
     let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
     info!("cluster created");
-    let result = quorum.get(102).await;
+    let result = quorum.get(BobKey::from(102)).await;
     info!("request finished");
     assert!(result.is_err());
 }
@@ -480,12 +620,501 @@ async fn simple_two_node_get_ok() {
 
     let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
 
-    let result = quorum.get(110).await;
+    let result = quorum.get(BobKey::from(110)).await;
 
     assert!(result.is_ok());
     assert_eq!(1, result.unwrap().meta().timestamp());
 }
 
+fn create_corrupted_node(name: &str) -> (&str, Call, Arc<CountCall>) {
+    let call = |client: &mut BobClient, n: Node, call: Arc<CountCall>| {
+        let f = |client: &mut BobClient, n: Node, c: Arc<CountCall>| {
+            ping_ok(client, n.clone());
+            put_ok(client, n.clone(), c.clone());
+            client.expect_get().returning(move |key, _options| {
+                c.get_inc();
+                Err(NodeOutput::new(
+                    n.name().to_owned(),
+                    Error::data_corrupted(key),
+                ))
+            });
+        };
+        f(client, n.clone(), call.clone());
+        client.expect_clone().returning(move || {
+            let mut cl = BobClient::default();
+            f(&mut cl, n.clone(), call.clone());
+            cl
+        });
+    };
+    (name, Box::new(call), Arc::new(CountCall::new()))
+}
+
+/// 2 nodes, 1 vdisk, 2 replics in vdisk, simple cluster
+/// first replica is corrupted => get data from second => corrupted replica repaired
+#[tokio::test]
+async fn simple_two_node_corrupted_replica_repaired() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+
+    let actions: Vec<(&str, Call, Arc<CountCall>)> =
+        vec![create_corrupted_node("0"), create_node("1", true, true, 3)];
+
+    let mapper = create_mapper(&node, &cluster, &actions).await;
+    let simple = SimpleQuorum::new(mapper, node.quorum());
+
+    let result = simple.get(BobKey::from(112)).await;
+    assert_eq!(3, result.unwrap().meta().timestamp());
+    sleep(Duration::from_millis(10)).await;
+    assert_eq!(1, actions[0].2.put_count());
+    assert_eq!(0, actions[1].2.put_count());
+}
+
+fn create_deleted_node(name: &str, deleted_at: u64) -> (&str, Call, Arc<CountCall>) {
+    let call = move |client: &mut BobClient, n: Node, call: Arc<CountCall>| {
+        let f = move |client: &mut BobClient, n: Node, c: Arc<CountCall>| {
+            ping_ok(client, n.clone());
+            put_ok(client, n.clone(), c.clone());
+            client.expect_get().returning(move |key, _options| {
+                c.get_inc();
+                Err(NodeOutput::new(
+                    n.name().to_owned(),
+                    Error::key_deleted(key, deleted_at),
+                ))
+            });
+        };
+        f(client, n.clone(), call.clone());
+        client.expect_clone().returning(move || {
+            let mut cl = BobClient::default();
+            f(&mut cl, n.clone(), call.clone());
+            cl
+        });
+    };
+    (name, Box::new(call), Arc::new(CountCall::new()))
+}
+
+/// 2 nodes, 1 vdisk, 2 replics in vdisk, simple cluster
+/// first replica has tombstone newer than data of the second => not found
+#[tokio::test]
+async fn simple_two_node_newer_tombstone_hides_stale_replica() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+
+    let actions: Vec<(&str, Call, Arc<CountCall>)> =
+        vec![create_deleted_node("0", 5), create_node("1", true, true, 3)];
+
+    let mapper = create_mapper(&node, &cluster, &actions).await;
+    let simple = SimpleQuorum::new(mapper, node.quorum());
+
+    let result = simple.get(BobKey::from(115)).await;
+    assert_eq!(Some(5), result.unwrap_err().deleted_at());
+}
+
+/// 2 nodes, 1 vdisk, 2 replics in vdisk, quorum = 1, read consistency = one
+/// local tombstone is newer than data on remote replica and in aliens => not found
+#[tokio::test]
+async fn two_node_one_read_newer_tombstone_hides_stale_replica_and_aliens() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+
+    // remote node returns stale data from both replica and alien dirs
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 3),
+    ];
+
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+
+    let key = BobKey::from(116);
+    let (vdisk_id, disk_path) = quorum_operation(&backend, key);
+    let op = Operation::new_local(vdisk_id, disk_path);
+    let tombstone = BobData::tombstone(BobMeta::new(5));
+    backend.put_local(key, tombstone, op).await.unwrap();
+    let mut op = Operation::new_alien(vdisk_id);
+    op.set_remote_folder("1".to_owned());
+    let data = BobData::new(vec![], BobMeta::new(3));
+    backend.put_local(key, data, op).await.unwrap();
+
+    let result = quorum.get(key).await;
+    assert_eq!(Some(5), result.unwrap_err().deleted_at());
+    let results = quorum.get_batch(&[key]).await;
+    assert_eq!(Some(5), results[0].as_ref().unwrap_err().deleted_at());
+
+    // newer version written after deletion is found
+    let other = BobKey::from(117);
+    let (vdisk_id, disk_path) = quorum_operation(&backend, other);
+    let op = Operation::new_local(vdisk_id, disk_path);
+    let tombstone = BobData::tombstone(BobMeta::new(2));
+    backend.put_local(other, tombstone, op).await.unwrap();
+    let result = quorum.get(other).await;
+    assert_eq!(3, result.unwrap().meta().timestamp());
+}
+
+/// 2 nodes, 1 vdisk, 2 replics in vdisk, quorum = 2, read consistency = quorum
+/// no data local, data on remote => get remote data => local replica repaired
+#[tokio::test]
+async fn two_node_quorum_read_repairs_local_replica() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 5),
+    ];
+
+    let (quorum, backend) =
+        create_cluster_with_consistency(&node, &cluster, &actions, ReadConsistency::Quorum).await;
+
+    let key = BobKey::from(111);
+    let result = quorum.get(key).await;
+    assert_eq!(5, result.unwrap().meta().timestamp());
+    sleep(Duration::from_millis(10)).await;
+
+    let (vdisk_id, disk_path) = quorum_operation(&backend, key);
+    let local = backend
+        .get_local(key, Operation::new_local(vdisk_id, disk_path))
+        .await;
+    assert_eq!(5, local.unwrap().meta().timestamp());
+}
+
+/// 3 nodes, 1 vdisk, 3 replics in vdisk, quorum = 2, read consistency = quorum
+/// replicas have different timestamps => get max => only outdated replica repaired
+#[tokio::test]
+async fn three_node_quorum_read_repairs_outdated_replica() {
+    let (node, cluster) = prepare_configs(3, 1, 3, 2);
+
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 3),
+        create_node("2", true, true, 10),
+    ];
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+
+    let (quorum, backend) =
+        create_cluster_with_consistency(&node, &cluster, &actions, ReadConsistency::Quorum).await;
+
+    let key = BobKey::from(112);
+    let (vdisk_id, disk_path) = quorum_operation(&backend, key);
+    let op = Operation::new_local(vdisk_id, disk_path);
+    let data = BobData::new(vec![], BobMeta::new(10));
+    backend.put_local(key, data, op).await.unwrap();
+
+    let result = quorum.get(key).await;
+    assert_eq!(10, result.unwrap().meta().timestamp());
+    sleep(Duration::from_millis(10)).await;
+
+    assert_eq!(1, calls[1].1.put_count());
+    assert_eq!(0, calls[2].1.put_count());
+}
+
+/// 2 nodes, 1 vdisk, 2 replics in vdisk, quorum = 1, read consistency = all
+/// one replica returns error => consistency not reached => err
+#[tokio::test]
+async fn two_node_all_read_one_node_failed_get_err() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, false, 0),
+    ];
+
+    let (quorum, backend) =
+        create_cluster_with_consistency(&node, &cluster, &actions, ReadConsistency::All).await;
+
+    let key = BobKey::from(113);
+    let (vdisk_id, disk_path) = quorum_operation(&backend, key);
+    let op = Operation::new_local(vdisk_id, disk_path);
+    let data = BobData::new(vec![], BobMeta::new(1));
+    backend.put_local(key, data, op).await.unwrap();
+
+    let result = quorum.get(key).await;
+    assert!(result.is_err());
+    assert!(!result.unwrap_err().is_key_not_found());
+}
+
+/// 2 nodes, 1 vdisk, 2 replics in vdisk, quorum = 2, read consistency = quorum
+/// local tombstone is newer than remote data => not found
+#[tokio::test]
+async fn two_node_quorum_read_newer_tombstone_get_err() {
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 5),
+    ];
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+
+    let (quorum, backend) =
+        create_cluster_with_consistency(&node, &cluster, &actions, ReadConsistency::Quorum).await;
+
+    let key = BobKey::from(114);
+    let (vdisk_id, disk_path) = quorum_operation(&backend, key);
+    let op = Operation::new_local(vdisk_id, disk_path);
+    let tombstone = BobData::tombstone(BobMeta::new(5));
+    backend.put_local(key, tombstone, op).await.unwrap();
+
+    let result = quorum.get(key).await;
+    assert!(result.unwrap_err().is_key_not_found());
+    sleep(Duration::from_millis(10)).await;
+
+    assert_eq!(1, calls[1].1.delete_count());
+}
+
+fn quorum_operation(backend: &Backend, key: BobKey) -> (VDiskID, DiskPath) {
+    let (vdisk_id, disk_path) = backend.mapper().get_operation(key);
+    (vdisk_id, disk_path.expect("local replica"))
+}
+
+//////////////////////////////////////////////////
+////////////////////////////////////////////////// delete
+//////////////////////////////////////////////////
+
+/// 2 nodes, 1 vdisk, 2 replics in vdisk, quorum = 2
+/// put data on both nodes, then delete => tombstone local and on remote node
+#[tokio::test]
+async fn two_node_one_vdisk_cluster_delete_ok() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+    ];
+
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+    let key = BobKey::from(7);
+    let (vdisk_id, path) = backend.mapper().get_operation(key);
+    let op = Operation::new_local(vdisk_id, path.expect("local replica"));
+
+    let result = quorum
+        .put(key, BobData::new(vec![], BobMeta::new(11)))
+        .await;
+    assert!(result.is_ok());
+    assert!(backend.get_local(key, op.clone()).await.is_ok());
+
+    let result = quorum.delete(key, BobMeta::new(12)).await;
+    sleep(Duration::from_millis(1)).await;
+
+    assert!(result.is_ok());
+    assert_eq!(1, calls[1].1.delete_count());
+    let get = backend.get_local(key, op).await;
+    assert!(get.err().unwrap().is_key_not_found());
+}
+
+/// 2 nodes, 1 vdisk, 2 replics in vdisk, quorum = 1
+/// remote node failed => tombstone written local => quorum => delete ok
+#[tokio::test]
+async fn two_node_one_vdisk_cluster_one_node_failed_delete_ok() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", false, true),
+    ];
+
+    let calls: Vec<_> = actions
+        .iter()
+        .map(|(name, _, call)| ((*name).to_string(), call.clone()))
+        .collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let result = quorum.delete(BobKey::from(8), BobMeta::new(12)).await;
+    sleep(Duration::from_millis(1000)).await;
+
+    assert!(result.is_ok());
+    assert_eq!(1, calls[1].1.delete_count());
+    assert_eq!(0, calls[1].1.put_count());
+}
+
+//////////////////////////////////////////////////
+////////////////////////////////////////////////// cluster map
+//////////////////////////////////////////////////
+
+fn next_version(cluster: &ClusterConfig) -> ClusterConfig {
+    ClusterConfig::new(
+        cluster.nodes().to_vec(),
+        cluster.vdisks().to_vec(),
+        cluster.key_length(),
+        cluster.version() + 1,
+    )
+}
+
+fn cluster_map_node(name: &str, cluster: ClusterConfig) -> (&str, Call, Arc<CountCall>) {
+    let call = move |client: &mut BobClient, n: Node, _: Arc<CountCall>| {
+        let cluster = cluster.clone();
+        client.expect_clone().returning(move || {
+            let mut cl = BobClient::default();
+            let (name, cluster) = (n.name().to_owned(), cluster.clone());
+            cl.expect_cluster_map()
+                .returning(move || Ok(NodeOutput::new(name.clone(), cluster.clone())));
+            cl
+        });
+    };
+    (name, Box::new(call), Arc::new(CountCall::new()))
+}
+
+//////////////////////////////////////////////////
+////////////////////////////////////////////////// batch
+//////////////////////////////////////////////////
+
+/// 2 nodes, 1 vdisk, 2 replicas in vdisk, quorum = 2
+/// put batch => one request to remote node => all puts ok
+#[tokio::test]
+async fn two_node_one_vdisk_cluster_put_batch_ok() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let records = (20..25)
+        .map(|key| (BobKey::from(key), BobData::new(vec![], BobMeta::new(11))))
+        .collect();
+    let results = quorum.put_batch(records).await;
+
+    assert_eq!(5, results.len());
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(1, calls[1].batch_count());
+    assert_eq!(0, calls[1].put_count());
+}
+
+/// 2 nodes, 1 vdisk, 2 replicas in vdisk, quorum = 2
+/// remote node failed => written local only => no quorum => records go to local alien
+#[tokio::test]
+async fn two_node_one_vdisk_cluster_one_node_failed_put_batch_to_alien() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 2);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", false, true),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, backend) = create_cluster(&node, &cluster, &actions).await;
+
+    let records = vec![
+        (BobKey::from(30), BobData::new(vec![], BobMeta::new(11))),
+        (BobKey::from(31), BobData::new(vec![], BobMeta::new(11))),
+    ];
+    let results = quorum.put_batch(records).await;
+
+    assert!(results.iter().all(Result::is_ok));
+    assert_eq!(1, calls[1].batch_count());
+    let get = backend
+        .get_local(BobKey::from(31), Operation::new_alien(0))
+        .await;
+    assert!(get.is_ok());
+}
+
+/// 2 nodes, 1 vdisk, 2 replicas in vdisk
+/// no data local => one batch request to remote node => data found
+#[tokio::test]
+async fn two_node_get_batch_reads_remote_node_once() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_node("0", true, true, 0),
+        create_node("1", true, true, 7),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let keys: Vec<_> = (40..44).map(BobKey::from).collect();
+    let results = quorum.get_batch(&keys).await;
+
+    assert_eq!(4, results.len());
+    for result in results {
+        assert_eq!(7, result.unwrap().meta().timestamp());
+    }
+    assert_eq!(1, calls[1].batch_count());
+}
+
+/// 2 nodes, 1 vdisk, 2 replicas in vdisk
+/// no data anywhere => remote replica and remote alien are asked once => not found
+#[tokio::test]
+async fn two_node_get_batch_not_found() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 1, 2, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, false),
+        create_ok_node("1", true, false),
+    ];
+    let calls: Vec<_> = actions.iter().map(|(_, _, call)| call.clone()).collect();
+    let (quorum, _) = create_cluster(&node, &cluster, &actions).await;
+
+    let keys: Vec<_> = (50..53).map(BobKey::from).collect();
+    let results = quorum.get_batch(&keys).await;
+
+    assert!(results
+        .iter()
+        .all(|result| result.as_ref().unwrap_err().is_key_not_found()));
+    assert_eq!(2, calls[1].batch_count());
+}
+
+/// map with the same version is rejected, newer map replaces current one
+#[tokio::test]
+async fn cluster_map_update_requires_newer_version() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 2, 1, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+    ];
+    let (_, backend) = create_cluster(&node, &cluster, &actions).await;
+
+    assert!(backend.update_cluster_map(&cluster).await.is_err());
+    assert_eq!(0, backend.mapper().version());
+
+    let newer = next_version(&cluster);
+    assert_eq!(1, backend.update_cluster_map(&newer).await.unwrap());
+    assert_eq!(1, backend.mapper().version());
+    assert_eq!(&newer, backend.mapper().cluster_config());
+}
+
+/// current map is kept, if storages can't be prepared for the new one
+#[tokio::test]
+async fn cluster_map_kept_if_not_applied() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 2, 1, 1);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        create_ok_node("1", true, true),
+    ];
+    let (_, backend) = create_cluster(&node, &cluster, &actions).await;
+
+    let newer = next_version(&cluster);
+    let failed = |_| async { Err(Error::failed("storages are not ready")) };
+    assert!(backend.cluster_map().update(&newer, failed).await.is_err());
+    assert_eq!(0, backend.mapper().version());
+    assert_eq!(&cluster, backend.mapper().cluster_config());
+}
+
+/// node pulls newer map from the other node
+#[tokio::test]
+async fn cluster_map_gossip_applies_newer_version() {
+    test_utils::init_logger();
+    let (node, cluster) = prepare_configs(2, 2, 1, 1);
+    let newer = next_version(&cluster);
+    let actions: Vec<(&str, Call, Arc<CountCall>)> = vec![
+        create_ok_node("0", true, true),
+        cluster_map_node("1", newer.clone()),
+    ];
+    let (_, backend) = create_cluster(&node, &cluster, &actions).await;
+
+    assert_eq!(Some(1), Gossip::run_once(&backend).await);
+    assert_eq!(&newer, backend.mapper().cluster_config());
+    // map of the same version is not applied again
+    assert_eq!(None, Gossip::run_once(&backend).await);
+}
+
 // 2 nodes, 2 vdisk, 1 replics in vdisk, quorum = 1
 // get data from 1 nodes => fail => read from sup node => ok
 // #[tokio::test]
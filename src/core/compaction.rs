@@ -0,0 +1,434 @@
+use super::prelude::*;
+use crate::core::backend::{Group, Holder};
+use crate::core::configs::CompactionConfig;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, UNIX_EPOCH};
+use tokio::runtime::Handle;
+use tokio::sync::{Mutex, RwLock};
+
+const BATCH_SIZE: usize = 100;
+
+/// Merges closed holders of local vdisks, which start in the same time window, into one holder.
+/// Only the most recent version of each key is kept, tombstones are dropped after the grace
+/// period if there are no older holders with the key, so deleted records can't reappear. Merged holder replaces the
+/// sources in the group only if they weren't changed during compaction.
+/// The same machinery rewrites single holders to re-encrypt their records with the current key.
+pub(crate) struct Compaction {
+    config: CompactionConfig,
+    last_report: RwLock<Option<CompactionReport>>,
+    last_reencryption: RwLock<Option<CompactionReport>>,
+    reencryption_running: AtomicBool,
+    // set before storages are closed, compaction in progress stops after the current window
+    stopped: AtomicBool,
+    // scheduled and requested compactions mustn't merge the same holders
+    lock: Mutex<()>,
+    // re-encryption is started from the http api thread, which has its own short-lived runtime
+    runtime: Option<Handle>,
+}
+
+/// Result of the compaction of all local groups
+#[derive(Debug, Default, Clone)]
+pub(crate) struct CompactionReport {
+    merged: usize,
+    created: usize,
+    records: usize,
+    dropped: usize,
+    skipped: usize,
+}
+
+impl CompactionReport {
+    /// Count of the source holders replaced by the merged ones
+    pub(crate) fn merged(&self) -> usize {
+        self.merged
+    }
+
+    /// Count of the created merged holders
+    pub(crate) fn created(&self) -> usize {
+        self.created
+    }
+
+    /// Count of the records written to the merged holders
+    pub(crate) fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Count of the superseded versions and tombstones, which were not written
+    pub(crate) fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Count of the windows left as is because of errors or concurrent changes. Re-encryption
+    /// also counts holders, which still have records with old keys, so old keys can be removed
+    /// only if it's zero.
+    pub(crate) fn skipped(&self) -> usize {
+        self.skipped
+    }
+}
+
+// sources of the merged holder, records counts are used to detect changes during compaction
+struct Window {
+    holders: Vec<Holder>,
+    records_counts: Vec<usize>,
+}
+
+impl Window {
+    fn start_timestamp(&self) -> u64 {
+        self.holders
+            .iter()
+            .map(Holder::start_timestamp)
+            .min()
+            .unwrap_or_default()
+    }
+
+    fn end_timestamp(&self) -> u64 {
+        self.holders
+            .iter()
+            .map(Holder::end_timestamp)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+impl Compaction {
+    pub(crate) fn new(config: CompactionConfig) -> Self {
+        Self {
+            config,
+            last_report: RwLock::default(),
+            last_reencryption: RwLock::default(),
+            reencryption_running: AtomicBool::new(false),
+            stopped: AtomicBool::new(false),
+            lock: Mutex::default(),
+            runtime: Handle::try_current().ok(),
+        }
+    }
+
+    pub(crate) fn spawn_task(self: Arc<Self>, backend: Arc<Backend>) -> Option<JoinHandle<()>> {
+        if let Some(interval) = self.config.interval() {
+            Some(tokio::spawn(self.task(backend, interval)))
+        } else {
+            info!("scheduled compaction is disabled");
+            None
+        }
+    }
+
+    async fn task(self: Arc<Self>, backend: Arc<Backend>, t: Duration) {
+        let mut interval = interval(t);
+        // first tick completes immediately, don't compact right after start
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            self.run_once(&backend).await;
+        }
+    }
+
+    /// Compacts all local groups and saves result as the last report
+    pub(crate) async fn run_once(&self, backend: &Backend) -> CompactionReport {
+        let _lock = self.lock.lock().await;
+        let mut report = CompactionReport::default();
+        for group in backend.vdisks_groups().await.unwrap_or_default() {
+            if group.is_ready() {
+                self.compact_group(&group, &mut report).await;
+            }
+        }
+        info!(
+            "compaction finished: {} holders merged into {}, {} records written, {} dropped, {} windows skipped",
+            report.merged, report.created, report.records, report.dropped, report.skipped
+        );
+        *self.last_report.write().await = Some(report.clone());
+        report
+    }
+
+    /// Stops compaction and re-encryption, waits for the window in progress to be swapped
+    pub(crate) async fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        let _lock = self.lock.lock().await;
+    }
+
+    /// Allows compaction again after storages are reopened
+    pub(crate) fn resume(&self) {
+        self.stopped.store(false, Ordering::SeqCst);
+    }
+
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Result of the last finished compaction, `None` if compaction wasn't run yet
+    pub(crate) async fn last_report(&self) -> Option<CompactionReport> {
+        self.last_report.read().await.clone()
+    }
+
+    /// Starts background re-encryption of closed holders of local and alien groups, which have
+    /// records not encrypted with the current key. Returns `false` if it's already running.
+    pub(crate) fn start_reencryption(self: Arc<Self>, backend: Arc<Backend>) -> bool {
+        if self.reencryption_running.swap(true, Ordering::SeqCst) {
+            return false;
+        }
+        let runtime = self.runtime.clone();
+        let task = async move {
+            self.reencrypt(&backend).await;
+            self.reencryption_running.store(false, Ordering::SeqCst);
+        };
+        if let Some(runtime) = runtime {
+            runtime.spawn(task);
+        } else {
+            tokio::spawn(task);
+        }
+        true
+    }
+
+    pub(crate) fn is_reencryption_running(&self) -> bool {
+        self.reencryption_running.load(Ordering::SeqCst)
+    }
+
+    /// Result of the last finished re-encryption, `None` if re-encryption wasn't run yet
+    pub(crate) async fn last_reencryption(&self) -> Option<CompactionReport> {
+        self.last_reencryption.read().await.clone()
+    }
+
+    /// Rewrites holders with records encrypted with the old keys or not encrypted at all, each
+    /// holder is compacted alone, so superseded versions are dropped too
+    pub(crate) async fn reencrypt(&self, backend: &Backend) -> CompactionReport {
+        let _lock = self.lock.lock().await;
+        let mut report = CompactionReport::default();
+        let mut groups = backend.vdisks_groups().await.unwrap_or_default();
+        groups.extend(backend.alien_vdisks_groups().await.unwrap_or_default());
+        for group in groups.iter().filter(|group| group.is_ready()) {
+            self.reencrypt_group(group, &mut report).await;
+        }
+        info!(
+            "re-encryption finished: {} holders rewritten, {} records written, {} dropped, {} holders with old keys skipped",
+            report.merged, report.records, report.dropped, report.skipped
+        );
+        *self.last_reencryption.write().await = Some(report.clone());
+        report
+    }
+
+    pub(crate) async fn reencrypt_group(&self, group: &Group, report: &mut CompactionReport) {
+        let holders = group.holders().read().await.clone();
+        for holder in &holders {
+            if self.is_stopped() {
+                return;
+            }
+            // records of the holder without keys log can't be enumerated, so they may have any key
+            if !holder.has_keys_log() {
+                if holder.records_count().await > 0 {
+                    warn!(
+                        "re-encryption: pearl:{} of vdisk {} has no keys log",
+                        holder.start_timestamp(),
+                        group.vdisk_id()
+                    );
+                    report.skipped += 1;
+                }
+                continue;
+            }
+            let window = Window {
+                holders: vec![holder.clone()],
+                records_counts: vec![holder.records_count().await],
+            };
+            let result = match holder.needs_reencryption().await {
+                Ok(true) if holder.is_outdated() => {
+                    self.compact_window(group, &holders, &window, report).await
+                }
+                // active holder receives writes, it's rewritten after it's closed
+                Ok(true) => {
+                    report.skipped += 1;
+                    continue;
+                }
+                Ok(false) => continue,
+                Err(e) => Err(e),
+            };
+            Self::count_result(group, &window, result, report);
+        }
+    }
+
+    pub(crate) async fn compact_group(&self, group: &Group, report: &mut CompactionReport) {
+        let holders = group.holders().read().await.clone();
+        for window in self.windows(&holders).await {
+            if self.is_stopped() {
+                return;
+            }
+            let result = self.compact_window(group, &holders, &window, report).await;
+            Self::count_result(group, &window, result, report);
+        }
+    }
+
+    fn count_result(
+        group: &Group,
+        window: &Window,
+        result: Result<bool, Error>,
+        report: &mut CompactionReport,
+    ) {
+        match result {
+            Ok(true) => {
+                report.merged += window.holders.len();
+                report.created += 1;
+            }
+            Ok(false) => {
+                debug!(
+                    "compaction: holders of vdisk {} were changed, window {} skipped",
+                    group.vdisk_id(),
+                    window.start_timestamp()
+                );
+                report.skipped += 1;
+            }
+            Err(e) => {
+                warn!(
+                    "compaction: window {} of vdisk {} skipped: {}",
+                    window.start_timestamp(),
+                    group.vdisk_id(),
+                    e
+                );
+                report.skipped += 1;
+            }
+        }
+    }
+
+    // closed holders with complete keys log grouped by the window of their start timestamp,
+    // windows overlapping with other holders are skipped, so records are routed to a single holder
+    async fn windows(&self, holders: &[Holder]) -> Vec<Window> {
+        let window_secs = self.config.window().as_secs();
+        let mut windows = BTreeMap::<_, Vec<_>>::new();
+        for holder in holders {
+            if holder.is_outdated() && holder.has_complete_keys_log().await.unwrap_or(false) {
+                let window = holder.start_timestamp() / window_secs;
+                windows.entry(window).or_default().push(holder.clone());
+            }
+        }
+        let mut result = vec![];
+        for (_, sources) in windows {
+            if sources.len() < 2 {
+                continue;
+            }
+            let mut records_counts = Vec::with_capacity(sources.len());
+            for holder in &sources {
+                records_counts.push(holder.records_count().await);
+            }
+            let window = Window {
+                holders: sources,
+                records_counts,
+            };
+            let overlapped = holders.iter().any(|holder| {
+                !window
+                    .holders
+                    .iter()
+                    .any(|source| source.start_timestamp() == holder.start_timestamp())
+                    && holder.start_timestamp() < window.end_timestamp()
+                    && window.start_timestamp() < holder.end_timestamp()
+            });
+            if !overlapped {
+                result.push(window);
+            }
+        }
+        result
+    }
+
+    async fn compact_window(
+        &self,
+        group: &Group,
+        holders: &[Holder],
+        window: &Window,
+        report: &mut CompactionReport,
+    ) -> Result<bool, Error> {
+        let start_timestamp = window.start_timestamp();
+        let merged = group.create_compaction_holder(start_timestamp, window.end_timestamp());
+        // directory may be left by the failed compaction
+        if merged.path().exists() {
+            merged.drop_directory()?;
+        }
+        merged
+            .prepare_storage()
+            .await
+            .map_err(|e| Error::storage(format!("{:#}", e)))?;
+        let result = match self.write_merged(holders, window, &merged, report).await {
+            Ok(()) => {
+                let sources: Vec<_> = window
+                    .holders
+                    .iter()
+                    .map(Holder::start_timestamp)
+                    .zip(window.records_counts.iter().copied())
+                    .collect();
+                group.swap_compacted(merged.clone(), &sources).await
+            }
+            Err(e) => Err(e),
+        };
+        // merged holder is moved to the group directory by successful swap
+        if merged.path().exists() {
+            merged.close().await;
+            if let Err(e) = merged.drop_directory() {
+                warn!("compaction: {}", e);
+            }
+        }
+        result
+    }
+
+    async fn write_merged(
+        &self,
+        holders: &[Holder],
+        window: &Window,
+        merged: &Holder,
+        report: &mut CompactionReport,
+    ) -> Result<(), Error> {
+        let mut keys = vec![];
+        for holder in &window.holders {
+            keys.extend(holder.keys().await?);
+        }
+        keys.sort_unstable();
+        keys.dedup();
+        // tombstone hides records of the older holders, so it's kept while they have the key
+        let grace_start = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("current time is before unix epoch")
+            .as_secs()
+            .saturating_sub(self.config.tombstone_grace_period().as_secs());
+        let older: Vec<_> = holders
+            .iter()
+            .filter(|holder| holder.start_timestamp() < window.start_timestamp())
+            .collect();
+        for keys in keys.chunks(BATCH_SIZE) {
+            let mut bytes = 0;
+            for &key in keys {
+                let mut versions = Vec::with_capacity(window.holders.len());
+                for holder in &window.holders {
+                    match holder.read(key).await {
+                        Ok(data) => versions.push(data),
+                        Err(e) if e.is_key_not_found() => {}
+                        // corrupted record may hide the most recent version
+                        Err(e) => return Err(e),
+                    }
+                }
+                let count = versions.len();
+                let data = match BobData::most_recent(versions) {
+                    Some(data) => data,
+                    None => continue,
+                };
+                if data.is_tombstone()
+                    && data.meta().timestamp() < grace_start
+                    && !Self::exists_in(&older, key).await?
+                {
+                    report.dropped += count;
+                    continue;
+                }
+                bytes += data.inner().len() as u64;
+                merged.write(key, &data).await?;
+                report.records += 1;
+                report.dropped += count - 1;
+                tokio::task::yield_now().await;
+            }
+            if let Some(delay) = Migration::throttle_delay(bytes, self.config.bandwidth()) {
+                sleep(delay).await;
+            }
+        }
+        Ok(())
+    }
+
+    async fn exists_in(holders: &[&Holder], key: BobKey) -> Result<bool, Error> {
+        for holder in holders {
+            if holder.exist(key).await? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+}
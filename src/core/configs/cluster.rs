@@ -21,7 +21,7 @@ impl Validatable for DiskPath {
 }
 
 /// Node config struct, with name, address and [`DiskPath`]s.
-#[derive(Debug, PartialEq, Serialize, Deserialize, new)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, new)]
 pub struct Node {
     name: String,
     address: String,
@@ -127,7 +127,7 @@ impl Validatable for Replica {
 }
 
 /// Config for virtual disks, stores replicas locations.
-#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct VDisk {
     id: u32,
     #[serde(default)]
@@ -185,15 +185,35 @@ impl Validatable for VDisk {
 }
 
 /// Config with cluster structure description.
-#[derive(Debug, PartialEq, Serialize, Deserialize, new)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, new)]
 pub struct Cluster {
     #[serde(default)]
     nodes: Vec<Node>,
     #[serde(default)]
     vdisks: Vec<VDisk>,
+    #[serde(default = "Cluster::default_key_length")]
+    key_length: usize,
+    #[serde(default)]
+    version: u64,
 }
 
 impl Cluster {
+    fn default_key_length() -> usize {
+        8
+    }
+
+    /// Length of the keys in bytes, shorter keys are padded with leading zeros.
+    #[must_use]
+    pub fn key_length(&self) -> usize {
+        self.key_length
+    }
+
+    /// Version of the cluster map, must be increased on every change of the running cluster.
+    #[must_use]
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
     /// Returns slice with [`Node`]s.
     #[must_use]
     pub fn nodes(&self) -> &[Node] {
@@ -319,7 +339,9 @@ impl Cluster {
         node.prepare(finded)
     }
 
-    #[cfg(test)]
+    /// Parses config from yaml, and validates it.
+    /// # Errors
+    /// Parse errors and failed validation.
     pub(crate) fn get_from_string(file: &str) -> Result<Self, String> {
         let config = YamlBobConfig::parse::<Self>(file)?;
         debug!("config: {:?}", config);
@@ -354,6 +376,16 @@ impl Validatable for Cluster {
             e
         })?;
 
+        if self.key_length < Self::default_key_length() || self.key_length > BobKey::MAX_LEN {
+            let msg = format!(
+                "field \'key_length\' must be in range from {} to {}",
+                Self::default_key_length(),
+                BobKey::MAX_LEN
+            );
+            error!("{}", msg);
+            return Err(msg);
+        }
+
         let mut vdisks_id = self.vdisks.iter().map(|vdisk| vdisk.id).collect::<Vec<_>>();
         vdisks_id.sort_unstable();
         if vdisks_id.windows(2).any(|pair| pair[0] == pair[1]) {
@@ -430,6 +462,11 @@ pub(crate) mod tests {
             })
             .collect();
 
-        Cluster { nodes, vdisks }
+        Cluster {
+            nodes,
+            vdisks,
+            key_length: 8,
+            version: 0,
+        }
     }
 }
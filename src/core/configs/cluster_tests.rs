@@ -788,6 +788,67 @@ cleanup_interval: 1d
 metrics:                      # optional, send metrics
   name: machine               # optional, add base name for metrics
   graphite: 127.0.0.0.1:2003    # optional, send metrics to graphite
+";
+        let d: NodeConfig = YamlBobConfig::parse(s).unwrap();
+        assert!(d.validate().is_err());
+    }
+    #[test]
+    fn test_node_config_with_prometheus_metrics() {
+        let s = "
+log_config: logger.yaml
+name: no
+quorum: 1
+operation_timeout: 12h 5min 2ns
+check_interval: 100ms
+cluster_policy: quorum # quorum
+backend_type: stub
+cleanup_interval: 1d
+
+metrics:
+  name: machine
+  prometheus: 0.0.0.0:9000
+";
+        let d: NodeConfig = YamlBobConfig::parse(s).unwrap();
+        assert!(d.validate().is_ok());
+        assert_eq!(d.metrics().graphite(), None);
+        assert_eq!(
+            d.metrics().prometheus(),
+            Some("0.0.0.0:9000".parse().unwrap())
+        );
+    }
+    #[test]
+    fn test_node_config_with_invalid_prometheus_address() {
+        let s = "
+log_config: logger.yaml
+name: no
+quorum: 1
+operation_timeout: 12h 5min 2ns
+check_interval: 100ms
+cluster_policy: quorum # quorum
+backend_type: stub
+cleanup_interval: 1d
+
+metrics:
+  name: machine
+  prometheus: localhost:9000
+";
+        let d: NodeConfig = YamlBobConfig::parse(s).unwrap();
+        assert!(d.validate().is_err());
+    }
+    #[test]
+    fn test_node_config_with_metrics_without_exporters() {
+        let s = "
+log_config: logger.yaml
+name: no
+quorum: 1
+operation_timeout: 12h 5min 2ns
+check_interval: 100ms
+cluster_policy: quorum # quorum
+backend_type: stub
+cleanup_interval: 1d
+
+metrics:
+  name: machine
 ";
         let d: NodeConfig = YamlBobConfig::parse(s).unwrap();
         assert!(d.validate().is_err());
@@ -811,4 +872,36 @@ metrics:                      # optional, send metrics
         let d: NodeConfig = YamlBobConfig::parse(s).unwrap();
         assert!(d.validate().is_ok());
     }
+    #[test]
+    fn test_node_config_with_free_space_watermarks() {
+        let s = "
+log_config: logger.yaml
+name: no
+quorum: 1
+operation_timeout: 12h 5min 2ns
+check_interval: 100ms
+cluster_policy: quorum # quorum
+backend_type: stub
+cleanup_interval: 1d
+
+free_space:
+  soft_watermark: 1000
+  hard_watermark: 100
+  disks:
+    disk2:
+      hard_watermark: 500
+";
+        let d: NodeConfig = YamlBobConfig::parse(s).unwrap();
+        assert!(d.validate().is_ok());
+        let disk1 = d.free_space().watermarks("disk1");
+        assert_eq!((disk1.soft(), disk1.hard()), (Some(1000), Some(100)));
+        let disk2 = d.free_space().watermarks("disk2");
+        assert_eq!((disk2.soft(), disk2.hard()), (Some(1000), Some(500)));
+
+        let d: NodeConfig = YamlBobConfig::parse(&s.replace("500", "5000")).unwrap();
+        assert!(
+            d.validate().is_err(),
+            "soft watermark of disk2 is less than hard"
+        );
+    }
 }
@@ -6,8 +6,12 @@ pub mod node;
 mod reader;
 
 pub use self::cluster::{Cluster, Node as ClusterNode, Replica, VDisk};
-pub(crate) use self::node::BackendType;
-pub use self::node::{BackendSettings, MetricsConfig, Node, Pearl};
+pub use self::node::{
+    AuthConfig, BackendSettings, CompactionConfig, CompressionAlgorithm, CompressionConfig,
+    EncryptionConfig, FreeSpaceConfig, MetricsConfig, Node, Pearl, Role, TlsConfig, UserConfig,
+    Watermarks,
+};
+pub(crate) use self::node::{BackendType, ReadConsistency};
 use super::prelude::*;
 
 mod prelude {
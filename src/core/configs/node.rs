@@ -1,4 +1,6 @@
 use super::prelude::*;
+use std::collections::BTreeMap;
+use std::str::FromStr;
 
 const PLACEHOLDER: &str = "~";
 
@@ -97,20 +99,35 @@ impl BackendSettings {
     }
 }
 
-/// Contains params for graphite metrics.
+/// Contains params for graphite and prometheus metrics.
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, new)]
 pub struct MetricsConfig {
     name: String,
-    graphite: String,
+    #[serde(default)]
+    graphite: Option<String>,
+    #[serde(default)]
+    prometheus: Option<String>,
 }
 
 impl MetricsConfig {
-    pub(crate) fn graphite(&self) -> &str {
-        &self.graphite
+    /// Address of the graphite server to push metrics to
+    pub(crate) fn graphite(&self) -> Option<&str> {
+        self.graphite.as_deref()
+    }
+
+    /// Address to expose metrics on for prometheus scraping, invalid address is rejected by
+    /// config validation
+    pub(crate) fn prometheus(&self) -> Option<SocketAddr> {
+        self.prometheus
+            .as_deref()
+            .and_then(|address| address.parse().ok())
     }
 
     fn check_unset(&self) -> Result<(), String> {
-        if self.name == PLACEHOLDER || self.graphite == PLACEHOLDER {
+        if self.name == PLACEHOLDER
+            || self.graphite.as_deref() == Some(PLACEHOLDER)
+            || self.prometheus.as_deref() == Some(PLACEHOLDER)
+        {
             let msg = "some of the fields present, but empty".to_string();
             error!("{}", msg);
             Err(msg)
@@ -128,8 +145,70 @@ impl Validatable for MetricsConfig {
             return Err("field 'name' for 'metrics config' is empty".to_string());
         }
 
-        if let Err(e) = self.graphite.parse::<SocketAddr>() {
-            let msg = format!("field 'graphite': {} for 'metrics config' is invalid", e);
+        if self.graphite.is_none() && self.prometheus.is_none() {
+            let msg = "one of 'graphite' or 'prometheus' for 'metrics config' must be set";
+            error!("{}", msg);
+            return Err(msg.to_string());
+        }
+        for (field, address) in &[
+            ("graphite", &self.graphite),
+            ("prometheus", &self.prometheus),
+        ] {
+            if let Some(Err(e)) = address.as_ref().map(|a| a.parse::<SocketAddr>()) {
+                let msg = format!("field '{}': {} for 'metrics config' is invalid", field, e);
+                error!("{}", msg);
+                return Err(msg);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Contains paths to certificates for TLS of the grpc and http api and connections to other nodes.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, new)]
+pub struct TlsConfig {
+    cert_path: String,
+    key_path: String,
+    ca_cert_path: String,
+    #[serde(default)]
+    require_client_cert: bool,
+    #[serde(default)]
+    domain_name: Option<String>,
+}
+
+impl TlsConfig {
+    /// Certificate of the node in PEM, presented to clients and to other nodes
+    pub(crate) fn cert_path(&self) -> &str {
+        &self.cert_path
+    }
+
+    /// Private key of the node certificate in PEM
+    pub(crate) fn key_path(&self) -> &str {
+        &self.key_path
+    }
+
+    /// CA certificates in PEM, used to verify other nodes and clients
+    pub(crate) fn ca_cert_path(&self) -> &str {
+        &self.ca_cert_path
+    }
+
+    /// Clients without certificate signed by CA are rejected if set
+    pub(crate) fn require_client_cert(&self) -> bool {
+        self.require_client_cert
+    }
+
+    /// Name in the certificates of other nodes, their addresses are used if not set
+    pub(crate) fn domain_name(&self) -> Option<&str> {
+        self.domain_name.as_deref()
+    }
+
+    fn check_unset(&self) -> Result<(), String> {
+        if self.cert_path == PLACEHOLDER
+            || self.key_path == PLACEHOLDER
+            || self.ca_cert_path == PLACEHOLDER
+            || self.domain_name.as_deref() == Some(PLACEHOLDER)
+        {
+            let msg = "some of the fields present, but empty".to_string();
             error!("{}", msg);
             Err(msg)
         } else {
@@ -138,6 +217,471 @@ impl Validatable for MetricsConfig {
     }
 }
 
+impl Validatable for TlsConfig {
+    fn validate(&self) -> Result<(), String> {
+        self.check_unset()?;
+        for (field, path) in &[
+            ("cert_path", &self.cert_path),
+            ("key_path", &self.key_path),
+            ("ca_cert_path", &self.ca_cert_path),
+        ] {
+            if !Path::new(path).is_file() {
+                let msg = format!("field '{}': {} for 'tls config' is not a file", field, path);
+                error!("{}", msg);
+                return Err(msg);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Contains users allowed to access grpc and http api, access isn't restricted if not set.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, new)]
+pub struct AuthConfig {
+    users: Vec<UserConfig>,
+    node_user: String,
+}
+
+impl AuthConfig {
+    pub(crate) fn users(&self) -> &[UserConfig] {
+        &self.users
+    }
+
+    /// Name of the user, whose credentials are used for requests to other nodes
+    pub(crate) fn node_user(&self) -> &str {
+        &self.node_user
+    }
+}
+
+impl Validatable for AuthConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.users.is_empty() {
+            let msg = "field 'users' for 'authentication config' is empty".to_string();
+            error!("{}", msg);
+            return Err(msg);
+        }
+        for (i, user) in self.users.iter().enumerate() {
+            user.validate()?;
+            let duplicate = self.users[..i].iter().any(|other| {
+                other.name == user.name || (other.token.is_some() && other.token == user.token)
+            });
+            if duplicate {
+                let msg = format!("name or token of user '{}' is not unique", user.name);
+                error!("{}", msg);
+                return Err(msg);
+            }
+        }
+        match self.users.iter().find(|user| user.name == self.node_user) {
+            Some(user) if user.role() >= Role::Write => Ok(()),
+            Some(_) => {
+                let msg = "field 'node_user' for 'authentication config' must have write role"
+                    .to_string();
+                error!("{}", msg);
+                Err(msg)
+            }
+            None => {
+                let msg = format!(
+                    "field 'node_user' for 'authentication config': user '{}' not found",
+                    self.node_user
+                );
+                error!("{}", msg);
+                Err(msg)
+            }
+        }
+    }
+}
+
+/// Free space watermarks of the disk in bytes, unset watermark isn't checked.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, Copy, new)]
+pub struct Watermarks {
+    #[serde(default)]
+    soft_watermark: Option<u64>,
+    #[serde(default)]
+    hard_watermark: Option<u64>,
+}
+
+impl Watermarks {
+    /// Disk is reported as low on space below this free space
+    pub(crate) fn soft(&self) -> Option<u64> {
+        self.soft_watermark
+    }
+
+    /// New writes to the disk are refused below this free space
+    pub(crate) fn hard(&self) -> Option<u64> {
+        self.hard_watermark
+    }
+}
+
+impl Validatable for Watermarks {
+    fn validate(&self) -> Result<(), String> {
+        match (self.soft_watermark, self.hard_watermark) {
+            (Some(soft), Some(hard)) if soft < hard => {
+                let msg = "field 'soft_watermark' for 'free space config' is less than \
+                           'hard_watermark'"
+                    .to_string();
+                error!("{}", msg);
+                Err(msg)
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Contains free space watermarks of the local disks, common watermarks are applied to the
+/// disks without their own ones.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize, Clone, new)]
+pub struct FreeSpaceConfig {
+    #[serde(flatten)]
+    common: Watermarks,
+    #[serde(default)]
+    disks: BTreeMap<String, Watermarks>,
+}
+
+impl FreeSpaceConfig {
+    /// Watermarks of the disk, unset ones are taken from the common watermarks
+    pub(crate) fn watermarks(&self, disk_name: &str) -> Watermarks {
+        let disk = self.disks.get(disk_name).copied().unwrap_or_default();
+        Watermarks {
+            soft_watermark: disk.soft_watermark.or(self.common.soft_watermark),
+            hard_watermark: disk.hard_watermark.or(self.common.hard_watermark),
+        }
+    }
+}
+
+impl Validatable for FreeSpaceConfig {
+    fn validate(&self) -> Result<(), String> {
+        self.common.validate()?;
+        for name in self.disks.keys() {
+            self.watermarks(name).validate()?;
+        }
+        Ok(())
+    }
+}
+
+/// Contains settings of the compaction, which merges closed partitions of the vdisk and removes
+/// superseded records.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, new)]
+pub struct CompactionConfig {
+    #[serde(default)]
+    interval: Option<String>,
+    #[serde(default = "CompactionConfig::default_window")]
+    window: String,
+    #[serde(default)]
+    bandwidth: Option<u64>,
+    #[serde(default = "CompactionConfig::default_tombstone_grace_period")]
+    tombstone_grace_period: String,
+}
+
+impl CompactionConfig {
+    fn default_window() -> String {
+        "7d".to_string()
+    }
+
+    fn default_tombstone_grace_period() -> String {
+        "10d".to_string()
+    }
+
+    /// Interval of the scheduled compaction, compaction runs only by request if not set
+    pub(crate) fn interval(&self) -> Option<Duration> {
+        self.interval.as_ref().map(|interval| {
+            interval
+                .parse::<HumanDuration>()
+                .expect("parse humantime duration")
+                .into()
+        })
+    }
+
+    /// Partitions with start timestamps in the same window are merged together
+    pub(crate) fn window(&self) -> Duration {
+        self.window
+            .parse::<HumanDuration>()
+            .expect("parse humantime duration")
+            .into()
+    }
+
+    /// Limit of the compaction writes in bytes per second, unlimited if not set
+    pub(crate) fn bandwidth(&self) -> Option<u64> {
+        self.bandwidth
+    }
+
+    /// Tombstones younger than this period are kept, so repair delivers them to the replicas,
+    /// which still have the deleted records
+    pub(crate) fn tombstone_grace_period(&self) -> Duration {
+        self.tombstone_grace_period
+            .parse::<HumanDuration>()
+            .expect("parse humantime duration")
+            .into()
+    }
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        Self {
+            interval: None,
+            window: Self::default_window(),
+            bandwidth: None,
+            tombstone_grace_period: Self::default_tombstone_grace_period(),
+        }
+    }
+}
+
+impl Validatable for CompactionConfig {
+    fn validate(&self) -> Result<(), String> {
+        let interval_is_valid = self
+            .interval
+            .as_ref()
+            .map_or(true, |interval| interval.parse::<HumanDuration>().is_ok());
+        let window = self.window.parse::<HumanDuration>();
+        let msg = if !interval_is_valid {
+            "field 'interval' for 'compaction config' is not valid"
+        } else if window.map_or(true, |window| Duration::from(window).as_secs() == 0) {
+            "field 'window' for 'compaction config' is not valid, it must be at least 1s"
+        } else if self.bandwidth == Some(0) {
+            "field 'bandwidth' for 'compaction config' must be greater than 0"
+        } else if self
+            .tombstone_grace_period
+            .parse::<HumanDuration>()
+            .is_err()
+        {
+            "field 'tombstone_grace_period' for 'compaction config' is not valid"
+        } else {
+            return Ok(());
+        };
+        error!("{}", msg);
+        Err(msg.to_string())
+    }
+}
+
+/// Contains credentials and role of the api user.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, new)]
+pub struct UserConfig {
+    name: String,
+    role: String,
+    #[serde(default)]
+    token: Option<String>,
+    #[serde(default)]
+    password: Option<String>,
+}
+
+impl UserConfig {
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn role(&self) -> Role {
+        self.role.parse().expect("parse role")
+    }
+
+    /// Static token, sent by the client as bearer
+    pub(crate) fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Password, sent by the client with the name in basic credentials
+    pub(crate) fn password(&self) -> Option<&str> {
+        self.password.as_deref()
+    }
+}
+
+impl Validatable for UserConfig {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || self.name.contains(':') {
+            let msg = format!("user name '{}' is empty or contains ':'", self.name);
+            error!("{}", msg);
+            return Err(msg);
+        }
+        if let Err(e) = self.role.parse::<Role>() {
+            let msg = format!("field 'role' of user '{}' is not valid: {}", self.name, e);
+            error!("{}", msg);
+            return Err(msg);
+        }
+        if self.token.is_none() && self.password.is_none() {
+            let msg = format!("token or password of user '{}' must be set", self.name);
+            error!("{}", msg);
+            return Err(msg);
+        }
+        let printable = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_graphic());
+        if !self.token.as_deref().map_or(true, printable) {
+            let msg = format!("token of user '{}' is not printable ascii", self.name);
+            error!("{}", msg);
+            return Err(msg);
+        }
+        Ok(())
+    }
+}
+
+/// Role of the api user, each role includes permissions of the previous ones.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Role {
+    /// Reading of records and node state
+    Read,
+    /// Writing and deletion of records
+    Write,
+    /// Management of the node, e.g. removal of partitions
+    Admin,
+}
+
+impl FromStr for Role {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "read" => Ok(Role::Read),
+            "write" => Ok(Role::Write),
+            "admin" => Ok(Role::Admin),
+            value => Err(format!("unknown role: {}", value)),
+        }
+    }
+}
+
+impl Display for Role {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        let name = match self {
+            Role::Read => "read",
+            Role::Write => "write",
+            Role::Admin => "admin",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Algorithm of the records compression in pearl backend.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CompressionAlgorithm {
+    /// Better compression ratio
+    Zstd,
+    /// Faster compression and decompression
+    Lz4,
+}
+
+impl FromStr for CompressionAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "zstd" => Ok(CompressionAlgorithm::Zstd),
+            "lz4" => Ok(CompressionAlgorithm::Lz4),
+            value => Err(format!("unknown compression algorithm: {}", value)),
+        }
+    }
+}
+
+/// Contains settings of the records compression in pearl backend.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, new)]
+pub struct CompressionConfig {
+    algorithm: String,
+    #[serde(default = "CompressionConfig::default_min_size")]
+    min_size: usize,
+    #[serde(default)]
+    level: Option<i32>,
+}
+
+impl CompressionConfig {
+    const ZSTD_LEVELS: std::ops::RangeInclusive<i32> = 1..=22;
+
+    fn default_min_size() -> usize {
+        1024
+    }
+
+    pub(crate) fn algorithm(&self) -> CompressionAlgorithm {
+        self.algorithm.parse().expect("parse compression algorithm")
+    }
+
+    /// Records smaller than this size in bytes are stored uncompressed
+    pub(crate) fn min_size(&self) -> usize {
+        self.min_size
+    }
+
+    /// Level of zstd compression, default level of zstd if not set
+    pub(crate) fn level(&self) -> i32 {
+        self.level.unwrap_or(zstd::DEFAULT_COMPRESSION_LEVEL)
+    }
+}
+
+impl Validatable for CompressionConfig {
+    fn validate(&self) -> Result<(), String> {
+        let algorithm = self.algorithm.parse::<CompressionAlgorithm>();
+        let msg = match (algorithm, self.level) {
+            (Err(e), _) => e,
+            (Ok(CompressionAlgorithm::Lz4), Some(_)) => {
+                "field 'level' for 'compression config' is supported only by zstd".to_string()
+            }
+            (Ok(CompressionAlgorithm::Zstd), Some(level))
+                if !Self::ZSTD_LEVELS.contains(&level) =>
+            {
+                format!(
+                    "field 'level' for 'compression config' must be in range {:?}",
+                    Self::ZSTD_LEVELS
+                )
+            }
+            _ => return Ok(()),
+        };
+        error!("{}", msg);
+        Err(msg)
+    }
+}
+
+/// Contains path to the key file of the records encryption in pearl backend.
+#[derive(Debug, PartialEq, Serialize, Deserialize, Clone, new)]
+pub struct EncryptionConfig {
+    // yaml with `keys` map of key ids to AES-256 keys in base64 and `current` key id, which is
+    // used for new records
+    key_file: String,
+}
+
+// content of the key file, keys are encoded in base64
+#[derive(Deserialize)]
+struct EncryptionKeyFile {
+    current: u32,
+    keys: BTreeMap<u32, String>,
+}
+
+impl EncryptionConfig {
+    const KEY_LEN: usize = 32;
+
+    /// Reads current key id and all keys from the key file
+    pub(crate) fn keys(&self) -> Result<(u32, BTreeMap<u32, Vec<u8>>), String> {
+        let content = std::fs::read_to_string(&self.key_file)
+            .map_err(|e| format!("can't read {}: {}", self.key_file, e))?;
+        let file: EncryptionKeyFile = serde_yaml::from_str(&content)
+            .map_err(|e| format!("can't parse {}: {}", self.key_file, e))?;
+        let keys = file
+            .keys
+            .into_iter()
+            .map(|(id, key)| match base64::decode(key.trim()) {
+                Ok(key) if key.len() == Self::KEY_LEN => Ok((id, key)),
+                _ => Err(format!(
+                    "key {} in {} must be {} bytes encoded in base64",
+                    id,
+                    self.key_file,
+                    Self::KEY_LEN
+                )),
+            })
+            .collect::<Result<BTreeMap<_, _>, _>>()?;
+        if keys.contains_key(&file.current) {
+            Ok((file.current, keys))
+        } else {
+            Err(format!(
+                "current key {} is missing in {}",
+                file.current, self.key_file
+            ))
+        }
+    }
+}
+
+impl Validatable for EncryptionConfig {
+    fn validate(&self) -> Result<(), String> {
+        self.keys().map(|_| ()).map_err(|e| {
+            let msg = format!(
+                "field 'key_file' for 'encryption config' is not valid: {}",
+                e
+            );
+            error!("{}", msg);
+            msg
+        })
+    }
+}
+
 /// Contains params for detailed pearl configuration in pearl backend.
 #[derive(Debug, PartialEq, Serialize, Deserialize, Clone, new)]
 pub struct Pearl {
@@ -159,6 +703,12 @@ pub struct Pearl {
     hash_chars_count: u32,
     #[serde(default = "Pearl::default_enable_aio")]
     enable_aio: bool,
+    #[serde(default)]
+    index_memory_limit: Option<u64>,
+    #[serde(default)]
+    compression: Option<CompressionConfig>,
+    #[serde(default)]
+    encryption: Option<EncryptionConfig>,
 }
 
 impl Pearl {
@@ -245,6 +795,22 @@ impl Pearl {
         self.enable_aio
     }
 
+    /// Max memory in bytes occupied by indices of loaded holders, all holders are kept loaded
+    /// if it's not set
+    pub(crate) fn index_memory_limit(&self) -> Option<u64> {
+        self.index_memory_limit
+    }
+
+    /// Settings of the records compression, records are stored uncompressed if not set
+    pub(crate) fn compression(&self) -> Option<&CompressionConfig> {
+        self.compression.as_ref()
+    }
+
+    /// Settings of the records encryption, records are stored unencrypted if not set
+    pub(crate) fn encryption(&self) -> Option<&EncryptionConfig> {
+        self.encryption.as_ref()
+    }
+
     fn check_unset(&self) -> Result<(), String> {
         if self.blob_file_name_prefix == PLACEHOLDER || self.fail_retry_timeout == PLACEHOLDER {
             let msg = "some of the fields present, but empty".to_string();
@@ -315,7 +881,13 @@ impl Validatable for Pearl {
             error!("{}", msg);
             Err(msg)
         } else {
-            self.settings.validate()
+            self.settings.validate()?;
+            self.compression
+                .as_ref()
+                .map_or(Ok(()), Validatable::validate)?;
+            self.encryption
+                .as_ref()
+                .map_or(Ok(()), Validatable::validate)
         }
     }
 }
@@ -327,6 +899,14 @@ pub(crate) enum BackendType {
     Pearl,
 }
 
+/// Count of replicas which must answer GET request in quorum cluster.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub(crate) enum ReadConsistency {
+    One,
+    Quorum,
+    All,
+}
+
 /// Node configuration struct, stored in node.yaml.
 #[derive(Clone, Debug, PartialEq, Deserialize, Serialize, new)]
 pub struct Node {
@@ -337,11 +917,33 @@ pub struct Node {
     check_interval: String,
     #[serde(default = "NodeConfig::default_count_interval")]
     count_interval: String,
+    #[serde(default = "NodeConfig::default_handoff_interval")]
+    handoff_interval: String,
+    #[serde(default)]
+    repair_interval: Option<String>,
+    #[serde(default)]
+    scrub_interval: Option<String>,
+    #[serde(default)]
+    retention_period: Option<String>,
+    #[serde(default = "NodeConfig::default_retention_check_interval")]
+    retention_check_interval: String,
+    #[serde(default)]
+    migration_bandwidth: Option<u64>,
     cluster_policy: String,
+    #[serde(default = "NodeConfig::default_read_consistency")]
+    read_consistency: String,
 
     backend_type: String,
     pearl: Option<Pearl>,
     metrics: Option<MetricsConfig>,
+    #[serde(default)]
+    tls: Option<TlsConfig>,
+    #[serde(default)]
+    authentication: Option<AuthConfig>,
+    #[serde(default)]
+    free_space: Option<FreeSpaceConfig>,
+    #[serde(default)]
+    compaction: Option<CompactionConfig>,
 
     #[serde(skip)]
     bind_ref: RefCell<String>,
@@ -353,9 +955,23 @@ pub struct Node {
     open_blobs_hard_limit: Option<usize>,
     #[serde(default = "Node::default_init_par_degree")]
     init_par_degree: usize,
+    #[serde(default = "NodeConfig::default_stream_chunk_size")]
+    stream_chunk_size: usize,
 }
 
 impl NodeConfig {
+    /// Fields, which are applied to the running node on config reload.
+    const RELOADABLE_FIELDS: [&'static str; 8] = [
+        "log_config",
+        "operation_timeout",
+        "check_interval",
+        "count_interval",
+        "cleanup_interval",
+        "open_blobs_soft_limit",
+        "open_blobs_hard_limit",
+        "free_space",
+    ];
+
     /// Get node name.
     pub fn name(&self) -> &str {
         &self.name
@@ -373,6 +989,26 @@ impl NodeConfig {
         self.metrics.as_ref().expect("metrics config")
     }
 
+    /// Get TLS config, servers and connections to other nodes are plaintext if not set.
+    pub fn tls(&self) -> Option<&TlsConfig> {
+        self.tls.as_ref()
+    }
+
+    /// Get users of the api, access isn't restricted if not set.
+    pub fn authentication(&self) -> Option<&AuthConfig> {
+        self.authentication.as_ref()
+    }
+
+    /// Get free space watermarks of the disks, writes aren't limited if not set.
+    pub(crate) fn free_space(&self) -> FreeSpaceConfig {
+        self.free_space.clone().unwrap_or_default()
+    }
+
+    /// Get compaction settings, compaction with default settings runs only by request if not set.
+    pub(crate) fn compaction(&self) -> CompactionConfig {
+        self.compaction.clone().unwrap_or_default()
+    }
+
     /// Get log config file path.
     pub fn log_config(&self) -> &str {
         &self.log_config
@@ -413,6 +1049,64 @@ impl NodeConfig {
             .into()
     }
 
+    fn default_handoff_interval() -> String {
+        "1m".to_string()
+    }
+
+    pub(crate) fn handoff_interval(&self) -> Duration {
+        self.handoff_interval
+            .parse::<HumanDuration>()
+            .expect("parse humantime duration")
+            .into()
+    }
+
+    /// Interval of the scheduled replicas repair, disabled if not set
+    pub(crate) fn repair_interval(&self) -> Option<Duration> {
+        self.repair_interval.as_ref().map(|interval| {
+            interval
+                .parse::<HumanDuration>()
+                .expect("parse humantime duration")
+                .into()
+        })
+    }
+
+    /// Interval of the scheduled checksums verification of all records, disabled if not set
+    pub(crate) fn scrub_interval(&self) -> Option<Duration> {
+        self.scrub_interval.as_ref().map(|interval| {
+            interval
+                .parse::<HumanDuration>()
+                .expect("parse humantime duration")
+                .into()
+        })
+    }
+
+    /// Period after which records expire since their timestamp, disabled if not set
+    pub(crate) fn retention_period(&self) -> Option<Duration> {
+        self.retention_period.as_ref().map(|period| {
+            period
+                .parse::<HumanDuration>()
+                .expect("parse humantime duration")
+                .into()
+        })
+    }
+
+    fn default_retention_check_interval() -> String {
+        "1h".to_string()
+    }
+
+    /// Interval of the search for fully expired partitions
+    pub(crate) fn retention_check_interval(&self) -> Duration {
+        self.retention_check_interval
+            .parse::<HumanDuration>()
+            .expect("parse humantime duration")
+            .into()
+    }
+
+    /// Limit of the vdisk migration traffic in bytes per second, unlimited if not set
+    pub(crate) fn migration_bandwidth(&self) -> Option<u64> {
+        self.migration_bandwidth
+    }
+
     /// Get reference to collection of disks [`DiskPath`]
     pub fn disks(&self) -> Ref<Vec<DiskPath>> {
         self.disks_ref.borrow()
@@ -431,6 +1125,68 @@ impl NodeConfig {
         }
     }
 
+    fn default_stream_chunk_size() -> usize {
+        1024 * 1024
+    }
+
+    /// Max size of the chunk in bytes, streamed blobs are stored in chunks of this size
+    pub(crate) fn stream_chunk_size(&self) -> usize {
+        self.stream_chunk_size
+    }
+
+    fn default_read_consistency() -> String {
+        "one".to_string()
+    }
+
+    pub(crate) fn read_consistency(&self) -> ReadConsistency {
+        self.read_consistency_result()
+            .expect("parse read consistency")
+    }
+
+    pub(crate) fn read_consistency_result(&self) -> Result<ReadConsistency, String> {
+        match self.read_consistency.as_str() {
+            "one" => Ok(ReadConsistency::One),
+            "quorum" => Ok(ReadConsistency::Quorum),
+            "all" => Ok(ReadConsistency::All),
+            value => Err(format!("unknown read consistency: {}", value)),
+        }
+    }
+
+    /// Reads and validates node config from file, e.g. to reload it.
+    /// # Errors
+    /// Fails if file can't be read or config is invalid
+    pub fn from_file(filename: &str) -> Result<Self, String> {
+        YamlBobConfig::get(filename)
+    }
+
+    /// Checks that the reloaded config changes only fields, which can be applied without
+    /// restart, returns names of the changed fields.
+    /// # Errors
+    /// Fails with the list of the fields, which can't be changed without restart
+    pub(crate) fn check_reload(&self, reloaded: &Self) -> Result<Vec<String>, String> {
+        let to_mapping = |config: &Self| match serde_yaml::to_value(config) {
+            Ok(serde_yaml::Value::Mapping(mapping)) => Ok(mapping),
+            Ok(_) => Err("node config isn't a mapping".to_string()),
+            Err(e) => Err(format!("can't serialize node config: {}", e)),
+        };
+        let (current, reloaded) = (to_mapping(self)?, to_mapping(reloaded)?);
+        let (reloadable, fixed): (Vec<_>, Vec<_>) = current
+            .iter()
+            .filter(|(field, value)| reloaded.get(field) != Some(value))
+            .filter_map(|(field, _)| field.as_str().map(ToOwned::to_owned))
+            .partition(|field| Self::RELOADABLE_FIELDS.contains(&field.as_str()));
+        if fixed.is_empty() {
+            Ok(reloadable)
+        } else {
+            let msg = format!(
+                "fields {} of node config can't be changed without restart",
+                fixed.join(", ")
+            );
+            error!("{}", msg);
+            Err(msg)
+        }
+    }
+
     pub(crate) fn prepare(&self, node: &ClusterNode) -> Result<(), String> {
         self.bind_ref.replace(node.address().to_owned());
 
@@ -528,6 +1284,10 @@ impl NodeConfig {
 impl Validatable for NodeConfig {
     fn validate(&self) -> Result<(), String> {
         self.check_unset()?;
+        self.read_consistency_result().map_err(|e| {
+            error!("{}", e);
+            e
+        })?;
         if self.backend_result().is_ok() && self.backend_type() == BackendType::Pearl {
             if let Some(pearl) = &self.pearl {
                 pearl.validate()?;
@@ -549,6 +1309,42 @@ impl Validatable for NodeConfig {
             error!("{}, {}", msg, e);
             msg
         })?;
+        self.handoff_interval
+            .parse::<HumanDuration>()
+            .map_err(|e| {
+                let msg = "field \'handoff_interval\' for \'config\' is not valid".to_string();
+                error!("{}, {}", msg, e);
+                msg
+            })?;
+        if let Some(repair_interval) = &self.repair_interval {
+            repair_interval.parse::<HumanDuration>().map_err(|e| {
+                let msg = "field \'repair_interval\' for \'config\' is not valid".to_string();
+                error!("{}, {}", msg, e);
+                msg
+            })?;
+        }
+        if let Some(scrub_interval) = &self.scrub_interval {
+            scrub_interval.parse::<HumanDuration>().map_err(|e| {
+                let msg = "field \'scrub_interval\' for \'config\' is not valid".to_string();
+                error!("{}, {}", msg, e);
+                msg
+            })?;
+        }
+        if let Some(retention_period) = &self.retention_period {
+            retention_period.parse::<HumanDuration>().map_err(|e| {
+                let msg = "field \'retention_period\' for \'config\' is not valid".to_string();
+                error!("{}, {}", msg, e);
+                msg
+            })?;
+        }
+        self.retention_check_interval
+            .parse::<HumanDuration>()
+            .map_err(|e| {
+                let msg =
+                    "field \'retention_check_interval\' for \'config\' is not valid".to_string();
+                error!("{}, {}", msg, e);
+                msg
+            })?;
         if self.name.is_empty() {
             let msg = "field \'name\' for \'config\' is empty".to_string();
             error!("{}", msg);
@@ -565,10 +1361,30 @@ impl Validatable for NodeConfig {
             let msg = "field \'quorum\' for \'config\' must be greater than 0".to_string();
             error!("{}", msg);
             Err(msg)
+        } else if self.migration_bandwidth == Some(0) {
+            let msg =
+                "field \'migration_bandwidth\' for \'config\' must be greater than 0".to_string();
+            error!("{}", msg);
+            Err(msg)
+        } else if self.stream_chunk_size == 0 {
+            let msg =
+                "field \'stream_chunk_size\' for \'config\' must be greater than 0".to_string();
+            error!("{}", msg);
+            Err(msg)
         } else {
             self.metrics
                 .as_ref()
-                .map_or(Ok(()), |metrics| metrics.validate())
+                .map_or(Ok(()), |metrics| metrics.validate())?;
+            self.tls.as_ref().map_or(Ok(()), |tls| tls.validate())?;
+            self.authentication
+                .as_ref()
+                .map_or(Ok(()), |authentication| authentication.validate())?;
+            self.free_space
+                .as_ref()
+                .map_or(Ok(()), |free_space| free_space.validate())?;
+            self.compaction
+                .as_ref()
+                .map_or(Ok(()), |compaction| compaction.validate())
         }
     }
 }
@@ -584,16 +1400,28 @@ pub(crate) mod tests {
             operation_timeout: "3sec".to_string(),
             check_interval: "3sec".to_string(),
             cluster_policy: "quorum".to_string(),
+            read_consistency: "one".to_string(),
             backend_type: "in_memory".to_string(),
             pearl: None,
             metrics: None,
+            tls: None,
+            authentication: None,
+            free_space: None,
+            compaction: None,
             bind_ref: RefCell::default(),
             disks_ref: RefCell::default(),
             cleanup_interval: "1d".to_string(),
             open_blobs_soft_limit: None,
             open_blobs_hard_limit: None,
             init_par_degree: 1,
+            stream_chunk_size: 1024 * 1024,
             count_interval: "10000ms".to_string(),
+            handoff_interval: "1m".to_string(),
+            repair_interval: None,
+            scrub_interval: None,
+            retention_period: None,
+            retention_check_interval: "1h".to_string(),
+            migration_bandwidth: None,
         }
     }
 }
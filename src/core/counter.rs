@@ -1,28 +1,39 @@
 use super::prelude::*;
+use std::sync::RwLock as SyncRwLock;
 use std::time::Duration;
 
 pub(crate) struct Counter {
-    count_interval: Duration,
+    count_interval: SyncRwLock<Duration>,
 }
 
 impl Counter {
     pub(crate) fn new(count_interval: Duration) -> Self {
-        Self { count_interval }
+        Self {
+            count_interval: SyncRwLock::new(count_interval),
+        }
+    }
+
+    /// Changes period of the running task, it's applied after the current one ends
+    pub(crate) fn update(&self, count_interval: Duration) {
+        *self.count_interval.write().expect("counter lock") = count_interval;
+    }
+
+    fn period(&self) -> Duration {
+        *self.count_interval.read().expect("counter lock")
     }
 
-    pub(crate) fn spawn_task(&self, backend: Arc<Backend>) {
-        tokio::spawn(Self::task(backend, self.count_interval));
+    pub(crate) fn spawn_task(self: Arc<Self>, backend: Arc<Backend>) -> JoinHandle<()> {
+        tokio::spawn(self.task(backend))
     }
 
-    async fn task(backend: Arc<Backend>, t: Duration) {
-        let mut interval = interval(t);
+    async fn task(self: Arc<Self>, backend: Arc<Backend>) {
         loop {
-            interval.tick().await;
             let (blobs_cnt, aliens_cnt) = backend.blobs_count().await;
             gauge!(BLOBS_COUNT, blobs_cnt as i64);
             gauge!(ALIEN_BLOBS_COUNT, aliens_cnt as i64);
             let index_memory = backend.index_memory().await;
             gauge!(INDEX_MEMORY, index_memory as i64);
+            sleep(self.period()).await;
         }
     }
 }
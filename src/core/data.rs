@@ -1,9 +1,171 @@
 use crate::mapper::NodesMap;
 
 use super::prelude::*;
+use crate::grpc::Checksum;
+use crc::crc32;
+use std::collections::BTreeMap;
 use std::hash::Hash;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Key of the blob, byte string of the length set by `key_length` in cluster config.
+/// Numeric keys are stored as big endian bytes and all keys are padded with leading zeros to the
+/// cluster key length, so numeric key and its padded bytes refer to the same blob.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct BobKey {
+    len: u8,
+    bytes: [u8; BobKey::MAX_LEN],
+}
+
+impl BobKey {
+    /// Max supported key length in bytes
+    pub const MAX_LEN: usize = 64;
+    pub(crate) const U64_LEN: usize = 8;
+
+    /// Creates key from bytes, returns `None` if key is longer than [`BobKey::MAX_LEN`]
+    #[must_use]
+    pub fn from_bytes(key: &[u8]) -> Option<Self> {
+        if key.len() > Self::MAX_LEN {
+            return None;
+        }
+        let mut bytes = [0; Self::MAX_LEN];
+        bytes[..key.len()].copy_from_slice(key);
+        Some(Self {
+            len: key.len() as u8,
+            bytes,
+        })
+    }
+
+    /// Pads key with leading zeros or strips them to fit into `len` bytes,
+    /// returns `None` if significant bytes of the key don't fit.
+    pub(crate) fn resize(&self, len: usize) -> Option<Self> {
+        let key = self.as_ref();
+        if key.len() > len {
+            let (zeros, rest) = key.split_at(key.len() - len);
+            if zeros.iter().any(|&b| b != 0) {
+                return None;
+            }
+            Self::from_bytes(rest)
+        } else {
+            let mut padded = vec![0; len - key.len()];
+            padded.extend_from_slice(key);
+            Self::from_bytes(&padded)
+        }
+    }
+
+    /// Key length in bytes
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns true for zero length key
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Numeric value of the key, if it fits into `u64`
+    pub(crate) fn to_u64(&self) -> Option<u64> {
+        let key = self.resize(Self::U64_LEN)?;
+        let bytes = key.as_ref().try_into().expect("key of u64 length");
+        Some(u64::from_be_bytes(bytes))
+    }
+}
+
+impl AsRef<[u8]> for BobKey {
+    fn as_ref(&self) -> &[u8] {
+        &self.bytes[..self.len()]
+    }
+}
+
+impl From<u64> for BobKey {
+    fn from(key: u64) -> Self {
+        Self::from_bytes(&key.to_be_bytes()).expect("u64 is shorter than max key length")
+    }
+}
+
+impl TryFrom<BlobKey> for BobKey {
+    type Error = Error;
+
+    /// Bytes are used if set, otherwise numeric key. Numeric key set together with bytes must
+    /// be the same key.
+    fn try_from(key: BlobKey) -> Result<Self, Error> {
+        if key.bytes_key.is_empty() {
+            return Ok(Self::from(key.key));
+        }
+        let bytes_key = Self::from_bytes(&key.bytes_key).ok_or_else(|| {
+            Error::invalid_key(format!("key is longer than {} bytes", Self::MAX_LEN))
+        })?;
+        if key.key != 0 && bytes_key.to_u64() != Some(key.key) {
+            return Err(Error::invalid_key(format!(
+                "numeric key {} doesn't match key {}",
+                key.key, bytes_key
+            )));
+        }
+        Ok(bytes_key)
+    }
+}
+
+impl From<BobKey> for BlobKey {
+    /// Numeric key is set if the key fits into it, so nodes and clients which know only numeric
+    /// keys still work with clusters with key length of 8 bytes. Longer keys have no numeric
+    /// value, they are sent only as bytes, and clusters with such keys reject requests with
+    /// numeric keys, so clients which don't know bytes keys get an error instead of key 0.
+    fn from(key: BobKey) -> Self {
+        BlobKey {
+            key: key.to_u64().unwrap_or_default(),
+            bytes_key: key.as_ref().to_vec(),
+        }
+    }
+}
 
-pub type BobKey = u64;
+/// Keys up to 8 bytes are displayed as numbers, longer keys as hex with `0x` prefix
+impl Display for BobKey {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        if self.len() <= Self::U64_LEN {
+            write!(f, "{}", self.to_u64().expect("key of u64 length"))
+        } else {
+            write!(f, "0x")?;
+            for byte in self.as_ref() {
+                write!(f, "{:02x}", byte)?;
+            }
+            Ok(())
+        }
+    }
+}
+
+impl Debug for BobKey {
+    fn fmt(&self, f: &mut Formatter) -> FmtResult {
+        Display::fmt(self, f)
+    }
+}
+
+impl FromStr for BobKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(hex) = s.strip_prefix("0x") {
+            // checked before slicing, so pairs never split multibyte chars
+            if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(format!("invalid hex key: {}", s));
+            }
+            if hex.len() % 2 != 0 {
+                return Err(format!("odd length of hex key: {}", s));
+            }
+            let bytes = (0..hex.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&hex[i..i + 2], 16))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|e| format!("invalid hex key {}: {}", s, e))?;
+            Self::from_bytes(&bytes).ok_or_else(|| format!("key is too long: {}", s))
+        } else {
+            s.parse::<u64>()
+                .map(Self::from)
+                .map_err(|e| format!("invalid key {}: {}", s, e))
+        }
+    }
+}
 
 pub type VDiskID = u32;
 
@@ -13,6 +175,7 @@ impl PutOptions {
             remote_nodes: vec![],
             force_node: true,
             overwrite: false,
+            ttl: 0,
         }
     }
 
@@ -21,6 +184,25 @@ impl PutOptions {
             remote_nodes,
             force_node: true,
             overwrite: false,
+            ttl: 0,
+        }
+    }
+}
+
+impl DeleteOptions {
+    pub(crate) fn new_local() -> Self {
+        DeleteOptions {
+            remote_nodes: vec![],
+            force_node: true,
+        }
+    }
+}
+
+impl From<PutOptions> for DeleteOptions {
+    fn from(options: PutOptions) -> Self {
+        DeleteOptions {
+            remote_nodes: options.remote_nodes,
+            force_node: options.force_node,
         }
     }
 }
@@ -66,11 +248,106 @@ impl From<i32> for GetSource {
 pub(crate) struct BobData {
     inner: Vec<u8>,
     meta: BobMeta,
+    tombstone: bool,
+    manifest: bool,
+    checksum: Option<u32>,
 }
 
 impl BobData {
     pub(crate) fn new(inner: Vec<u8>, meta: BobMeta) -> Self {
-        BobData { inner, meta }
+        BobData {
+            inner,
+            meta,
+            tombstone: false,
+            manifest: false,
+            checksum: None,
+        }
+    }
+
+    /// Creates record which marks key as deleted at `meta.timestamp()`
+    pub(crate) fn tombstone(meta: BobMeta) -> Self {
+        BobData {
+            inner: vec![],
+            meta,
+            tombstone: true,
+            manifest: false,
+            checksum: None,
+        }
+    }
+
+    /// Creates record with the list of chunks of the large blob, see `chunks::Manifest`
+    pub(crate) fn manifest(inner: Vec<u8>, meta: BobMeta) -> Self {
+        BobData {
+            inner,
+            meta,
+            tombstone: false,
+            manifest: true,
+            checksum: None,
+        }
+    }
+
+    /// Returns the most recent version, tombstone wins over data with the same timestamp
+    pub(crate) fn most_recent(records: impl IntoIterator<Item = BobData>) -> Option<BobData> {
+        records
+            .into_iter()
+            .max_by_key(|x| (x.meta().timestamp(), x.is_tombstone()))
+    }
+
+    /// Makes data expire after `ttl` since its timestamp, earlier expiration is kept.
+    /// Tombstones never expire, so they keep hiding older versions.
+    pub(crate) fn expire_after(mut self, ttl: Option<Duration>) -> Self {
+        if let Some(ttl) = ttl {
+            if !self.tombstone {
+                let expires_at = self.meta.timestamp().saturating_add(ttl.as_secs());
+                let expires_at = self
+                    .meta
+                    .expires_at()
+                    .map_or(expires_at, |t| t.min(expires_at));
+                self.meta.set_expires_at(Some(expires_at));
+            }
+        }
+        self
+    }
+
+    /// Sets checksum of the data, which was computed by the writer
+    pub(crate) fn with_checksum(mut self, checksum: u32) -> Self {
+        self.checksum = Some(checksum);
+        self
+    }
+
+    /// Sets checksum computed over the data
+    pub(crate) fn checksummed(self) -> Self {
+        let checksum = self.compute_checksum();
+        self.with_checksum(checksum)
+    }
+
+    /// CRC32C of the data, `None` for tombstones and records written without checksum
+    pub(crate) fn checksum(&self) -> Option<u32> {
+        self.checksum
+    }
+
+    fn compute_checksum(&self) -> u32 {
+        crc32::checksum_castagnoli(&self.inner)
+    }
+
+    /// Returns error if data doesn't match stored checksum, records without checksum are
+    /// considered valid
+    pub(crate) fn verify_checksum(&self, key: BobKey) -> Result<(), Error> {
+        match self.checksum {
+            Some(checksum) if checksum != self.compute_checksum() => {
+                Err(Error::data_corrupted(key))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub(crate) fn is_tombstone(&self) -> bool {
+        self.tombstone
+    }
+
+    pub(crate) fn is_manifest(&self) -> bool {
+        self.manifest
     }
 
     pub(crate) fn inner(&self) -> &[u8] {
@@ -91,17 +368,111 @@ impl Debug for BobData {
         f.debug_struct("BobData")
             .field("len", &self.inner.len())
             .field("meta", self.meta())
+            .field("tombstone", &self.tombstone)
+            .field("manifest", &self.manifest)
+            .field("checksum", &self.checksum)
             .finish()
     }
 }
 
+/// Version of the blob without data
 #[derive(Debug, Clone)]
+pub(crate) struct BobHead {
+    meta: BobMeta,
+    size: u64,
+    tombstone: bool,
+    manifest: bool,
+}
+
+impl BobHead {
+    pub(crate) fn new(meta: BobMeta, size: u64, tombstone: bool, manifest: bool) -> Self {
+        Self {
+            meta,
+            size,
+            tombstone,
+            manifest,
+        }
+    }
+
+    /// Ordering is the same as in [`BobData::most_recent`]
+    pub(crate) fn is_newer_than(&self, other: &Self) -> bool {
+        (self.meta.timestamp(), self.tombstone) > (other.meta.timestamp(), other.tombstone)
+    }
+
+    pub(crate) fn meta(&self) -> &BobMeta {
+        &self.meta
+    }
+
+    /// Size of the data, size of the large blob is stored in its manifest, so head of the
+    /// manifest has the size of the whole blob once it's read by grinder
+    pub(crate) fn size(&self) -> u64 {
+        self.size
+    }
+
+    pub(crate) fn is_tombstone(&self) -> bool {
+        self.tombstone
+    }
+
+    pub(crate) fn is_manifest(&self) -> bool {
+        self.manifest
+    }
+}
+
+impl From<&BobData> for BobHead {
+    fn from(data: &BobData) -> Self {
+        Self::new(
+            data.meta().clone(),
+            data.inner().len() as u64,
+            data.is_tombstone(),
+            data.is_manifest(),
+        )
+    }
+}
+
+/// Timestamp of the version and user-defined headers, e.g. content type or original filename
+#[derive(Debug, Clone, PartialEq)]
 pub(crate) struct BobMeta {
     timestamp: u64,
+    headers: BTreeMap<String, String>,
+    expires_at: Option<u64>,
 }
 impl BobMeta {
+    /// Max total length of header names and values in bytes
+    pub(crate) const MAX_HEADERS_LEN: usize = 16 * 1024;
+
     pub(crate) fn new(timestamp: u64) -> Self {
-        Self { timestamp }
+        Self {
+            timestamp,
+            headers: BTreeMap::new(),
+            expires_at: None,
+        }
+    }
+
+    pub(crate) fn with_headers(timestamp: u64, headers: BTreeMap<String, String>) -> Self {
+        Self {
+            timestamp,
+            headers,
+            expires_at: None,
+        }
+    }
+
+    /// Timestamp after which blob is hidden, `None` if blob doesn't expire
+    pub(crate) fn expires_at(&self) -> Option<u64> {
+        self.expires_at
+    }
+
+    pub(crate) fn set_expires_at(&mut self, expires_at: Option<u64>) {
+        self.expires_at = expires_at;
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        self.expires_at.map_or(false, |expires_at| {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("current time is before unix epoch")
+                .as_secs();
+            expires_at <= now
+        })
     }
 
     #[inline]
@@ -109,8 +480,71 @@ impl BobMeta {
         self.timestamp
     }
 
+    pub(crate) fn headers(&self) -> &BTreeMap<String, String> {
+        &self.headers
+    }
+
+    pub(crate) fn headers_len(&self) -> usize {
+        self.headers.iter().map(|(k, v)| k.len() + v.len()).sum()
+    }
+
     pub(crate) fn stub() -> Self {
-        BobMeta { timestamp: 1 }
+        BobMeta::new(1)
+    }
+}
+
+impl From<BlobMeta> for BobMeta {
+    fn from(meta: BlobMeta) -> Self {
+        let mut result = Self::with_headers(meta.timestamp, meta.headers.into_iter().collect());
+        if meta.expires_at > 0 {
+            result.set_expires_at(Some(meta.expires_at));
+        }
+        result
+    }
+}
+
+impl From<&BobMeta> for BlobMeta {
+    fn from(meta: &BobMeta) -> Self {
+        BlobMeta {
+            timestamp: meta.timestamp(),
+            headers: meta.headers().clone().into_iter().collect(),
+            expires_at: meta.expires_at().unwrap_or_default(),
+        }
+    }
+}
+
+// tombstones are not transferred as blobs, they are sent with delete request
+impl From<BobData> for Blob {
+    fn from(data: BobData) -> Self {
+        let meta = Some(BlobMeta::from(data.meta()));
+        let manifest = data.is_manifest();
+        let checksum = data.checksum().map(|crc32c| Checksum { crc32c });
+        Blob {
+            meta,
+            data: data.into_inner(),
+            manifest,
+            checksum,
+        }
+    }
+}
+
+impl TryFrom<Blob> for BobData {
+    type Error = Error;
+
+    fn try_from(blob: Blob) -> Result<Self, Self::Error> {
+        let meta = blob
+            .meta
+            .map(BobMeta::from)
+            .ok_or_else(|| Error::failed("blob without meta"))?;
+        let data = if blob.manifest {
+            Self::manifest(blob.data, meta)
+        } else {
+            Self::new(blob.data, meta)
+        };
+        Ok(match blob.checksum {
+            Some(checksum) => data.with_checksum(checksum.crc32c),
+            None => data,
+        })
     }
 }
 
@@ -121,26 +555,48 @@ bitflags! {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct BobOptions {
     flags: BobFlags,
     remote_nodes: Vec<String>,
     get_source: Option<GetSource>,
+    ttl: Option<Duration>,
 }
 
 impl BobOptions {
     pub(crate) fn new_put(options: Option<PutOptions>) -> Self {
         let mut flags = BobFlags::default();
+        let mut ttl = None;
         let remote_nodes = options.map_or(Vec::new(), |vopts| {
             if vopts.force_node {
                 flags |= BobFlags::FORCE_NODE;
             }
+            if vopts.ttl > 0 {
+                ttl = Some(Duration::from_secs(vopts.ttl));
+            }
             vopts.remote_nodes
         });
         BobOptions {
             flags,
             remote_nodes,
             get_source: None,
+            ttl,
+        }
+    }
+
+    pub(crate) fn new_delete(options: Option<DeleteOptions>) -> Self {
+        let mut flags = BobFlags::default();
+        let remote_nodes = options.map_or(Vec::new(), |vopts| {
+            if vopts.force_node {
+                flags |= BobFlags::FORCE_NODE;
+            }
+            vopts.remote_nodes
+        });
+        BobOptions {
+            flags,
+            remote_nodes,
+            get_source: None,
+            ttl: None,
         }
     }
 
@@ -157,9 +613,15 @@ impl BobOptions {
             flags,
             remote_nodes: Vec::new(),
             get_source,
+            ttl: None,
         }
     }
 
+    /// Time to live of the written data
+    pub(crate) fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
     pub(crate) fn remote_nodes(&self) -> &[String] {
         &self.remote_nodes
     }
@@ -257,3 +719,63 @@ impl From<&NodeDisk> for DiskPath {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BobData, BobKey, BobMeta};
+    use crate::grpc::BlobKey;
+    use std::convert::TryFrom;
+    use std::time::Duration;
+
+    #[test]
+    fn expire_after_keeps_earliest_expiration() {
+        let data = BobData::new(vec![1], BobMeta::new(100));
+        assert_eq!(data.clone().expire_after(None).meta().expires_at(), None);
+        let data = data
+            .expire_after(Some(Duration::from_secs(60)))
+            .expire_after(Some(Duration::from_secs(3600)));
+        assert_eq!(data.meta().expires_at(), Some(160));
+        assert!(data.meta().is_expired());
+        let tombstone = BobData::tombstone(BobMeta::new(100));
+        let tombstone = tombstone.expire_after(Some(Duration::from_secs(60)));
+        assert_eq!(tombstone.meta().expires_at(), None);
+    }
+
+    #[test]
+    fn key_resize() {
+        let key = BobKey::from(258);
+        let long = key.resize(16).unwrap();
+        assert_eq!(long.len(), 16);
+        assert_eq!(long.to_u64(), Some(258));
+        assert_eq!(long.resize(8), Some(key));
+        assert_eq!(BobKey::from_bytes(&[1; 9]).unwrap().resize(8), None);
+        assert!(BobKey::from_bytes(&[0; BobKey::MAX_LEN + 1]).is_none());
+    }
+
+    #[test]
+    fn blob_key_numeric_matches_bytes() {
+        let key = BobKey::from(42);
+        let blob_key = BlobKey::from(key);
+        assert_eq!(blob_key.key, 42);
+        assert_eq!(BobKey::try_from(blob_key).unwrap(), key);
+        let long = BobKey::from_bytes(&[1; 16]).unwrap();
+        let blob_key = BlobKey::from(long);
+        assert_eq!(blob_key.key, 0);
+        assert_eq!(BobKey::try_from(blob_key.clone()).unwrap(), long);
+        let mismatched = BlobKey { key: 5, ..blob_key };
+        assert!(BobKey::try_from(mismatched).is_err());
+    }
+
+    #[test]
+    fn key_display_parse_roundtrip() {
+        let key = BobKey::from(42);
+        assert_eq!(key.to_string(), "42");
+        assert_eq!("42".parse::<BobKey>().unwrap(), key);
+        let long = BobKey::from_bytes(&[0xab; 10]).unwrap();
+        assert_eq!(long.to_string(), "0xabababababababababab");
+        assert_eq!(long.to_string().parse::<BobKey>().unwrap(), long);
+        assert!("0xabc".parse::<BobKey>().is_err());
+        assert!("0xaéb".parse::<BobKey>().is_err());
+        assert!("0x+a".parse::<BobKey>().is_err());
+    }
+}
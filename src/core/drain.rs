@@ -0,0 +1,253 @@
+use super::prelude::*;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use tokio::runtime::Handle;
+
+const ACTIVE: u8 = 0;
+const DRAINING: u8 = 1;
+const DRAINED: u8 = 2;
+const UNDRAINING: u8 = 3;
+
+/// Operations in flight are waited for at most this time before storages are closed
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+const DRAIN_CHECK_PERIOD: Duration = Duration::from_millis(100);
+
+/// State of the node in the drain process
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum DrainState {
+    /// Node serves all operations
+    Active,
+    /// New writes are rejected, operations in flight are finishing
+    Draining,
+    /// Storages are closed, node can be stopped
+    Drained,
+    /// Storages are reopened, node returns to service when they are ready
+    Undraining,
+}
+
+impl DrainState {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            DrainState::Active => "active",
+            DrainState::Draining => "draining",
+            DrainState::Drained => "drained",
+            DrainState::Undraining => "undraining",
+        }
+    }
+}
+
+/// Tracks operations in flight and drains the node before planned maintenance: new writes are
+/// rejected, peers stop selecting the node as put target after the next ping, background tasks
+/// are stopped and storages are closed when operations in flight finish. Drained node returns
+/// to service by undrain or restart.
+#[derive(Debug)]
+pub(crate) struct Drain {
+    state: AtomicU8,
+    in_flight: AtomicUsize,
+    // drain is started from the http api thread, which has its own short-lived runtime
+    runtime: Option<Handle>,
+}
+
+/// Operation in flight, which is finished on drop. Guard may be moved to the task, which
+/// streams the response.
+#[derive(Debug)]
+pub(crate) struct OperationGuard {
+    drain: Arc<Drain>,
+}
+
+impl Drop for OperationGuard {
+    fn drop(&mut self) {
+        self.drain.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Default for Drain {
+    fn default() -> Self {
+        Self {
+            state: AtomicU8::new(ACTIVE),
+            in_flight: AtomicUsize::new(0),
+            runtime: Handle::try_current().ok(),
+        }
+    }
+}
+
+impl Drain {
+    pub(crate) fn state(&self) -> DrainState {
+        match self.state.load(Ordering::SeqCst) {
+            ACTIVE => DrainState::Active,
+            DRAINING => DrainState::Draining,
+            DRAINED => DrainState::Drained,
+            _ => DrainState::Undraining,
+        }
+    }
+
+    pub(crate) fn is_draining(&self) -> bool {
+        self.state() != DrainState::Active
+    }
+
+    pub(crate) fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Registers new operation, writes are rejected since drain start until undrain is finished,
+    /// reads are rejected while storages are closed
+    pub(crate) fn start_operation(self: &Arc<Self>, write: bool) -> Result<OperationGuard, Status> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        let guard = OperationGuard {
+            drain: self.clone(),
+        };
+        match self.state() {
+            DrainState::Active => Ok(guard),
+            DrainState::Draining if !write => Ok(guard),
+            state => Err(Status::unavailable(format!("node is {}", state.as_str()))),
+        }
+    }
+
+    /// Starts drain in background, returns false if the node isn't active
+    pub(crate) fn start(self: &Arc<Self>, grinder: Arc<Grinder>) -> bool {
+        if !self.begin() {
+            return false;
+        }
+        let drain = self.clone();
+        self.spawn(async move { drain.finish(&grinder).await });
+        true
+    }
+
+    /// Drains the node and waits until storages are closed, returns false if the node isn't
+    /// active
+    pub(crate) async fn run(&self, grinder: &Grinder) -> bool {
+        if !self.begin() {
+            return false;
+        }
+        self.finish(grinder).await;
+        true
+    }
+
+    /// Starts reopening of storages of the drained node in background, returns false if the
+    /// node isn't drained
+    pub(crate) fn undrain(self: &Arc<Self>, grinder: Arc<Grinder>) -> bool {
+        if self
+            .state
+            .compare_exchange(DRAINED, UNDRAINING, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return false;
+        }
+        let drain = self.clone();
+        self.spawn(async move { drain.reopen(&grinder).await });
+        true
+    }
+
+    fn begin(&self) -> bool {
+        let started = self
+            .state
+            .compare_exchange(ACTIVE, DRAINING, Ordering::SeqCst, Ordering::SeqCst)
+            .is_ok();
+        if started {
+            info!("drain started, {} operations in flight", self.in_flight());
+        }
+        started
+    }
+
+    // waits for operations in flight, stops background tasks and closes storages
+    async fn finish(&self, grinder: &Grinder) {
+        let deadline = Instant::now() + DRAIN_TIMEOUT;
+        while self.in_flight() > 0 && Instant::now() < deadline {
+            sleep(DRAIN_CHECK_PERIOD).await;
+        }
+        if self.in_flight() > 0 {
+            warn!(
+                "drain timeout, {} operations are still in flight",
+                self.in_flight()
+            );
+        }
+        grinder.stop_periodic_tasks().await;
+        grinder.backend().shutdown().await;
+        self.state.store(DRAINED, Ordering::SeqCst);
+        info!("drain finished, storages are closed");
+    }
+
+    async fn reopen(&self, grinder: &Grinder) {
+        info!("undrain started, storages are reopened");
+        match grinder.backend().reopen().await {
+            Ok(()) => {
+                grinder.restart_periodic_tasks();
+                self.state.store(ACTIVE, Ordering::SeqCst);
+                info!("undrain finished, node is active");
+            }
+            Err(e) => {
+                error!("undrain failed, storages are closed: {:#}", e);
+                grinder.backend().shutdown().await;
+                self.state.store(DRAINED, Ordering::SeqCst);
+            }
+        }
+    }
+
+    fn spawn(&self, task: impl Future<Output = ()> + Send + 'static) {
+        if let Some(runtime) = &self.runtime {
+            runtime.spawn(task);
+        } else {
+            tokio::spawn(task);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_utils;
+
+    async fn wait_for(drain: &Drain, state: DrainState) {
+        for _ in 0..50 {
+            if drain.state() == state {
+                return;
+            }
+            sleep(Duration::from_millis(100)).await;
+        }
+        panic!("drain state is {:?}, expected {:?}", drain.state(), state);
+    }
+
+    #[tokio::test]
+    async fn drain_waits_for_operations_in_flight() {
+        let grinder = test_utils::single_node_grinder().await;
+        let drain = Arc::new(Drain::default());
+
+        let read = drain.start_operation(false).unwrap();
+        let (drained, _) = tokio::join!(drain.run(&grinder), async {
+            sleep(Duration::from_millis(300)).await;
+            assert_eq!(drain.state(), DrainState::Draining);
+            assert!(drain.start_operation(true).is_err());
+            assert!(drain.start_operation(false).is_ok());
+            drop(read);
+        });
+        assert!(drained);
+        assert_eq!(drain.state(), DrainState::Drained);
+        assert_eq!(drain.in_flight(), 0);
+        assert!(drain.start_operation(false).is_err());
+        assert!(!drain.run(&grinder).await);
+    }
+
+    #[tokio::test]
+    async fn drained_node_returns_to_service_on_undrain() {
+        let grinder = Arc::new(test_utils::single_node_grinder().await);
+        grinder.run_backend().await.unwrap();
+        let drain = Arc::new(Drain::default());
+        assert!(!drain.undrain(grinder.clone()));
+
+        let read = drain.start_operation(false).unwrap();
+        assert!(drain.start(grinder.clone()));
+        assert!(!drain.start(grinder.clone()));
+        assert_eq!(drain.state(), DrainState::Draining);
+        drop(read);
+        wait_for(&drain, DrainState::Drained).await;
+
+        assert!(drain.undrain(grinder.clone()));
+        wait_for(&drain, DrainState::Active).await;
+        assert!(drain.start_operation(true).is_ok());
+        let key = BobKey::from(1);
+        let data = BobData::new(vec![1], BobMeta::new(1));
+        grinder
+            .put(key, data, BobOptions::new_put(None))
+            .await
+            .unwrap();
+    }
+}
@@ -1,4 +1,5 @@
 use super::prelude::*;
+use crate::grpc::BobError;
 
 #[derive(Debug, Clone, ErrorTrait)]
 pub struct Error {
@@ -18,7 +19,32 @@ impl Error {
     }
 
     pub(crate) fn is_key_not_found(&self) -> bool {
-        matches!(&self.ctx, Kind::KeyNotFound(_))
+        matches!(&self.ctx, Kind::KeyNotFound(_) | Kind::KeyDeleted(_, _))
+    }
+
+    pub(crate) fn is_data_corrupted(&self) -> bool {
+        matches!(&self.ctx, Kind::DataCorrupted(_))
+    }
+
+    pub(crate) fn is_disk_unavailable(&self) -> bool {
+        matches!(&self.ctx, Kind::DiskUnavailable(_))
+    }
+
+    pub(crate) fn is_no_space(&self) -> bool {
+        matches!(&self.ctx, Kind::NoSpace(_))
+    }
+
+    pub(crate) fn is_encryption_key_missing(&self) -> bool {
+        matches!(&self.ctx, Kind::EncryptionKeyMissing(_))
+    }
+
+    /// Timestamp of the tombstone, if key was not found because of deletion
+    pub(crate) fn deleted_at(&self) -> Option<u64> {
+        if let Kind::KeyDeleted(_, timestamp) = self.ctx {
+            Some(timestamp)
+        } else {
+            None
+        }
     }
 
     #[cfg(test)]
@@ -34,10 +60,22 @@ impl Error {
         Self::new(Kind::Timeout)
     }
 
-    pub(crate) fn key_not_found(key: u64) -> Self {
+    pub(crate) fn key_not_found(key: BobKey) -> Self {
         Self::new(Kind::KeyNotFound(key))
     }
 
+    pub(crate) fn key_deleted(key: BobKey, timestamp: u64) -> Self {
+        Self::new(Kind::KeyDeleted(key, timestamp))
+    }
+
+    pub(crate) fn data_corrupted(key: BobKey) -> Self {
+        Self::new(Kind::DataCorrupted(key))
+    }
+
+    pub(crate) fn invalid_key(msg: impl Into<String>) -> Self {
+        Self::new(Kind::InvalidKey(msg.into()))
+    }
+
     pub(crate) fn pearl_change_state(msg: impl Into<String>) -> Self {
         Self::new(Kind::PearlChangeState(msg.into()))
     }
@@ -62,6 +100,18 @@ impl Error {
         Self::new(Kind::Storage(msg.into()))
     }
 
+    pub(crate) fn disk_unavailable(name: impl Into<String>) -> Self {
+        Self::new(Kind::DiskUnavailable(name.into()))
+    }
+
+    pub(crate) fn no_space(disk_name: impl Into<String>) -> Self {
+        Self::new(Kind::NoSpace(disk_name.into()))
+    }
+
+    pub(crate) fn encryption_key_missing(msg: impl Into<String>) -> Self {
+        Self::new(Kind::EncryptionKeyMissing(msg.into()))
+    }
+
     pub(crate) fn request_failed_completely(local: &Error, alien: &Error) -> Self {
         let msg = format!("local error: {}\nalien error: {}", local, alien);
         let ctx = Kind::RequestFailedCompletely(msg);
@@ -80,6 +130,9 @@ impl Display for Kind {
         match self {
             Self::VDiskNotFound(id) => write!(f, "vdisk: {:?} not found", id),
             Self::Storage(description) => write!(f, "backend storage error: {}", description),
+            Self::DiskUnavailable(name) => write!(f, "disk: {} is unavailable", name),
+            Self::NoSpace(name) => write!(f, "disk: {} has no free space", name),
+            Self::EncryptionKeyMissing(description) => write!(f, "{}", description),
             Self::PearlChangeState(description) => {
                 write!(f, "backend pearl change state error: {}", description)
             }
@@ -94,11 +147,21 @@ impl Into<Status> for Error {
         trace!("Error: {}", self);
         match &self.ctx {
             Kind::KeyNotFound(key) => Status::not_found(format!("KeyNotFound {}", key)),
+            Kind::KeyDeleted(key, timestamp) => {
+                Status::not_found(format!("KeyDeleted {} {}", key, timestamp))
+            }
             Kind::DuplicateKey => Status::already_exists("DuplicateKey"),
+            Kind::DataCorrupted(key) => Status::data_loss(format!("DataCorrupted {}", key)),
+            Kind::InvalidKey(msg) => Status::invalid_argument(format!("InvalidKey {}", msg)),
             Kind::Timeout => Status::deadline_exceeded("Timeout"),
             Kind::VDiskNotFound(id) => Status::not_found(format!("VDiskNotFound {}", id)),
             Kind::Storage(msg) => Status::internal(format!("Storage {}", msg)),
-            Kind::VDiskIsNotReady => Status::internal("VDiskIsNotReady"),
+            Kind::VDiskIsNotReady => Status::unavailable("VDiskIsNotReady"),
+            Kind::DiskUnavailable(name) => Status::unavailable(format!("DiskUnavailable {}", name)),
+            Kind::NoSpace(name) => Status::resource_exhausted(format!("NoSpace {}", name)),
+            Kind::EncryptionKeyMissing(msg) => {
+                Status::failed_precondition(format!("EncryptionKeyMissing {}", msg))
+            }
             Kind::Failed(msg) => Status::internal(format!("Failed {}", msg)),
             Kind::Internal => Status::internal("Internal"),
             Kind::PearlChangeState(msg) => Status::internal(format!("PearlChangeState {}", msg)),
@@ -119,11 +182,19 @@ impl From<Status> for Error {
             None => None,
             Some(name) => match name {
                 "KeyNotFound" => parse_next(words, Self::key_not_found),
+                "KeyDeleted" => parse_key_deleted(words),
                 "DuplicateKey" => Some(Self::duplicate_key()),
+                "DataCorrupted" => parse_next(words, Self::data_corrupted),
+                "InvalidKey" => Some(Self::invalid_key(rest_words(words, length))),
                 "Timeout" => Some(Self::timeout()),
                 "VDiskNotFound" => parse_next(words, Self::vdisk_not_found),
                 "Storage" => Some(Self::storage(rest_words(words, length))),
                 "VDiskIsNotReady" => Some(Self::vdisk_is_not_ready()),
+                "DiskUnavailable" => Some(Self::disk_unavailable(rest_words(words, length))),
+                "NoSpace" => Some(Self::no_space(rest_words(words, length))),
+                "EncryptionKeyMissing" => {
+                    Some(Self::encryption_key_missing(rest_words(words, length)))
+                }
                 "Failed" => Some(Self::failed(rest_words(words, length))),
                 "Internal" => Some(Self::internal()),
                 "PearlChangeState" => Some(Self::pearl_change_state(rest_words(words, length))),
@@ -134,6 +205,29 @@ impl From<Status> for Error {
     }
 }
 
+// per-key errors of batch operations are transferred in the same format as statuses
+impl From<Error> for BobError {
+    fn from(error: Error) -> Self {
+        let status: Status = error.into();
+        status.into()
+    }
+}
+
+impl From<Status> for BobError {
+    fn from(status: Status) -> Self {
+        BobError {
+            code: status.code() as i32,
+            desc: status.message().to_owned(),
+        }
+    }
+}
+
+impl From<BobError> for Error {
+    fn from(error: BobError) -> Self {
+        Status::new(Code::from(error.code), error.desc).into()
+    }
+}
+
 fn rest_words<'a>(words: impl Iterator<Item = &'a str>, length: usize) -> String {
     words.fold(String::with_capacity(length), |s, n| s + n)
 }
@@ -145,14 +239,26 @@ where
     words.next().and_then(|w| w.parse().ok()).map(f)
 }
 
+fn parse_key_deleted<'a>(mut words: impl Iterator<Item = &'a str>) -> Option<Error> {
+    let key = words.next()?.parse().ok()?;
+    let timestamp = words.next()?.parse().ok()?;
+    Some(Error::key_deleted(key, timestamp))
+}
+
 #[derive(PartialEq, Debug, Clone)]
 pub enum Kind {
     Timeout,
     VDiskNotFound(VDiskID),
     Storage(String),
     DuplicateKey,
+    InvalidKey(String),
     KeyNotFound(BobKey),
+    KeyDeleted(BobKey, u64),
+    DataCorrupted(BobKey),
     VDiskIsNotReady,
+    DiskUnavailable(String),
+    NoSpace(String),
+    EncryptionKeyMissing(String),
     Failed(String),
     Internal,
     PearlChangeState(String),
@@ -0,0 +1,81 @@
+use super::prelude::*;
+use std::sync::RwLock as SyncRwLock;
+use std::time::Duration;
+
+/// Pulls cluster maps of other nodes and applies the newest one, so all nodes of the cluster
+/// converge to the same version of the map, pushed to any of them. With authentication maps are
+/// signed with the node credentials, so maps of nodes with other credentials are rejected.
+pub(crate) struct Gossip {
+    interval: SyncRwLock<Duration>,
+}
+
+impl Gossip {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self {
+            interval: SyncRwLock::new(interval),
+        }
+    }
+
+    /// Changes period of the running task, it's applied after the current one ends
+    pub(crate) fn update(&self, interval: Duration) {
+        *self.interval.write().expect("gossip lock") = interval;
+    }
+
+    fn period(&self) -> Duration {
+        *self.interval.read().expect("gossip lock")
+    }
+
+    pub(crate) fn spawn_task(self: Arc<Self>, backend: Arc<Backend>) -> JoinHandle<()> {
+        tokio::spawn(self.task(backend))
+    }
+
+    async fn task(self: Arc<Self>, backend: Arc<Backend>) {
+        loop {
+            Self::run_once(&backend).await;
+            sleep(self.period()).await;
+        }
+    }
+
+    /// Applies the newest cluster map of reachable nodes, if it is newer than the local one.
+    /// Returns version of the applied map.
+    pub(crate) async fn run_once(backend: &Backend) -> Option<u64> {
+        let mapper = backend.mapper();
+        let remote_nodes = mapper
+            .nodes()
+            .values()
+            .filter(|node| node.name() != mapper.local_node_name());
+        let results =
+            LinkManager::call_nodes(remote_nodes, |client| Box::pin(client.cluster_map())).await;
+        let newest = results
+            .into_iter()
+            .filter_map(|result| match result {
+                Ok(output) => Some(output.into_inner()),
+                Err(e) => {
+                    debug!(
+                        "gossip: can't get cluster map from {}: {}",
+                        e.node_name(),
+                        e.inner()
+                    );
+                    None
+                }
+            })
+            .max_by_key(ClusterConfig::version)?;
+        if newest.version() <= mapper.version() {
+            return None;
+        }
+        match backend.update_cluster_map(&newest).await {
+            Ok(version) => {
+                info!("gossip: cluster map version {} applied", version);
+                Some(version)
+            }
+            Err(e) => {
+                warn!(
+                    "gossip: cluster map version {} rejected: {}",
+                    newest.version(),
+                    e
+                );
+                None
+            }
+        }
+    }
+}
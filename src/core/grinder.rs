@@ -1,4 +1,6 @@
 use super::prelude::*;
+use crate::core::chunks;
+use std::sync::{Mutex as SyncMutex, RwLock as SyncRwLock};
 
 /// Struct for cooperation backend, link manager and cluster
 pub struct Grinder {
@@ -7,14 +9,22 @@ pub struct Grinder {
     cluster: Arc<dyn Cluster + Send + Sync>,
     cleaner: Arc<Cleaner>,
     counter: Arc<BlobsCounter>,
+    handoff: Arc<Handoff>,
+    repair: Arc<Repair>,
+    scrubber: Arc<Scrubber>,
+    compaction: Arc<Compaction>,
+    retention: Arc<Retention>,
+    gossip: Arc<Gossip>,
+    client_factory: SyncRwLock<Option<Factory>>,
+    tasks: SyncMutex<Vec<JoinHandle<()>>>,
+    stream_chunk_size: usize,
 }
 
 impl Grinder {
     /// Creates new instance of the Grinder
     pub fn new(mapper: Virtual, config: &NodeConfig) -> Grinder {
-        let nodes = mapper.nodes().values().cloned().collect::<Vec<_>>();
-        let link_manager = Arc::new(LinkManager::new(nodes.as_slice(), config.check_interval()));
-        let mapper = Arc::new(mapper);
+        let mapper = Arc::new(ClusterMap::new(mapper));
+        let link_manager = Arc::new(LinkManager::new(mapper.clone(), config.check_interval()));
         let backend = Arc::new(Backend::new(mapper.clone(), config));
         let cleaner = Arc::new(Cleaner::new(
             config.cleanup_interval(),
@@ -22,12 +32,30 @@ impl Grinder {
             config.hard_open_blobs(),
         ));
         let counter = Arc::new(BlobsCounter::new(config.count_interval()));
+        let handoff = Arc::new(Handoff::new(config.handoff_interval()));
+        let repair = Arc::new(Repair::new(config.repair_interval()));
+        let scrubber = Arc::new(Scrubber::new(config.scrub_interval()));
+        let compaction = Arc::new(Compaction::new(config.compaction()));
+        let retention = Arc::new(Retention::new(
+            config.retention_period(),
+            config.retention_check_interval(),
+        ));
+        let gossip = Arc::new(Gossip::new(config.check_interval()));
         Grinder {
             backend: backend.clone(),
             link_manager,
             cluster: get_cluster(mapper, config, backend),
             cleaner,
             counter,
+            handoff,
+            repair,
+            scrubber,
+            compaction,
+            retention,
+            gossip,
+            client_factory: SyncRwLock::new(None),
+            tasks: SyncMutex::default(),
+            stream_chunk_size: config.stream_chunk_size(),
         }
     }
 
@@ -35,6 +63,18 @@ impl Grinder {
         &self.backend
     }
 
+    pub(crate) fn scrubber(&self) -> &Scrubber {
+        &self.scrubber
+    }
+
+    pub(crate) fn compaction(&self) -> &Arc<Compaction> {
+        &self.compaction
+    }
+
+    pub(crate) fn stream_chunk_size(&self) -> usize {
+        self.stream_chunk_size
+    }
+
     pub(crate) async fn run_backend(&self) -> Result<()> {
         self.backend.run_backend().await
     }
@@ -46,6 +86,9 @@ impl Grinder {
         opts: BobOptions,
     ) -> Result<(), Error> {
         let sw = Stopwatch::start_new();
+        let data = data
+            .expire_after(opts.ttl())
+            .expire_after(self.retention.period());
         if opts.flags().contains(BobFlags::FORCE_NODE) {
             trace!(">>>- - - - - GRINDER PUT START - - - - -");
             debug!(
@@ -83,6 +126,104 @@ impl Grinder {
         }
     }
 
+    /// Version of the key without data, size of the large blob is taken from its manifest, so
+    /// chunks aren't read. Manifest flag is kept, so large blobs are told apart.
+    pub(crate) async fn head(&self, key: BobKey, opts: &BobOptions) -> Result<BobHead, Error> {
+        if opts.flags().contains(BobFlags::FORCE_NODE) {
+            debug!(
+                "HEAD[{}] FORCE_NODE=true - will handle it by local node",
+                key
+            );
+            let head = self.backend.head(key, opts).await?;
+            return self.blob_head(key, head, opts).await;
+        }
+        match self.replicas_head(key).await {
+            Err(e) if e.is_key_not_found() && e.deleted_at().is_none() => {
+                // aliens and replicas, which didn't answer, are read with data
+                debug!("HEAD[{}] not found in replicas, read from cluster", key);
+                let data = self.cluster.get(key).await?;
+                let size = if data.is_manifest() {
+                    chunks::blob_size(&data)?
+                } else {
+                    data.inner().len() as u64
+                };
+                Ok(BobHead::new(
+                    data.meta().clone(),
+                    size,
+                    false,
+                    data.is_manifest(),
+                ))
+            }
+            result => result,
+        }
+    }
+
+    // the most recent version of the replicas, tombstone of any replica hides older versions
+    async fn replicas_head(&self, key: BobKey) -> Result<BobHead, Error> {
+        let mapper = self.backend.mapper();
+        let mut results = Vec::new();
+        if mapper.get_operation(key).1.is_some() {
+            let opts = BobOptions::new_get(Some(GetOptions::new_local()));
+            let head = match self.backend.head(key, &opts).await {
+                Ok(head) => self.blob_head(key, head, &opts).await,
+                Err(e) => Err(e),
+            };
+            results.push(head);
+        }
+        let remote_nodes = mapper
+            .get_target_nodes_for_key(key)
+            .iter()
+            .filter(|node| node.name() != mapper.local_node_name());
+        let remote = LinkManager::call_nodes(remote_nodes, |conn| {
+            conn.head(key, GetOptions::new_local()).boxed()
+        })
+        .await;
+        results.extend(
+            remote
+                .into_iter()
+                .map(|result| result.map(|o| o.into_inner()).map_err(|o| o.into_inner())),
+        );
+        let mut latest: Option<BobHead> = None;
+        let mut deleted_at = None;
+        for result in results {
+            match result {
+                Ok(head) => {
+                    if latest
+                        .as_ref()
+                        .map_or(true, |latest| head.is_newer_than(latest))
+                    {
+                        latest = Some(head);
+                    }
+                }
+                Err(e) => {
+                    debug!("HEAD[{}] replica returned error: {}", key, e);
+                    deleted_at = deleted_at.max(e.deleted_at());
+                }
+            }
+        }
+        match (latest, deleted_at) {
+            (Some(head), Some(timestamp)) if timestamp < head.meta().timestamp() => Ok(head),
+            (Some(head), None) => Ok(head),
+            (_, Some(timestamp)) => Err(Error::key_deleted(key, timestamp)),
+            (None, None) => Err(Error::key_not_found(key)),
+        }
+    }
+
+    // manifest of the local version is read to get the size of the large blob
+    async fn blob_head(
+        &self,
+        key: BobKey,
+        head: BobHead,
+        opts: &BobOptions,
+    ) -> Result<BobHead, Error> {
+        if !head.is_manifest() {
+            return Ok(head);
+        }
+        let manifest = self.backend.get(key, opts).await?;
+        let size = chunks::blob_size(&manifest)?;
+        Ok(BobHead::new(manifest.meta().clone(), size, false, true))
+    }
+
     pub(crate) async fn get(&self, key: BobKey, opts: &BobOptions) -> Result<BobData, Error> {
         trace!(">>>- - - - - GRINDER GET START - - - - -");
         let sw = Stopwatch::start_new();
@@ -132,6 +273,65 @@ impl Grinder {
         }
     }
 
+    /// Reads keys with one request per node, results are in the order of keys
+    pub(crate) async fn get_batch(
+        &self,
+        keys: &[BobKey],
+        opts: &BobOptions,
+    ) -> Vec<Result<BobData, Error>> {
+        if opts.flags().contains(BobFlags::FORCE_NODE) {
+            counter!(CLIENT_GET_COUNTER, keys.len() as u64);
+            let mut results = Vec::with_capacity(keys.len());
+            for &key in keys {
+                results.push(self.backend.get(key, opts).await);
+            }
+            let errors = results.iter().filter(|r| r.is_err()).count();
+            counter!(CLIENT_GET_ERROR_COUNT_COUNTER, errors as u64);
+            results
+        } else {
+            debug!("GET_BATCH[{}] will route to cluster", keys.len());
+            counter!(GRINDER_GET_COUNTER, keys.len() as u64);
+            let results = self.cluster.get_batch(keys).await;
+            let errors = results.iter().filter(|r| r.is_err()).count();
+            counter!(GRINDER_GET_ERROR_COUNT_COUNTER, errors as u64);
+            results
+        }
+    }
+
+    /// Writes records with one request per node, results are in the order of records
+    pub(crate) async fn put_batch(
+        &self,
+        records: Vec<(BobKey, BobData)>,
+        opts: BobOptions,
+    ) -> Vec<Result<(), Error>> {
+        let records: Vec<_> = records
+            .into_iter()
+            .map(|(key, data)| {
+                let data = data
+                    .expire_after(opts.ttl())
+                    .expire_after(self.retention.period());
+                (key, data)
+            })
+            .collect();
+        if opts.flags().contains(BobFlags::FORCE_NODE) {
+            counter!(CLIENT_PUT_COUNTER, records.len() as u64);
+            let mut results = Vec::with_capacity(records.len());
+            for (key, data) in records {
+                results.push(self.backend.put(key, data, opts.clone()).await);
+            }
+            let errors = results.iter().filter(|r| r.is_err()).count();
+            counter!(CLIENT_PUT_ERROR_COUNT_COUNTER, errors as u64);
+            results
+        } else {
+            debug!("PUT_BATCH[{}] will route to cluster", records.len());
+            counter!(GRINDER_PUT_COUNTER, records.len() as u64);
+            let results = self.cluster.put_batch(records).await;
+            let errors = results.iter().filter(|r| r.is_err()).count();
+            counter!(GRINDER_PUT_ERROR_COUNT_COUNTER, errors as u64);
+            results
+        }
+    }
+
     pub(crate) async fn exist(
         &self,
         keys: &[BobKey],
@@ -167,11 +367,98 @@ impl Grinder {
         }
     }
 
+    pub(crate) async fn delete(
+        &self,
+        key: BobKey,
+        meta: BobMeta,
+        opts: BobOptions,
+    ) -> Result<(), Error> {
+        if opts.flags().contains(BobFlags::FORCE_NODE) {
+            debug!(
+                "DELETE[{}] FORCE_NODE=true - will handle it by local node. Delete params: {:?}",
+                key, opts
+            );
+            counter!(CLIENT_DELETE_COUNTER, 1);
+            let time = Instant::now();
+            let result = self.backend.delete(key, meta, opts).await;
+            if result.is_err() {
+                counter!(CLIENT_DELETE_ERROR_COUNT_COUNTER, 1);
+            }
+            timing!(CLIENT_DELETE_TIMER, time.elapsed().as_nanos() as u64);
+            result
+        } else {
+            debug!("DELETE[{}] will route to cluster", key);
+            counter!(GRINDER_DELETE_COUNTER, 1);
+            let time = Instant::now();
+            let result = self.cluster.delete(key, meta).await;
+            if result.is_err() {
+                counter!(GRINDER_DELETE_ERROR_COUNT_COUNTER, 1);
+            }
+            timing!(GRINDER_DELETE_TIMER, time.elapsed().as_nanos() as u64);
+            result
+        }
+    }
+
     #[inline]
     pub(crate) fn run_periodic_tasks(&self, client_factory: Factory) {
-        self.link_manager.spawn_checker(client_factory);
-        self.cleaner.spawn_task(self.backend.clone());
-        self.counter.spawn_task(self.backend.clone());
+        *self.client_factory.write().expect("client factory lock") = Some(client_factory.clone());
+        self.spawn_periodic_tasks(client_factory);
+    }
+
+    fn spawn_periodic_tasks(&self, client_factory: Factory) {
+        let tasks = vec![
+            Some(self.link_manager.clone().spawn_checker(client_factory)),
+            Some(self.cleaner.clone().spawn_task(self.backend.clone())),
+            Some(self.counter.clone().spawn_task(self.backend.clone())),
+            Some(self.handoff.spawn_task(self.backend.clone())),
+            self.repair.spawn_task(self.backend.clone()),
+            self.scrubber.spawn_task(self.backend.clone()),
+            self.compaction.clone().spawn_task(self.backend.clone()),
+            Some(self.retention.spawn_task(self.backend.clone())),
+            Some(self.gossip.clone().spawn_task(self.backend.clone())),
+            Some(self.backend.disks().clone().spawn_checker()),
+        ];
+        let mut running = self.tasks.lock().expect("tasks lock");
+        running.extend(tasks.into_iter().flatten());
+    }
+
+    /// Stops periodic tasks, so they don't access storages after they are closed. Compaction
+    /// of the window in progress is finished first.
+    pub(crate) async fn stop_periodic_tasks(&self) {
+        self.compaction.stop().await;
+        for task in self.tasks.lock().expect("tasks lock").drain(..) {
+            task.abort();
+        }
+    }
+
+    /// Starts periodic tasks again after storages are reopened, they aren't started if they
+    /// weren't running before
+    pub(crate) fn restart_periodic_tasks(&self) {
+        self.compaction.resume();
+        let factory = self
+            .client_factory
+            .read()
+            .expect("client factory lock")
+            .clone();
+        if let Some(factory) = factory {
+            self.spawn_periodic_tasks(factory);
+        }
+    }
+
+    /// Applies fields of the reloaded node config, which can be changed without restart
+    pub(crate) fn apply_config(&self, config: &NodeConfig) {
+        self.cleaner.update(
+            config.cleanup_interval(),
+            config.open_blobs_soft(),
+            config.hard_open_blobs(),
+        );
+        self.counter.update(config.count_interval());
+        self.link_manager.update(config.check_interval());
+        self.gossip.update(config.check_interval());
+        self.backend.disks().set_free_space(config.free_space());
+        if let Some(factory) = &*self.client_factory.read().expect("client factory lock") {
+            factory.set_operation_timeout(config.operation_timeout());
+        }
     }
 }
 
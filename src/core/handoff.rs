@@ -0,0 +1,165 @@
+use super::prelude::*;
+use crate::core::backend::{Group, Holder};
+use std::time::Duration;
+
+/// Moves alien records back to the owner nodes, once they become reachable, and drops
+/// released vdisks, once their records are held by the new replicas.
+pub(crate) struct Handoff {
+    interval: Duration,
+}
+
+impl Handoff {
+    pub(crate) fn new(interval: Duration) -> Self {
+        Self { interval }
+    }
+
+    pub(crate) fn spawn_task(&self, backend: Arc<Backend>) -> JoinHandle<()> {
+        tokio::spawn(Self::task(backend, self.interval))
+    }
+
+    async fn task(backend: Arc<Backend>, t: Duration) {
+        let mut interval = interval(t);
+        loop {
+            interval.tick().await;
+            Self::run_once(&backend).await;
+            Self::drop_replicated(&backend).await;
+        }
+    }
+
+    /// Drops released groups, which records are held by all replicas of the current map,
+    /// returns number of dropped groups.
+    pub(crate) async fn drop_replicated(backend: &Backend) -> usize {
+        let released = backend.released_vdisks_groups().await.unwrap_or_default();
+        if released.is_empty() {
+            return 0;
+        }
+        let mapper = backend.mapper();
+        let local_groups = backend.vdisks_groups().await.unwrap_or_default();
+        let mut dropped = 0;
+        for group in released {
+            let replicated = backend
+                .migration()
+                .is_replicated(&group, &mapper, &local_groups)
+                .await;
+            match replicated {
+                Ok(true) => match backend.drop_released_group(&group).await {
+                    Ok(()) => dropped += 1,
+                    Err(e) => warn!("released vdisk {} is not dropped: {}", group.vdisk_id(), e),
+                },
+                Ok(false) => {}
+                Err(e) => debug!(
+                    "released vdisk {} on disk {} is kept: {}",
+                    group.vdisk_id(),
+                    group.disk_name(),
+                    e
+                ),
+            }
+        }
+        dropped
+    }
+
+    /// Replays alien holders to reachable owners, returns number of drained holders.
+    pub(crate) async fn run_once(backend: &Backend) -> usize {
+        let groups = if let Some(groups) = backend.alien_vdisks_groups().await {
+            groups
+        } else {
+            return 0;
+        };
+        let mut drained = 0;
+        for group in groups {
+            let mapper = backend.mapper();
+            let owner = mapper
+                .nodes()
+                .values()
+                .find(|node| node.name() == group.node_name());
+            let client = if let Some(client) = Self::owner_connection(owner).await {
+                client
+            } else {
+                debug!("handoff: owner of {} is unreachable", group.node_name());
+                continue;
+            };
+            drained += Self::drain_group(&group, &client).await;
+        }
+        drained
+    }
+
+    async fn owner_connection(owner: Option<&Node>) -> Option<BobClient> {
+        owner?.get_connection().await
+    }
+
+    async fn drain_group(group: &Group, client: &BobClient) -> usize {
+        let holders = group.holders().read().await.clone();
+        let mut drained = 0;
+        for holder in holders {
+            match Self::drain_holder(group, &holder, client).await {
+                Ok(true) => drained += 1,
+                Ok(false) => {}
+                Err(e) => warn!(
+                    "handoff of alien holder {} to {} failed: {}",
+                    holder.get_id(),
+                    group.node_name(),
+                    e
+                ),
+            }
+        }
+        drained
+    }
+
+    async fn drain_holder(
+        group: &Group,
+        holder: &Holder,
+        client: &BobClient,
+    ) -> Result<bool, Error> {
+        if !holder.no_writes_recently().await {
+            return Ok(false);
+        }
+        let records_count = holder.records_count().await;
+        if records_count > 0 {
+            if !holder.has_complete_keys_log().await? {
+                warn!(
+                    "alien holder {} has no complete keys log and can't be handed off",
+                    holder.get_id()
+                );
+                return Ok(false);
+            }
+            let keys = holder.keys().await?;
+            let mut existing = vec![];
+            for key in keys {
+                let data = holder.read(key).await?;
+                let is_tombstone = data.is_tombstone();
+                let result = if is_tombstone {
+                    client
+                        .delete(key, data.meta().clone(), DeleteOptions::new_local())
+                        .await
+                } else {
+                    client.put(key, data, PutOptions::new_local()).await
+                };
+                result.map_err(|e| e.into_inner())?;
+                if !is_tombstone {
+                    existing.push(key);
+                }
+            }
+            if !existing.is_empty() {
+                let confirmed = client
+                    .exist(existing, GetOptions::new_local())
+                    .await
+                    .map_err(|e| e.into_inner())?;
+                if !confirmed.into_inner().iter().all(|&exist| exist) {
+                    return Err(Error::failed("owner doesn't confirm handed off records"));
+                }
+            }
+        }
+        let removed = group
+            .remove_drained_holder(holder.start_timestamp(), records_count)
+            .await?;
+        if removed {
+            info!(
+                "alien holder {} ({} records) handed off to {}",
+                holder.get_id(),
+                records_count,
+                group.node_name()
+            );
+        }
+        Ok(removed)
+    }
+}
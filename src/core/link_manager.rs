@@ -1,9 +1,10 @@
 use super::prelude::*;
+use std::sync::RwLock as SyncRwLock;
 
 #[derive(Debug)]
 pub(crate) struct LinkManager {
-    nodes: Arc<[Node]>,
-    check_interval: Duration,
+    mapper: Arc<ClusterMap>,
+    check_interval: SyncRwLock<Duration>,
 }
 
 pub(crate) type ClusterCallOutput<T> = Result<NodeOutput<T>, NodeOutput<Error>>;
@@ -11,20 +12,30 @@ pub(crate) type ClusterCallFuture<'a, T> =
     Pin<Box<dyn Future<Output = ClusterCallOutput<T>> + Send + 'a>>;
 
 impl LinkManager {
-    pub(crate) fn new(nodes: &[Node], check_interval: Duration) -> LinkManager {
+    pub(crate) fn new(mapper: Arc<ClusterMap>, check_interval: Duration) -> LinkManager {
         LinkManager {
-            nodes: Arc::from(nodes),
-            check_interval,
+            mapper,
+            check_interval: SyncRwLock::new(check_interval),
         }
     }
 
-    async fn checker_task(factory: Factory, nodes: Arc<[Node]>, period: Duration) {
-        let mut interval = interval(period);
+    /// Changes period of the connections check, it's applied after the current one ends
+    pub(crate) fn update(&self, check_interval: Duration) {
+        *self.check_interval.write().expect("link manager lock") = check_interval;
+    }
+
+    fn period(&self) -> Duration {
+        *self.check_interval.read().expect("link manager lock")
+    }
+
+    async fn checker_task(self: Arc<Self>, factory: Factory) {
         loop {
-            interval.tick().await;
+            // nodes of the current cluster map, so nodes added at runtime get connections
+            let mapper = self.mapper.current();
+            let nodes = mapper.nodes();
             let mut err_cnt = 0;
             let mut status = String::from("Node status: ");
-            for node in nodes.iter() {
+            for node in nodes.values() {
                 if let Err(e) = node.check(&factory).await {
                     error!(
                         "No connection to {}:[{}] - {}",
@@ -41,12 +52,12 @@ impl LinkManager {
             info!("{}{}", status, color::Fg(color::Reset));
             let cnt = nodes.len() - err_cnt;
             gauge!(AVAILABLE_NODES_COUNT, cnt as i64);
+            sleep(self.period()).await;
         }
     }
 
-    pub(crate) fn spawn_checker(&self, factory: Factory) {
-        let nodes = self.nodes.clone();
-        tokio::spawn(Self::checker_task(factory, nodes, self.check_interval));
+    pub(crate) fn spawn_checker(self: Arc<Self>, factory: Factory) -> JoinHandle<()> {
+        tokio::spawn(self.checker_task(factory))
     }
 
     pub(crate) async fn call_nodes<'a, F, T>(
@@ -1,4 +1,7 @@
 use super::prelude::*;
+use ring::digest::{digest, SHA256};
+use std::sync::RwLock as SyncRwLock;
+use tokio::sync::Mutex;
 
 /// Hash map with IDs as keys and `VDisk`s as values.
 pub type VDisksMap = HashMap<VDiskID, DataVDisk>;
@@ -14,13 +17,15 @@ pub struct Virtual {
     disks: Vec<DiskPath>,
     vdisks: VDisksMap,
     nodes: NodesMap,
+    key_length: usize,
+    cluster: Arc<ClusterConfig>,
 }
 
 impl Virtual {
     /// Creates new instance of the Virtual disk mapper
     pub async fn new(config: &NodeConfig, cluster: &ClusterConfig) -> Self {
         let mut vdisks = cluster.create_vdisks_map().unwrap();
-        let nodes = Self::prepare_nodes(&mut vdisks, cluster).await;
+        let nodes = Self::prepare_nodes(&mut vdisks, cluster, None).await;
         let local_node_name = config.name().to_owned();
         let local_node_address = nodes
             .values()
@@ -34,11 +39,55 @@ impl Virtual {
             disks: config.disks().clone(),
             vdisks,
             nodes,
+            key_length: cluster.key_length(),
+            cluster: Arc::new(cluster.clone()),
         }
     }
 
-    async fn prepare_nodes(vdisks: &mut VDisksMap, cluster: &ClusterConfig) -> NodesMap {
-        let nodes = cluster
+    /// Creates mapper for the new version of the cluster config. Connections to the nodes,
+    /// which are kept in the new config with the same address, are reused.
+    /// # Errors
+    /// New config must have greater version, the same key length and count of vdisks, because
+    /// keys are mapped to vdisks by the count, and must contain the local node.
+    pub(crate) async fn update(&self, cluster: &ClusterConfig) -> Result<Self, Error> {
+        if cluster.version() <= self.version() {
+            return Err(Error::failed(format!(
+                "cluster map version {} is not newer than current {}",
+                cluster.version(),
+                self.version()
+            )));
+        }
+        if cluster.key_length() != self.key_length {
+            return Err(Error::failed("cluster key length can't be changed"));
+        }
+        if cluster.vdisks().len() != self.vdisks.len() {
+            return Err(Error::failed("count of vdisks can't be changed"));
+        }
+        let local_node = cluster
+            .nodes()
+            .iter()
+            .find(|node| node.name() == self.local_node_name)
+            .ok_or_else(|| Error::failed("local node is missing in cluster map"))?;
+        let disks = local_node.disks().to_vec();
+        let mut vdisks = cluster.create_vdisks_map().map_err(Error::failed)?;
+        let nodes = Self::prepare_nodes(&mut vdisks, cluster, Some(&self.nodes)).await;
+        Ok(Self {
+            local_node_name: self.local_node_name.clone(),
+            local_node_address: self.local_node_address.clone(),
+            disks,
+            vdisks,
+            nodes,
+            key_length: self.key_length,
+            cluster: Arc::new(cluster.clone()),
+        })
+    }
+
+    async fn prepare_nodes(
+        vdisks: &mut VDisksMap,
+        cluster: &ClusterConfig,
+        current: Option<&NodesMap>,
+    ) -> NodesMap {
+        let mut nodes: NodesMap = cluster
             .nodes()
             .iter()
             .enumerate()
@@ -54,6 +103,15 @@ impl Virtual {
             .collect::<FuturesUnordered<_>>()
             .collect()
             .await;
+        for node in nodes.values_mut() {
+            if let Some(existing) = current
+                .into_iter()
+                .flat_map(HashMap::values)
+                .find(|n| n.name() == node.name() && n.address() == node.address())
+            {
+                node.share_connection(existing);
+            }
+        }
 
         vdisks
             .values_mut()
@@ -61,6 +119,15 @@ impl Virtual {
         nodes
     }
 
+    /// Version of the cluster config, the map was built from
+    pub(crate) fn version(&self) -> u64 {
+        self.cluster.version()
+    }
+
+    pub(crate) fn cluster_config(&self) -> &ClusterConfig {
+        &self.cluster
+    }
+
     pub(crate) fn local_node_name(&self) -> &str {
         &self.local_node_name
     }
@@ -109,7 +176,7 @@ impl Virtual {
         self.nodes
             .iter()
             .filter_map(|(id, node)| {
-                if target_indexes.all(|i| &i != id) {
+                if target_indexes.all(|i| &i != id) && !node.is_draining() {
                     Some(node)
                 } else {
                     None
@@ -119,12 +186,34 @@ impl Virtual {
             .collect()
     }
 
+    /// Keys with numeric value are mapped by value, other keys are mapped by hash
     pub(crate) fn vdisk_id_from_key(&self, key: BobKey) -> VDiskID {
-        (key % self.vdisks.len() as u64)
+        let value = key.to_u64().unwrap_or_else(|| {
+            let hash = digest(&SHA256, key.as_ref());
+            let prefix = hash.as_ref()[..8]
+                .try_into()
+                .expect("sha256 is longer than 8 bytes");
+            u64::from_be_bytes(prefix)
+        });
+        (value % self.vdisks.len() as u64)
             .try_into()
             .expect("u64 to u32")
     }
 
+    pub(crate) fn key_length(&self) -> usize {
+        self.key_length
+    }
+
+    /// Brings key to the cluster key length
+    pub(crate) fn normalize_key(&self, key: BobKey) -> Result<BobKey, Error> {
+        key.resize(self.key_length).ok_or_else(|| {
+            Error::invalid_key(format!(
+                "key {} is longer than {} bytes",
+                key, self.key_length
+            ))
+        })
+    }
+
     /// Returns ref to `VDisk` with given ID
     #[must_use]
     pub fn get_vdisk(&self, vdisk_id: VDiskID) -> Option<&DataVDisk> {
@@ -181,3 +270,46 @@ impl Virtual {
             .any(|node| node.name() == node_name)
     }
 }
+
+/// Current [`Virtual`] mapper, which can be replaced with the new version of the cluster config
+/// without restart. Components take the snapshot with [`ClusterMap::current`] for every
+/// operation, so operation works with the consistent map.
+#[derive(Debug)]
+pub struct ClusterMap {
+    current: SyncRwLock<Arc<Virtual>>,
+    update_lock: Mutex<()>,
+}
+
+impl ClusterMap {
+    /// Creates cluster map with the initial mapper
+    #[must_use]
+    pub fn new(mapper: Virtual) -> Self {
+        Self {
+            current: SyncRwLock::new(Arc::new(mapper)),
+            update_lock: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn current(&self) -> Arc<Virtual> {
+        self.current.read().expect("cluster map lock").clone()
+    }
+
+    /// Builds mapper for the new cluster config, prepares storages for it with `apply` and
+    /// replaces current one, returns new mapper. Current mapper is kept if `apply` fails.
+    pub(crate) async fn update<F, Fut>(
+        &self,
+        cluster: &ClusterConfig,
+        apply: F,
+    ) -> Result<Arc<Virtual>, Error>
+    where
+        F: FnOnce(Arc<Virtual>) -> Fut,
+        Fut: Future<Output = Result<(), Error>>,
+    {
+        let _guard = self.update_lock.lock().await;
+        let mapper = Arc::new(self.current().update(cluster).await?);
+        apply(mapper.clone()).await?;
+        *self.current.write().expect("cluster map lock") = mapper.clone();
+        info!("cluster map updated to version {}", mapper.version());
+        Ok(mapper)
+    }
+}
@@ -3,8 +3,10 @@ use metrics::{Key, Recorder, SetRecorderError};
 use std::time::Duration;
 use tokio::sync::mpsc::{channel, Sender};
 
+mod prometheus;
 mod retry_socket;
 mod send;
+pub(crate) use prometheus::PrometheusBuilder;
 use send::send_metrics;
 
 const DEFAULT_ADDRESS: &str = "localhost:2003";
@@ -82,11 +84,6 @@ impl GraphiteBuilder {
         self
     }
 
-    pub(crate) fn install(self) -> Result<(), SetRecorderError> {
-        let recorder = self.build();
-        metrics::set_boxed_recorder(Box::new(recorder))
-    }
-
     pub(crate) fn build(self) -> GraphiteRecorder {
         let (tx, rx) = channel(BUFFER_SIZE);
         let recorder = GraphiteRecorder { tx };
@@ -132,3 +129,38 @@ impl Recorder for GraphiteRecorder {
         )));
     }
 }
+
+/// Passes metrics to all exporters, used when both graphite and prometheus are enabled
+pub(crate) struct FanoutRecorder {
+    recorders: Vec<Box<dyn Recorder + Send + Sync>>,
+}
+
+impl FanoutRecorder {
+    pub(crate) fn new(recorders: Vec<Box<dyn Recorder + Send + Sync>>) -> Self {
+        Self { recorders }
+    }
+
+    pub(crate) fn install(self) -> Result<(), SetRecorderError> {
+        metrics::set_boxed_recorder(Box::new(self))
+    }
+}
+
+impl Recorder for FanoutRecorder {
+    fn increment_counter(&self, key: Key, value: u64) {
+        for recorder in &self.recorders {
+            recorder.increment_counter(key.clone(), value);
+        }
+    }
+
+    fn update_gauge(&self, key: Key, value: i64) {
+        for recorder in &self.recorders {
+            recorder.update_gauge(key.clone(), value);
+        }
+    }
+
+    fn record_histogram(&self, key: Key, value: u64) {
+        for recorder in &self.recorders {
+            recorder.record_histogram(key.clone(), value);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use metrics::{Key, Recorder};
+use std::collections::BTreeMap;
+use std::convert::Infallible;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+
+/// Upper bounds of the histogram buckets in seconds
+const BUCKETS: [f64; 12] = [
+    0.000_1, 0.000_5, 0.001, 0.005, 0.01, 0.05, 0.1, 0.25, 0.5, 1.0, 5.0, 10.0,
+];
+const OPERATIONS: [&str; 4] = ["put", "get", "exist", "delete"];
+const NAME_PREFIX: &str = "bob_";
+const REMOTE_CLIENT_FAMILY: &str = "remote_client";
+
+type Labels = Vec<(String, String)>;
+
+enum Value {
+    Counter(u64),
+    Gauge(i64),
+    Histogram {
+        buckets: [u64; BUCKETS.len()],
+        sum: f64,
+        count: u64,
+    },
+}
+
+impl Value {
+    fn histogram() -> Self {
+        Value::Histogram {
+            buckets: [0; BUCKETS.len()],
+            sum: 0.0,
+            count: 0,
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Counter(_) => "counter",
+            Value::Gauge(_) => "gauge",
+            Value::Histogram { .. } => "histogram",
+        }
+    }
+}
+
+/// Keeps the current values of all metrics and renders them in the Prometheus text format.
+/// Graphite style names are converted to Prometheus names with labels:
+/// `grinder.put_count` becomes `bob_grinder_count_total{node="..",operation="put"}` and
+/// `<local address>.<remote node>.get_timer` of the `BobClient` metrics becomes
+/// `bob_remote_client_timer_seconds{node="..",remote_node="..",operation="get"}`.
+struct Registry {
+    node_name: String,
+    client_prefix: String,
+    series: Mutex<BTreeMap<(String, Labels), Value>>,
+}
+
+impl Registry {
+    fn series_key(&self, key: &Key) -> (String, Labels) {
+        let mut labels = vec![("node".to_owned(), self.node_name.clone())];
+        labels.extend(
+            key.labels()
+                .map(|label| (label.key().to_owned(), label.value().to_owned())),
+        );
+        let name = key.name();
+        let name = if let Some(rest) = name.strip_prefix(&self.client_prefix) {
+            let mut parts = rest.rsplitn(2, '.');
+            let metric = parts.next().expect("rsplitn returns at least one item");
+            if let Some(remote_node) = parts.next() {
+                labels.push(("remote_node".to_owned(), remote_node.to_owned()));
+            }
+            format!("{}.{}", REMOTE_CLIENT_FAMILY, metric)
+        } else {
+            name.into_owned()
+        };
+        let mut parts = name.splitn(2, '.');
+        let family = parts.next().expect("splitn returns at least one item");
+        let name = match parts.next() {
+            Some(metric) => {
+                let mut metric_parts = metric.splitn(2, '_');
+                let operation = metric_parts
+                    .next()
+                    .expect("splitn returns at least one item");
+                match metric_parts.next() {
+                    Some(rest) if OPERATIONS.contains(&operation) => {
+                        labels.push(("operation".to_owned(), operation.to_owned()));
+                        format!("{}_{}", family, rest)
+                    }
+                    _ => format!("{}_{}", family, metric),
+                }
+            }
+            None => family.to_owned(),
+        };
+        (Self::sanitize(&name), labels)
+    }
+
+    fn sanitize(name: &str) -> String {
+        let name: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        NAME_PREFIX.to_owned() + &name
+    }
+
+    fn update(&self, key: &Key, f: impl FnOnce(&mut Value), default: impl FnOnce() -> Value) {
+        let series_key = self.series_key(key);
+        let mut series = self.series.lock().expect("metrics registry lock");
+        f(series.entry(series_key).or_insert_with(default));
+    }
+
+    fn increment_counter(&self, key: &Key, value: u64) {
+        self.update(
+            key,
+            |v| match v {
+                Value::Counter(counter) => *counter += value,
+                // counters with initial zero value may be updated as gauges later
+                _ => *v = Value::Counter(value),
+            },
+            || Value::Counter(0),
+        );
+    }
+
+    fn update_gauge(&self, key: &Key, value: i64) {
+        self.update(key, |v| *v = Value::Gauge(value), || Value::Gauge(0));
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    fn record_histogram(&self, key: &Key, nanos: u64) {
+        let seconds = nanos as f64 / 1_000_000_000.0;
+        self.update(
+            key,
+            |v| {
+                if !matches!(v, Value::Histogram { .. }) {
+                    *v = Value::histogram();
+                }
+                if let Value::Histogram {
+                    buckets,
+                    sum,
+                    count,
+                } = v
+                {
+                    for (bucket, bound) in buckets.iter_mut().zip(BUCKETS.iter()) {
+                        if seconds <= *bound {
+                            *bucket += 1;
+                        }
+                    }
+                    *sum += seconds;
+                    *count += 1;
+                }
+            },
+            Value::histogram,
+        );
+    }
+
+    fn render(&self) -> String {
+        let series = self.series.lock().expect("metrics registry lock");
+        // series of the same name may have values of different types, e.g. get timer is a
+        // counter and other timers are histograms, so samples are grouped by the rendered name
+        // to write the type of each family once
+        let mut families = BTreeMap::<_, Vec<_>>::new();
+        for ((name, labels), value) in series.iter() {
+            let name = match value {
+                Value::Counter(_) => format!("{}_total", name),
+                Value::Gauge(_) => name.clone(),
+                Value::Histogram { .. } => format!("{}_seconds", name),
+            };
+            families.entry(name).or_default().push((labels, value));
+        }
+        let mut result = String::new();
+        for (name, samples) in families {
+            let _ = writeln!(result, "# TYPE {} {}", name, samples[0].1.type_name());
+            for (labels, value) in samples {
+                Self::write_value(&mut result, &name, labels, value);
+            }
+        }
+        result
+    }
+
+    fn write_value(result: &mut String, name: &str, labels: &[(String, String)], value: &Value) {
+        match value {
+            Value::Counter(v) => Self::write_sample(result, name, labels, None, v),
+            Value::Gauge(v) => Self::write_sample(result, name, labels, None, v),
+            Value::Histogram {
+                buckets,
+                sum,
+                count,
+            } => {
+                let bucket_name = format!("{}_bucket", name);
+                for (bound, bucket) in BUCKETS.iter().zip(buckets.iter()) {
+                    let le = bound.to_string();
+                    Self::write_sample(result, &bucket_name, labels, Some(&le), bucket);
+                }
+                Self::write_sample(result, &bucket_name, labels, Some("+Inf"), count);
+                Self::write_sample(result, &format!("{}_sum", name), labels, None, sum);
+                Self::write_sample(result, &format!("{}_count", name), labels, None, count);
+            }
+        }
+    }
+
+    fn write_sample(
+        result: &mut String,
+        name: &str,
+        labels: &[(String, String)],
+        le: Option<&str>,
+        value: impl std::fmt::Display,
+    ) {
+        let labels = labels
+            .iter()
+            .map(|(k, v)| (k.as_str(), v.as_str()))
+            .chain(le.map(|le| ("le", le)))
+            .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+            .collect::<Vec<_>>()
+            .join(",");
+        let _ = writeln!(result, "{}{{{}}} {}", name, labels, value);
+    }
+}
+
+pub(crate) struct PrometheusRecorder {
+    registry: Arc<Registry>,
+}
+
+pub(crate) struct PrometheusBuilder {
+    address: SocketAddr,
+    node_name: String,
+    client_prefix: String,
+}
+
+impl PrometheusBuilder {
+    /// `client_prefix` is the prefix of the `BobClient` metrics, which is the local node address
+    pub(crate) fn new(address: SocketAddr, node_name: String, client_prefix: &str) -> Self {
+        Self {
+            address,
+            node_name,
+            client_prefix: client_prefix.to_owned() + ".",
+        }
+    }
+
+    /// Builds recorder and spawns the HTTP server, which exposes metrics on `/metrics`
+    pub(crate) fn build(self) -> PrometheusRecorder {
+        let registry = Arc::new(Registry {
+            node_name: self.node_name,
+            client_prefix: self.client_prefix,
+            series: Mutex::default(),
+        });
+        tokio::spawn(serve(self.address, registry.clone()));
+        PrometheusRecorder { registry }
+    }
+}
+
+async fn serve(address: SocketAddr, registry: Arc<Registry>) {
+    let make_service = make_service_fn(move |_| {
+        let registry = registry.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                let registry = registry.clone();
+                async move { Ok::<_, Infallible>(respond(&req, &registry)) }
+            }))
+        }
+    });
+    info!(
+        "prometheus metrics are exposed on http://{}/metrics",
+        address
+    );
+    if let Err(e) = Server::bind(&address).serve(make_service).await {
+        error!("prometheus metrics server error: {}", e);
+    }
+}
+
+fn respond(req: &Request<Body>, registry: &Registry) -> Response<Body> {
+    if req.method() == Method::GET && req.uri().path() == "/metrics" {
+        Response::builder()
+            .header("Content-Type", "text/plain; version=0.0.4")
+            .body(Body::from(registry.render()))
+            .expect("valid response")
+    } else {
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .body(Body::empty())
+            .expect("valid response")
+    }
+}
+
+impl Recorder for PrometheusRecorder {
+    fn increment_counter(&self, key: Key, value: u64) {
+        self.registry.increment_counter(&key, value);
+    }
+
+    fn update_gauge(&self, key: Key, value: i64) {
+        self.registry.update_gauge(&key, value);
+    }
+
+    fn record_histogram(&self, key: Key, value: u64) {
+        self.registry.record_histogram(&key, value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> Registry {
+        Registry {
+            node_name: "node1".to_owned(),
+            client_prefix: "127.0.0.1:20000.".to_owned(),
+            series: Mutex::default(),
+        }
+    }
+
+    #[test]
+    fn names_are_converted_to_labels() {
+        let registry = registry();
+        registry.increment_counter(&Key::from_name("grinder.put_count"), 2);
+        registry.update_gauge(&Key::from_name("backend.blob_count"), 5);
+        registry.record_histogram(
+            &Key::from_name("127.0.0.1:20000.node2.get_timer"),
+            2_000_000,
+        );
+        let pearl = Key::from_name_and_labels(
+            "pearl.get_error_count",
+            metrics::labels!("vdisk" => "3", "disk" => "d1"),
+        );
+        registry.increment_counter(&pearl, 1);
+        let text = registry.render();
+        assert!(text.contains("# TYPE bob_grinder_count_total counter\n"));
+        assert!(text.contains("bob_grinder_count_total{node=\"node1\",operation=\"put\"} 2\n"));
+        assert!(text.contains("bob_backend_blob_count{node=\"node1\"} 5\n"));
+        assert!(text.contains(
+            "bob_pearl_error_count_total{node=\"node1\",vdisk=\"3\",disk=\"d1\",operation=\"get\"} 1\n"
+        ));
+        let labels = "node=\"node1\",remote_node=\"node2\",operation=\"get\"";
+        assert!(text.contains("# TYPE bob_remote_client_timer_seconds histogram\n"));
+        assert!(text.contains(&format!(
+            "bob_remote_client_timer_seconds_bucket{{{},le=\"0.001\"}} 0\n",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "bob_remote_client_timer_seconds_bucket{{{},le=\"0.005\"}} 1\n",
+            labels
+        )));
+        assert!(text.contains(&format!(
+            "bob_remote_client_timer_seconds_count{{{}}} 1\n",
+            labels
+        )));
+    }
+
+    #[test]
+    fn families_of_mixed_types_are_typed_once() {
+        let registry = registry();
+        registry.record_histogram(&Key::from_name("grinder.delete_timer"), 1_000);
+        registry.increment_counter(&Key::from_name("grinder.get_timer"), 1_000);
+        registry.record_histogram(&Key::from_name("grinder.put_timer"), 1_000);
+        let text = registry.render();
+        let types: Vec<_> = text.lines().filter(|l| l.starts_with("# TYPE")).collect();
+        assert_eq!(
+            types,
+            vec![
+                "# TYPE bob_grinder_timer_seconds histogram",
+                "# TYPE bob_grinder_timer_total counter",
+            ]
+        );
+        assert!(text.contains("bob_grinder_timer_total{node=\"node1\",operation=\"get\"} 1000\n"));
+        // samples of the family follow its type line
+        let family: Vec<_> = text
+            .lines()
+            .skip_while(|l| !l.starts_with("# TYPE bob_grinder_timer_seconds"))
+            .skip(1)
+            .take_while(|l| !l.starts_with('#'))
+            .collect();
+        assert!(family.iter().any(|l| l.contains("operation=\"delete\"")));
+        assert!(family.iter().any(|l| l.contains("operation=\"put\"")));
+    }
+}
@@ -1,4 +1,5 @@
 use super::prelude::*;
+use metrics_ext::Recorder;
 
 mod exporter;
 
@@ -13,7 +14,7 @@ pub const GRINDER_PUT_TIMER: &str = "grinder.put_timer";
 pub const GRINDER_GET_COUNTER: &str = "grinder.get_count";
 /// Counts number of GET requests return error, processed by Grinder
 pub const GRINDER_GET_ERROR_COUNT_COUNTER: &str = "grinder.get_error_count";
-/// Measures processing time of the GET request
+/// Sums processing time of the GET requests in nanoseconds, unlike other timers it's a counter
 pub const GRINDER_GET_TIMER: &str = "grinder.get_timer";
 
 /// Counts number of EXIST requests, processed by Grinder
@@ -23,6 +24,13 @@ pub const GRINDER_EXIST_ERROR_COUNT_COUNTER: &str = "grinder.exist_error_count";
 /// Measures processing time of the EXIST request
 pub const GRINDER_EXIST_TIMER: &str = "grinder.exist_timer";
 
+/// Counts number of DELETE requests, processed by Grinder
+pub const GRINDER_DELETE_COUNTER: &str = "grinder.delete_count";
+/// Counts number of DELETE requests return error, processed by Grinder
+pub const GRINDER_DELETE_ERROR_COUNT_COUNTER: &str = "grinder.delete_error_count";
+/// Measures processing time of the DELETE request
+pub const GRINDER_DELETE_TIMER: &str = "grinder.delete_timer";
+
 /// Counts number of PUT requests, processed by Client
 pub const CLIENT_PUT_COUNTER: &str = "client.put_count";
 /// Counts number of PUT requests return error, processed by Client
@@ -44,17 +52,43 @@ pub const CLIENT_EXIST_ERROR_COUNT_COUNTER: &str = "client.exist_error_count";
 /// Measures processing time of the EXIST request
 pub const CLIENT_EXIST_TIMER: &str = "client.exist_timer";
 
+/// Counts number of DELETE requests, processed by Client
+pub const CLIENT_DELETE_COUNTER: &str = "client.delete_count";
+/// Counts number of DELETE requests return error, processed by Client
+pub const CLIENT_DELETE_ERROR_COUNT_COUNTER: &str = "client.delete_error_count";
+/// Measures processing time of the DELETE request
+pub const CLIENT_DELETE_TIMER: &str = "client.delete_timer";
+
+/// Counts number of replicas updated by read-repair
+pub const CLUSTER_READ_REPAIR_COUNTER: &str = "cluster.read_repair_count";
+/// Counts number of failed read-repair writes
+pub const CLUSTER_READ_REPAIR_ERROR_COUNTER: &str = "cluster.read_repair_error_count";
+
 /// Observes number of connected nodes
 pub const AVAILABLE_NODES_COUNT: &str = "link_manager.nodes_number";
 
 /// Observes if bob has started already
 pub const BACKEND_STATE: &str = "backend.backend_state";
+/// Count local vdisks
+pub const BACKEND_VDISKS_TOTAL: &str = "backend.vdisks_total";
+/// Count local vdisks, which storages are loaded and serve operations
+pub const BACKEND_VDISKS_READY: &str = "backend.vdisks_ready";
 /// Count blobs (without aliens)
 pub const BLOBS_COUNT: &str = "backend.blob_count";
 /// Count alien blobs
 pub const ALIEN_BLOBS_COUNT: &str = "backend.alien_count";
 /// Count memory occupied by indices
 pub const INDEX_MEMORY: &str = "backend.index_memory";
+/// Count records checked by the last scrub
+pub const SCRUB_CHECKED_RECORDS: &str = "backend.scrub_checked_records";
+/// Count corrupted records found by the last scrub
+pub const SCRUB_CORRUPTED_RECORDS: &str = "backend.scrub_corrupted_records";
+/// Count holders, which records couldn't be listed by the last scrub
+pub const SCRUB_UNCHECKED_HOLDERS: &str = "backend.scrub_unchecked_holders";
+/// Free space of the local disk in bytes
+pub const DISK_FREE_SPACE: &str = "backend.disk_free_space";
+/// Total space of the local disk in bytes
+pub const DISK_TOTAL_SPACE: &str = "backend.disk_total_space";
 
 /// Type to measure time of requests processing
 pub type Timer = Instant;
@@ -119,6 +153,22 @@ impl BobClient {
             timer.elapsed().as_nanos() as u64
         );
     }
+
+    pub(crate) fn delete_count(&self) {
+        counter!(self.prefix.clone() + ".delete_count", 1);
+    }
+
+    pub(crate) fn delete_error_count(&self) {
+        counter!(self.prefix.clone() + ".delete_error_count", 1);
+    }
+
+    #[allow(clippy::cast_possible_truncation)]
+    pub(crate) fn delete_timer_stop(&self, timer: Timer) {
+        timing!(
+            self.prefix.clone() + ".delete_timer",
+            timer.elapsed().as_nanos() as u64
+        );
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -152,9 +202,22 @@ pub fn init_counters(
     local_address: &str,
 ) -> Arc<dyn ContainerBuilder + Send + Sync> {
     let prefix = local_address;
-    exporter::GraphiteBuilder::new()
-        .set_address(node_config.metrics().graphite().to_string())
-        .set_interval(Duration::from_secs(1))
+    let config = node_config.metrics();
+    let mut recorders: Vec<Box<dyn Recorder + Send + Sync>> = Vec::new();
+    if let Some(graphite) = config.graphite() {
+        let recorder = exporter::GraphiteBuilder::new()
+            .set_address(graphite.to_string())
+            .set_interval(Duration::from_secs(1))
+            .build();
+        recorders.push(Box::new(recorder));
+    }
+    if let Some(address) = config.prometheus() {
+        let recorder =
+            exporter::PrometheusBuilder::new(address, node_config.name().to_owned(), prefix)
+                .build();
+        recorders.push(Box::new(recorder));
+    }
+    exporter::FanoutRecorder::new(recorders)
         .install()
         .expect("Can't install metrics");
     let container = MetricsContainer::new(Duration::from_secs(1), prefix.to_string());
@@ -164,6 +227,7 @@ pub fn init_counters(
     );
     let metrics = Arc::new(container);
     init_grinder();
+    init_cluster();
     init_bob_client();
     init_backend();
     init_link_manager();
@@ -178,12 +242,24 @@ fn init_grinder() {
     counter!(GRINDER_GET_ERROR_COUNT_COUNTER, 0);
     counter!(GRINDER_PUT_ERROR_COUNT_COUNTER, 0);
     counter!(GRINDER_EXIST_ERROR_COUNT_COUNTER, 0);
+    counter!(GRINDER_DELETE_COUNTER, 0);
+    counter!(GRINDER_DELETE_ERROR_COUNT_COUNTER, 0);
+}
+
+fn init_cluster() {
+    counter!(CLUSTER_READ_REPAIR_COUNTER, 0);
+    counter!(CLUSTER_READ_REPAIR_ERROR_COUNTER, 0);
 }
 
 fn init_backend() {
-    counter!(BACKEND_STATE, 0);
-    counter!(BLOBS_COUNT, 0);
-    counter!(ALIEN_BLOBS_COUNT, 0);
+    gauge!(BACKEND_STATE, 0);
+    gauge!(BACKEND_VDISKS_TOTAL, 0);
+    gauge!(BACKEND_VDISKS_READY, 0);
+    gauge!(BLOBS_COUNT, 0);
+    gauge!(ALIEN_BLOBS_COUNT, 0);
+    gauge!(SCRUB_CHECKED_RECORDS, 0);
+    gauge!(SCRUB_CORRUPTED_RECORDS, 0);
+    gauge!(SCRUB_UNCHECKED_HOLDERS, 0);
 }
 
 fn init_link_manager() {
@@ -197,4 +273,6 @@ fn init_bob_client() {
     counter!(CLIENT_GET_ERROR_COUNT_COUNTER, 0);
     counter!(CLIENT_PUT_ERROR_COUNT_COUNTER, 0);
     counter!(CLIENT_EXIST_ERROR_COUNT_COUNTER, 0);
+    counter!(CLIENT_DELETE_COUNTER, 0);
+    counter!(CLIENT_DELETE_ERROR_COUNT_COUNTER, 0);
 }
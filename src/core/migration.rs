@@ -0,0 +1,754 @@
+use super::prelude::*;
+use crate::core::backend::{Group, HolderDigest, RecordVersion};
+use crate::core::data::VDisk as DataVDisk;
+use std::fs::{rename, write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex as SyncMutex;
+use std::time::Duration;
+use tokio::runtime::Handle;
+
+/// Count of keys requested from the source at once
+const BATCH_SIZE: usize = 100;
+const RETRY_INTERVAL: Duration = Duration::from_secs(30);
+// progress is saved in the directory of the target group and removed once it is caught up
+const PROGRESS_FILE_NAME: &str = "migration.yaml";
+
+/// Replica of the vdisk from the previous cluster map, which migrating data is pulled from
+#[derive(Debug, Clone)]
+pub(crate) enum Source {
+    /// Released group on the other disk of the local node
+    Local(Group),
+    Remote(Node),
+}
+
+impl Source {
+    fn name(&self) -> String {
+        match self {
+            Self::Local(group) => Self::local_name(group),
+            Self::Remote(node) => node.name().to_owned(),
+        }
+    }
+
+    fn local_name(group: &Group) -> String {
+        format!("{}/{}", group.node_name(), group.disk_name())
+    }
+
+    /// Finds source by the saved name among the released groups and the remote nodes
+    fn find(name: &str, current: &Virtual, released: &[Group]) -> Option<Self> {
+        let local = released
+            .iter()
+            .find(|group| Self::local_name(group) == name)
+            .cloned()
+            .map(Self::Local);
+        local.or_else(|| {
+            current
+                .nodes()
+                .values()
+                .find(|node| node.name() == name && node.name() != current.local_node_name())
+                .cloned()
+                .map(Self::Remote)
+        })
+    }
+
+    async fn client(node: &Node) -> Result<BobClient, Error> {
+        node.get_connection()
+            .await
+            .ok_or_else(|| Error::failed(format!("no connection to {}", node.name())))
+    }
+
+    async fn digest(&self, vdisk_id: VDiskID) -> Result<Vec<HolderDigest>, Error> {
+        match self {
+            Self::Local(group) => group.digest().await,
+            Self::Remote(node) => Self::client(node)
+                .await?
+                .digest(vdisk_id)
+                .await
+                .map(NodeOutput::into_inner)
+                .map_err(NodeOutput::into_inner),
+        }
+    }
+
+    async fn versions(
+        &self,
+        vdisk_id: VDiskID,
+        start_timestamp: u64,
+    ) -> Result<Vec<RecordVersion>, Error> {
+        match self {
+            Self::Local(group) => group.versions(start_timestamp).await,
+            Self::Remote(node) => Self::client(node)
+                .await?
+                .versions(vdisk_id, start_timestamp)
+                .await
+                .map(NodeOutput::into_inner)
+                .map_err(NodeOutput::into_inner),
+        }
+    }
+
+    async fn records(
+        &self,
+        vdisk_id: VDiskID,
+        start_timestamp: u64,
+        keys: Vec<BobKey>,
+    ) -> Result<Vec<(BobKey, BobData)>, Error> {
+        match self {
+            Self::Local(group) => group.records(start_timestamp, &keys).await,
+            Self::Remote(node) => Self::client(node)
+                .await?
+                .records(vdisk_id, start_timestamp, keys)
+                .await
+                .map(NodeOutput::into_inner)
+                .map_err(NodeOutput::into_inner),
+        }
+    }
+
+    async fn latest(&self, vdisk_id: VDiskID, key: BobKey) -> Result<Option<BobData>, Error> {
+        let records = match self {
+            Self::Local(group) => group.latest_records(&[key]).await?,
+            Self::Remote(node) => Self::client(node)
+                .await?
+                .latest_records(vdisk_id, vec![key])
+                .await
+                .map(NodeOutput::into_inner)
+                .map_err(NodeOutput::into_inner)?,
+        };
+        Ok(records.into_iter().next().map(|(_, data)| data))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum MigrationState {
+    Running,
+    Done,
+    /// Source is caught up, except holders without keys log, so reads of the vdisk are still
+    /// completed with the source data
+    Incomplete,
+    /// Migration is retried with the next source
+    Failed(String),
+}
+
+/// Progress of the vdisk migration
+#[derive(Debug, Clone)]
+pub(crate) struct MigrationStatus {
+    vdisk_id: VDiskID,
+    disk_name: String,
+    source: String,
+    state: MigrationState,
+    holders_total: usize,
+    holders_done: usize,
+    holders_skipped: usize,
+    records: usize,
+    bytes: u64,
+}
+
+impl MigrationStatus {
+    fn new(vdisk_id: VDiskID, disk_name: String) -> Self {
+        Self {
+            vdisk_id,
+            disk_name,
+            source: String::new(),
+            state: MigrationState::Running,
+            holders_total: 0,
+            holders_done: 0,
+            holders_skipped: 0,
+            records: 0,
+            bytes: 0,
+        }
+    }
+
+    pub(crate) fn vdisk_id(&self) -> VDiskID {
+        self.vdisk_id
+    }
+
+    pub(crate) fn disk_name(&self) -> &str {
+        &self.disk_name
+    }
+
+    /// Name of the current source replica
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub(crate) fn state(&self) -> &MigrationState {
+        &self.state
+    }
+
+    /// Holders of the source found by the last pass
+    pub(crate) fn holders_total(&self) -> usize {
+        self.holders_total
+    }
+
+    pub(crate) fn holders_done(&self) -> usize {
+        self.holders_done
+    }
+
+    /// Holders without keys log, which can't be enumerated and migrated
+    pub(crate) fn holders_skipped(&self) -> usize {
+        self.holders_skipped
+    }
+
+    /// Count of the pulled records
+    pub(crate) fn records(&self) -> usize {
+        self.records
+    }
+
+    /// Size of the pulled records data
+    pub(crate) fn bytes(&self) -> u64 {
+        self.bytes
+    }
+}
+
+/// Migration state, which is kept between restarts of the node
+#[derive(Debug, Serialize, Deserialize)]
+struct SavedProgress {
+    sources: Vec<String>,
+    records: usize,
+    bytes: u64,
+}
+
+impl SavedProgress {
+    fn read(path: &Path) -> Result<Option<Self>, Error> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = read_to_string(path)
+            .map_err(|e| Error::storage(format!("can't read {:?}: {}", path, e)))?;
+        serde_yaml::from_str(&content)
+            .map(Some)
+            .map_err(|e| Error::storage(format!("can't parse {:?}: {}", path, e)))
+    }
+
+    fn write(&self, path: &Path) -> Result<(), Error> {
+        let content = serde_yaml::to_string(self).map_err(|e| Error::failed(e.to_string()))?;
+        // file is replaced at once, so it's never read partially written
+        let tmp = path.with_extension("tmp");
+        write(&tmp, content)
+            .and_then(|_| rename(&tmp, path))
+            .map_err(|e| Error::storage(format!("can't write {:?}: {}", path, e)))
+    }
+}
+
+#[derive(Debug)]
+struct Progress {
+    sources: Vec<Source>,
+    current: AtomicUsize,
+    cancelled: AtomicBool,
+    status: SyncMutex<MigrationStatus>,
+    path: PathBuf,
+}
+
+impl Progress {
+    fn new(group: &Group, sources: Vec<Source>, status: MigrationStatus) -> Self {
+        Self {
+            sources,
+            current: AtomicUsize::new(0),
+            cancelled: AtomicBool::new(false),
+            status: SyncMutex::new(status),
+            path: group.directory_path().join(PROGRESS_FILE_NAME),
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut MigrationStatus)) {
+        f(&mut self.status.lock().expect("migration status lock"));
+    }
+
+    /// Failed save is only logged, migration is started from the beginning after restart then
+    fn save(&self) {
+        if self.is_cancelled() {
+            return;
+        }
+        let status = self.status();
+        let saved = SavedProgress {
+            sources: self.sources.iter().map(Source::name).collect(),
+            records: status.records,
+            bytes: status.bytes,
+        };
+        if let Err(e) = saved.write(&self.path) {
+            warn!("migration: vdisk {} progress: {}", status.vdisk_id, e);
+        }
+    }
+
+    fn remove_saved(&self) {
+        if self.path.exists() {
+            if let Err(e) = remove_file(&self.path) {
+                warn!("migration: can't remove {:?}: {}", self.path, e);
+            }
+        }
+    }
+
+    fn status(&self) -> MigrationStatus {
+        self.status.lock().expect("migration status lock").clone()
+    }
+
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+
+    fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Source, until the local replica is caught up
+    fn pending_source(&self) -> Option<&Source> {
+        if self.status().state == MigrationState::Done || self.is_cancelled() {
+            None
+        } else {
+            self.sources.get(self.current.load(Ordering::Acquire))
+        }
+    }
+}
+
+/// Moves data of vdisks, which were assigned to the local node by the new cluster map, from
+/// replicas of the previous map. Holders are pulled record by record with bandwidth limit,
+/// until a pass finds nothing new. Until then local reads of the vdisk are completed
+/// with the data of the source replica.
+#[derive(Debug)]
+pub(crate) struct Migration {
+    bandwidth: Option<u64>,
+    migrations: SyncMutex<HashMap<VDiskID, Arc<Progress>>>,
+    // cluster map may be updated from the http api thread, which has its own short-lived runtime
+    runtime: Option<Handle>,
+}
+
+impl Migration {
+    pub(crate) fn new(bandwidth: Option<u64>) -> Self {
+        Self {
+            bandwidth,
+            migrations: SyncMutex::default(),
+            runtime: Handle::try_current().ok(),
+        }
+    }
+
+    /// Starts migration of the local groups, which replicas were not on the local disks in the
+    /// previous map, and cancels migrations of vdisks released by the local node
+    pub(crate) fn start(
+        &self,
+        previous: &Virtual,
+        current: &Virtual,
+        groups: Vec<Group>,
+        released: &[Group],
+    ) {
+        let local_node = current.local_node_name();
+        let mut migrations = self.migrations.lock().expect("migrations lock");
+        migrations.retain(|vdisk_id, progress| {
+            let disk_name = progress.status().disk_name;
+            let kept = groups
+                .iter()
+                .any(|group| group.vdisk_id() == *vdisk_id && group.disk_name() == disk_name);
+            if !kept {
+                progress.cancel();
+                progress.remove_saved();
+            }
+            kept
+        });
+        for group in groups {
+            let vdisk_id = group.vdisk_id();
+            let previous_vdisk = if let Some(vdisk) = previous.get_vdisk(vdisk_id) {
+                vdisk
+            } else {
+                continue;
+            };
+            let was_local = previous_vdisk
+                .replicas()
+                .iter()
+                .any(|r| r.node_name() == local_node && r.disk_name() == group.disk_name());
+            if was_local {
+                continue;
+            }
+            let sources = Self::sources(previous_vdisk, local_node, released);
+            if sources.is_empty() {
+                info!("migration: vdisk {} has no previous replicas", vdisk_id);
+                continue;
+            }
+            let status = MigrationStatus::new(vdisk_id, group.disk_name().to_owned());
+            let progress = Arc::new(Progress::new(&group, sources, status));
+            self.spawn(&mut migrations, group, progress);
+        }
+    }
+
+    /// Cancels all migrations before storages are closed, their saved progress is kept, so they
+    /// are resumed when storages are opened again
+    pub(crate) fn stop(&self) {
+        let migrations = self.migrations.lock().expect("migrations lock");
+        for progress in migrations.values() {
+            progress.cancel();
+        }
+    }
+
+    /// Resumes migrations of the local groups, which progress was saved before restart
+    pub(crate) fn resume(&self, current: &Virtual, groups: Vec<Group>, released: &[Group]) {
+        let mut migrations = self.migrations.lock().expect("migrations lock");
+        for group in groups {
+            let vdisk_id = group.vdisk_id();
+            let path = group.directory_path().join(PROGRESS_FILE_NAME);
+            let saved = match SavedProgress::read(&path) {
+                Ok(Some(saved)) => saved,
+                Ok(None) => continue,
+                Err(e) => {
+                    warn!("migration: vdisk {} is not resumed: {}", vdisk_id, e);
+                    continue;
+                }
+            };
+            let sources: Vec<_> = saved
+                .sources
+                .iter()
+                .filter_map(|name| Source::find(name, current, released))
+                .collect();
+            if sources.is_empty() {
+                warn!(
+                    "migration: vdisk {} is not resumed, sources {:?} are not found",
+                    vdisk_id, saved.sources
+                );
+                continue;
+            }
+            let mut status = MigrationStatus::new(vdisk_id, group.disk_name().to_owned());
+            status.records = saved.records;
+            status.bytes = saved.bytes;
+            let progress = Arc::new(Progress::new(&group, sources, status));
+            self.spawn(&mut migrations, group, progress);
+        }
+    }
+
+    fn spawn(
+        &self,
+        migrations: &mut HashMap<VDiskID, Arc<Progress>>,
+        group: Group,
+        progress: Arc<Progress>,
+    ) {
+        let vdisk_id = group.vdisk_id();
+        if let Some(previous) = migrations.insert(vdisk_id, progress.clone()) {
+            previous.cancel();
+        }
+        progress.save();
+        info!("migration: vdisk {} started", vdisk_id);
+        let task = Self::task(group, progress, self.bandwidth);
+        if let Some(runtime) = &self.runtime {
+            runtime.spawn(task);
+        } else {
+            tokio::spawn(task);
+        }
+    }
+
+    /// Replicas of the previous map, the local ones are preferred
+    fn sources(previous: &DataVDisk, local_node: &str, released: &[Group]) -> Vec<Source> {
+        let local = released
+            .iter()
+            .filter(|group| group.vdisk_id() == previous.id())
+            .filter(|group| {
+                previous
+                    .replicas()
+                    .iter()
+                    .any(|r| r.node_name() == local_node && r.disk_name() == group.disk_name())
+            })
+            .cloned()
+            .map(Source::Local);
+        let remote = previous
+            .nodes()
+            .iter()
+            .filter(|node| node.name() != local_node)
+            .cloned()
+            .map(Source::Remote);
+        local.chain(remote).collect()
+    }
+
+    pub(crate) fn statuses(&self) -> Vec<MigrationStatus> {
+        let migrations = self.migrations.lock().expect("migrations lock");
+        let mut statuses: Vec<_> = migrations.values().map(|p| p.status()).collect();
+        statuses.sort_by_key(MigrationStatus::vdisk_id);
+        statuses
+    }
+
+    pub(crate) fn status(&self, vdisk_id: VDiskID) -> Option<MigrationStatus> {
+        let migrations = self.migrations.lock().expect("migrations lock");
+        migrations.get(&vdisk_id).map(|progress| progress.status())
+    }
+
+    fn pending_source(&self, vdisk_id: VDiskID) -> Option<Source> {
+        let migrations = self.migrations.lock().expect("migrations lock");
+        migrations
+            .get(&vdisk_id)
+            .and_then(|progress| progress.pending_source().cloned())
+    }
+
+    /// Checks if local replica of the vdisk may still miss records of the migration source
+    pub(crate) fn is_pending(&self, vdisk_id: VDiskID) -> bool {
+        self.pending_source(vdisk_id).is_some()
+    }
+
+    /// Local replica of the migrating vdisk may miss records, so until it is caught up,
+    /// the most recent of the local and the source versions is returned
+    pub(crate) async fn read_through(
+        &self,
+        vdisk_id: VDiskID,
+        key: BobKey,
+        local: Result<BobData, Error>,
+    ) -> Result<BobData, Error> {
+        let source = if let Some(source) = self.pending_source(vdisk_id) {
+            source
+        } else {
+            return local;
+        };
+        match source.latest(vdisk_id, key).await {
+            Ok(remote) => Self::merge(key, local, remote),
+            Err(e) => {
+                debug!(
+                    "migration: can't read {} from {}: {}",
+                    key,
+                    source.name(),
+                    e
+                );
+                local
+            }
+        }
+    }
+
+    fn merge(
+        key: BobKey,
+        local: Result<BobData, Error>,
+        remote: Option<BobData>,
+    ) -> Result<BobData, Error> {
+        let remote = if let Some(remote) = remote {
+            remote
+        } else {
+            return local;
+        };
+        let local = match local {
+            Ok(data) => Some(data),
+            Err(e) => e
+                .deleted_at()
+                .map(|timestamp| BobData::tombstone(BobMeta::new(timestamp))),
+        };
+        let data = BobData::most_recent(local.into_iter().chain(Some(remote)))
+            .expect("remote version is present");
+        if data.is_tombstone() {
+            Err(Error::key_deleted(key, data.meta().timestamp()))
+        } else {
+            Ok(data)
+        }
+    }
+
+    async fn task(group: Group, progress: Arc<Progress>, bandwidth: Option<u64>) {
+        let vdisk_id = group.vdisk_id();
+        loop {
+            for (index, source) in progress.sources.iter().enumerate() {
+                if progress.is_cancelled() {
+                    info!("migration: vdisk {} cancelled", vdisk_id);
+                    return;
+                }
+                progress.current.store(index, Ordering::Release);
+                progress.update(|status| {
+                    status.source = source.name();
+                    status.state = MigrationState::Running;
+                });
+                match Self::catch_up(&group, source, &progress, bandwidth).await {
+                    Ok(()) if progress.is_cancelled() => {}
+                    Ok(()) => {
+                        Self::finish(&progress);
+                        return;
+                    }
+                    Err(e) => {
+                        warn!(
+                            "migration: vdisk {} from {} failed: {}",
+                            vdisk_id,
+                            source.name(),
+                            e
+                        );
+                        progress
+                            .update(|status| status.state = MigrationState::Failed(e.to_string()));
+                    }
+                }
+            }
+            sleep(RETRY_INTERVAL).await;
+        }
+    }
+
+    /// Saved progress of the incomplete migration is kept, so holders skipped by it are checked
+    /// again after restart
+    fn finish(progress: &Progress) {
+        progress.update(|status| {
+            status.state = if status.holders_skipped > 0 {
+                MigrationState::Incomplete
+            } else {
+                MigrationState::Done
+            }
+        });
+        let status = progress.status();
+        if status.state == MigrationState::Done {
+            progress.remove_saved();
+            info!(
+                "migration: vdisk {} caught up with {}, {} records ({} bytes) pulled",
+                status.vdisk_id, status.source, status.records, status.bytes
+            );
+        } else {
+            progress.save();
+            warn!(
+                "migration: vdisk {} caught up with {}, except {} holders without keys log",
+                status.vdisk_id, status.source, status.holders_skipped
+            );
+        }
+    }
+
+    /// Records written to the source during the pass are pulled by the next one
+    async fn catch_up(
+        group: &Group,
+        source: &Source,
+        progress: &Progress,
+        bandwidth: Option<u64>,
+    ) -> Result<(), Error> {
+        while Self::pull(group, source, progress, bandwidth).await? > 0 {}
+        Ok(())
+    }
+
+    /// Pulls all missing or outdated records from the source, returns count of pulled records
+    async fn pull(
+        group: &Group,
+        source: &Source,
+        progress: &Progress,
+        bandwidth: Option<u64>,
+    ) -> Result<usize, Error> {
+        let vdisk_id = group.vdisk_id();
+        let holders = source.digest(vdisk_id).await?;
+        progress.update(|status| {
+            status.holders_total = holders.len();
+            status.holders_done = 0;
+            status.holders_skipped = 0;
+        });
+        let mut pulled = 0;
+        for digest in holders {
+            if progress.is_cancelled() {
+                return Err(Error::failed("migration cancelled"));
+            }
+            let start_timestamp = digest.start_timestamp();
+            if digest.hash().is_none() {
+                warn!(
+                    "migration: holder {} of vdisk {} has no keys log and is skipped",
+                    start_timestamp, vdisk_id
+                );
+                progress.update(|status| status.holders_skipped += 1);
+                continue;
+            }
+            let local = group.versions(start_timestamp).await?;
+            let remote = source.versions(vdisk_id, start_timestamp).await?;
+            let (keys, _) = Repair::diff(&local, &remote);
+            for keys in keys.chunks(BATCH_SIZE) {
+                let records = source
+                    .records(vdisk_id, start_timestamp, keys.to_vec())
+                    .await?;
+                let count = records.len();
+                let mut bytes = 0;
+                for (key, data) in records {
+                    bytes += data.inner().len() as u64;
+                    group.put(key, data).await?;
+                }
+                pulled += count;
+                progress.update(|status| {
+                    status.records += count;
+                    status.bytes += bytes;
+                });
+                if let Some(delay) = Self::throttle_delay(bytes, bandwidth) {
+                    sleep(delay).await;
+                }
+            }
+            progress.update(|status| status.holders_done += 1);
+            progress.save();
+        }
+        Ok(pulled)
+    }
+
+    /// Checks if all replicas of the current map hold the records of the released group,
+    /// holders without keys log can't be compared, so their groups are never replicated
+    pub(crate) async fn is_replicated(
+        &self,
+        group: &Group,
+        current: &Virtual,
+        local_groups: &[Group],
+    ) -> Result<bool, Error> {
+        let vdisk_id = group.vdisk_id();
+        // local replica may still read through the released group
+        if self.is_pending(vdisk_id) {
+            return Ok(false);
+        }
+        let vdisk = current
+            .get_vdisk(vdisk_id)
+            .ok_or_else(|| Error::vdisk_not_found(vdisk_id))?;
+        let released = group.digest().await?;
+        if vdisk.replicas().is_empty() || released.iter().any(|d| d.hash().is_none()) {
+            return Ok(false);
+        }
+        for replica in vdisk.replicas() {
+            let target = if replica.node_name() == current.local_node_name() {
+                local_groups
+                    .iter()
+                    .find(|g| g.vdisk_id() == vdisk_id && g.disk_name() == replica.disk_name())
+                    .cloned()
+                    .map(Source::Local)
+            } else {
+                current
+                    .nodes()
+                    .values()
+                    .find(|node| node.name() == replica.node_name())
+                    .cloned()
+                    .map(Source::Remote)
+            };
+            let target = target.ok_or_else(|| {
+                Error::failed(format!("replica {} is not found", replica.node_name()))
+            })?;
+            let digests = target.digest(vdisk_id).await?;
+            for digest in released.iter().filter(|d| !digests.contains(d)) {
+                let start_timestamp = digest.start_timestamp();
+                let local = group.versions(start_timestamp).await?;
+                let remote = target.versions(vdisk_id, start_timestamp).await?;
+                let (missing, _) = Repair::diff(&remote, &local);
+                if !missing.is_empty() {
+                    return Ok(false);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub(crate) fn throttle_delay(bytes: u64, bandwidth: Option<u64>) -> Option<Duration> {
+        bandwidth
+            .filter(|_| bytes > 0)
+            .map(|bandwidth| Duration::from_secs_f64(bytes as f64 / bandwidth as f64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merge_returns_most_recent_version() {
+        let key = BobKey::from(1);
+        let local = Ok(BobData::new(vec![1], BobMeta::new(10)));
+        let remote = Some(BobData::new(vec![2], BobMeta::new(20)));
+        let data = Migration::merge(key, local, remote).unwrap();
+        assert_eq!(data.inner(), &[2]);
+
+        let local = Err(Error::key_deleted(key, 30));
+        let remote = Some(BobData::new(vec![2], BobMeta::new(20)));
+        let err = Migration::merge(key, local, remote).unwrap_err();
+        assert_eq!(err.deleted_at(), Some(30));
+
+        let local = Err(Error::key_not_found(key));
+        let remote = Some(BobData::tombstone(BobMeta::new(20)));
+        let err = Migration::merge(key, local, remote).unwrap_err();
+        assert_eq!(err.deleted_at(), Some(20));
+
+        let local = Err(Error::key_not_found(key));
+        assert!(Migration::merge(key, local, None)
+            .unwrap_err()
+            .is_key_not_found());
+    }
+
+    #[test]
+    fn throttle_delay_depends_on_bandwidth() {
+        assert_eq!(Migration::throttle_delay(1000, None), None);
+        assert_eq!(Migration::throttle_delay(0, Some(100)), None);
+        assert_eq!(
+            Migration::throttle_delay(1000, Some(100)),
+            Some(Duration::from_secs(10))
+        );
+    }
+}
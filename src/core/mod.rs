@@ -1,26 +1,50 @@
 extern crate metrics as metrics_ext;
+/// Authentication and authorization of the api clients.
+pub mod auth;
 /// Component responsible for working with I/O.
 pub mod backend;
 /// GRPC client to deal with backend.
 pub mod bob_client;
+/// Component for storing large blobs in chunks
+pub(crate) mod chunks;
 /// Component for cleaning up memory
 pub(crate) mod cleaner;
 pub(crate) mod cluster;
+/// Component for merging closed partitions and removing superseded records
+pub(crate) mod compaction;
 /// Configuration tools.
 pub mod configs;
 pub(crate) mod counter;
 pub mod data;
+/// Component for draining the node before maintenance
+pub(crate) mod drain;
 pub(crate) mod error;
+/// Component for exchanging cluster map versions between nodes
+pub(crate) mod gossip;
 /// Component to manage cluster I/O and connections.
 pub mod grinder;
+/// Component for moving alien records back to their owners
+pub(crate) mod handoff;
 pub(crate) mod link_manager;
 /// Component to map storage space on disks.
 pub mod mapper;
 /// Tools for tracking bob different indicators.
 pub mod metrics;
+/// Component for moving vdisk data to new replicas
+pub(crate) mod migration;
 pub mod node;
+/// Component for reloading node config without restart
+pub mod reload;
+/// Component for repairing diverged vdisk replicas
+pub(crate) mod repair;
+/// Component for removing partitions with expired records
+pub(crate) mod retention;
+/// Component for verifying checksums of stored records
+pub(crate) mod scrubber;
 /// GRPC server to receive and process requests from clients.
 pub mod server;
+/// TLS of the grpc and http api and of the connections to other nodes.
+pub mod tls;
 
 pub(crate) use self::error::Error;
 pub(crate) use super::prelude::*;
@@ -31,42 +55,57 @@ mod prelude {
 
     pub(crate) use crate::metrics::{
         BobClient as BobClientMetrics, ContainerBuilder as MetricsContainerBuilder,
-        ALIEN_BLOBS_COUNT, AVAILABLE_NODES_COUNT, BACKEND_STATE, BLOBS_COUNT, CLIENT_EXIST_COUNTER,
+        ALIEN_BLOBS_COUNT, AVAILABLE_NODES_COUNT, BACKEND_STATE, BACKEND_VDISKS_READY,
+        BACKEND_VDISKS_TOTAL, BLOBS_COUNT, CLIENT_DELETE_COUNTER,
+        CLIENT_DELETE_ERROR_COUNT_COUNTER, CLIENT_DELETE_TIMER, CLIENT_EXIST_COUNTER,
         CLIENT_EXIST_ERROR_COUNT_COUNTER, CLIENT_EXIST_TIMER, CLIENT_GET_COUNTER,
         CLIENT_GET_ERROR_COUNT_COUNTER, CLIENT_GET_TIMER, CLIENT_PUT_COUNTER,
-        CLIENT_PUT_ERROR_COUNT_COUNTER, CLIENT_PUT_TIMER, GRINDER_EXIST_COUNTER,
-        GRINDER_EXIST_ERROR_COUNT_COUNTER, GRINDER_EXIST_TIMER, GRINDER_GET_COUNTER,
-        GRINDER_GET_ERROR_COUNT_COUNTER, GRINDER_GET_TIMER, GRINDER_PUT_COUNTER,
-        GRINDER_PUT_ERROR_COUNT_COUNTER, GRINDER_PUT_TIMER, INDEX_MEMORY,
+        CLIENT_PUT_ERROR_COUNT_COUNTER, CLIENT_PUT_TIMER, CLUSTER_READ_REPAIR_COUNTER,
+        CLUSTER_READ_REPAIR_ERROR_COUNTER, DISK_FREE_SPACE, DISK_TOTAL_SPACE,
+        GRINDER_DELETE_COUNTER, GRINDER_DELETE_ERROR_COUNT_COUNTER, GRINDER_DELETE_TIMER,
+        GRINDER_EXIST_COUNTER, GRINDER_EXIST_ERROR_COUNT_COUNTER, GRINDER_EXIST_TIMER,
+        GRINDER_GET_COUNTER, GRINDER_GET_ERROR_COUNT_COUNTER, GRINDER_GET_TIMER,
+        GRINDER_PUT_COUNTER, GRINDER_PUT_ERROR_COUNT_COUNTER, GRINDER_PUT_TIMER, INDEX_MEMORY,
+        SCRUB_CHECKED_RECORDS, SCRUB_CORRUPTED_RECORDS, SCRUB_UNCHECKED_HOLDERS,
     };
     pub(crate) use bob_client::{BobClient, Factory};
     pub(crate) use cleaner::Cleaner;
     pub(crate) use cluster::{get_cluster, Cluster};
-    pub(crate) use configs::{Cluster as ClusterConfig, Node as NodeConfig};
+    pub(crate) use compaction::Compaction;
+    pub(crate) use configs::{Cluster as ClusterConfig, Node as NodeConfig, ReadConsistency};
     pub(crate) use counter::Counter as BlobsCounter;
-    pub(crate) use data::{BobData, BobFlags, BobKey, BobMeta, BobOptions, DiskPath, VDiskID};
+    pub(crate) use data::{
+        BobData, BobFlags, BobHead, BobKey, BobMeta, BobOptions, DiskPath, VDiskID,
+    };
     pub(crate) use futures::{
         future, stream::FuturesUnordered, Future, FutureExt, StreamExt, TryFutureExt,
     };
+    pub(crate) use gossip::Gossip;
     pub(crate) use grinder::Grinder;
     pub(crate) use grpc::{
-        bob_api_server::BobApi, ExistRequest, ExistResponse, GetOptions, GetSource, Null, OpStatus,
-        PutOptions,
+        bob_api_server::BobApi, DeleteOptions, DeleteRequest, ExistRequest, ExistResponse,
+        GetOptions, GetSource, Null, OpStatus, PutOptions,
     };
+    pub(crate) use handoff::Handoff;
     pub(crate) use http::Uri;
     pub(crate) use link_manager::LinkManager;
-    pub(crate) use mapper::Virtual;
+    pub(crate) use mapper::{ClusterMap, Virtual};
     pub(crate) use metrics_ext::{counter, gauge, timing}; // !gauge will be used in additional metrics
+    pub(crate) use migration::Migration;
     pub(crate) use node::{Disk as NodeDisk, Node, Output as NodeOutput, ID as NodeID};
+    pub(crate) use repair::Repair;
+    pub(crate) use retention::Retention;
+    pub(crate) use scrubber::Scrubber;
     pub(crate) use std::time::Instant;
     pub(crate) use stopwatch::Stopwatch;
     pub(crate) use termion::color;
     pub(crate) use tokio::{
         net::lookup_host,
+        task::JoinHandle,
         time::{interval, sleep, timeout},
     };
     pub(crate) use tonic::{
-        transport::{Channel, Endpoint},
+        transport::{Channel, ClientTlsConfig, Endpoint},
         Code, Request, Response, Status,
     };
 }
@@ -74,16 +113,21 @@ mod prelude {
 #[cfg(test)]
 pub(crate) mod test_utils {
     use super::{
-        bob_client::{GetResult, PingResult, PutResult},
+        bob_client::{DeleteResult, GetResult, PingResult, PutResult},
+        configs::{cluster::tests::cluster_config, node::tests::node_config},
         prelude::*,
     };
+    use crate::grpc::{bob_api_server::BobApiServer, PingResponse};
+    use crate::server::Server as BobServer;
     use chrono::Local;
     use env_logger::fmt::{Color, Formatter as EnvFormatter};
     use log::{Level, Record};
     use std::io::Write;
+    use std::net::TcpListener as StdTcpListener;
+    use tonic::transport::{Server, ServerTlsConfig};
 
     pub(crate) fn ping_ok(node_name: String) -> PingResult {
-        Ok(NodeOutput::new(node_name, ()))
+        Ok(NodeOutput::new(node_name, PingResponse { draining: false }))
     }
 
     pub(crate) fn put_ok(node_name: String) -> PutResult {
@@ -95,6 +139,15 @@ pub(crate) mod test_utils {
         Err(NodeOutput::new(node_name, Error::internal()))
     }
 
+    pub(crate) fn delete_ok(node_name: String) -> DeleteResult {
+        Ok(NodeOutput::new(node_name, ()))
+    }
+
+    pub(crate) fn delete_err(node_name: String) -> DeleteResult {
+        debug!("return internal error on DELETE");
+        Err(NodeOutput::new(node_name, Error::internal()))
+    }
+
     pub(crate) fn get_ok(node_name: String, timestamp: u64) -> GetResult {
         let inner = BobData::new(vec![], BobMeta::new(timestamp));
         Ok(NodeOutput::new(node_name, inner))
@@ -105,6 +158,37 @@ pub(crate) mod test_utils {
         Err(NodeOutput::new(node_name, Error::internal()))
     }
 
+    /// Config and cluster map of the single node with in-memory backend
+    pub(crate) async fn single_node() -> (NodeConfig, Virtual) {
+        let node = node_config("0", 1);
+        let cluster = cluster_config(1, 1, 1);
+        cluster.check(&node).expect("check node config");
+        let mapper = Virtual::new(&node, &cluster).await;
+        (node, mapper)
+    }
+
+    pub(crate) async fn single_node_grinder() -> Grinder {
+        let (node, mapper) = single_node().await;
+        Grinder::new(mapper, &node)
+    }
+
+    /// Serves grpc api on the free local port
+    pub(crate) async fn serve_grpc(
+        service: BobApiServer<BobServer>,
+        tls: Option<ServerTlsConfig>,
+    ) -> SocketAddr {
+        let address = StdTcpListener::bind("127.0.0.1:0")
+            .and_then(|l| l.local_addr())
+            .expect("find free local port");
+        let mut server = Server::builder();
+        if let Some(tls) = tls {
+            server = server.tls_config(tls).expect("grpc tls config");
+        }
+        tokio::spawn(server.add_service(service).serve(address));
+        sleep(Duration::from_millis(200)).await;
+        address
+    }
+
     #[allow(dead_code)]
     pub(crate) fn init_logger() {
         let _ = env_logger::builder()
@@ -1,4 +1,5 @@
 use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use super::prelude::*;
 
@@ -12,6 +13,7 @@ pub(crate) struct Node {
     address: SocketAddr,
     index: ID,
     conn: Arc<RwLock<Option<BobClient>>>,
+    draining: Arc<AtomicBool>,
 }
 
 #[derive(Debug)]
@@ -37,6 +39,7 @@ impl Node {
             address,
             index,
             conn: Arc::default(),
+            draining: Arc::default(),
         }
     }
 
@@ -65,6 +68,23 @@ impl Node {
         self.address.to_string().replace(".", "_")
     }
 
+    /// Uses connection of the node from the previous cluster map
+    pub(crate) fn share_connection(&mut self, other: &Node) {
+        self.conn = other.conn.clone();
+        self.draining = other.draining.clone();
+    }
+
+    /// Node reported on the last ping, that it's draining before maintenance
+    pub(crate) fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn set_draining(&self, draining: bool) {
+        if self.draining.swap(draining, Ordering::Relaxed) != draining {
+            info!("node {} draining: {}", self.name, draining);
+        }
+    }
+
     pub(crate) async fn set_connection(&self, client: BobClient) {
         *self.conn.write().await = Some(client);
     }
@@ -79,13 +99,17 @@ impl Node {
 
     pub(crate) async fn check(&self, client_fatory: &Factory) -> Result<(), String> {
         if let Some(conn) = self.get_connection().await {
-            if let Err(e) = conn.ping().await {
-                debug!("Got broken connection to node {:?}", self);
-                self.clear_connection().await;
-                Err(format!("{:?}", e))
-            } else {
-                debug!("All good with pinging node {:?}", self);
-                Ok(())
+            match conn.ping().await {
+                Ok(output) => {
+                    debug!("All good with pinging node {:?}", self);
+                    self.set_draining(output.inner().draining);
+                    Ok(())
+                }
+                Err(e) => {
+                    debug!("Got broken connection to node {:?}", self);
+                    self.clear_connection().await;
+                    Err(format!("{:?}", e))
+                }
             }
         } else {
             debug!("will connect to {:?}", self);
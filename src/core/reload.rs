@@ -0,0 +1,164 @@
+use super::prelude::*;
+use log4rs::{
+    config::{Config as LogConfig, RawConfig as RawLogConfig},
+    Handle as LogHandle,
+};
+use std::{
+    sync::{Mutex as SyncMutex, Weak},
+    time::SystemTime,
+};
+
+// log config without refresh rate is checked with this interval, as its refresh rate may be set
+const LOG_CONFIG_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Re-reads node config file and applies fields, which can be changed without restart, to the
+/// running node. Config with other changed fields is rejected.
+pub struct ConfigReloader {
+    path: String,
+    current: SyncMutex<NodeConfig>,
+    log_handle: Option<LogHandle>,
+}
+
+impl ConfigReloader {
+    /// Creates reloader of the node config file, logger is reconfigured on reload only if its
+    /// handle is set
+    #[must_use]
+    pub fn new(path: String, config: NodeConfig, log_handle: Option<LogHandle>) -> Self {
+        Self {
+            path,
+            current: SyncMutex::new(config),
+            log_handle,
+        }
+    }
+
+    /// Reloads node config and applies it to the grinder components and logger, returns names
+    /// of the changed fields
+    pub(crate) fn reload(&self, grinder: &Grinder) -> Result<Vec<String>, String> {
+        let mut current = self.current.lock().expect("node config lock");
+        let config = NodeConfig::from_file(&self.path)?;
+        let changed = current.check_reload(&config)?;
+        // log config file itself may change, so it's reloaded even if its path is the same
+        if let Some(handle) = &self.log_handle {
+            handle.set_config(load_log_config(config.log_config())?);
+        }
+        grinder.apply_config(&config);
+        info!(
+            "node config {} reloaded, changed fields: {:?}",
+            self.path, changed
+        );
+        *current = config;
+        Ok(changed)
+    }
+
+    /// Starts re-applying the log config file on its modification at the `refresh_rate` set in
+    /// it, as `log4rs::init_file` does
+    pub(crate) fn spawn_log_refresh(self: &Arc<Self>) {
+        if self.log_handle.is_some() {
+            tokio::spawn(Self::refresh_log_config(Arc::downgrade(self)));
+        }
+    }
+
+    async fn refresh_log_config(reloader: Weak<Self>) {
+        let mut modified = None;
+        while let Some(reloader) = reloader.upgrade() {
+            let refresh_rate = reloader
+                .refresh_log_config_file(&mut modified)
+                .unwrap_or_else(|e| {
+                    warn!("{}", e);
+                    None
+                });
+            drop(reloader);
+            sleep(refresh_rate.unwrap_or(LOG_CONFIG_CHECK_INTERVAL)).await;
+        }
+    }
+
+    // applies log config if it was modified since the previous check, returns its refresh rate
+    fn refresh_log_config_file(
+        &self,
+        modified: &mut Option<SystemTime>,
+    ) -> Result<Option<Duration>, String> {
+        let path = self
+            .current
+            .lock()
+            .expect("node config lock")
+            .log_config()
+            .to_owned();
+        let raw = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_yaml::from_str::<RawLogConfig>(&s).map_err(|e| e.to_string()))
+            .map_err(|e| format!("can't refresh log config {}: {}", path, e))?;
+        let last_modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        let changed = modified.is_some() && *modified != last_modified;
+        *modified = last_modified;
+        if let (Some(handle), Some(_), true) = (&self.log_handle, raw.refresh_rate(), changed) {
+            handle.set_config(load_log_config(&path)?);
+            info!("log config {} refreshed", path);
+        }
+        Ok(raw.refresh_rate())
+    }
+}
+
+fn load_log_config(path: &str) -> Result<LogConfig, String> {
+    log4rs::config::load_config_file(path, Default::default())
+        .map_err(|e| format!("can't load log config {}: {}", path, e))
+}
+
+impl Debug for ConfigReloader {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("ConfigReloader")
+            .field("path", &self.path)
+            .field("log_handle", &self.log_handle.is_some())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::test_utils;
+    use serde_yaml::Value;
+
+    const DIR: &str = "/tmp/bob_reload";
+
+    // writes node config with the changed fields
+    fn write_config(config: &NodeConfig, changes: &[(&str, Value)]) -> String {
+        let mut value = serde_yaml::to_value(config).unwrap();
+        value["log_config"] = Value::from("logger.yaml");
+        for (field, new) in changes {
+            value[*field] = new.clone();
+        }
+        let path = format!("{}/node.yaml", DIR);
+        std::fs::write(&path, serde_yaml::to_string(&value).unwrap()).unwrap();
+        path
+    }
+
+    #[tokio::test]
+    async fn reload_applies_only_reloadable_fields() {
+        let _ = std::fs::remove_dir_all(DIR);
+        create_dir_all(DIR).unwrap();
+        let (node, mapper) = test_utils::single_node().await;
+        let grinder = Grinder::new(mapper, &node);
+        let path = write_config(&node, &[]);
+        let node = NodeConfig::from_file(&path).unwrap();
+        let reloader = ConfigReloader::new(path, node.clone(), None);
+        assert!(reloader.reload(&grinder).unwrap().is_empty());
+
+        let timeout = ("operation_timeout", Value::from("10sec"));
+        let soft_limit = ("open_blobs_soft_limit", Value::from(5));
+        write_config(&node, &[timeout.clone(), soft_limit]);
+        let changed = reloader.reload(&grinder).unwrap();
+        assert_eq!(changed, vec!["operation_timeout", "open_blobs_soft_limit"]);
+
+        write_config(&node, &[timeout, ("quorum", Value::from(2))]);
+        let e = reloader.reload(&grinder).unwrap_err();
+        assert!(e.contains("quorum"), "{}", e);
+        assert!(!e.contains("operation_timeout"), "{}", e);
+
+        write_config(
+            &node,
+            &[("operation_timeout", Value::from("not a duration"))],
+        );
+        assert!(reloader.reload(&grinder).is_err());
+        std::fs::remove_dir_all(DIR).unwrap();
+    }
+}
@@ -0,0 +1,309 @@
+use super::prelude::*;
+use crate::core::backend::{Group, HolderDigest, RecordVersion};
+use std::collections::BTreeSet;
+use std::ops::AddAssign;
+use std::time::Duration;
+
+// records are pulled in batches, so the response of the replica fits in memory
+const BATCH_SIZE: usize = 100;
+
+/// Repairs diverged replicas of local vdisks. Holders digests are compared with the peer
+/// replicas and only missing or outdated records are transferred.
+pub(crate) struct Repair {
+    interval: Option<Duration>,
+}
+
+/// Counts of the records transferred during repair
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct RepairReport {
+    pulled: usize,
+    pushed: usize,
+}
+
+impl RepairReport {
+    pub(crate) fn pulled(&self) -> usize {
+        self.pulled
+    }
+
+    pub(crate) fn pushed(&self) -> usize {
+        self.pushed
+    }
+}
+
+impl AddAssign for RepairReport {
+    fn add_assign(&mut self, other: Self) {
+        self.pulled += other.pulled;
+        self.pushed += other.pushed;
+    }
+}
+
+impl Repair {
+    pub(crate) fn new(interval: Option<Duration>) -> Self {
+        Self { interval }
+    }
+
+    pub(crate) fn spawn_task(&self, backend: Arc<Backend>) -> Option<JoinHandle<()>> {
+        if let Some(interval) = self.interval {
+            Some(tokio::spawn(Self::task(backend, interval)))
+        } else {
+            info!("scheduled replicas repair is disabled");
+            None
+        }
+    }
+
+    async fn task(backend: Arc<Backend>, t: Duration) {
+        let mut interval = interval(t);
+        loop {
+            interval.tick().await;
+            let vdisks = backend
+                .vdisks_groups()
+                .await
+                .map(|groups| groups.iter().map(Group::vdisk_id).collect::<Vec<_>>())
+                .unwrap_or_default();
+            for vdisk_id in vdisks {
+                if let Err(e) = Self::run_vdisk(&backend, vdisk_id).await {
+                    warn!("repair of vdisk {} failed: {}", vdisk_id, e);
+                }
+            }
+        }
+    }
+
+    pub(crate) async fn local_group(backend: &Backend, vdisk_id: VDiskID) -> Result<Group, Error> {
+        let groups = backend
+            .vdisks_groups()
+            .await
+            .ok_or_else(|| Error::failed("repair is supported only by pearl backend"))?;
+        groups
+            .iter()
+            .find(|group| group.vdisk_id() == vdisk_id)
+            .cloned()
+            .ok_or_else(|| Error::vdisk_not_found(vdisk_id))
+    }
+
+    /// Repairs local replica of the vdisk with all reachable replicas
+    pub(crate) async fn run_vdisk(
+        backend: &Backend,
+        vdisk_id: VDiskID,
+    ) -> Result<RepairReport, Error> {
+        let group = Self::local_group(backend, vdisk_id).await?;
+        let mapper = backend.mapper();
+        let vdisk = mapper
+            .get_vdisk(vdisk_id)
+            .ok_or_else(|| Error::vdisk_not_found(vdisk_id))?;
+        let mut report = RepairReport::default();
+        for node in vdisk.nodes() {
+            if node.name() == mapper.local_node_name() {
+                continue;
+            }
+            if let Some(client) = node.get_connection().await {
+                report += Self::repair_with(&group, &client).await?;
+            } else {
+                debug!(
+                    "repair: replica of vdisk {} on {} is unreachable",
+                    vdisk_id,
+                    node.name()
+                );
+            }
+        }
+        if report.pulled > 0 || report.pushed > 0 {
+            info!(
+                "vdisk {} repaired: {} records pulled, {} records pushed",
+                vdisk_id, report.pulled, report.pushed
+            );
+        }
+        Ok(report)
+    }
+
+    async fn repair_with(group: &Group, client: &BobClient) -> Result<RepairReport, Error> {
+        let vdisk_id = group.vdisk_id();
+        let local = group.digest().await?;
+        let remote = client
+            .digest(vdisk_id)
+            .await
+            .map_err(|e| e.into_inner())?
+            .into_inner();
+        let mut report = RepairReport::default();
+        for start_timestamp in Self::diverged_holders(&local, &remote) {
+            debug!(
+                "repair: holder {} of vdisk {} diverged with {}",
+                start_timestamp,
+                vdisk_id,
+                client.node().name()
+            );
+            let local_versions = group.versions(start_timestamp).await?;
+            let remote_versions = client
+                .versions(vdisk_id, start_timestamp)
+                .await
+                .map_err(|e| e.into_inner())?
+                .into_inner();
+            let (pull, push) = Self::diff(&local_versions, &remote_versions);
+            // compaction drops superseded versions, so keys with more recent local version are
+            // not transferred, otherwise they would be repaired endlessly
+            let latest = Self::latest_timestamps(group, pull.iter().chain(&push)).await?;
+            let remote_timestamps = Self::timestamps(&remote_versions);
+            let local_timestamps = Self::timestamps(&local_versions);
+            let pull: Vec<_> = pull
+                .into_iter()
+                .filter(|key| match (latest.get(key), remote_timestamps.get(key)) {
+                    (Some(latest), Some(remote)) => remote > latest,
+                    _ => true,
+                })
+                .collect();
+            let push: Vec<_> = push
+                .into_iter()
+                .filter(|key| match (latest.get(key), local_timestamps.get(key)) {
+                    (Some(latest), Some(local)) => local >= latest,
+                    _ => true,
+                })
+                .collect();
+            for keys in pull.chunks(BATCH_SIZE) {
+                let records = client
+                    .records(vdisk_id, start_timestamp, keys.to_vec())
+                    .await
+                    .map_err(|e| e.into_inner())?
+                    .into_inner();
+                for (key, data) in records {
+                    group.put(key, data).await?;
+                    report.pulled += 1;
+                }
+            }
+            for &key in &push {
+                let data = if let Some(data) = group.record(start_timestamp, key).await? {
+                    data
+                } else {
+                    continue;
+                };
+                let result = if data.is_tombstone() {
+                    client
+                        .delete(key, data.meta().clone(), DeleteOptions::new_local())
+                        .await
+                } else {
+                    client.put(key, data, PutOptions::new_local()).await
+                };
+                result.map_err(|e| e.into_inner())?;
+                report.pushed += 1;
+            }
+        }
+        Ok(report)
+    }
+
+    async fn latest_timestamps(
+        group: &Group,
+        keys: impl Iterator<Item = &BobKey>,
+    ) -> Result<HashMap<BobKey, u64>, Error> {
+        let keys: Vec<_> = keys.copied().collect();
+        let records = group.latest_records(&keys).await?;
+        Ok(records
+            .into_iter()
+            .map(|(key, data)| (key, data.meta().timestamp()))
+            .collect())
+    }
+
+    fn timestamps(versions: &[RecordVersion]) -> HashMap<BobKey, u64> {
+        versions
+            .iter()
+            .map(|version| (version.key(), version.timestamp()))
+            .collect()
+    }
+
+    /// Start timestamps of holders with different hashes or present only on one side.
+    /// Holders with unknown hash are skipped.
+    fn diverged_holders(local: &[HolderDigest], remote: &[HolderDigest]) -> Vec<u64> {
+        let local: HashMap<_, _> = local
+            .iter()
+            .map(|digest| (digest.start_timestamp(), digest))
+            .collect();
+        let remote: HashMap<_, _> = remote
+            .iter()
+            .map(|digest| (digest.start_timestamp(), digest))
+            .collect();
+        let timestamps: BTreeSet<_> = local.keys().chain(remote.keys()).copied().collect();
+        timestamps
+            .into_iter()
+            .filter(
+                |timestamp| match (local.get(timestamp), remote.get(timestamp)) {
+                    (Some(local), Some(remote)) => local.differs_from(remote),
+                    (Some(digest), None) | (None, Some(digest)) => {
+                        digest.hash().is_some() && digest.records() > 0
+                    }
+                    (None, None) => false,
+                },
+            )
+            .collect()
+    }
+
+    /// Returns keys which should be pulled from remote and pushed to remote
+    pub(crate) fn diff(
+        local: &[RecordVersion],
+        remote: &[RecordVersion],
+    ) -> (Vec<BobKey>, Vec<BobKey>) {
+        let local: HashMap<_, _> = local
+            .iter()
+            .map(|version| (version.key(), version))
+            .collect();
+        let remote: HashMap<_, _> = remote
+            .iter()
+            .map(|version| (version.key(), version))
+            .collect();
+        let pull = remote
+            .values()
+            .filter(|version| {
+                local
+                    .get(&version.key())
+                    .map_or(true, |local| version.is_newer_than(local))
+            })
+            .map(|version| version.key())
+            .collect();
+        let push = local
+            .values()
+            .filter(|version| {
+                remote
+                    .get(&version.key())
+                    .map_or(true, |remote| version.is_newer_than(remote))
+            })
+            .map(|version| version.key())
+            .collect();
+        (pull, push)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BobKey, HolderDigest, RecordVersion, Repair};
+
+    #[test]
+    fn diff_finds_missing_and_outdated_keys() {
+        let local = vec![
+            RecordVersion::new(BobKey::from(1), 10, false),
+            RecordVersion::new(BobKey::from(2), 10, false),
+            RecordVersion::new(BobKey::from(3), 20, false),
+        ];
+        let remote = vec![
+            RecordVersion::new(BobKey::from(1), 10, false),
+            RecordVersion::new(BobKey::from(2), 10, true),
+            RecordVersion::new(BobKey::from(3), 10, false),
+            RecordVersion::new(BobKey::from(4), 10, false),
+        ];
+        let (mut pull, push) = Repair::diff(&local, &remote);
+        pull.sort_unstable();
+        assert_eq!(pull, vec![BobKey::from(2), BobKey::from(4)]);
+        assert_eq!(push, vec![BobKey::from(3)]);
+    }
+
+    #[test]
+    fn diverged_holders_skip_unknown_and_equal() {
+        let versions = [RecordVersion::new(BobKey::from(1), 10, false)];
+        let local = vec![
+            HolderDigest::from_versions(0, &versions),
+            HolderDigest::from_versions(100, &versions),
+            HolderDigest::unknown(200, 1),
+        ];
+        let remote = vec![
+            HolderDigest::from_versions(0, &versions),
+            HolderDigest::from_versions(100, &[]),
+            HolderDigest::from_versions(200, &[]),
+            HolderDigest::from_versions(300, &versions),
+        ];
+        assert_eq!(Repair::diverged_holders(&local, &remote), vec![100, 300]);
+    }
+}
@@ -0,0 +1,144 @@
+use super::prelude::*;
+use crate::core::backend::{Group, Holder};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Keeps expiration settings and removes partitions which contain only expired records.
+/// Records expire by per-put TTL or by the node retention period, both are stored in the record
+/// at put, so changed period doesn't affect written records. Expired records are hidden from
+/// reads right away, but the disk space is reclaimed only with the whole partition.
+#[derive(Debug)]
+pub(crate) struct Retention {
+    period: Option<Duration>,
+    interval: Duration,
+}
+
+impl Retention {
+    pub(crate) fn new(period: Option<Duration>, interval: Duration) -> Self {
+        Self { period, interval }
+    }
+
+    /// Period after which records expire since their timestamp
+    pub(crate) fn period(&self) -> Option<Duration> {
+        self.period
+    }
+
+    pub(crate) fn spawn_task(&self, backend: Arc<Backend>) -> JoinHandle<()> {
+        tokio::spawn(Self::task(backend, self.interval))
+    }
+
+    async fn task(backend: Arc<Backend>, t: Duration) {
+        let mut interval = interval(t);
+        loop {
+            interval.tick().await;
+            let removed = Self::remove_expired(&backend).await;
+            if removed > 0 {
+                info!("retention: {} expired partitions removed", removed);
+            }
+        }
+    }
+
+    async fn remove_expired(backend: &Backend) -> usize {
+        let mut groups = backend.vdisks_groups().await.unwrap_or_default();
+        groups.extend(backend.alien_vdisks_groups().await.unwrap_or_default());
+        let mut removed = 0;
+        for group in &groups {
+            removed += Self::remove_expired_holders(group).await;
+        }
+        removed
+    }
+
+    /// Removes holders of the group with expired records only, returns count of removed holders.
+    /// Holder is kept while older holders have its keys, otherwise their older versions would be
+    /// returned by reads after removal.
+    pub(crate) async fn remove_expired_holders(group: &Group) -> usize {
+        let mut holders = group.holders().read().await.clone();
+        // older holders are removed first, so they don't keep newer ones
+        holders.sort_by_key(Holder::start_timestamp);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .expect("current time is before unix epoch")
+            .as_secs();
+        let mut removed = 0;
+        for holder in holders {
+            // the current partition still accepts writes
+            if holder.end_timestamp() > now {
+                continue;
+            }
+            let records_count = holder.records_count().await;
+            let keys = match Self::expired_keys(&holder).await {
+                Some(keys) => keys,
+                None => continue,
+            };
+            if Self::older_holders_have_keys(group, &holder, &keys).await {
+                debug!(
+                    "retention: {} is expired, but older partitions have its keys",
+                    holder.get_id()
+                );
+                continue;
+            }
+            match group
+                .remove_drained_holder(holder.start_timestamp(), records_count)
+                .await
+            {
+                Ok(true) => {
+                    info!(
+                        "retention: partition {} of vdisk {} on {} (node {}) removed, {} records expired",
+                        holder.start_timestamp(),
+                        group.vdisk_id(),
+                        group.disk_name(),
+                        group.node_name(),
+                        records_count,
+                    );
+                    removed += 1;
+                }
+                Ok(false) => debug!("retention: {} got new records", holder.get_id()),
+                Err(e) => warn!("retention: can't remove {}: {}", holder.get_id(), e),
+            }
+        }
+        removed
+    }
+
+    /// Keys of the holder if all its records are expired, tombstones and records without
+    /// expiration keep the partition alive
+    async fn expired_keys(holder: &Holder) -> Option<Vec<BobKey>> {
+        // records of the holder can be listed only by keys log
+        if !holder.has_complete_keys_log().await.unwrap_or(false) {
+            return None;
+        }
+        let keys = match holder.keys().await {
+            Ok(keys) => keys,
+            Err(e) => {
+                warn!("retention: can't read keys of {}: {}", holder.get_id(), e);
+                return None;
+            }
+        };
+        for &key in &keys {
+            match holder.read_head(key).await {
+                Ok(head) if !head.is_tombstone() && head.meta().is_expired() => {}
+                _ => return None,
+            }
+            tokio::task::yield_now().await;
+        }
+        Some(keys)
+    }
+
+    // holder is considered as having the key, if it can't be checked
+    async fn older_holders_have_keys(group: &Group, holder: &Holder, keys: &[BobKey]) -> bool {
+        let older: Vec<_> = group
+            .holders()
+            .read()
+            .await
+            .iter()
+            .filter(|older| older.start_timestamp() < holder.start_timestamp())
+            .cloned()
+            .collect();
+        for older in &older {
+            for &key in keys {
+                if older.exist(key).await.unwrap_or(true) {
+                    return true;
+                }
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,232 @@
+use super::prelude::*;
+use crate::core::backend::Group;
+use std::time::Duration;
+use tokio::sync::RwLock;
+
+/// Verifies checksums of the records in all holders of local and alien groups. Records are
+/// read one by one with yields in between, so scrubbing doesn't hold back client requests.
+/// Corrupted records are only reported, they are fixed by read-repair and replicas repair.
+/// Records of the holders without keys log can't be listed, such holders are reported as
+/// unchecked.
+pub(crate) struct Scrubber {
+    interval: Option<Duration>,
+    last_report: Arc<RwLock<Option<ScrubReport>>>,
+}
+
+/// Result of the scrubbing of all groups
+#[derive(Debug, Default, Clone)]
+pub(crate) struct ScrubReport {
+    checked: usize,
+    corrupted: Vec<CorruptedRecord>,
+    unchecked: Vec<UncheckedHolder>,
+}
+
+impl ScrubReport {
+    pub(crate) fn checked(&self) -> usize {
+        self.checked
+    }
+
+    pub(crate) fn corrupted(&self) -> &[CorruptedRecord] {
+        &self.corrupted
+    }
+
+    /// Holders with records, which weren't verified
+    pub(crate) fn unchecked(&self) -> &[UncheckedHolder] {
+        &self.unchecked
+    }
+
+    fn append(&mut self, other: Self) {
+        self.checked += other.checked;
+        self.corrupted.extend(other.corrupted);
+        self.unchecked.extend(other.unchecked);
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct CorruptedRecord {
+    vdisk_id: VDiskID,
+    node_name: String,
+    disk_name: String,
+    start_timestamp: u64,
+    key: BobKey,
+}
+
+impl CorruptedRecord {
+    pub(crate) fn vdisk_id(&self) -> VDiskID {
+        self.vdisk_id
+    }
+
+    /// Owner of the group, differs from the local node for aliens
+    pub(crate) fn node_name(&self) -> &str {
+        &self.node_name
+    }
+
+    pub(crate) fn disk_name(&self) -> &str {
+        &self.disk_name
+    }
+
+    /// Start timestamp of the holder with corrupted record
+    pub(crate) fn start_timestamp(&self) -> u64 {
+        self.start_timestamp
+    }
+
+    pub(crate) fn key(&self) -> BobKey {
+        self.key
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct UncheckedHolder {
+    vdisk_id: VDiskID,
+    node_name: String,
+    disk_name: String,
+    start_timestamp: u64,
+    records: usize,
+}
+
+impl UncheckedHolder {
+    pub(crate) fn vdisk_id(&self) -> VDiskID {
+        self.vdisk_id
+    }
+
+    /// Owner of the group, differs from the local node for aliens
+    pub(crate) fn node_name(&self) -> &str {
+        &self.node_name
+    }
+
+    pub(crate) fn disk_name(&self) -> &str {
+        &self.disk_name
+    }
+
+    pub(crate) fn start_timestamp(&self) -> u64 {
+        self.start_timestamp
+    }
+
+    /// Count of records in the holder
+    pub(crate) fn records(&self) -> usize {
+        self.records
+    }
+}
+
+impl Scrubber {
+    pub(crate) fn new(interval: Option<Duration>) -> Self {
+        Self {
+            interval,
+            last_report: Arc::default(),
+        }
+    }
+
+    pub(crate) fn spawn_task(&self, backend: Arc<Backend>) -> Option<JoinHandle<()>> {
+        if let Some(interval) = self.interval {
+            let last_report = self.last_report.clone();
+            Some(tokio::spawn(Self::task(backend, interval, last_report)))
+        } else {
+            info!("scheduled scrubbing is disabled");
+            None
+        }
+    }
+
+    async fn task(
+        backend: Arc<Backend>,
+        t: Duration,
+        last_report: Arc<RwLock<Option<ScrubReport>>>,
+    ) {
+        let mut interval = interval(t);
+        loop {
+            interval.tick().await;
+            let report = Self::scrub(&backend).await;
+            Self::save_report(&last_report, report).await;
+        }
+    }
+
+    /// Scrubs all groups and saves result as the last report
+    pub(crate) async fn run_once(&self, backend: &Backend) -> ScrubReport {
+        let report = Self::scrub(backend).await;
+        Self::save_report(&self.last_report, report.clone()).await;
+        report
+    }
+
+    /// Result of the last finished scrubbing, `None` if scrubbing wasn't run yet
+    pub(crate) async fn last_report(&self) -> Option<ScrubReport> {
+        self.last_report.read().await.clone()
+    }
+
+    async fn save_report(last_report: &RwLock<Option<ScrubReport>>, report: ScrubReport) {
+        gauge!(SCRUB_CHECKED_RECORDS, report.checked as i64);
+        gauge!(SCRUB_CORRUPTED_RECORDS, report.corrupted.len() as i64);
+        gauge!(SCRUB_UNCHECKED_HOLDERS, report.unchecked.len() as i64);
+        if !report.unchecked.is_empty() {
+            warn!(
+                "scrubbing: {} holders weren't checked, records can't be listed",
+                report.unchecked.len()
+            );
+        }
+        if report.corrupted.is_empty() {
+            info!("scrubbing finished: {} records checked", report.checked);
+        } else {
+            warn!(
+                "scrubbing finished: {} records checked, {} corrupted",
+                report.checked,
+                report.corrupted.len()
+            );
+        }
+        *last_report.write().await = Some(report);
+    }
+
+    async fn scrub(backend: &Backend) -> ScrubReport {
+        let mut groups = backend.vdisks_groups().await.unwrap_or_default();
+        groups.extend(backend.alien_vdisks_groups().await.unwrap_or_default());
+        let mut report = ScrubReport::default();
+        for group in &groups {
+            report.append(Self::scrub_group(group).await);
+        }
+        report
+    }
+
+    /// Verifies records of all holders of the group
+    pub(crate) async fn scrub_group(group: &Group) -> ScrubReport {
+        let holders = group.holders().read().await.clone();
+        let mut report = ScrubReport::default();
+        for holder in holders {
+            // records of the holder can be listed only by keys log
+            let keys = if holder.has_keys_log() {
+                holder.keys().await
+            } else {
+                Err(Error::storage("keys log is missing"))
+            };
+            let keys = match keys {
+                Ok(keys) => keys,
+                Err(e) => {
+                    let records = holder.records_count().await;
+                    if records > 0 {
+                        warn!("scrub: can't list keys of {}: {}", holder.get_id(), e);
+                        report.unchecked.push(UncheckedHolder {
+                            vdisk_id: group.vdisk_id(),
+                            node_name: group.node_name().to_owned(),
+                            disk_name: group.disk_name().to_owned(),
+                            start_timestamp: holder.start_timestamp(),
+                            records,
+                        });
+                    }
+                    continue;
+                }
+            };
+            for key in keys {
+                match holder.read(key).await {
+                    Err(e) if e.is_data_corrupted() => report.corrupted.push(CorruptedRecord {
+                        vdisk_id: group.vdisk_id(),
+                        node_name: group.node_name().to_owned(),
+                        disk_name: group.disk_name().to_owned(),
+                        start_timestamp: holder.start_timestamp(),
+                        key,
+                    }),
+                    Err(e) => debug!("scrub: can't read {} from {}: {}", key, holder.get_id(), e),
+                    Ok(_) => {}
+                }
+                report.checked += 1;
+                tokio::task::yield_now().await;
+            }
+        }
+        report
+    }
+}
@@ -1,27 +1,73 @@
+use std::time::UNIX_EPOCH;
+
 use super::prelude::*;
+use crate::core::auth::Authenticator;
+use crate::core::backend::Group;
+use crate::core::chunks::{self, ChunkedPut, ReplacedChunks};
+use crate::core::configs::{Role, TlsConfig};
+use crate::core::drain::{Drain, DrainState, OperationGuard};
+use crate::core::reload::ConfigReloader;
+use crate::core::tls;
+use crate::grpc::{
+    bob_repair_server::BobRepair, BatchBlob, BlobChunk, BlobHead, BobError,
+    ClusterMap as ClusterMapMessage, DigestRequest, GetBatchRequest, GetBatchResponse, HolderHash,
+    KeyVersion, ListKeysRequest, PingResponse, PutBatchRequest, PutBatchResponse, PutChunk, Record,
+    RecordsRequest, VDiskDigest, Versions, VersionsRequest,
+};
+use futures::{
+    channel::mpsc::{self, Receiver},
+    SinkExt,
+};
+use tonic::Streaming;
 
 /// Struct contains `Grinder` and receives incomming GRPC requests
 #[derive(Clone, Debug)]
 pub struct Server {
     grinder: Arc<Grinder>,
+    authenticator: Authenticator,
+    reloader: Option<Arc<ConfigReloader>>,
+    drain: Arc<Drain>,
 }
 
 impl Server {
-    /// Creates new bob server
+    /// Creates new bob server, clients are checked by authenticator
     #[must_use]
-    pub fn new(grinder: Grinder) -> Self {
+    pub fn new(grinder: Grinder, authenticator: Authenticator) -> Self {
         Self {
             grinder: Arc::new(grinder),
+            authenticator,
+            reloader: None,
+            drain: Arc::default(),
         }
     }
 
+    /// Sets reloader of the node config, reload isn't available without it. Logger of the
+    /// reloader is also refreshed at the `refresh_rate` of its config.
+    #[must_use]
+    pub fn with_config_reloader(mut self, reloader: ConfigReloader) -> Self {
+        let reloader = Arc::new(reloader);
+        reloader.spawn_log_refresh();
+        self.reloader = Some(reloader);
+        self
+    }
+
     pub(crate) fn grinder(&self) -> &Grinder {
         self.grinder.as_ref()
     }
 
+    /// Authenticator of the grpc and http api clients
+    #[must_use]
+    pub fn authenticator(&self) -> &Authenticator {
+        &self.authenticator
+    }
+
     /// Call to run HTTP API server, not required for normal functioning
-    pub fn run_api_server(&self, port: u16) {
-        api::http::spawn(self.clone(), port);
+    /// # Errors
+    /// Fails if TLS certificates or key can't be read
+    pub fn run_api_server(&self, port: u16, tls: Option<&TlsConfig>) -> Result<(), String> {
+        let tls = tls.map(tls::http_server_config).transpose()?;
+        api::http::spawn(self.clone(), port, tls);
+        Ok(())
     }
 
     /// Start backend component, required before starting bob service
@@ -41,32 +87,151 @@ impl Server {
         self.grinder.run_periodic_tasks(client_factory);
     }
 
-    /// Gracefully shutdowns bob
+    /// Replaces cluster map with the new version, e.g. reloaded from the cluster config file
+    /// # Errors
+    /// Returns error if the map is not newer than the current one or can't be applied
+    pub async fn update_cluster_map(&self, cluster: &ClusterConfig) -> Result<u64, Error> {
+        self.grinder.backend().update_cluster_map(cluster).await
+    }
+
+    /// Reloads node config and applies fields, which can be changed without restart, returns
+    /// names of the changed fields
+    /// # Errors
+    /// Fails if config can't be read, is invalid or changes fields, which require restart
+    pub fn reload_node_config(&self) -> Result<Vec<String>, String> {
+        self.reloader
+            .as_ref()
+            .ok_or_else(|| "node config reload isn't configured".to_string())?
+            .reload(&self.grinder)
+    }
+
+    pub(crate) fn drain(&self) -> &Drain {
+        &self.drain
+    }
+
+    /// Starts drain of the node in background: rejects new writes, reports draining state to
+    /// other nodes, waits for operations in flight, stops background tasks and closes storages.
+    /// Returns false if the node isn't active.
+    #[must_use]
+    pub fn start_drain(&self) -> bool {
+        self.drain.start(self.grinder.clone())
+    }
+
+    /// Starts reopening of storages of the drained node in background, node accepts writes
+    /// again when they are ready. Returns false if the node isn't drained.
+    #[must_use]
+    pub fn undrain(&self) -> bool {
+        self.drain.undrain(self.grinder.clone())
+    }
+
+    /// Gracefully shutdowns bob, operations in flight are finished first
     pub async fn shutdown(&self) {
-        let backend = self.grinder.backend().clone();
-        backend.shutdown().await;
+        while !self.drain.run(&self.grinder).await {
+            if self.drain.state() == DrainState::Drained {
+                return;
+            }
+            debug!(
+                "node is {}, wait for it before shutdown",
+                self.drain.state().as_str()
+            );
+            sleep(Duration::from_millis(100)).await;
+        }
     }
 }
 
-fn put_extract(req: PutRequest) -> Option<(u64, Vec<u8>, u64, Option<PutOptions>)> {
-    let key = req.key?.key;
-    let blob = req.data?;
-    let timestamp = blob.meta.as_ref()?.timestamp;
+fn put_extract(req: PutRequest) -> Option<(BlobKey, BobData, Option<PutOptions>)> {
+    let key = req.key?;
+    let data = BobData::try_from(req.data?).ok()?;
     let options = req.options;
-    Some((key, blob.data, timestamp, options))
+    Some((key, data, options))
+}
+
+fn put_chunk_extract(chunk: PutChunk) -> Option<(BlobKey, BobMeta, Option<PutOptions>, Vec<u8>)> {
+    let key = chunk.key?;
+    let meta = BobMeta::from(chunk.meta?);
+    Some((key, meta, chunk.options, chunk.data))
 }
 
-fn get_extract(req: GetRequest) -> Option<(u64, Option<GetOptions>)> {
-    let key = req.key?.key;
+fn get_extract(req: GetRequest) -> Option<(BlobKey, Option<GetOptions>)> {
+    let key = req.key?;
     let options = req.options;
     Some((key, options))
 }
 
+fn delete_extract(req: DeleteRequest) -> Option<(BlobKey, Option<u64>, Option<DeleteOptions>)> {
+    let key = req.key?;
+    let timestamp = req.meta.map(|meta| meta.timestamp);
+    Some((key, timestamp, req.options))
+}
+
+fn check_meta(meta: &BobMeta) -> Result<(), Status> {
+    if meta.headers_len() > BobMeta::MAX_HEADERS_LEN {
+        warn!("invalid arguments - headers are too long");
+        return Err(Status::new(
+            Code::InvalidArgument,
+            format!("Headers are longer than {} bytes", BobMeta::MAX_HEADERS_LEN),
+        ));
+    }
+    Ok(())
+}
+
+// records are written as is only to replicas by other nodes, force node flag of the
+// client doesn't allow it
+fn is_replica_put(options: &BobOptions, from_node: bool) -> bool {
+    from_node && options.flags().contains(BobFlags::FORCE_NODE)
+}
+
+// manifests are built only by the chunked put, other nodes send them to replicas as is
+fn check_manifest(data: &BobData, replica: bool) -> Result<(), Status> {
+    if data.is_manifest() && !replica {
+        warn!("invalid arguments - manifest is sent by client");
+        return Err(Status::new(
+            Code::InvalidArgument,
+            "Manifest can't be written by client",
+        ));
+    }
+    Ok(())
+}
+
+// expiration is set by the first node from ttl, other nodes send it to replicas as is
+fn check_expiration(meta: &BobMeta, replica: bool) -> Result<(), Status> {
+    if meta.expires_at().is_some() && !replica {
+        warn!("invalid arguments - expiration is sent by client");
+        return Err(Status::new(
+            Code::InvalidArgument,
+            "Expiration can't be set by client, use ttl",
+        ));
+    }
+    Ok(())
+}
+
+// checksum is computed by the first node, replicas verify it
+fn checksummed(key: BobKey, data: BobData) -> Result<BobData, Status> {
+    if data.checksum().is_some() {
+        // mismatch is caused by the sender, stored records aren't corrupted
+        data.verify_checksum(key).map_err(|_| {
+            warn!("PUT[{}] data doesn't match checksum", key);
+            Status::new(Code::InvalidArgument, "Data doesn't match checksum")
+        })?;
+        Ok(data)
+    } else {
+        Ok(data.checksummed())
+    }
+}
+
 type ApiResult<T> = Result<Response<T>, Status>;
 
+type ChunksStream = Receiver<Result<BlobChunk, Status>>;
+
 #[tonic::async_trait]
 impl BobApi for Server {
+    type GetStreamStream = ChunksStream;
+    type ListKeysStream = KeysStream;
+
     async fn put(&self, req: Request<PutRequest>) -> ApiResult<OpStatus> {
+        self.authenticator.authorize_request(&req, Role::Write)?;
+        let _operation = self.drain.start_operation(true)?;
+        let from_node = self.authenticator.is_node_request(&req);
         trace!("- - - - - SERVER PUT START - - - - -");
         let sw = Stopwatch::start_new();
         trace!(
@@ -79,24 +244,30 @@ impl BobApi for Server {
             sw.elapsed().as_secs_f64() * 1000.0
         );
 
-        if let Some((key, inner, timestamp, options)) = put_extract(put_request) {
+        if let Some((key, data, options)) = put_extract(put_request) {
+            let key = self.key(key).map_err::<Status, _>(Error::into)?;
+            let options = BobOptions::new_put(options);
+            check_meta(data.meta())?;
+            let replica = is_replica_put(&options, from_node);
+            check_manifest(&data, replica)?;
+            check_expiration(data.meta(), replica)?;
+            let data = checksummed(key, data)?;
             trace!(
                 "extract params from request, /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
             );
-            let meta = BobMeta::new(timestamp);
-            let data = BobData::new(inner, meta);
-
             trace!(
                 "PUT[{}] data size: {}, /{:.3}ms/",
                 key,
                 data.inner().len(),
                 sw.elapsed().as_secs_f64() * 1000.0
             );
-            let put_result = self
-                .grinder
-                .put(key, data, BobOptions::new_put(options))
-                .await;
+            let timestamp = data.meta().timestamp();
+            let replaced = ReplacedChunks::find(&self.grinder, key, timestamp, &options).await;
+            let put_result = self.grinder.put(key, data, options).await;
+            if put_result.is_ok() {
+                replaced.remove(&self.grinder, key).await;
+            }
             trace!(
                 "grinder processed put request, /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
@@ -125,6 +296,8 @@ impl BobApi for Server {
     }
 
     async fn get(&self, req: Request<GetRequest>) -> ApiResult<Blob> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        let _operation = self.drain.start_operation(false)?;
         trace!("- - - - - SERVER GET START - - - - -");
         let sw = Stopwatch::start_new();
         trace!(
@@ -137,6 +310,7 @@ impl BobApi for Server {
             sw.elapsed().as_secs_f64() * 1000.0
         );
         if let Some((key, options)) = get_extract(get_req) {
+            let key = self.key(key).map_err::<Status, _>(Error::into)?;
             trace!(
                 "create new bob options /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
@@ -146,23 +320,24 @@ impl BobApi for Server {
                 "pass request to grinder /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
             );
-            let get_res = self
+            let mut get_res = self
                 .grinder
                 .get(key, &options)
                 .await
                 .map_err::<Status, _>(|e| e.into())?;
+            // replicas exchange manifests as is, clients receive assembled blob
+            if get_res.is_manifest() && !options.flags().contains(BobFlags::FORCE_NODE) {
+                get_res = chunks::assemble(&self.grinder, key, get_res, &options)
+                    .await
+                    .map_err::<Status, _>(|e| e.into())?;
+            }
             trace!(
                 "grinder finished request processing /{:.3}ms/",
                 sw.elapsed().as_secs_f64() * 1000.0
             );
             let elapsed = sw.elapsed_ms();
             debug!("GET[{}]-OK dt: {}ms", key, elapsed);
-            let meta = Some(BlobMeta {
-                timestamp: get_res.meta().timestamp(),
-            });
-            let data = get_res.into_inner();
-            let blob = Blob { meta, data };
-            let response = Response::new(blob);
+            let response = Response::new(Blob::from(get_res));
             trace!("- - - - - SERVER GET FINISHED - - - - -");
             Ok(response)
         } else {
@@ -171,15 +346,24 @@ impl BobApi for Server {
         }
     }
 
-    async fn ping(&self, _: Request<Null>) -> ApiResult<Null> {
+    async fn ping(&self, req: Request<Null>) -> ApiResult<PingResponse> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
         debug!("PING");
-        Ok(Response::new(Null {}))
+        let draining = self.drain.is_draining();
+        Ok(Response::new(PingResponse { draining }))
     }
 
     async fn exist(&self, req: Request<ExistRequest>) -> ApiResult<ExistResponse> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        let _operation = self.drain.start_operation(false)?;
         let sw = Stopwatch::start_new();
         let req = req.into_inner();
-        let keys = req.keys.iter().map(|k| k.key).collect::<Vec<_>>();
+        let keys = req
+            .keys
+            .into_iter()
+            .map(|key| self.key(key))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err::<Status, _>(Error::into)?;
         let options = BobOptions::new_get(req.options);
         let exist = self
             .grinder
@@ -192,4 +376,491 @@ impl BobApi for Server {
         let response = Response::new(response);
         Ok(response)
     }
+
+    async fn delete(&self, req: Request<DeleteRequest>) -> ApiResult<OpStatus> {
+        self.authenticator.authorize_request(&req, Role::Write)?;
+        let _operation = self.drain.start_operation(true)?;
+        let sw = Stopwatch::start_new();
+        if let Some((key, timestamp, options)) = delete_extract(req.into_inner()) {
+            let key = self.key(key).map_err::<Status, _>(Error::into)?;
+            // tombstone must be newer than any version written before the request
+            let timestamp = timestamp.unwrap_or_else(|| {
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .expect("current time is before unix epoch")
+                    .as_secs()
+            });
+            let options = BobOptions::new_delete(options);
+            let replaced = ReplacedChunks::find(&self.grinder, key, timestamp, &options).await;
+            let delete_result = self
+                .grinder
+                .delete(key, BobMeta::new(timestamp), options)
+                .await;
+            if delete_result.is_ok() {
+                replaced.remove(&self.grinder, key).await;
+            }
+            let elapsed = sw.elapsed_ms();
+            delete_result
+                .map(|()| {
+                    debug!("DELETE[{}]-OK dt: {}ms", key, elapsed);
+                    Response::new(OpStatus { error: None })
+                })
+                .map_err(|e| {
+                    error!("DELETE[{}]-ERR dt: {}ms {:?}", key, elapsed, e);
+                    e.into()
+                })
+        } else {
+            warn!("DELETE[-] invalid arguments - key is mandatory");
+            Err(Status::new(Code::InvalidArgument, "Key is mandatory"))
+        }
+    }
+
+    async fn put_stream(&self, req: Request<Streaming<PutChunk>>) -> ApiResult<OpStatus> {
+        self.authenticator.authorize_request(&req, Role::Write)?;
+        let _operation = self.drain.start_operation(true)?;
+        let sw = Stopwatch::start_new();
+        let from_node = self.authenticator.is_node_request(&req);
+        let mut stream = req.into_inner();
+        let first = stream.message().await?.and_then(put_chunk_extract);
+        if let Some((key, meta, options, data)) = first {
+            let key = self.key(key).map_err::<Status, _>(Error::into)?;
+            check_meta(&meta)?;
+            let replica = is_replica_put(&BobOptions::new_put(options.clone()), from_node);
+            check_expiration(&meta, replica)?;
+            let put_result = self.put_chunks(key, meta, options, data, stream).await;
+            let elapsed = sw.elapsed_ms();
+            put_result
+                .map(|()| {
+                    debug!("PUT_STREAM[{}]-OK dt: {}ms", key, elapsed);
+                    Response::new(OpStatus { error: None })
+                })
+                .map_err(|e| {
+                    error!("PUT_STREAM[{}]-ERR dt: {}ms {:?}", key, elapsed, e);
+                    e.into()
+                })
+        } else {
+            warn!("PUT_STREAM[-] invalid arguments - key and meta in first chunk is mandatory");
+            Err(Status::new(
+                Code::InvalidArgument,
+                "Key and timestamp in meta of the first chunk is mandatory",
+            ))
+        }
+    }
+
+    async fn get_stream(&self, req: Request<GetRequest>) -> ApiResult<ChunksStream> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        let operation = self.drain.start_operation(false)?;
+        if let Some((key, options)) = get_extract(req.into_inner()) {
+            let key = self.key(key).map_err::<Status, _>(Error::into)?;
+            let options = BobOptions::new_get(options);
+            let data = self
+                .grinder
+                .get(key, &options)
+                .await
+                .map_err::<Status, _>(|e| e.into())?;
+            debug!(
+                "GET_STREAM[{}] send blob, manifest: {}",
+                key,
+                data.is_manifest()
+            );
+            let (tx, rx) = mpsc::channel(1);
+            let grinder = self.grinder.clone();
+            // operation is finished when the whole blob is sent
+            tokio::spawn(async move {
+                chunks::send(grinder, key, data, options, tx).await;
+                drop(operation);
+            });
+            Ok(Response::new(rx))
+        } else {
+            warn!("GET_STREAM[-] invalid arguments - key is mandatory");
+            Err(Status::new(Code::InvalidArgument, "Key is mandatory"))
+        }
+    }
+
+    async fn head(&self, req: Request<GetRequest>) -> ApiResult<BlobHead> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        let _operation = self.drain.start_operation(false)?;
+        let sw = Stopwatch::start_new();
+        if let Some((key, options)) = get_extract(req.into_inner()) {
+            let key = self.key(key).map_err::<Status, _>(Error::into)?;
+            let options = BobOptions::new_get(options);
+            let head = self
+                .grinder
+                .head(key, &options)
+                .await
+                .map_err::<Status, _>(|e| e.into())?;
+            debug!("HEAD[{}]-OK dt: {}ms", key, sw.elapsed_ms());
+            Ok(Response::new(BlobHead {
+                meta: Some(BlobMeta::from(head.meta())),
+                size: head.size(),
+                manifest: head.is_manifest(),
+            }))
+        } else {
+            warn!("HEAD[-] invalid arguments - key is mandatory");
+            Err(Status::new(Code::InvalidArgument, "Key is mandatory"))
+        }
+    }
+
+    async fn get_batch(&self, req: Request<GetBatchRequest>) -> ApiResult<GetBatchResponse> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        let _operation = self.drain.start_operation(false)?;
+        let sw = Stopwatch::start_new();
+        let req = req.into_inner();
+        let options = BobOptions::new_get(req.options);
+        let results = self.get_keys(req.keys, &options).await;
+        debug!("GET_BATCH[{}]-OK dt: {}ms", results.len(), sw.elapsed_ms());
+        Ok(Response::new(GetBatchResponse { results }))
+    }
+
+    async fn put_batch(&self, req: Request<PutBatchRequest>) -> ApiResult<PutBatchResponse> {
+        self.authenticator.authorize_request(&req, Role::Write)?;
+        let _operation = self.drain.start_operation(true)?;
+        let sw = Stopwatch::start_new();
+        let from_node = self.authenticator.is_node_request(&req);
+        let req = req.into_inner();
+        let options = BobOptions::new_put(req.options);
+        let statuses = self.put_blobs(req.blobs, options, from_node).await;
+        debug!("PUT_BATCH[{}]-OK dt: {}ms", statuses.len(), sw.elapsed_ms());
+        Ok(Response::new(PutBatchResponse { statuses }))
+    }
+
+    async fn list_keys(&self, req: Request<ListKeysRequest>) -> ApiResult<KeysStream> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        let operation = self.drain.start_operation(false)?;
+        let req = req.into_inner();
+        let source = GetSource::from_i32(req.source).ok_or_else(|| {
+            Status::new(
+                Code::InvalidArgument,
+                format!("unknown source: {}", req.source),
+            )
+        })?;
+        let start_timestamp = req.partition.map(|p| p.start_timestamp);
+        self.keys_stream(req.vdisk_id, source, start_timestamp, operation)
+            .await
+            .map(Response::new)
+            .map_err(Error::into)
+    }
+}
+
+impl Server {
+    /// Converts key from request to the cluster key length
+    fn key(&self, key: BlobKey) -> Result<BobKey, Error> {
+        let mapper = self.grinder.backend().mapper();
+        // client which sends only numeric keys can't read keys of the longer responses
+        if key.bytes_key.is_empty() && mapper.key_length() > BobKey::U64_LEN {
+            return Err(Error::invalid_key(format!(
+                "numeric key {} is used in cluster with key length of {} bytes",
+                key.key,
+                mapper.key_length()
+            )));
+        }
+        let key = BobKey::try_from(key)?;
+        mapper.normalize_key(key)
+    }
+
+    // invalid keys and blobs fail only their own items, the rest of the batch is processed
+    async fn get_keys(&self, keys: Vec<BlobKey>, options: &BobOptions) -> Vec<BatchBlob> {
+        let keys: Vec<_> = keys
+            .into_iter()
+            .map(|key| (key.clone(), self.key(key)))
+            .collect();
+        let valid: Vec<_> = keys
+            .iter()
+            .filter_map(|(_, key)| key.clone().ok())
+            .collect();
+        let mut found = self.grinder.get_batch(&valid, options).await.into_iter();
+        let mut results = Vec::with_capacity(keys.len());
+        for (blob_key, key) in keys {
+            let result = match key {
+                Ok(key) => {
+                    let data = found.next().expect("result for every key");
+                    match data {
+                        // replicas exchange manifests as is, clients receive assembled blob
+                        Ok(data)
+                            if data.is_manifest()
+                                && !options.flags().contains(BobFlags::FORCE_NODE) =>
+                        {
+                            chunks::assemble(&self.grinder, key, data, options).await
+                        }
+                        data => data,
+                    }
+                }
+                Err(e) => Err(e),
+            };
+            let (blob, error) = match result {
+                Ok(data) => (Some(Blob::from(data)), None),
+                Err(e) => (None, Some(BobError::from(e))),
+            };
+            results.push(BatchBlob {
+                key: Some(blob_key),
+                blob,
+                error,
+            });
+        }
+        results
+    }
+
+    async fn put_blobs(
+        &self,
+        blobs: Vec<BatchBlob>,
+        options: BobOptions,
+        from_node: bool,
+    ) -> Vec<OpStatus> {
+        let mut records = Vec::with_capacity(blobs.len());
+        let mut errors = Vec::with_capacity(blobs.len());
+        for blob in blobs {
+            match self.batch_record(blob, &options, from_node) {
+                Ok(record) => {
+                    records.push(record);
+                    errors.push(None);
+                }
+                Err(status) => errors.push(Some(BobError::from(status))),
+            }
+        }
+        let mut written = self.grinder.put_batch(records, options).await.into_iter();
+        errors
+            .into_iter()
+            .map(|error| {
+                let error = error.or_else(|| {
+                    let result = written.next().expect("result for every record");
+                    result.err().map(BobError::from)
+                });
+                OpStatus { error }
+            })
+            .collect()
+    }
+
+    fn batch_record(
+        &self,
+        blob: BatchBlob,
+        options: &BobOptions,
+        from_node: bool,
+    ) -> Result<(BobKey, BobData), Status> {
+        let key = blob
+            .key
+            .ok_or_else(|| Status::new(Code::InvalidArgument, "Key is mandatory"))?;
+        let key = self.key(key).map_err::<Status, _>(Error::into)?;
+        let blob = blob
+            .blob
+            .ok_or_else(|| Status::new(Code::InvalidArgument, "Data is mandatory"))?;
+        let data = BobData::try_from(blob).map_err::<Status, _>(Error::into)?;
+        check_meta(data.meta())?;
+        let replica = is_replica_put(options, from_node);
+        check_manifest(&data, replica)?;
+        check_expiration(data.meta(), replica)?;
+        checksummed(key, data).map(|data| (key, data))
+    }
+
+    async fn put_chunks(
+        &self,
+        key: BobKey,
+        meta: BobMeta,
+        options: Option<PutOptions>,
+        data: Vec<u8>,
+        mut stream: Streaming<PutChunk>,
+    ) -> Result<(), Error> {
+        let mut put = ChunkedPut::new(&self.grinder, key, meta, options);
+        put.write(&data).await?;
+        while let Some(chunk) = stream.message().await.map_err(Error::from)? {
+            put.write(&chunk.data).await?;
+        }
+        put.finish().await
+    }
+}
+
+type RecordsStream = Receiver<Result<Record, Status>>;
+
+type KeysStream = Receiver<Result<KeyVersion, Status>>;
+
+// keys of the vdisk are listed by pages of this size while the stream is consumed
+const KEYS_PAGE_SIZE: usize = 1000;
+
+impl Server {
+    /// Local group of the vdisk or the released one, which is still source of the migration
+    async fn source_group(&self, vdisk_id: VDiskID) -> Result<Group, Error> {
+        let backend = self.grinder.backend();
+        match Repair::local_group(backend, vdisk_id).await {
+            Ok(group) => Ok(group),
+            Err(e) => backend
+                .released_vdisks_groups()
+                .await
+                .and_then(|groups| groups.into_iter().find(|g| g.vdisk_id() == vdisk_id))
+                .ok_or(e),
+        }
+    }
+
+    async fn repair_digest(&self, vdisk_id: VDiskID) -> Result<VDiskDigest, Error> {
+        let group = self.source_group(vdisk_id).await?;
+        let holders = group.digest().await?;
+        let holders = holders.into_iter().map(HolderHash::from).collect();
+        Ok(VDiskDigest { holders })
+    }
+
+    async fn repair_versions(&self, req: VersionsRequest) -> Result<Versions, Error> {
+        let group = self.source_group(req.vdisk_id).await?;
+        let versions = group.versions(req.start_timestamp).await?;
+        let versions = versions.into_iter().map(KeyVersion::from).collect();
+        Ok(Versions { versions })
+    }
+
+    async fn repair_records(
+        &self,
+        req: RecordsRequest,
+        operation: OperationGuard,
+    ) -> Result<RecordsStream, Error> {
+        let group = self.source_group(req.vdisk_id).await?;
+        let keys = req
+            .keys
+            .into_iter()
+            .map(|key| self.key(key))
+            .collect::<Result<Vec<_>, _>>()?;
+        debug!(
+            "send {} records of vdisk {} for repair",
+            keys.len(),
+            req.vdisk_id
+        );
+        // records are read one by one while the stream is consumed, so the response isn't
+        // buffered in memory
+        let (latest, start_timestamp) = (req.latest, req.start_timestamp);
+        let (mut tx, rx) = mpsc::channel(1);
+        tokio::spawn(async move {
+            for key in keys {
+                let record = if latest {
+                    group.latest_record(key).await
+                } else {
+                    group.record(start_timestamp, key).await
+                };
+                let message = match record {
+                    Ok(Some(data)) => {
+                        let tombstone = data.is_tombstone();
+                        Ok(Record {
+                            key: Some(BlobKey::from(key)),
+                            blob: Some(Blob::from(data)),
+                            tombstone,
+                        })
+                    }
+                    Ok(None) => continue,
+                    Err(e) => Err(e.into()),
+                };
+                let failed = message.is_err();
+                if tx.send(message).await.is_err() || failed {
+                    break;
+                }
+            }
+            drop(operation);
+        });
+        Ok(rx)
+    }
+
+    async fn keys_stream(
+        &self,
+        vdisk_id: VDiskID,
+        source: GetSource,
+        start_timestamp: Option<u64>,
+        operation: OperationGuard,
+    ) -> Result<KeysStream, Error> {
+        let backend = self.grinder.backend().clone();
+        // first page is listed before the response, so unknown vdisk is returned as status
+        let mut page = backend
+            .list_keys(
+                vdisk_id,
+                source,
+                start_timestamp,
+                None,
+                Some(KEYS_PAGE_SIZE),
+            )
+            .await?;
+        let (mut tx, rx) = mpsc::channel(1);
+        // next pages are listed after the previous ones are consumed, so the response isn't
+        // buffered in memory
+        tokio::spawn(async move {
+            let mut listed = 0;
+            'pages: loop {
+                // listing returns less keys than the limit only on the last page
+                let complete = page.len() < KEYS_PAGE_SIZE;
+                let after = page.last().map(|version| version.key());
+                listed += page.len();
+                for version in page {
+                    if tx.send(Ok(KeyVersion::from(version))).await.is_err() {
+                        break 'pages;
+                    }
+                }
+                if complete {
+                    break;
+                }
+                page = match backend
+                    .list_keys(
+                        vdisk_id,
+                        source,
+                        start_timestamp,
+                        after,
+                        Some(KEYS_PAGE_SIZE),
+                    )
+                    .await
+                {
+                    Ok(page) => page,
+                    Err(e) => {
+                        let _ = tx.send(Err(e.into())).await;
+                        break;
+                    }
+                };
+            }
+            debug!("listed {} keys of vdisk {}", listed, vdisk_id);
+            drop(operation);
+        });
+        Ok(rx)
+    }
+
+    fn cluster_map(&self) -> Result<ClusterMapMessage, Error> {
+        let mapper = self.grinder.backend().mapper();
+        let config = serde_yaml::to_string(mapper.cluster_config())
+            .map_err(|e| Error::failed(format!("cluster map serialization failed: {}", e)))?;
+        let signature = self
+            .authenticator
+            .node_credentials()
+            .map(|credentials| credentials.sign_cluster_map(mapper.version(), &config))
+            .unwrap_or_default();
+        Ok(ClusterMapMessage {
+            version: mapper.version(),
+            config,
+            signature,
+        })
+    }
+}
+
+#[tonic::async_trait]
+impl BobRepair for Server {
+    type GetRecordsStream = RecordsStream;
+
+    async fn get_digest(&self, req: Request<DigestRequest>) -> ApiResult<VDiskDigest> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        let _operation = self.drain.start_operation(false)?;
+        self.repair_digest(req.into_inner().vdisk_id)
+            .await
+            .map(Response::new)
+            .map_err(Error::into)
+    }
+
+    async fn get_versions(&self, req: Request<VersionsRequest>) -> ApiResult<Versions> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        let _operation = self.drain.start_operation(false)?;
+        self.repair_versions(req.into_inner())
+            .await
+            .map(Response::new)
+            .map_err(Error::into)
+    }
+
+    async fn get_records(&self, req: Request<RecordsRequest>) -> ApiResult<RecordsStream> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        let operation = self.drain.start_operation(false)?;
+        self.repair_records(req.into_inner(), operation)
+            .await
+            .map(Response::new)
+            .map_err(Error::into)
+    }
+
+    async fn get_cluster_map(&self, req: Request<Null>) -> ApiResult<ClusterMapMessage> {
+        self.authenticator.authorize_request(&req, Role::Read)?;
+        self.cluster_map().map(Response::new).map_err(Error::into)
+    }
 }
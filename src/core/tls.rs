@@ -0,0 +1,345 @@
+use super::prelude::*;
+use crate::core::configs::TlsConfig;
+use hyper::{
+    server::conn::Http, service::service_fn, Body, Request as HttpRequest,
+    Response as HttpResponse, StatusCode,
+};
+use rocket::{
+    http::{Header, Method},
+    local::Client,
+};
+use std::convert::Infallible;
+use std::io::BufReader;
+use std::str::FromStr;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_rustls::{
+    rustls::{
+        internal::pemfile::{certs, pkcs8_private_keys, rsa_private_keys},
+        AllowAnyAuthenticatedClient, Certificate as RustlsCertificate, NoClientAuth, PrivateKey,
+        RootCertStore, ServerConfig, Session,
+    },
+    TlsAcceptor,
+};
+use tonic::transport::{Certificate, ClientTlsConfig, Identity, ServerTlsConfig};
+
+/// Header with base64 encoded DER of the verified client certificate, it's set only by the tls
+/// server, the header sent by client is dropped
+pub(crate) const CLIENT_CERT_HEADER: &str = "x-client-cert";
+
+/// Config of the grpc server, clients are verified with CA if client certificate is required
+/// # Errors
+/// Fails if certificates or key can't be read
+pub fn server_config(config: &TlsConfig) -> Result<ServerTlsConfig, String> {
+    let tls = ServerTlsConfig::new().identity(identity(config)?);
+    if config.require_client_cert() {
+        Ok(tls.client_ca_root(ca_certificate(config)?))
+    } else {
+        Ok(tls)
+    }
+}
+
+/// Config of the connections to other nodes, node certificate is presented as client one
+/// # Errors
+/// Fails if certificates or key can't be read
+pub fn client_config(config: &TlsConfig) -> Result<ClientTlsConfig, String> {
+    let tls = ClientTlsConfig::new()
+        .ca_certificate(ca_certificate(config)?)
+        .identity(identity(config)?);
+    if let Some(domain_name) = config.domain_name() {
+        Ok(tls.domain_name(domain_name))
+    } else {
+        Ok(tls)
+    }
+}
+
+/// Config of the http api server
+pub(crate) fn http_server_config(config: &TlsConfig) -> Result<ServerConfig, String> {
+    let mut server = if config.require_client_cert() {
+        let mut roots = RootCertStore::empty();
+        for cert in load_certs(config.ca_cert_path())? {
+            roots
+                .add(&cert)
+                .map_err(|e| format!("invalid CA certificate: {}", e))?;
+        }
+        ServerConfig::new(AllowAnyAuthenticatedClient::new(roots))
+    } else {
+        ServerConfig::new(NoClientAuth::new())
+    };
+    server
+        .set_single_cert(
+            load_certs(config.cert_path())?,
+            load_key(config.key_path())?,
+        )
+        .map_err(|e| format!("invalid certificate or key: {}", e))?;
+    server.set_protocols(&[b"http/1.1".to_vec()]);
+    Ok(server)
+}
+
+/// Terminates TLS of the accepted connections and dispatches their requests to the rocket in
+/// process, so the api has no plaintext socket
+pub(crate) async fn serve(listener: TcpListener, config: ServerConfig, client: Client) {
+    let acceptor = TlsAcceptor::from(Arc::new(config));
+    let client = Arc::new(client);
+    loop {
+        match listener.accept().await {
+            Ok((stream, peer)) => {
+                let acceptor = acceptor.clone();
+                let client = client.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = serve_connection(&acceptor, stream, peer, client).await {
+                        debug!("tls connection from {} closed: {}", peer, e);
+                    }
+                });
+            }
+            Err(e) => warn!("can't accept tls connection: {}", e),
+        }
+    }
+}
+
+async fn serve_connection(
+    acceptor: &TlsAcceptor,
+    stream: TcpStream,
+    peer: SocketAddr,
+    client: Arc<Client>,
+) -> Result<(), String> {
+    let tls = acceptor.accept(stream).await.map_err(|e| e.to_string())?;
+    let client_cert = tls
+        .get_ref()
+        .1
+        .get_peer_certificates()
+        .and_then(|certs| certs.into_iter().next())
+        .map(|cert| base64::encode(cert.0));
+    let service = service_fn(move |request| {
+        let response = dispatch(client.clone(), peer, client_cert.clone(), request);
+        async move { Ok::<_, Infallible>(response.await) }
+    });
+    Http::new()
+        .http1_only(true)
+        .serve_connection(tls, service)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn dispatch(
+    client: Arc<Client>,
+    peer: SocketAddr,
+    client_cert: Option<String>,
+    request: HttpRequest<Body>,
+) -> HttpResponse<Body> {
+    let (parts, body) = request.into_parts();
+    let body = match hyper::body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(e) => return error_response(StatusCode::BAD_REQUEST, e.to_string()),
+    };
+    // rocket handlers block, so they are run on the blocking threads as by rocket itself
+    let response = tokio::task::spawn_blocking(move || {
+        let method = Method::from_str(parts.method.as_str())
+            .map_err(|_| format!("unsupported method: {}", parts.method))?;
+        let uri = parts
+            .uri
+            .path_and_query()
+            .map_or("/", |path| path.as_str())
+            .to_owned();
+        let mut request = client.req(method, uri).remote(peer).body(&body);
+        for (name, value) in &parts.headers {
+            if name == CLIENT_CERT_HEADER {
+                continue;
+            }
+            if let Ok(value) = value.to_str() {
+                request.add_header(Header::new(name.as_str().to_owned(), value.to_owned()));
+            }
+        }
+        if let Some(cert) = client_cert {
+            request.add_header(Header::new(CLIENT_CERT_HEADER, cert));
+        }
+        let mut response = request.dispatch();
+        let mut builder = HttpResponse::builder().status(response.status().code);
+        for header in response.headers().iter() {
+            builder = builder.header(header.name(), header.value());
+        }
+        let body = response.body_bytes().unwrap_or_default();
+        builder.body(Body::from(body)).map_err(|e| e.to_string())
+    })
+    .await;
+    match response {
+        Ok(Ok(response)) => response,
+        Ok(Err(e)) => error_response(StatusCode::BAD_REQUEST, e),
+        Err(e) => error_response(StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+    }
+}
+
+fn error_response(status: StatusCode, msg: String) -> HttpResponse<Body> {
+    let mut response = HttpResponse::new(Body::from(msg));
+    *response.status_mut() = status;
+    response
+}
+
+fn identity(config: &TlsConfig) -> Result<Identity, String> {
+    load_key(config.key_path())?;
+    Ok(Identity::from_pem(
+        read(config.cert_path())?,
+        read(config.key_path())?,
+    ))
+}
+
+// tonic panics on invalid CA certificate, so it's parsed here first
+fn ca_certificate(config: &TlsConfig) -> Result<Certificate, String> {
+    load_certs(config.ca_cert_path())?;
+    Ok(Certificate::from_pem(read(config.ca_cert_path())?))
+}
+
+fn load_certs(path: &str) -> Result<Vec<RustlsCertificate>, String> {
+    let buf = read(path)?;
+    match certs(&mut BufReader::new(buf.as_slice())) {
+        Ok(certs) if !certs.is_empty() => Ok(certs),
+        _ => Err(format!("no certificates in {}", path)),
+    }
+}
+
+fn load_key(path: &str) -> Result<PrivateKey, String> {
+    let buf = read(path)?;
+    let mut keys = pkcs8_private_keys(&mut BufReader::new(buf.as_slice())).unwrap_or_default();
+    if keys.is_empty() {
+        keys = rsa_private_keys(&mut BufReader::new(buf.as_slice())).unwrap_or_default();
+    }
+    keys.into_iter()
+        .next()
+        .ok_or_else(|| format!("no private key in {}", path))
+}
+
+fn read(path: &str) -> Result<Vec<u8>, String> {
+    std::fs::read(path).map_err(|e| format!("can't read {}: {}", path, e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::auth::Authenticator;
+    use crate::core::test_utils;
+    use crate::grpc::{bob_api_client::BobApiClient, bob_api_server::BobApiServer, Null};
+    use crate::server::Server as BobServer;
+    use rcgen::{BasicConstraints, Certificate as RcgenCertificate, CertificateParams, IsCa};
+    use rocket::{
+        request::{FromRequest, Outcome as RequestOutcome},
+        Config as RocketConfig, Outcome, Request as RocketRequest, Rocket,
+    };
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio_rustls::{rustls::ClientConfig, webpki::DNSNameRef, TlsConnector};
+
+    const DOMAIN_NAME: &str = "bob";
+
+    // writes self-signed CA and node certificate signed by it
+    fn tls_config(dir: &str, require_client_cert: bool) -> TlsConfig {
+        let _ = std::fs::remove_dir_all(dir);
+        create_dir_all(dir).unwrap();
+        let mut params = CertificateParams::new(vec![]);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        let ca = RcgenCertificate::from_params(params).unwrap();
+        let node =
+            RcgenCertificate::from_params(CertificateParams::new(vec![DOMAIN_NAME.to_owned()]))
+                .unwrap();
+        let path = |name: &str| format!("{}/{}", dir, name);
+        std::fs::write(path("ca.crt"), ca.serialize_pem().unwrap()).unwrap();
+        let cert = node.serialize_pem_with_signer(&ca).unwrap();
+        std::fs::write(path("node.crt"), cert).unwrap();
+        std::fs::write(path("node.key"), node.serialize_private_key_pem()).unwrap();
+        TlsConfig::new(
+            path("node.crt"),
+            path("node.key"),
+            path("ca.crt"),
+            require_client_cert,
+            Some(DOMAIN_NAME.to_owned()),
+        )
+    }
+
+    async fn serve_grpc(tls: &TlsConfig) -> SocketAddr {
+        let grinder = test_utils::single_node_grinder().await;
+        let bob = BobServer::new(grinder, Authenticator::default());
+        let tls = server_config(tls).unwrap();
+        test_utils::serve_grpc(BobApiServer::new(bob), Some(tls)).await
+    }
+
+    async fn ping(address: SocketAddr, tls: Option<ClientTlsConfig>) -> Result<(), String> {
+        let mut endpoint = Endpoint::from_shared(format!("http://{}", address)).unwrap();
+        if let Some(tls) = tls {
+            endpoint = endpoint.tls_config(tls).unwrap();
+        }
+        let channel = endpoint.connect().await.map_err(|e| e.to_string())?;
+        BobApiClient::new(channel)
+            .ping(Request::new(Null {}))
+            .await
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    }
+
+    #[tokio::test]
+    async fn grpc_requires_client_certificate() {
+        let tls = tls_config("/tmp/bob_tls_grpc", true);
+        let address = serve_grpc(&tls).await;
+        assert!(ping(address, Some(client_config(&tls).unwrap()))
+            .await
+            .is_ok());
+
+        let anonymous = ClientTlsConfig::new()
+            .ca_certificate(ca_certificate(&tls).unwrap())
+            .domain_name(DOMAIN_NAME);
+        assert!(ping(address, Some(anonymous)).await.is_err());
+        assert!(ping(address, None).await.is_err());
+        std::fs::remove_dir_all("/tmp/bob_tls_grpc").unwrap();
+    }
+
+    struct ClientCert(Option<String>);
+
+    impl<'a, 'r> FromRequest<'a, 'r> for ClientCert {
+        type Error = ();
+
+        fn from_request(request: &'a RocketRequest<'r>) -> RequestOutcome<Self, ()> {
+            let cert = request.headers().get_one(CLIENT_CERT_HEADER);
+            Outcome::Success(Self(cert.map(str::to_owned)))
+        }
+    }
+
+    #[get("/cert")]
+    fn cert(cert: ClientCert) -> String {
+        cert.0.unwrap_or_default()
+    }
+
+    #[tokio::test]
+    async fn http_requests_are_served_over_tls() {
+        let tls = tls_config("/tmp/bob_tls_http", true);
+        let rocket = Rocket::custom(RocketConfig::development()).mount("/", routes![cert]);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        let client = Client::untracked(rocket).unwrap();
+        tokio::spawn(serve(listener, http_server_config(&tls).unwrap(), client));
+
+        let mut config = ClientConfig::new();
+        let ca = read(tls.ca_cert_path()).unwrap();
+        config
+            .root_store
+            .add_pem_file(&mut BufReader::new(ca.as_slice()))
+            .unwrap();
+        let cert = load_certs(tls.cert_path()).unwrap();
+        let expected = base64::encode(&cert[0].0);
+        config
+            .set_single_client_cert(cert, load_key(tls.key_path()).unwrap())
+            .unwrap();
+        let stream = TcpStream::connect(address).await.unwrap();
+        let domain = DNSNameRef::try_from_ascii_str(DOMAIN_NAME).unwrap();
+        let mut stream = TlsConnector::from(Arc::new(config))
+            .connect(domain, stream)
+            .await
+            .unwrap();
+        // header sent by client is replaced with the verified certificate
+        let request = format!(
+            "GET /cert HTTP/1.1\r\nHost: {}\r\n{}: forged\r\nConnection: close\r\n\r\n",
+            DOMAIN_NAME, CLIENT_CERT_HEADER
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"));
+        assert!(response.ends_with(&expected));
+        std::fs::remove_dir_all("/tmp/bob_tls_http").unwrap();
+    }
+}
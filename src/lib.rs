@@ -30,7 +30,9 @@ mod core;
 pub use self::{
     api::grpc,
     core::{
-        backend, bob_client as client, configs, data::DiskPath, grinder, mapper, metrics, server,
+        auth, backend, bob_client as client, configs,
+        data::{BobKey, DiskPath},
+        grinder, mapper, metrics, reload, server, tls,
     },
 };
 
@@ -44,7 +46,7 @@ mod prelude {
     pub(crate) use std::{
         cell::{Ref, RefCell},
         collections::HashMap,
-        convert::TryInto,
+        convert::{TryFrom, TryInto},
         fmt::{Debug, Display, Formatter, Result as FmtResult},
         fs::{create_dir_all, read_dir, read_to_string, remove_file, DirEntry, Metadata},
         io::{Cursor, Error as IOError, ErrorKind as IOErrorKind, Result as IOResult},